@@ -0,0 +1,8 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  // use a bundled protoc, so that building ord doesn't require one installed
+  std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+
+  tonic_build::compile_protos("proto/ord.proto")?;
+
+  Ok(())
+}
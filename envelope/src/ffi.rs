@@ -0,0 +1,123 @@
+//! C ABI for the envelope parser.
+
+use {
+  super::{json::ParseWitnessError, *},
+  std::{
+    ffi::{c_char, c_int, CString},
+    slice,
+  },
+};
+
+pub const ORD_OK: c_int = 0;
+pub const ORD_ERROR_NULL_POINTER: c_int = -1;
+pub const ORD_ERROR_INVALID_WITNESS: c_int = -2;
+pub const ORD_ERROR_EMPTY_WITNESS: c_int = -3;
+pub const ORD_ERROR_KEY_PATH_SPEND: c_int = -4;
+pub const ORD_ERROR_INVALID_INSCRIPTION: c_int = -5;
+pub const ORD_ERROR_UNRECOGNIZED_EVEN_FIELD: c_int = -6;
+pub const ORD_ERROR_SCRIPT: c_int = -7;
+
+/// Parse the inscriptions in a consensus-serialized witness. On success,
+/// `*json` is set to a JSON array of `{"content_type", "body"}` objects, with
+/// bodies hex encoded, which must be freed with `ord_free_string`. Envelopes
+/// with unrecognized even tags, such as the cursed tag 66, make the whole
+/// witness fail with `ORD_ERROR_UNRECOGNIZED_EVEN_FIELD`, just like in the
+/// indexer.
+///
+/// # Safety
+///
+/// `witness` must point to `witness_len` readable bytes and `json` must be
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ord_parse_witness(
+  witness: *const u8,
+  witness_len: usize,
+  json: *mut *mut c_char,
+) -> c_int {
+  if witness.is_null() || json.is_null() {
+    return ORD_ERROR_NULL_POINTER;
+  }
+
+  let parsed = match json::parse_witness(slice::from_raw_parts(witness, witness_len)) {
+    Ok(parsed) => parsed,
+    Err(ParseWitnessError::InvalidWitness) => return ORD_ERROR_INVALID_WITNESS,
+    Err(ParseWitnessError::Envelope(err)) => {
+      return match err {
+        Error::EmptyWitness => ORD_ERROR_EMPTY_WITNESS,
+        Error::KeyPathSpend => ORD_ERROR_KEY_PATH_SPEND,
+        Error::InvalidInscription | Error::NoInscription => ORD_ERROR_INVALID_INSCRIPTION,
+        Error::UnrecognizedEvenField => ORD_ERROR_UNRECOGNIZED_EVEN_FIELD,
+        Error::Script(_) => ORD_ERROR_SCRIPT,
+      }
+    }
+  };
+
+  // JSON escapes NUL, so this never fails
+  *json = CString::new(parsed).unwrap().into_raw();
+
+  ORD_OK
+}
+
+/// Free a string returned by `ord_parse_witness`.
+///
+/// # Safety
+///
+/// `string` must be null or have been returned by `ord_parse_witness`, and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ord_free_string(string: *mut c_char) {
+  if !string.is_null() {
+    drop(CString::from_raw(string));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, bitcoin::consensus, std::ffi::CStr};
+
+  fn parse(witness: &Witness) -> Result<String, c_int> {
+    let witness = consensus::serialize(witness);
+    let mut json = std::ptr::null_mut();
+
+    let code = unsafe { ord_parse_witness(witness.as_ptr(), witness.len(), &mut json) };
+
+    if code != ORD_OK {
+      return Err(code);
+    }
+
+    let result = unsafe { CStr::from_ptr(json) }.to_str().unwrap().to_owned();
+
+    unsafe { ord_free_string(json) };
+
+    Ok(result)
+  }
+
+  #[test]
+  fn parse_witness() {
+    assert_eq!(
+      parse(&envelope(&[b"ord", &[1], b"text/plain", &[], b"ord"])),
+      Ok(r#"[{"content_type":"text/plain","body":"6f7264"}]"#.into()),
+    );
+  }
+
+  #[test]
+  fn parse_witness_errors() {
+    assert_eq!(
+      parse(&envelope(&[b"ord", &[66], b"cursed"])),
+      Err(ORD_ERROR_UNRECOGNIZED_EVEN_FIELD),
+    );
+
+    assert_eq!(parse(&Witness::new()), Err(ORD_ERROR_EMPTY_WITNESS));
+
+    let mut json = std::ptr::null_mut();
+    assert_eq!(
+      unsafe { ord_parse_witness([0xff].as_ptr(), 1, &mut json) },
+      ORD_ERROR_INVALID_WITNESS
+    );
+
+    assert_eq!(
+      unsafe { ord_parse_witness(std::ptr::null(), 0, &mut json) },
+      ORD_ERROR_NULL_POINTER
+    );
+  }
+}
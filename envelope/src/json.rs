@@ -0,0 +1,97 @@
+use {super::*, bitcoin::consensus, serde::Serialize};
+
+#[derive(Serialize)]
+struct ParsedEnvelope {
+  content_type: Option<String>,
+  body: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ParseWitnessError {
+  InvalidWitness,
+  Envelope(Error),
+}
+
+impl fmt::Display for ParseWitnessError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::InvalidWitness => write!(f, "invalid witness"),
+      Self::Envelope(err) => err.fmt(f),
+    }
+  }
+}
+
+/// Parse the envelopes in a consensus-serialized witness into a JSON array
+/// of `{"content_type", "body"}` objects, with bodies hex encoded, for the
+/// C and WebAssembly bindings. A witness without envelopes has none.
+pub(crate) fn parse_witness(witness: &[u8]) -> Result<String, ParseWitnessError> {
+  let witness =
+    consensus::deserialize::<Witness>(witness).map_err(|_| ParseWitnessError::InvalidWitness)?;
+
+  let envelopes = match parse(&witness) {
+    Ok(envelopes) => envelopes,
+    Err(Error::NoInscription) => Vec::new(),
+    Err(err) => return Err(ParseWitnessError::Envelope(err)),
+  };
+
+  let parsed = envelopes
+    .into_iter()
+    .map(|envelope| ParsedEnvelope {
+      content_type: envelope
+        .content_type
+        .and_then(|content_type| String::from_utf8(content_type).ok()),
+      body: envelope.body.map(hex::encode),
+    })
+    .collect::<Vec<ParsedEnvelope>>();
+
+  Ok(serde_json::to_string(&parsed).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_witness_into_json() {
+    assert_eq!(
+      parse_witness(&consensus::serialize(&envelope(&[
+        b"ord",
+        &[1],
+        b"text/plain",
+        &[],
+        b"ord"
+      ]))),
+      Ok(r#"[{"content_type":"text/plain","body":"6f7264"}]"#.into()),
+    );
+  }
+
+  #[test]
+  fn witness_without_envelopes_has_none() {
+    assert_eq!(
+      parse_witness(&consensus::serialize(&Witness::from_slice(&[
+        vec![0x51],
+        Vec::new()
+      ]))),
+      Ok("[]".into()),
+    );
+  }
+
+  #[test]
+  fn parse_witness_errors() {
+    assert_eq!(
+      parse_witness(&[0xff]),
+      Err(ParseWitnessError::InvalidWitness)
+    );
+
+    assert_eq!(
+      parse_witness(&consensus::serialize(&envelope(&[
+        b"ord",
+        &[66],
+        b"cursed"
+      ])))
+      .unwrap_err()
+      .to_string(),
+      "unrecognized even field",
+    );
+  }
+}
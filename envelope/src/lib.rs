@@ -0,0 +1,302 @@
+//! The inscription envelope parser of ord's indexer, on its own and `no_std`,
+//! so that other programs parse envelopes exactly as the indexer does,
+//! including rejecting envelopes with unrecognized even tags, like the cursed
+//! tag 66.
+//!
+//! With the `ffi` feature, it has a C ABI, and with the `wasm` feature,
+//! WebAssembly bindings. Build them with:
+//!
+//! ```sh
+//! cargo rustc -p ord-envelope --release --features ffi --crate-type cdylib
+//! cargo rustc -p ord-envelope --release --features wasm --crate-type cdylib \
+//!   --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --out-dir pkg \
+//!   target/wasm32-unknown-unknown/release/ord_envelope.wasm
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use {
+  alloc::{collections::BTreeMap, vec::Vec},
+  bitcoin::{
+    blockdata::{
+      opcodes,
+      script::{self, Instruction, Instructions},
+    },
+    taproot::TAPROOT_ANNEX_PREFIX,
+    ScriptBuf, Witness,
+  },
+  core::{fmt, iter::Peekable},
+};
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub const PROTOCOL_ID: [u8; 3] = *b"ord";
+pub const BODY_TAG: [u8; 0] = [];
+pub const CONTENT_TYPE_TAG: [u8; 1] = [1];
+pub const CONTENT_ENCODING_TAG: [u8; 1] = [9];
+
+/// The fields of an envelope that ord knows about.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Envelope {
+  pub body: Option<Vec<u8>>,
+  pub content_type: Option<Vec<u8>>,
+  pub content_encoding: Option<Vec<u8>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+  EmptyWitness,
+  InvalidInscription,
+  KeyPathSpend,
+  NoInscription,
+  Script(script::Error),
+  UnrecognizedEvenField,
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::EmptyWitness => write!(f, "empty witness"),
+      Self::InvalidInscription => write!(f, "invalid inscription"),
+      Self::KeyPathSpend => write!(f, "witness is a key path spend"),
+      Self::NoInscription => write!(f, "no inscription"),
+      Self::Script(err) => write!(f, "invalid script: {err}"),
+      Self::UnrecognizedEvenField => write!(f, "unrecognized even field"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// Parse the envelopes in the tapscript of `witness`. If any envelope is
+/// invalid, the whole witness is.
+pub fn parse(witness: &Witness) -> Result<Vec<Envelope>, Error> {
+  if witness.is_empty() {
+    return Err(Error::EmptyWitness);
+  }
+
+  if witness.len() == 1 {
+    return Err(Error::KeyPathSpend);
+  }
+
+  let annex = witness
+    .last()
+    .and_then(|element| element.first().map(|byte| *byte == TAPROOT_ANNEX_PREFIX))
+    .unwrap_or(false);
+
+  if witness.len() == 2 && annex {
+    return Err(Error::KeyPathSpend);
+  }
+
+  let script = witness
+    .iter()
+    .nth(if annex {
+      witness.len() - 1
+    } else {
+      witness.len() - 2
+    })
+    .unwrap();
+
+  Parser {
+    instructions: ScriptBuf::from(Vec::from(script)).instructions().peekable(),
+  }
+  .parse_envelopes()
+  .into_iter()
+  .collect()
+}
+
+#[derive(Debug)]
+struct Parser<'a> {
+  instructions: Peekable<Instructions<'a>>,
+}
+
+impl<'a> Parser<'a> {
+  fn parse_envelopes(&mut self) -> Vec<Result<Envelope, Error>> {
+    let mut envelopes = Vec::new();
+    loop {
+      let current = self.parse_one_envelope();
+      if current == Err(Error::NoInscription) {
+        break;
+      }
+      envelopes.push(current);
+    }
+
+    envelopes
+  }
+
+  fn parse_one_envelope(&mut self) -> Result<Envelope, Error> {
+    self.advance_into_envelope()?;
+    let mut fields = BTreeMap::new();
+
+    loop {
+      match self.advance()? {
+        Instruction::PushBytes(tag) if tag.as_bytes() == BODY_TAG.as_slice() => {
+          let mut body = Vec::new();
+          while !self.accept(&Instruction::Op(opcodes::all::OP_ENDIF))? {
+            body.extend_from_slice(self.expect_push()?);
+          }
+          fields.insert(BODY_TAG.as_slice(), body);
+          break;
+        }
+        Instruction::PushBytes(tag) => {
+          if fields.contains_key(tag.as_bytes()) {
+            return Err(Error::InvalidInscription);
+          }
+          fields.insert(tag.as_bytes(), self.expect_push()?.to_vec());
+        }
+        Instruction::Op(opcodes::all::OP_ENDIF) => break,
+        _ => return Err(Error::InvalidInscription),
+      }
+    }
+
+    let body = fields.remove(BODY_TAG.as_slice());
+    let content_type = fields.remove(CONTENT_TYPE_TAG.as_slice());
+    let content_encoding = fields.remove(CONTENT_ENCODING_TAG.as_slice());
+
+    for tag in fields.keys() {
+      if let Some(lsb) = tag.first() {
+        if lsb % 2 == 0 {
+          return Err(Error::UnrecognizedEvenField);
+        }
+      }
+    }
+
+    Ok(Envelope {
+      body,
+      content_type,
+      content_encoding,
+    })
+  }
+
+  fn advance(&mut self) -> Result<Instruction<'a>, Error> {
+    self
+      .instructions
+      .next()
+      .ok_or(Error::NoInscription)?
+      .map_err(Error::Script)
+  }
+
+  fn advance_into_envelope(&mut self) -> Result<(), Error> {
+    loop {
+      if self.match_instructions(&[
+        Instruction::PushBytes((&[]).into()), // represents an OF_FALSE
+        Instruction::Op(opcodes::all::OP_IF),
+        Instruction::PushBytes((&PROTOCOL_ID).into()),
+      ])? {
+        break;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn match_instructions(&mut self, instructions: &[Instruction]) -> Result<bool, Error> {
+    for instruction in instructions {
+      if &self.advance()? != instruction {
+        return Ok(false);
+      }
+    }
+
+    Ok(true)
+  }
+
+  fn expect_push(&mut self) -> Result<&'a [u8], Error> {
+    match self.advance()? {
+      Instruction::PushBytes(bytes) => Ok(bytes.as_bytes()),
+      _ => Err(Error::InvalidInscription),
+    }
+  }
+
+  fn accept(&mut self, instruction: &Instruction) -> Result<bool, Error> {
+    match self.instructions.peek() {
+      Some(Ok(next)) => {
+        if next == instruction {
+          self.advance()?;
+          Ok(true)
+        } else {
+          Ok(false)
+        }
+      }
+      Some(Err(err)) => Err(Error::Script(*err)),
+      None => Ok(false),
+    }
+  }
+}
+
+#[cfg(test)]
+pub(crate) fn envelope(payload: &[&[u8]]) -> Witness {
+  let mut builder = script::Builder::new()
+    .push_opcode(opcodes::OP_FALSE)
+    .push_opcode(opcodes::all::OP_IF);
+
+  for data in payload {
+    let mut buf = script::PushBytesBuf::new();
+    buf.extend_from_slice(data).unwrap();
+    builder = builder.push_slice(buf);
+  }
+
+  let script = builder.push_opcode(opcodes::all::OP_ENDIF).into_script();
+
+  Witness::from_slice(&[script.into_bytes(), Vec::new()])
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, alloc::vec};
+
+  #[test]
+  fn parse_envelope() {
+    assert_eq!(
+      parse(&envelope(&[
+        b"ord",
+        &[1],
+        b"text/plain",
+        &[9],
+        b"br",
+        &[],
+        b"ord"
+      ])),
+      Ok(vec![Envelope {
+        body: Some(b"ord".to_vec()),
+        content_type: Some(b"text/plain".to_vec()),
+        content_encoding: Some(b"br".to_vec()),
+      }]),
+    );
+  }
+
+  #[test]
+  fn unrecognized_even_fields_are_invalid() {
+    assert_eq!(
+      parse(&envelope(&[b"ord", &[66], b"cursed", &[], b"ord"])),
+      Err(Error::UnrecognizedEvenField),
+    );
+  }
+
+  #[test]
+  fn unrecognized_odd_fields_are_ignored() {
+    assert_eq!(
+      parse(&envelope(&[b"ord", &[3], b"foo", &[], b"ord"])),
+      Ok(vec![Envelope {
+        body: Some(b"ord".to_vec()),
+        ..Default::default()
+      }]),
+    );
+  }
+
+  #[test]
+  fn key_path_spends_have_no_envelopes() {
+    assert_eq!(
+      parse(&Witness::from_slice(&[Vec::new()])),
+      Err(Error::KeyPathSpend),
+    );
+  }
+}
@@ -0,0 +1,12 @@
+//! WebAssembly bindings for the envelope parser.
+
+use wasm_bindgen::prelude::*;
+
+/// Parse the inscriptions in a consensus-serialized witness into a JSON array
+/// of `{"content_type", "body"}` objects, with bodies hex encoded. Throws if
+/// the witness is invalid, including if an envelope has an unrecognized even
+/// tag, such as the cursed tag 66, just like in the indexer.
+#[wasm_bindgen(js_name = parseWitness)]
+pub fn parse_witness(witness: &[u8]) -> Result<String, JsError> {
+  super::json::parse_witness(witness).map_err(|err| JsError::new(&err.to_string()))
+}
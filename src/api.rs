@@ -0,0 +1,127 @@
+//! Library interface for embedding ord's index, inscription parser, and
+//! transaction builder in other programs, enabled with the `api` feature.
+
+use super::*;
+
+pub use crate::{
+  chain::Chain,
+  fee_rate::FeeRate,
+  inscription::Inscription,
+  inscription_id::InscriptionId,
+  sat_point::SatPoint,
+  subcommand::wallet::transaction_builder::{
+    CoinSelection, DustPolicy, Error as TransactionBuilderError, Satisfaction, TransactionBuilder,
+  },
+};
+
+/// Where to find Bitcoin Core and the index. Unset fields, and all other
+/// options, have the same defaults as the corresponding `ord` command line
+/// options.
+#[derive(Clone, Debug, Default)]
+pub struct IndexSettings {
+  pub chain: Chain,
+  pub bitcoin_data_dir: Option<PathBuf>,
+  pub bitcoin_rpc_pass: Option<String>,
+  pub bitcoin_rpc_user: Option<String>,
+  pub cookie_file: Option<PathBuf>,
+  pub data_dir: Option<PathBuf>,
+  pub index: Option<PathBuf>,
+  pub index_sats: bool,
+  pub rpc_url: Option<String>,
+}
+
+pub struct Index(crate::index::Index);
+
+impl Index {
+  pub fn open(settings: IndexSettings) -> Result<Self> {
+    // `Options::default` doesn't apply clap's defaults, like the commit interval
+    let defaults = Options::try_parse_from(["ord"])?;
+
+    Ok(Self(crate::index::Index::open(&Options {
+      chain_argument: settings.chain,
+      bitcoin_data_dir: settings.bitcoin_data_dir,
+      bitcoin_rpc_pass: settings.bitcoin_rpc_pass,
+      bitcoin_rpc_user: settings.bitcoin_rpc_user,
+      cookie_file: settings.cookie_file,
+      data_dir: settings.data_dir,
+      index: settings.index,
+      index_sats: settings.index_sats,
+      rpc_url: settings.rpc_url,
+      ..defaults
+    })?))
+  }
+
+  /// Index blocks up to the current Bitcoin Core tip.
+  pub fn update(&self) -> Result {
+    self.0.update()
+  }
+
+  pub fn block_count(&self) -> Result<u64> {
+    self.0.block_count()
+  }
+
+  pub fn inscription(&self, inscription_id: InscriptionId) -> Result<Option<Inscription>> {
+    self.0.get_inscription_by_id(inscription_id)
+  }
+
+  pub fn inscription_genesis(
+    &self,
+    inscription_id: InscriptionId,
+  ) -> Result<Option<InscriptionGenesis>> {
+    let Some(entry) = self.0.get_inscription_entry(inscription_id)? else {
+      return Ok(None);
+    };
+
+    Ok(Some(InscriptionGenesis {
+      fee: entry.fee,
+      height: entry.height,
+      vsize: self.0.get_inscription_genesis_vsize(inscription_id)?,
+    }))
+  }
+
+  pub fn inscription_satpoint(&self, inscription_id: InscriptionId) -> Result<Option<SatPoint>> {
+    self.0.get_inscription_satpoint_by_id(inscription_id)
+  }
+
+  pub fn inscriptions_on_output(&self, outpoint: OutPoint) -> Result<Vec<InscriptionId>> {
+    self.0.get_inscriptions_on_output(outpoint)
+  }
+
+  pub fn inscriptions_on_outputs(
+    &self,
+    utxos: BTreeMap<OutPoint, Amount>,
+  ) -> Result<BTreeMap<SatPoint, InscriptionId>> {
+    self.0.get_inscriptions(utxos)
+  }
+}
+
+/// How an inscription was revealed. `fee` is the reveal transaction's fee
+/// split evenly between the inscriptions it revealed, and `vsize` is the
+/// whole transaction's, `None` for inscriptions indexed before it was
+/// recorded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InscriptionGenesis {
+  pub fee: u64,
+  pub height: u64,
+  pub vsize: Option<u64>,
+}
+
+/// An inscription revealed in a transaction, by input index and position in
+/// that input's witness.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedInscription {
+  pub input: u32,
+  pub offset: u32,
+  pub inscription: Inscription,
+}
+
+pub fn parse_inscriptions(tx: &Transaction) -> Vec<ParsedInscription> {
+  Inscription::from_transaction(tx)
+    .into_iter()
+    .map(|transaction_inscription| ParsedInscription {
+      input: transaction_inscription.tx_in_index,
+      offset: transaction_inscription.tx_in_offset,
+      inscription: transaction_inscription.inscription,
+    })
+    .collect()
+}
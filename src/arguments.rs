@@ -11,6 +11,19 @@ pub(crate) struct Arguments {
 
 impl Arguments {
   pub(crate) fn run(self) -> Result {
+    if self.options.index_snapshot
+      && (!self.subcommand.uses_shared_index() || matches!(self.subcommand, Subcommand::Wallet(_)))
+    {
+      bail!("--index-snapshot can only be used with commands that only query the index");
+    }
+
+    #[cfg(unix)]
+    if self.subcommand.uses_shared_index()
+      && subcommand::indexd::proxy(&self.options, &self.subcommand)?
+    {
+      return Ok(());
+    }
+
     self.subcommand.run(self.options)
   }
 }
@@ -8,6 +8,7 @@ pub enum Chain {
   Mainnet,
   #[clap(alias("test"))]
   Testnet,
+  Testnet4,
   Signet,
   Regtest,
 }
@@ -16,7 +17,8 @@ impl Chain {
   pub(crate) fn network(self) -> Network {
     match self {
       Self::Mainnet => Network::Bitcoin,
-      Self::Testnet => Network::Testnet,
+      // testnet4 uses testnet3's address prefixes
+      Self::Testnet | Self::Testnet4 => Network::Testnet,
       Self::Signet => Network::Signet,
       Self::Regtest => Network::Regtest,
     }
@@ -28,27 +30,86 @@ impl Chain {
       Self::Regtest => 18443,
       Self::Signet => 38332,
       Self::Testnet => 18332,
+      Self::Testnet4 => 48332,
     }
   }
 
   pub(crate) fn inscription_content_size_limit(self) -> Option<usize> {
     match self {
       Self::Mainnet | Self::Regtest => None,
-      Self::Testnet | Self::Signet => Some(1024),
+      Self::Testnet | Self::Testnet4 | Self::Signet => Some(1024),
     }
   }
 
   pub(crate) fn first_inscription_height(self) -> u64 {
     match self {
       Self::Mainnet => 767430,
-      Self::Regtest => 0,
+      Self::Regtest | Self::Testnet4 => 0,
       Self::Signet => 112402,
       Self::Testnet => 2413343,
     }
   }
 
+  /// Base URL of the explorer that command output links to.
+  pub(crate) fn explorer_url(self) -> &'static str {
+    match self {
+      Self::Mainnet => "https://ordinals.com",
+      Self::Regtest => "http://localhost",
+      Self::Signet => "https://signet.ordinals.com",
+      Self::Testnet => "https://testnet.ordinals.com",
+      Self::Testnet4 => "https://testnet4.ordinals.com",
+    }
+  }
+
   pub(crate) fn genesis_block(self) -> Block {
-    bitcoin::blockdata::constants::genesis_block(self.network())
+    match self {
+      Self::Testnet4 => Self::testnet4_genesis_block(),
+      _ => bitcoin::blockdata::constants::genesis_block(self.network()),
+    }
+  }
+
+  /// rust-bitcoin doesn't know about testnet4, so its genesis block is built
+  /// here, as in Bitcoin Core's `CreateGenesisBlock`.
+  fn testnet4_genesis_block() -> Block {
+    const MESSAGE: &[u8] =
+      b"03/May/2024 000000000000000000001ebd58c244970b3aa9d783bb001011fbe8ea8e98e00e";
+
+    // <486604799> <4> OP_PUSHDATA1 <MESSAGE>
+    let mut script_sig = vec![0x04, 0xff, 0xff, 0x00, 0x1d, 0x01, 0x04, 0x4c];
+    script_sig.push(MESSAGE.len().try_into().unwrap());
+    script_sig.extend_from_slice(MESSAGE);
+
+    // <33 zero bytes> OP_CHECKSIG
+    let mut script_pubkey = vec![0x21];
+    script_pubkey.extend_from_slice(&[0; 33]);
+    script_pubkey.push(0xac);
+
+    let coinbase = Transaction {
+      version: 1,
+      lock_time: bitcoin::locktime::absolute::LockTime::ZERO,
+      input: vec![TxIn {
+        previous_output: OutPoint::null(),
+        script_sig: ScriptBuf::from_bytes(script_sig),
+        sequence: Sequence::MAX,
+        witness: bitcoin::Witness::new(),
+      }],
+      output: vec![TxOut {
+        value: 50 * COIN_VALUE,
+        script_pubkey: ScriptBuf::from_bytes(script_pubkey),
+      }],
+    };
+
+    Block {
+      header: bitcoin::block::Header {
+        version: bitcoin::block::Version::ONE,
+        prev_blockhash: BlockHash::all_zeros(),
+        merkle_root: bitcoin::TxMerkleNode::from_byte_array(coinbase.txid().to_byte_array()),
+        time: 1714777860,
+        bits: bitcoin::CompactTarget::from_consensus(0x1d00ffff),
+        nonce: 393743547,
+      },
+      txdata: vec![coinbase],
+    }
   }
 
   pub(crate) fn address_from_script(
@@ -62,6 +123,7 @@ impl Chain {
     match self {
       Self::Mainnet => data_dir.to_owned(),
       Self::Testnet => data_dir.join("testnet3"),
+      Self::Testnet4 => data_dir.join("testnet4"),
       Self::Signet => data_dir.join("signet"),
       Self::Regtest => data_dir.join("regtest"),
     }
@@ -78,7 +140,28 @@ impl Display for Chain {
         Self::Regtest => "regtest",
         Self::Signet => "signet",
         Self::Testnet => "testnet",
+        Self::Testnet4 => "testnet4",
       }
     )
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn testnet4_genesis_block() {
+    let genesis = Chain::Testnet4.genesis_block();
+
+    assert_eq!(
+      genesis.header.merkle_root.to_string(),
+      "7aa0a7ae1e223414cb807e40cd57e667b718e42aaf9306db9102fe28912b7b4e"
+    );
+
+    assert_eq!(
+      genesis.block_hash().to_string(),
+      "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043"
+    );
+  }
+}
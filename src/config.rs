@@ -6,6 +6,9 @@ pub(crate) struct Config {
   pub(crate) hidden: HashSet<InscriptionId>,
   pub(crate) bitcoin_rpc_pass: Option<String>,
   pub(crate) bitcoin_rpc_user: Option<String>,
+  pub(crate) explorer_url: Option<String>,
+  pub(crate) max_content_size: Option<usize>,
+  pub(crate) proxy: Option<String>,
 }
 
 impl Config {
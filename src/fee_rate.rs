@@ -34,6 +34,39 @@ impl FeeRate {
   }
 }
 
+/// Fee rates for a series of transactions, from `<START>:<STEP>`. The last
+/// transaction pays `START` and each earlier one pays `STEP` more, so that
+/// earlier transactions confirm first.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct FeeLadder {
+  start: FeeRate,
+  step: FeeRate,
+}
+
+impl FromStr for FeeLadder {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (start, step) = s
+      .split_once(':')
+      .ok_or_else(|| anyhow!("fee ladder `{s}` is not of the form <START>:<STEP>"))?;
+
+    Ok(Self {
+      start: start.parse()?,
+      step: step.parse()?,
+    })
+  }
+}
+
+impl FeeLadder {
+  pub(crate) fn rates(self, n: usize) -> Vec<FeeRate> {
+    (0..n)
+      .rev()
+      .map(|steps| FeeRate(self.start.0 + self.step.0 * steps as f64))
+      .collect()
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -79,4 +112,14 @@ mod tests {
       Amount::from_sat(123456789)
     );
   }
+
+  #[test]
+  fn fee_ladder() {
+    assert_eq!(
+      "2:0.5".parse::<FeeLadder>().unwrap().rates(3),
+      [FeeRate(3.0), FeeRate(2.5), FeeRate(2.0)]
+    );
+    assert!("2".parse::<FeeLadder>().is_err());
+    assert!("2:-1".parse::<FeeLadder>().is_err());
+  }
 }
@@ -1,9 +1,9 @@
 use {
   self::{
     entry::{
-      outpoint_prefix_end, BlockHashValue, Entry, InscriptionEntry, InscriptionEntryValue,
-      InscriptionIdValue, OutPointPrefix, OutPointPrefixValue, OutPointValue, SatPointValue,
-      SatRange,
+      load_sat_ranges, outpoint_prefix_end, store_sat_ranges, BlockHashValue, Entry,
+      InscriptionEntry, InscriptionEntryValue, InscriptionIdValue, OutPointPrefix,
+      OutPointPrefixValue, OutPointValue, SatPointValue, SatRange, TxidValue,
     },
     index::block_index::BlockIndex,
     reorg::*,
@@ -17,11 +17,12 @@ use {
     Client,
   },
   chrono::SubsecRound,
+  clap::ValueEnum,
   indicatif::{ProgressBar, ProgressStyle},
   log::log_enabled,
   redb::{
-    CompactionError, Database, MultimapTable, MultimapTableDefinition, ReadableMultimapTable,
-    ReadableTable, Table, TableDefinition, WriteTransaction,
+    Database, MultimapTable, MultimapTableDefinition, ReadableMultimapTable, ReadableTable, Table,
+    TableDefinition, WriteTransaction,
   },
   std::collections::HashMap,
   std::io::{BufWriter, Read, Write},
@@ -30,11 +31,17 @@ use {
 pub mod block_index;
 mod entry;
 mod fetcher;
+mod migrations;
 mod reorg;
 mod rtx;
 mod updater;
+mod utxo_snapshot;
 
-const SCHEMA_VERSION: u64 = 5;
+const SCHEMA_VERSION: u64 = 8;
+
+/// Index opened by `ord daemon`, which the commands it runs use instead of
+/// opening their own.
+static SHARED_INDEX: Mutex<Option<Arc<Index>>> = Mutex::new(None);
 
 macro_rules! define_table {
   ($name:ident, $key:ty, $value:ty) => {
@@ -49,18 +56,25 @@ macro_rules! define_multimap_table {
   };
 }
 
+define_table! { COMMIT_TXID_TO_FAILED_REVEALS, &TxidValue, &str }
 define_table! { HEIGHT_TO_BLOCK_HASH, u64, &BlockHashValue }
 define_multimap_table! { HEIGHT_TO_INSCRIPTION_ID, u64, &InscriptionIdValue }
+define_multimap_table! { HEIGHT_TO_NEW_INSCRIPTION_ID, u64, &InscriptionIdValue }
+define_table! { INSCRIPTION_ID_TO_GENESIS_VSIZE, &InscriptionIdValue, u64 }
 define_table! { INSCRIPTION_ID_TO_INSCRIPTION_ENTRY, &InscriptionIdValue, InscriptionEntryValue }
 define_table! { INSCRIPTION_ID_TO_SATPOINT, &InscriptionIdValue, &SatPointValue }
 define_table! { INSCRIPTION_NUMBER_TO_INSCRIPTION_ID, i64, &InscriptionIdValue }
+define_table! { LABEL_TO_DESTINATION, &str, &str }
 define_table! { OUTPOINT_TO_SAT_RANGES, &OutPointValue, &[u8] }
 define_table! { SAT_TO_OUTPOINT, u64, &OutPointPrefixValue }
+define_table! { OUTPOINT_TO_SCRIPT_PUBKEY, &OutPointValue, (u64, &[u8]) }
 define_table! { OUTPOINT_TO_VALUE, &OutPointValue, u64}
 define_table! { REINSCRIPTION_ID_TO_SEQUENCE_NUMBER, &InscriptionIdValue, u64 }
 define_multimap_table! { SATPOINT_TO_INSCRIPTION_ID, &SatPointValue, &InscriptionIdValue }
 define_multimap_table! { SAT_TO_INSCRIPTION_ID, u64, &InscriptionIdValue }
 define_table! { SAT_TO_SATPOINT, u64, &SatPointValue }
+define_table! { SCHEMA_VERSION_TO_TIMESTAMP, u64, u128 }
+define_multimap_table! { SCRIPT_PUBKEY_TO_OUTPOINT, &[u8], &OutPointValue }
 define_table! { STATISTIC_TO_COUNT, u64, u64 }
 define_table! { WRITE_TRANSACTION_STARTING_BLOCK_COUNT_TO_TIMESTAMP, u64, u128 }
 
@@ -80,6 +94,26 @@ pub(crate) enum Statistic {
   SatRanges = 4,
   UnboundInscriptions = 5,
   LastSavePointHeight = 6,
+  FirstInscriptionHeight = 7,
+  CompressedSatRanges = 8,
+}
+
+#[derive(Default, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Durability {
+  Eventual,
+  #[default]
+  Immediate,
+  Paranoid,
+}
+
+impl From<Durability> for redb::Durability {
+  fn from(durability: Durability) -> Self {
+    match durability {
+      Durability::Eventual => Self::Eventual,
+      Durability::Immediate => Self::Immediate,
+      Durability::Paranoid => Self::Paranoid,
+    }
+  }
 }
 
 impl Statistic {
@@ -148,8 +182,18 @@ pub struct FindRangeOutput {
   pub satpoint: SatPoint,
 }
 
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct Compaction {
+  pub(crate) compacted: bool,
+  pub(crate) fragmented_bytes_after: u64,
+  pub(crate) fragmented_bytes_before: u64,
+  pub(crate) size_after: u64,
+  pub(crate) size_before: u64,
+}
+
 pub(crate) struct Index {
   client: Client,
+  compressed_sat_ranges: bool,
   database: Database,
   path: PathBuf,
   first_inscription_height: u64,
@@ -158,12 +202,39 @@ pub(crate) struct Index {
   height_limit: Option<u64>,
   no_progress_bar: bool,
   options: Options,
+  /// The temporary copy of a locked index opened with `--index-snapshot`.
+  snapshot: Option<TempDir>,
   unrecoverably_reorged: AtomicBool,
 }
 
+/// The process that has the index open, as recorded in `<INDEX>.holder.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IndexHolder {
+  pub(crate) pid: u32,
+  pub(crate) command: Vec<String>,
+  /// Unix timestamp at which the index was opened.
+  pub(crate) since: u64,
+}
+
 impl Index {
+  pub(crate) fn share(index: Arc<Self>) {
+    SHARED_INDEX.lock().unwrap().replace(index);
+  }
+
+  pub(crate) fn open_shared(options: &Options) -> Result<Arc<Self>> {
+    if let Some(index) = SHARED_INDEX.lock().unwrap().clone() {
+      return Ok(index);
+    }
+
+    Ok(Arc::new(Self::open(options)?))
+  }
+
   pub(crate) fn open(options: &Options) -> Result<Self> {
-    let client = options.bitcoin_rpc_client()?;
+    let client = if options.no_sync {
+      options.unchecked_bitcoin_rpc_client()?
+    } else {
+      options.bitcoin_rpc_client()?
+    };
 
     let path = if let Some(path) = &options.index {
       path.clone()
@@ -195,10 +266,39 @@ impl Index {
 
     log::info!("Setting DB cache size to {} bytes", db_cache_size);
 
-    let database = match Database::builder()
-      .set_cache_size(db_cache_size)
-      .open(&path)
-    {
+    let open = |path: &Path| Database::builder().set_cache_size(db_cache_size).open(path);
+
+    let mut snapshot = None;
+    let mut result = open(&path);
+    let start = Instant::now();
+
+    // redb locks the index file while it is open, so another ord process may
+    // be holding it, most likely while updating it
+    while let Err(redb::DatabaseError::DatabaseAlreadyOpen) = result {
+      if options.index_snapshot {
+        let tempdir = TempDir::new()?;
+        let copy = tempdir.path().join("index.redb");
+        Self::copy_snapshot(&path, &copy)?;
+        result = Ok(open(&copy)?);
+        snapshot = Some(tempdir);
+        break;
+      }
+
+      let timeout = Duration::from_secs(options.index_lock_timeout.unwrap_or_default());
+
+      if start.elapsed() >= timeout || SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        return Err(Self::locked_error(
+          &path,
+          options.index_lock_timeout.is_some(),
+        ));
+      }
+
+      thread::sleep(Duration::from_secs(1).min(timeout.saturating_sub(start.elapsed())));
+
+      result = open(&path);
+    }
+
+    let database = match result {
       Ok(database) => {
         let schema_version = database
           .begin_read()?
@@ -208,6 +308,8 @@ impl Index {
           .unwrap_or(0);
 
         match schema_version.cmp(&SCHEMA_VERSION) {
+          cmp::Ordering::Less if migrations::can_migrate(schema_version) =>
+            migrations::run(&database, &path, schema_version)?,
           cmp::Ordering::Less =>
             bail!(
               "index at `{}` appears to have been built with an older, incompatible version of ord, consider deleting and rebuilding the index: index schema {schema_version}, ord schema {SCHEMA_VERSION}",
@@ -235,6 +337,8 @@ impl Index {
 
         tx.open_table(HEIGHT_TO_BLOCK_HASH)?;
         tx.open_multimap_table(HEIGHT_TO_INSCRIPTION_ID)?;
+        tx.open_multimap_table(HEIGHT_TO_NEW_INSCRIPTION_ID)?;
+        tx.open_table(INSCRIPTION_ID_TO_GENESIS_VSIZE)?;
         tx.open_table(INSCRIPTION_ID_TO_INSCRIPTION_ENTRY)?;
         tx.open_table(INSCRIPTION_ID_TO_SATPOINT)?;
         tx.open_table(INSCRIPTION_NUMBER_TO_INSCRIPTION_ID)?;
@@ -245,41 +349,181 @@ impl Index {
         tx.open_table(SAT_TO_SATPOINT)?;
         tx.open_table(WRITE_TRANSACTION_STARTING_BLOCK_COUNT_TO_TIMESTAMP)?;
 
-        tx.open_table(STATISTIC_TO_COUNT)?
-          .insert(&Statistic::Schema.key(), &SCHEMA_VERSION)?;
+        migrations::record(&tx, SCHEMA_VERSION)?;
+
+        let mut statistic_to_count = tx.open_table(STATISTIC_TO_COUNT)?;
+
+        statistic_to_count.insert(
+          &Statistic::FirstInscriptionHeight.key(),
+          &options.first_inscription_height(),
+        )?;
+
+        drop(statistic_to_count);
 
         if options.index_sats || options.index_utxos {
           tx.open_table(OUTPOINT_TO_SAT_RANGES)?
             .insert(&OutPoint::null().store(), [].as_slice())?;
+          if options.compress_sat_ranges {
+            tx.open_table(STATISTIC_TO_COUNT)?
+              .insert(&Statistic::CompressedSatRanges.key(), &1)?;
+          }
           if options.index_utxos {
             tx.open_table(SAT_TO_OUTPOINT)?
               .insert(0, &OutPoint::null().store().store())?;
           }
         }
 
+        if options.index_addresses {
+          tx.open_table(OUTPOINT_TO_SCRIPT_PUBKEY)?;
+          tx.open_multimap_table(SCRIPT_PUBKEY_TO_OUTPOINT)?;
+        }
+
         tx.commit()?;
 
         database
       }
     };
 
+    if snapshot.is_none() {
+      Self::write_holder(&path);
+    }
+
+    // indexes remember the first inscription height they were created with,
+    // since blocks below it were never scanned for inscriptions
+    let first_inscription_height = match database
+      .begin_read()?
+      .open_table(STATISTIC_TO_COUNT)?
+      .get(&Statistic::FirstInscriptionHeight.key())?
+      .map(|x| x.value())
+    {
+      Some(first_inscription_height) => {
+        if options.first_inscription_height.is_some()
+          && options.first_inscription_height() != first_inscription_height
+        {
+          bail!(
+            "index at `{}` was created with --first-inscription-height {first_inscription_height}, delete it to rebuild it with --first-inscription-height {}",
+            path.display(),
+            options.first_inscription_height(),
+          );
+        }
+        first_inscription_height
+      }
+      None => options.first_inscription_height(),
+    };
+
+    let compressed_sat_ranges = database
+      .begin_read()?
+      .open_table(STATISTIC_TO_COUNT)?
+      .get(&Statistic::CompressedSatRanges.key())?
+      .map(|x| x.value())
+      .unwrap_or(0)
+      != 0;
+
+    if options.compress_sat_ranges && !compressed_sat_ranges {
+      bail!(
+        "index at `{}` was created without --compress-sat-ranges, delete it to rebuild it with compressed sat ranges",
+        path.display()
+      );
+    }
+
     let genesis_block_coinbase_transaction =
       options.chain().genesis_block().coinbase().unwrap().clone();
 
     Ok(Self {
       genesis_block_coinbase_txid: genesis_block_coinbase_transaction.txid(),
       client,
+      compressed_sat_ranges,
       database,
       path,
-      first_inscription_height: options.first_inscription_height(),
+      first_inscription_height,
       genesis_block_coinbase_transaction,
       height_limit: options.height_limit,
       no_progress_bar: options.no_progress_bar,
       options: options.clone(),
+      snapshot,
       unrecoverably_reorged: AtomicBool::new(false),
     })
   }
 
+  fn holder_path(path: &Path) -> PathBuf {
+    let mut holder = path.as_os_str().to_owned();
+    holder.push(".holder.json");
+    holder.into()
+  }
+
+  /// Record which process holds the index next to it, for the error other
+  /// processes get when they try to open it. A stale record does no harm,
+  /// since it is only read while the index is locked, and so was written by
+  /// the process that locked it.
+  fn write_holder(path: &Path) {
+    let holder = IndexHolder {
+      pid: process::id(),
+      command: env::args().collect(),
+      since: SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default(),
+    };
+
+    if let Err(err) = fs::write(
+      Self::holder_path(path),
+      serde_json::to_string(&holder).unwrap(),
+    ) {
+      log::warn!("failed to record index holder: {err}");
+    }
+  }
+
+  /// Copy the index at `path`, which another process has open, to `copy`.
+  /// redb has no way to read a consistent view of an index another process
+  /// holds, so the copy is only consistent if the index doesn't change while
+  /// it's copied. If the index file was modified during the copy, the copy is
+  /// retried, and an error is returned if it keeps changing.
+  fn copy_snapshot(path: &Path, copy: &Path) -> Result {
+    const ATTEMPTS: usize = 3;
+
+    let version = |path: &Path| -> Result<(u64, SystemTime)> {
+      let metadata = fs::metadata(path)?;
+      Ok((metadata.len(), metadata.modified()?))
+    };
+
+    for _ in 0..ATTEMPTS {
+      let before = version(path)?;
+
+      fs::copy(path, copy)
+        .with_context(|| format!("failed to copy index `{}` to snapshot", path.display()))?;
+
+      if version(path)? == before {
+        return Ok(());
+      }
+    }
+
+    bail!(
+      "index `{}` changed while being copied to a snapshot {ATTEMPTS} times, stop the ord process that is updating it and try again",
+      path.display()
+    )
+  }
+
+  fn locked_error(path: &Path, waited: bool) -> Error {
+    let holder = fs::read_to_string(Self::holder_path(path))
+      .ok()
+      .and_then(|holder| serde_json::from_str::<IndexHolder>(&holder).ok());
+
+    let holder = match holder {
+      Some(holder) => serde_json::to_string(&holder).unwrap(),
+      None => "null".into(),
+    };
+
+    anyhow!(
+      "index at `{}` is locked by another ord process: {{\"holder\":{holder}}}. {}, or pass --index-snapshot to query a copy of the index as it is now",
+      path.display(),
+      if waited {
+        "Timed out waiting for it, wait longer with a larger --index-lock-timeout"
+      } else {
+        "Wait for it with --index-lock-timeout <SECONDS>"
+      }
+    )
+  }
+
   pub(crate) fn get_unspent_outputs(&self, _wallet: Wallet) -> Result<BTreeMap<OutPoint, Amount>> {
     let mut utxos = BTreeMap::new();
     utxos.extend(
@@ -358,6 +602,14 @@ impl Index {
     }
   }
 
+  pub(crate) fn has_address_index(&self) -> Result<bool> {
+    match self.begin_read()?.0.open_table(OUTPOINT_TO_SCRIPT_PUBKEY) {
+      Ok(_) => Ok(true),
+      Err(redb::TableError::TableDoesNotExist(_)) => Ok(false),
+      Err(err) => Err(err.into()),
+    }
+  }
+
   fn require_sat_index(&self, feature: &str) -> Result {
     if !self.has_sat_index()? {
       bail!("{feature} requires index created with `--index-sats` flag")
@@ -366,6 +618,14 @@ impl Index {
     Ok(())
   }
 
+  fn require_address_index(&self, feature: &str) -> Result {
+    if !self.has_address_index()? {
+      bail!("{feature} requires index created with `--index-addresses` flag")
+    }
+
+    Ok(())
+  }
+
   pub(crate) fn info(&self) -> Result<Info> {
     let wtx = self.begin_write()?;
 
@@ -420,6 +680,10 @@ impl Index {
   }
 
   pub(crate) fn update(&self) -> Result {
+    if self.options.no_sync || self.snapshot.is_some() {
+      return Ok(());
+    }
+
     let mut updater = Updater::new(self)?;
 
     loop {
@@ -653,6 +917,53 @@ impl Index {
     Ok(ret)
   }
 
+  /// The heights that each of `inscription_ids` was transferred at, oldest
+  /// first, as far back as the transfer log goes.
+  pub(crate) fn get_transfer_heights(
+    &self,
+    inscription_ids: &HashSet<InscriptionId>,
+  ) -> Result<HashMap<InscriptionId, Vec<u64>>> {
+    let mut heights = HashMap::<InscriptionId, Vec<u64>>::new();
+
+    for result in self
+      .database
+      .begin_read()?
+      .open_multimap_table(HEIGHT_TO_INSCRIPTION_ID)?
+      .iter()?
+    {
+      let (height, ids) = result?;
+
+      for id in ids {
+        let id = InscriptionId::load(*id?.value());
+
+        if inscription_ids.contains(&id) {
+          heights.entry(id).or_default().push(height.value());
+        }
+      }
+    }
+
+    Ok(heights)
+  }
+
+  /// Inscriptions created in the block at `height`, as opposed to those
+  /// transferred in it, which are in the transfer log.
+  pub(crate) fn get_new_inscription_ids_by_height(
+    &self,
+    height: u64,
+  ) -> Result<Vec<InscriptionId>> {
+    let mut ret = Vec::new();
+    for inscription_id in self
+      .database
+      .begin_read()?
+      .open_multimap_table(HEIGHT_TO_NEW_INSCRIPTION_ID)?
+      .get(height)?
+    {
+      ret.push(Entry::load(*inscription_id?.value()));
+    }
+
+    Ok(ret)
+  }
+
   pub(crate) fn get_inscription_ids_by_sat(&self, sat: Sat) -> Result<Vec<InscriptionId>> {
     let rtx = &self.database.begin_read()?;
 
@@ -752,6 +1063,37 @@ impl Index {
     )
   }
 
+  /// Inscriptions on sats that were spent as fees but not claimed by the
+  /// coinbase. They are at the null outpoint, at an offset that counts every
+  /// sat lost before theirs.
+  pub(crate) fn get_lost_inscriptions(&self) -> Result<Vec<(SatPoint, InscriptionId)>> {
+    self.get_inscriptions_on_output_with_satpoints(OutPoint::null())
+  }
+
+  /// The unspent outputs paying to `script_pubkey`, with their values.
+  pub(crate) fn get_address_outputs(&self, script_pubkey: &Script) -> Result<Vec<(OutPoint, u64)>> {
+    self.require_address_index("address")?;
+
+    let rtx = self.database.begin_read()?;
+    let script_pubkey_to_outpoint = rtx.open_multimap_table(SCRIPT_PUBKEY_TO_OUTPOINT)?;
+    let outpoint_to_script_pubkey = rtx.open_table(OUTPOINT_TO_SCRIPT_PUBKEY)?;
+
+    let mut outputs = Vec::new();
+
+    for outpoint in script_pubkey_to_outpoint.get(script_pubkey.as_bytes())? {
+      let outpoint = outpoint?;
+
+      let value = outpoint_to_script_pubkey
+        .get(outpoint.value())?
+        .map(|entry| entry.value().0)
+        .unwrap_or_default();
+
+      outputs.push((OutPoint::load(*outpoint.value()), value));
+    }
+
+    Ok(outputs)
+  }
+
   pub(crate) fn get_outpoint_value(&self, outpoint: &OutPoint) -> Result<u64> {
     Ok(self.get_transaction(outpoint.txid)?.unwrap().output[outpoint.vout as usize].value)
   }
@@ -856,7 +1198,8 @@ impl Index {
             );
 
             // loop through the ranges in the output
-            for chunk in value.value().chunks_exact(11) {
+            for chunk in load_sat_ranges(value.value(), self.compressed_sat_ranges).chunks_exact(11)
+            {
               let (start, end) = SatRange::load(chunk.try_into().unwrap());
 
               tprintln!("| | | found range in output: {} - {}", start, end);
@@ -939,7 +1282,7 @@ impl Index {
         for range in outpoint_to_sat_ranges.range::<&[u8; 36]>(&[0; 36]..)? {
           let (key, value) = range?;
           let mut offset = 0;
-          for chunk in value.value().chunks_exact(11) {
+          for chunk in load_sat_ranges(value.value(), self.compressed_sat_ranges).chunks_exact(11) {
             let (start, end) = SatRange::load(chunk.try_into().unwrap());
             if start < search_end && search_start < end {
               let overlap_start = cmp::max(start, search_start);
@@ -1001,6 +1344,125 @@ impl Index {
     Ok(Some(result))
   }
 
+  /// Where `sat` was once block `height` had been mined, or `None` if it
+  /// hadn't been mined yet. The sat is followed back from where it is now
+  /// through the transactions that moved it, which are fetched from Bitcoin
+  /// Core, until one mined at or before `height` is reached.
+  pub(crate) fn find_sat_at_height(&self, sat: Sat, height: u64) -> Result<Option<SatPoint>> {
+    self.require_sat_index("find")?;
+
+    if height < sat.height().n() {
+      return Ok(None);
+    }
+
+    let mut satpoint = self
+      .find(sat, sat + 1, &Vec::new(), false)?
+      .and_then(|ranges| ranges.into_iter().next())
+      .map(|range| range.satpoint)
+      .ok_or_else(|| anyhow!("sat has not been mined as of index height"))?;
+
+    loop {
+      let txid = satpoint.outpoint.txid;
+
+      if txid == self.genesis_block_coinbase_txid {
+        return Ok(Some(satpoint));
+      }
+
+      if satpoint.outpoint == OutPoint::null() {
+        bail!("sat {sat} was lost, so where it was can't be followed");
+      }
+
+      let blockhash = self
+        .get_transaction_blockhash(txid)?
+        .ok_or_else(|| anyhow!("block of transaction {txid} not found"))?;
+
+      let tx_height = u64::try_from(self.get_block_height(blockhash)?).unwrap();
+
+      if tx_height <= height {
+        return Ok(Some(satpoint));
+      }
+
+      let tx = self
+        .get_transaction(txid)?
+        .ok_or_else(|| anyhow!("transaction {txid} not found"))?;
+
+      // offset of the sat among all of the transaction's output sats
+      let mut offset = tx.output[..usize::try_from(satpoint.outpoint.vout).unwrap()]
+        .iter()
+        .map(|output| output.value)
+        .sum::<u64>()
+        + satpoint.offset;
+
+      if !tx.is_coin_base() {
+        satpoint = self.input_satpoint(&tx, offset)?;
+        continue;
+      }
+
+      // the sats of a coinbase after the subsidy are fees, paid by the
+      // block's other transactions in order
+      let block = self
+        .get_block_by_hash(blockhash)?
+        .ok_or_else(|| anyhow!("block {blockhash} not found"))?;
+
+      offset = offset
+        .checked_sub(Height(tx_height).subsidy())
+        .ok_or_else(|| anyhow!("sat {sat} was mined after height {height}"))?;
+
+      let mut payer = None;
+
+      for tx in block.txdata.iter().skip(1) {
+        let output_value = tx.output.iter().map(|output| output.value).sum::<u64>();
+        let fee = self.input_value(tx)? - output_value;
+
+        if offset < fee {
+          payer = Some((tx, output_value + offset));
+          break;
+        }
+
+        offset -= fee;
+      }
+
+      let (tx, offset) = payer.ok_or_else(|| anyhow!("fee paying sat {sat} not found"))?;
+
+      satpoint = self.input_satpoint(tx, offset)?;
+    }
+  }
+
+  /// The satpoint of the sat at `offset` among the sats of `tx`'s inputs.
+  fn input_satpoint(&self, tx: &Transaction, mut offset: u64) -> Result<SatPoint> {
+    for input in &tx.input {
+      let value = self.prevout_value(input.previous_output)?;
+
+      if offset < value {
+        return Ok(SatPoint {
+          outpoint: input.previous_output,
+          offset,
+        });
+      }
+
+      offset -= value;
+    }
+
+    bail!("transaction {} has too few input sats", tx.txid())
+  }
+
+  fn input_value(&self, tx: &Transaction) -> Result<u64> {
+    tx.input
+      .iter()
+      .map(|input| self.prevout_value(input.previous_output))
+      .sum()
+  }
+
+  fn prevout_value(&self, outpoint: OutPoint) -> Result<u64> {
+    Ok(
+      self
+        .get_transaction(outpoint.txid)?
+        .and_then(|tx| tx.output.into_iter().nth(outpoint.vout.try_into().unwrap()))
+        .ok_or_else(|| anyhow!("output {outpoint} not found"))?
+        .value,
+    )
+  }
+
   fn list_inner(&self, outpoint: OutPointValue) -> Result<Option<Vec<u8>>> {
     Ok(
       self
@@ -1008,7 +1470,7 @@ impl Index {
         .begin_read()?
         .open_table(OUTPOINT_TO_SAT_RANGES)?
         .get(&outpoint)?
-        .map(|outpoint| outpoint.value().to_vec()),
+        .map(|outpoint| load_sat_ranges(outpoint.value(), self.compressed_sat_ranges).into_owned()),
     )
   }
 
@@ -1250,6 +1712,53 @@ impl Index {
     block_index.get_inscriptions_in_block(self, block_height)
   }
 
+  /// The numbers and ids of the inscriptions revealed at each of `heights`,
+  /// in inscription number order. Scans every inscription entry, so that no
+  /// block index is needed.
+  pub(crate) fn get_inscriptions_at_heights(
+    &self,
+    heights: &BTreeSet<u64>,
+  ) -> Result<BTreeMap<u64, Vec<(i64, InscriptionId)>>> {
+    let mut inscriptions = heights
+      .iter()
+      .map(|height| (*height, Vec::new()))
+      .collect::<BTreeMap<u64, Vec<(i64, InscriptionId)>>>();
+
+    self.for_each_inscription_entry(|id, entry| {
+      if let Some(at_height) = inscriptions.get_mut(&entry.height) {
+        at_height.push((entry.number, id));
+      }
+      Ok(())
+    })?;
+
+    for at_height in inscriptions.values_mut() {
+      at_height.sort_by_key(|(number, _id)| *number);
+    }
+
+    Ok(inscriptions)
+  }
+
+  /// Call `f` with the id and entry of every inscription, in id order.
+  pub(crate) fn for_each_inscription_entry(
+    &self,
+    mut f: impl FnMut(InscriptionId, InscriptionEntry) -> Result,
+  ) -> Result {
+    for result in self
+      .database
+      .begin_read()?
+      .open_table(INSCRIPTION_ID_TO_INSCRIPTION_ENTRY)?
+      .iter()?
+    {
+      let (id, entry) = result?;
+      f(
+        InscriptionId::load(*id.value()),
+        InscriptionEntry::load(entry.value()),
+      )?;
+    }
+
+    Ok(())
+  }
+
   pub(crate) fn get_feed_inscriptions(&self, n: usize) -> Result<Vec<(i64, InscriptionId)>> {
     Ok(
       self
@@ -1278,8 +1787,225 @@ impl Index {
     )
   }
 
-  pub(crate) fn compact_db(&mut self) -> Result<bool, CompactionError> {
-    self.database.compact()
+  /// The vsize of the transaction that revealed the inscription, shared by
+  /// every inscription it revealed. `None` for inscriptions indexed before
+  /// schema 8, which didn't record it.
+  pub(crate) fn get_inscription_genesis_vsize(
+    &self,
+    inscription_id: InscriptionId,
+  ) -> Result<Option<u64>> {
+    Ok(
+      self
+        .database
+        .begin_read()?
+        .open_table(INSCRIPTION_ID_TO_GENESIS_VSIZE)?
+        .get(&inscription_id.store())?
+        .map(|value| value.value()),
+    )
+  }
+
+  /// Seed the outpoint value table with the coins in `path`, a snapshot of
+  /// the UTXO set written by Bitcoin Core's `dumptxoutset`, so that the
+  /// values of inputs spending them needn't be fetched from Bitcoin Core while
+  /// indexing. Returns the snapshot's base block hash and height, and the
+  /// number of coins loaded.
+  ///
+  /// Coins are removed from the table when spent, so the snapshot can't be
+  /// of a UTXO set older than the index, whose coins spent since would never
+  /// be removed, and is most useful when taken at the block before the first
+  /// inscription, with `dumptxoutset <PATH> rollback=<HEIGHT>`.
+  pub(crate) fn load_utxo_snapshot(&self, path: &Path) -> Result<(BlockHash, u64, u64)> {
+    if self.has_sat_index()? {
+      bail!("UTXO snapshots can't be loaded into an index with `--index-sats`, which tracks values in its sat ranges");
+    }
+
+    let mut snapshot = utxo_snapshot::UtxoSnapshot::new(io::BufReader::new(
+      File::open(path).with_context(|| format!("failed to open {}", path.display()))?,
+    ))
+    .with_context(|| format!("failed to read UTXO snapshot {}", path.display()))?;
+
+    let base_height = self
+      .block_header_info(snapshot.base_hash)?
+      .ok_or_else(|| {
+        anyhow!(
+          "UTXO snapshot base block {} is not in Bitcoin Core's chain",
+          snapshot.base_hash
+        )
+      })?
+      .height as u64;
+
+    let block_count = self.block_count()?;
+
+    if base_height + 1 < block_count {
+      bail!(
+        "UTXO snapshot is of height {base_height}, but the index has already indexed blocks up to {}",
+        block_count - 1
+      );
+    }
+
+    if base_height + 1 < self.first_inscription_height {
+      bail!(
+        "UTXO snapshot is of height {base_height}, but spent coins are only removed from block {} on, so coins spent before then would never be removed",
+        self.first_inscription_height
+      );
+    }
+
+    let mut loaded = 0;
+
+    loop {
+      let wtx = self.begin_write()?;
+
+      {
+        let mut outpoint_to_value = wtx.open_table(OUTPOINT_TO_VALUE)?;
+
+        for _ in 0..1_000_000 {
+          let Some((outpoint, value)) = snapshot.next_coin().with_context(|| {
+            format!(
+              "failed to read UTXO snapshot {} coin {loaded}",
+              path.display()
+            )
+          })?
+          else {
+            break;
+          };
+
+          outpoint_to_value.insert(&outpoint.store(), &value)?;
+
+          loaded += 1;
+        }
+      }
+
+      wtx.commit()?;
+
+      log::info!("Loaded {loaded} of {} UTXO snapshot coins", snapshot.coins);
+
+      if loaded == snapshot.coins {
+        break;
+      }
+    }
+
+    Ok((snapshot.base_hash, base_height, loaded))
+  }
+
+  /// Labels are set with `ord wallet label`, and, along with failed reveals,
+  /// are the only data in the index that doesn't come from the chain.
+  pub(crate) fn set_label(&self, label: &str, destination: &str) -> Result {
+    let wtx = self.begin_write()?;
+    wtx
+      .open_table(LABEL_TO_DESTINATION)?
+      .insert(label, destination)?;
+    Ok(wtx.commit()?)
+  }
+
+  pub(crate) fn get_label(&self, label: &str) -> Result<Option<String>> {
+    match self.begin_read()?.0.open_table(LABEL_TO_DESTINATION) {
+      Ok(table) => Ok(
+        table
+          .get(label)?
+          .map(|destination| destination.value().to_string()),
+      ),
+      Err(redb::TableError::TableDoesNotExist(_)) => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  /// Record the reveals of `commit` that failed to broadcast, as signed
+  /// transaction hex, along with `#` comment lines. Recording no lines
+  /// removes the record.
+  pub(crate) fn set_failed_reveals(&self, commit: Txid, lines: &[String]) -> Result {
+    let wtx = self.begin_write()?;
+
+    {
+      let mut table = wtx.open_table(COMMIT_TXID_TO_FAILED_REVEALS)?;
+
+      if lines.is_empty() {
+        table.remove(&commit.store())?;
+      } else {
+        table.insert(&commit.store(), lines.join("\n").as_str())?;
+      }
+    }
+
+    Ok(wtx.commit()?)
+  }
+
+  pub(crate) fn get_failed_reveals(&self, commit: Txid) -> Result<Option<Vec<String>>> {
+    match self
+      .begin_read()?
+      .0
+      .open_table(COMMIT_TXID_TO_FAILED_REVEALS)
+    {
+      Ok(table) => Ok(
+        table
+          .get(&commit.store())?
+          .map(|lines| lines.value().lines().map(str::to_string).collect()),
+      ),
+      Err(redb::TableError::TableDoesNotExist(_)) => Ok(None),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  pub(crate) fn get_all_failed_reveals(&self) -> Result<Vec<(Txid, Vec<String>)>> {
+    match self
+      .begin_read()?
+      .0
+      .open_table(COMMIT_TXID_TO_FAILED_REVEALS)
+    {
+      Ok(table) => table
+        .iter()?
+        .map(|result| {
+          result
+            .map(|(commit, lines)| {
+              (
+                Txid::load(*commit.value()),
+                lines.value().lines().map(str::to_string).collect(),
+              )
+            })
+            .map_err(|err| err.into())
+        })
+        .collect(),
+      Err(redb::TableError::TableDoesNotExist(_)) => Ok(Vec::new()),
+      Err(err) => Err(err.into()),
+    }
+  }
+
+  pub(crate) fn compact(&mut self) -> Result<Compaction> {
+    let size_before = fs::metadata(&self.path)?.len();
+    let fragmented_bytes_before = self.fragmented_bytes()?;
+
+    let compacted = self.database.compact()?;
+
+    Ok(Compaction {
+      compacted,
+      fragmented_bytes_after: self.fragmented_bytes()?,
+      fragmented_bytes_before,
+      size_after: fs::metadata(&self.path)?.len(),
+      size_before,
+    })
+  }
+
+  /// Compact the index if more than `--auto-compact-threshold` percent of the
+  /// index file is fragmented. Compaction takes `&mut self` because redb only
+  /// compacts a database with no other open transactions, so this is only
+  /// called by `ord index run`, never by commands that share the index.
+  pub(crate) fn auto_compact(&mut self) -> Result<Option<Compaction>> {
+    let Some(threshold) = self.options.auto_compact_threshold else {
+      return Ok(None);
+    };
+
+    let fragmentation =
+      self.fragmented_bytes()? as f64 / fs::metadata(&self.path)?.len() as f64 * 100.0;
+
+    if fragmentation <= threshold {
+      return Ok(None);
+    }
+
+    log::info!("Compacting index, {fragmentation:.1}% of index file is fragmented");
+
+    self.compact().map(Some)
+  }
+
+  fn fragmented_bytes(&self) -> Result<u64> {
+    Ok(self.begin_write()?.stats()?.fragmented_bytes())
   }
 
   pub(crate) fn delete_transfer_log(&self) -> Result {
@@ -1653,6 +2379,9 @@ mod tests {
       vec![
         Context::builder().build(),
         Context::builder().arg("--index-sats").build(),
+        Context::builder()
+          .args(["--index-sats", "--compress-sat-ranges"])
+          .build(),
       ]
     }
   }
@@ -2485,6 +3214,36 @@ mod tests {
     }
   }
 
+  #[test]
+  fn lost_inscriptions_are_listed() {
+    for context in Context::configurations() {
+      context.mine_blocks(1);
+
+      let txid = context.rpc_server.broadcast_tx(TransactionTemplate {
+        inputs: &[(1, 0, 0)],
+        fee: 50 * COIN_VALUE,
+        witness: inscription("text/plain", "hello").to_witness(),
+        ..Default::default()
+      });
+      let inscription_id = InscriptionId::from(txid);
+
+      assert_eq!(context.index.get_lost_inscriptions().unwrap(), []);
+
+      context.mine_blocks_with_subsidy(1, 0);
+
+      assert_eq!(
+        context.index.get_lost_inscriptions().unwrap(),
+        [(
+          SatPoint {
+            outpoint: OutPoint::null(),
+            offset: 0,
+          },
+          inscription_id
+        )]
+      );
+    }
+  }
+
   #[test]
   fn multiple_inscriptions_can_be_lost() {
     for context in Context::configurations() {
@@ -2724,6 +3483,80 @@ mod tests {
     );
   }
 
+  #[test]
+  fn auto_compact_below_threshold() {
+    let mut context = Context::builder().build();
+    assert_eq!(context.index.auto_compact().unwrap(), None);
+
+    let mut context = Context::builder()
+      .args(["--auto-compact-threshold", "100"])
+      .build();
+    assert_eq!(context.index.auto_compact().unwrap(), None);
+  }
+
+  #[test]
+  fn compressed_sat_ranges_round_trip() {
+    let sat_ranges = [
+      (5_000_000_000, 10_000_000_000),
+      (0, 1),
+      (2_099_999_997_689_999, 2_099_999_997_690_000),
+    ]
+    .into_iter()
+    .flat_map(|range| range.store())
+    .collect::<Vec<u8>>();
+
+    let compressed = store_sat_ranges(&sat_ranges, true);
+    assert!(compressed.len() < sat_ranges.len());
+    assert_eq!(load_sat_ranges(&compressed, true), sat_ranges);
+
+    assert_eq!(store_sat_ranges(&sat_ranges, false), sat_ranges);
+  }
+
+  #[test]
+  fn compress_sat_ranges_requires_compressed_index() {
+    let tempdir = Context::builder().arg("--index-sats").build().tempdir;
+
+    assert!(Context::builder()
+      .tempdir(tempdir)
+      .args(["--index-sats", "--compress-sat-ranges"])
+      .try_build()
+      .err()
+      .unwrap()
+      .to_string()
+      .contains("was created without --compress-sat-ranges"));
+  }
+
+  #[test]
+  fn first_inscription_height_is_stored_in_index() {
+    let tempdir = {
+      let context = Context::builder()
+        .arg("--first-inscription-height=3")
+        .build();
+      context.tempdir
+    };
+
+    let tempdir = {
+      let context = Context::builder().tempdir(tempdir).build();
+      assert_eq!(context.index.first_inscription_height, 3);
+      context.tempdir
+    };
+
+    let path = tempdir.path().to_owned();
+
+    let delimiter = if cfg!(windows) { '\\' } else { '/' };
+
+    assert_eq!(
+      Context::builder()
+        .tempdir(tempdir)
+        .arg("--first-inscription-height=5")
+        .try_build()
+        .err()
+        .unwrap()
+        .to_string(),
+      format!("index at `{}{delimiter}regtest{delimiter}index.redb` was created with --first-inscription-height 3, delete it to rebuild it with --first-inscription-height 5", path.display()),
+    );
+  }
+
   #[test]
   fn old_schema_gives_correct_error() {
     let tempdir = {
@@ -2778,6 +3611,130 @@ mod tests {
       format!("index at `{}{delimiter}regtest{delimiter}index.redb` appears to have been built with a newer, incompatible version of ord, consider updating ord: index schema {}, ord schema {SCHEMA_VERSION}", path.display(), u64::MAX));
   }
 
+  #[test]
+  fn migrations_end_at_current_schema() {
+    for (i, migration) in migrations::MIGRATIONS.iter().enumerate() {
+      assert_eq!(
+        migration.from,
+        migrations::MIGRATIONS[0].from + u64::try_from(i).unwrap()
+      );
+    }
+
+    assert_eq!(
+      migrations::MIGRATIONS.last().unwrap().from + 1,
+      SCHEMA_VERSION
+    );
+  }
+
+  #[test]
+  fn old_schema_is_migrated() {
+    let tempdir = {
+      let context = Context::builder().build();
+
+      let wtx = context.index.database.begin_write().unwrap();
+
+      wtx.delete_table(SCHEMA_VERSION_TO_TIMESTAMP).unwrap();
+
+      wtx
+        .open_table(STATISTIC_TO_COUNT)
+        .unwrap()
+        .insert(&Statistic::Schema.key(), &5)
+        .unwrap();
+
+      wtx.commit().unwrap();
+
+      context.tempdir
+    };
+
+    let context = Context::builder().tempdir(tempdir).build();
+
+    assert_eq!(context.index.statistic(Statistic::Schema), SCHEMA_VERSION);
+
+    assert!(context
+      .index
+      .database
+      .begin_read()
+      .unwrap()
+      .open_table(SCHEMA_VERSION_TO_TIMESTAMP)
+      .unwrap()
+      .get(&SCHEMA_VERSION)
+      .unwrap()
+      .is_some());
+  }
+
+  #[test]
+  fn created_and_transferred_inscriptions_are_indexed_by_height() {
+    for context in Context::configurations() {
+      context.mine_blocks(1);
+
+      let txid = context.rpc_server.broadcast_tx(TransactionTemplate {
+        inputs: &[(1, 0, 0)],
+        witness: inscription("text/plain", "hello").to_witness(),
+        ..Default::default()
+      });
+
+      let inscription_id = InscriptionId::from(txid);
+
+      context.mine_blocks(1);
+
+      context.rpc_server.broadcast_tx(TransactionTemplate {
+        inputs: &[(2, 1, 0)],
+        ..Default::default()
+      });
+
+      context.mine_blocks(1);
+
+      assert_eq!(
+        context.index.get_new_inscription_ids_by_height(2).unwrap(),
+        [inscription_id]
+      );
+      assert_eq!(context.index.get_inscription_ids_by_height(2).unwrap(), []);
+      assert_eq!(
+        context.index.get_new_inscription_ids_by_height(3).unwrap(),
+        []
+      );
+      assert_eq!(
+        context.index.get_inscription_ids_by_height(3).unwrap(),
+        [inscription_id]
+      );
+    }
+  }
+
+  #[test]
+  fn inscription_genesis_vsize_is_recorded() {
+    for context in Context::configurations() {
+      context.mine_blocks(1);
+
+      let txid = context.rpc_server.broadcast_tx(TransactionTemplate {
+        inputs: &[(1, 0, 0)],
+        witness: inscription("text/plain", "hello").to_witness(),
+        fee: 50,
+        ..Default::default()
+      });
+
+      let inscription_id = InscriptionId::from(txid);
+
+      let reveal = context.mine_blocks(1)[0].txdata[1].clone();
+
+      assert_eq!(
+        context
+          .index
+          .get_inscription_genesis_vsize(inscription_id)
+          .unwrap(),
+        Some(reveal.vsize().try_into().unwrap())
+      );
+      assert_eq!(
+        context
+          .index
+          .get_inscription_entry(inscription_id)
+          .unwrap()
+          .unwrap()
+          .fee,
+        50
+      );
+    }
+  }
+
   #[test]
   fn inscriptions_on_output() {
     for context in Context::configurations() {
@@ -3913,4 +4870,39 @@ mod tests {
         .assert_inscription_location(first_id, first_location, Some(50 * COIN_VALUE));
     }
   }
+
+  #[test]
+  fn opening_locked_index_names_holder() {
+    let context = Context::builder().build();
+
+    let error = Index::open(&context.options).err().unwrap().to_string();
+
+    assert!(
+      error.contains("is locked by another ord process"),
+      "{error}"
+    );
+    assert!(
+      error.contains(&format!("\"pid\":{}", process::id())),
+      "{error}"
+    );
+  }
+
+  #[test]
+  fn locked_index_can_be_queried_as_snapshot() {
+    let context = Context::builder().build();
+
+    context.mine_blocks(1);
+
+    let mut options = context.options.clone();
+    options.index_snapshot = true;
+
+    let snapshot = Index::open(&options).unwrap();
+
+    assert_eq!(snapshot.block_count().unwrap(), 2);
+
+    context.rpc_server.mine_blocks(1);
+    snapshot.update().unwrap();
+
+    assert_eq!(snapshot.block_count().unwrap(), 2);
+  }
 }
@@ -12,7 +12,7 @@ pub struct BlockIndex {
 impl BlockIndex {
   pub(crate) fn new(index: &Index) -> Result<BlockIndex> {
     Ok(BlockIndex {
-      first_inscription_height: index.options.first_inscription_height(),
+      first_inscription_height: index.first_inscription_height,
       lowest_blessed_by_block: Vec::new(),
       lowest_cursed_by_block: Vec::new(),
       highest_indexed_blessed: i64::MIN,
@@ -1,4 +1,4 @@
-use super::*;
+use {super::*, std::borrow::Cow};
 
 pub(super) trait Entry: Sized {
   type Value;
@@ -22,6 +22,20 @@ impl Entry for BlockHash {
   }
 }
 
+pub(super) type TxidValue = [u8; 32];
+
+impl Entry for Txid {
+  type Value = TxidValue;
+
+  fn load(value: Self::Value) -> Self {
+    Txid::from_raw_hash(Hash::from_byte_array(value))
+  }
+
+  fn store(self) -> Self::Value {
+    *self.as_ref()
+  }
+}
+
 #[derive(Debug)]
 pub(crate) struct InscriptionEntry {
   pub(crate) fee: u64,
@@ -169,3 +183,68 @@ impl Entry for SatRange {
     n.to_le_bytes()[0..11].try_into().unwrap()
   }
 }
+
+/// Values of `OUTPOINT_TO_SAT_RANGES` in indexes created with
+/// `--compress-sat-ranges` hold, for each range, a zigzag varint of the
+/// distance from the end of the previous range to its start, followed by a
+/// varint of its size. Everywhere else sat ranges are handled as concatenated
+/// 11 byte `SatRange` values, which these functions convert to and from.
+pub(super) fn load_sat_ranges(value: &[u8], compressed: bool) -> Cow<[u8]> {
+  if !compressed {
+    return Cow::Borrowed(value);
+  }
+
+  let mut sat_ranges = Vec::new();
+  let mut end: u64 = 0;
+  let mut i = 0;
+
+  while i < value.len() {
+    let delta = read_varint(value, &mut i);
+    let start = end.wrapping_add_signed((delta >> 1) as i64 ^ -((delta & 1) as i64));
+    end = start + read_varint(value, &mut i);
+    sat_ranges.extend_from_slice(&(start, end).store());
+  }
+
+  Cow::Owned(sat_ranges)
+}
+
+pub(super) fn store_sat_ranges(sat_ranges: &[u8], compressed: bool) -> Cow<[u8]> {
+  if !compressed {
+    return Cow::Borrowed(sat_ranges);
+  }
+
+  let mut value = Vec::new();
+  let mut previous_end: u64 = 0;
+
+  for chunk in sat_ranges.chunks_exact(11) {
+    let (start, end) = SatRange::load(chunk.try_into().unwrap());
+    let delta = start.wrapping_sub(previous_end) as i64;
+    write_varint(&mut value, ((delta << 1) ^ (delta >> 63)) as u64);
+    write_varint(&mut value, end - start);
+    previous_end = end;
+  }
+
+  Cow::Owned(value)
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut n: u64) {
+  while n >= 0x80 {
+    buffer.push(n as u8 | 0x80);
+    n >>= 7;
+  }
+  buffer.push(n as u8);
+}
+
+fn read_varint(buffer: &[u8], i: &mut usize) -> u64 {
+  let mut n = 0;
+  let mut shift = 0;
+  loop {
+    let byte = buffer[*i];
+    *i += 1;
+    n |= u64::from(byte & 0x7f) << shift;
+    if byte & 0x80 == 0 {
+      return n;
+    }
+    shift += 7;
+  }
+}
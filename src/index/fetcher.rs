@@ -1,17 +1,17 @@
 use {
   crate::Options,
   anyhow::{anyhow, Result},
-  base64::Engine,
   bitcoin::{Transaction, Txid},
-  hyper::{client::HttpConnector, Body, Client, Method, Request, Uri},
+  reqwest::{Client, Proxy, Url},
   serde::Deserialize,
   serde_json::{json, Value},
 };
 
 pub(crate) struct Fetcher {
-  auth: String,
-  client: Client<HttpConnector>,
-  url: Uri,
+  client: Client,
+  password: String,
+  url: Url,
+  user: String,
 }
 
 #[derive(Deserialize, Debug)]
@@ -29,7 +29,11 @@ struct JsonError {
 
 impl Fetcher {
   pub(crate) fn new(options: &Options) -> Result<Self> {
-    let client = Client::new();
+    let mut client = Client::builder();
+
+    if let Some(proxy) = options.proxy()? {
+      client = client.proxy(Proxy::all(proxy)?);
+    }
 
     let url = if options.rpc_url().starts_with("http://") {
       options.rpc_url()
@@ -37,15 +41,16 @@ impl Fetcher {
       "http://".to_string() + &options.rpc_url()
     };
 
-    let url = Uri::try_from(&url).map_err(|e| anyhow!("Invalid rpc url {url}: {e}"))?;
+    let url = Url::parse(&url).map_err(|e| anyhow!("Invalid rpc url {url}: {e}"))?;
 
     let (user, password) = options.auth()?.get_user_pass()?;
-    let auth = format!("{}:{}", user.unwrap(), password.unwrap());
-    let auth = format!(
-      "Basic {}",
-      &base64::engine::general_purpose::STANDARD.encode(auth)
-    );
-    Ok(Fetcher { client, url, auth })
+
+    Ok(Fetcher {
+      client: client.build()?,
+      password: password.unwrap(),
+      url,
+      user: user.unwrap(),
+    })
   }
 
   pub(crate) async fn get_transactions(&self, txids: Vec<Txid>) -> Result<Vec<Transaction>> {
@@ -126,16 +131,16 @@ impl Fetcher {
   }
 
   async fn try_get_transactions(&self, body: String) -> Result<Vec<JsonResponse<String>>> {
-    let req = Request::builder()
-      .method(Method::POST)
-      .uri(&self.url)
-      .header(hyper::header::AUTHORIZATION, &self.auth)
-      .header(hyper::header::CONTENT_TYPE, "application/json")
-      .body(Body::from(body))?;
-
-    let response = self.client.request(req).await?;
-
-    let buf = hyper::body::to_bytes(response).await?;
+    let response = self
+      .client
+      .post(self.url.clone())
+      .basic_auth(&self.user, Some(&self.password))
+      .header(reqwest::header::CONTENT_TYPE, "application/json")
+      .body(body)
+      .send()
+      .await?;
+
+    let buf = response.bytes().await?;
 
     let results: Vec<JsonResponse<String>> = match serde_json::from_slice(&buf) {
       Ok(results) => results,
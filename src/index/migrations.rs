@@ -0,0 +1,115 @@
+use super::*;
+
+/// An in-place upgrade of an index from schema `from` to `from + 1`.
+pub(super) struct Migration {
+  pub(super) from: u64,
+  pub(super) description: &'static str,
+  pub(super) run: fn(&WriteTransaction) -> Result,
+}
+
+/// Every migration, in order, ending at `SCHEMA_VERSION`. To change the
+/// schema, bump `SCHEMA_VERSION`, make the change for new indexes in
+/// `Index::open`, and add a migration here that makes the same change to
+/// existing indexes, so that they don't have to be rebuilt.
+pub(super) const MIGRATIONS: &[Migration] = &[
+  Migration {
+    from: 5,
+    description: "record schema migrations",
+    run: |wtx| {
+      wtx.open_table(SCHEMA_VERSION_TO_TIMESTAMP)?;
+      Ok(())
+    },
+  },
+  Migration {
+    from: 6,
+    description: "index inscriptions by the height they were created at",
+    run: |wtx| {
+      let mut height_to_new_inscription_id =
+        wtx.open_multimap_table(HEIGHT_TO_NEW_INSCRIPTION_ID)?;
+
+      for result in wtx
+        .open_table(INSCRIPTION_ID_TO_INSCRIPTION_ENTRY)?
+        .iter()?
+      {
+        let (id, entry) = result?;
+        height_to_new_inscription_id
+          .insert(&InscriptionEntry::load(entry.value()).height, id.value())?;
+      }
+
+      Ok(())
+    },
+  },
+  Migration {
+    from: 7,
+    description: "record the vsize of each inscription's reveal transaction",
+    run: |wtx| {
+      // the reveal transactions of inscriptions that are already indexed
+      // aren't stored, so their vsizes stay unknown
+      wtx.open_table(INSCRIPTION_ID_TO_GENESIS_VSIZE)?;
+      Ok(())
+    },
+  },
+];
+
+/// Whether an index at `schema_version` can be migrated to `SCHEMA_VERSION`.
+pub(super) fn can_migrate(schema_version: u64) -> bool {
+  MIGRATIONS
+    .first()
+    .map(|migration| migration.from <= schema_version)
+    .unwrap_or_default()
+    && schema_version < SCHEMA_VERSION
+}
+
+/// Apply each migration after `schema_version` in its own write transaction,
+/// so that an interrupted upgrade resumes from the last completed migration.
+pub(super) fn run(database: &Database, path: &Path, schema_version: u64) -> Result {
+  for migration in MIGRATIONS
+    .iter()
+    .filter(|migration| migration.from >= schema_version)
+  {
+    let to = migration.from + 1;
+
+    log::info!(
+      "Migrating index at `{}` from schema {} to {to}: {}",
+      path.display(),
+      migration.from,
+      migration.description
+    );
+
+    let mut wtx = database.begin_write()?;
+
+    wtx.set_durability(redb::Durability::Immediate);
+
+    (migration.run)(&wtx).with_context(|| {
+      format!(
+        "failed to migrate index at `{}` from schema {} to {to}",
+        path.display(),
+        migration.from
+      )
+    })?;
+
+    record(&wtx, to)?;
+
+    wtx.commit()?;
+  }
+
+  Ok(())
+}
+
+/// Record that the index reached `schema_version`, both as the current schema
+/// and in the schema history.
+pub(super) fn record(wtx: &WriteTransaction, schema_version: u64) -> Result {
+  wtx
+    .open_table(STATISTIC_TO_COUNT)?
+    .insert(&Statistic::Schema.key(), &schema_version)?;
+
+  wtx.open_table(SCHEMA_VERSION_TO_TIMESTAMP)?.insert(
+    &schema_version,
+    &SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .map(|duration| duration.as_millis())
+      .unwrap_or(0),
+  )?;
+
+  Ok(())
+}
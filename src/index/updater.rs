@@ -31,8 +31,10 @@ impl From<Block> for BlockData {
 
 pub(crate) struct Updater<'index> {
   range_cache: HashMap<OutPointValue, Vec<u8>>,
+  compressed_sat_ranges: bool,
   height: u64,
   index: &'index Index,
+  index_addresses: bool,
   index_sats: bool,
   index_utxos: bool,
   skip_empty_outputs: bool,
@@ -46,8 +48,10 @@ impl<'index> Updater<'_> {
   pub(crate) fn new(index: &'index Index) -> Result<Updater<'index>> {
     Ok(Updater {
       range_cache: HashMap::new(),
+      compressed_sat_ranges: index.compressed_sat_ranges,
       height: index.block_count()?,
       index,
+      index_addresses: index.has_address_index()?,
       index_sats: index.has_sat_index()?,
       index_utxos: index.has_utxo_index()?,
       skip_empty_outputs: index.options.skip_empty_outputs,
@@ -59,8 +63,8 @@ impl<'index> Updater<'_> {
   }
 
   pub(crate) fn update_index(&mut self) -> Result {
-    let mut wtx = self.index.begin_write()?;
     let starting_height = self.index.client.get_block_count()? + 1;
+    let mut wtx = self.begin_write(starting_height)?;
 
     wtx
       .open_table(WRITE_TRANSACTION_STARTING_BLOCK_COUNT_TO_TIMESTAMP)?
@@ -94,6 +98,7 @@ impl<'index> Updater<'_> {
 
     let mut uncommitted = 0;
     let mut value_cache = HashMap::new();
+    let mut interrupted = false;
     while let Ok(block) = rx.recv() {
       self.index_block(
         self.index,
@@ -122,7 +127,7 @@ impl<'index> Updater<'_> {
         self.commit(wtx, value_cache)?;
         value_cache = HashMap::new();
         uncommitted = 0;
-        wtx = self.index.begin_write()?;
+        wtx = self.begin_write(starting_height)?;
         let height = wtx
           .open_table(HEIGHT_TO_BLOCK_HASH)?
           .range(0..)?
@@ -147,6 +152,7 @@ impl<'index> Updater<'_> {
       }
 
       if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        interrupted = true;
         break;
       }
     }
@@ -155,6 +161,13 @@ impl<'index> Updater<'_> {
       self.commit(wtx, value_cache)?;
     }
 
+    if interrupted {
+      eprintln!(
+        "Interrupted while indexing, the index is up to date through block {}. Run ord again to resume indexing.",
+        self.height.saturating_sub(1)
+      );
+    }
+
     if let Some(progress_bar) = &mut progress_bar {
       progress_bar.finish_and_clear();
     }
@@ -162,6 +175,23 @@ impl<'index> Updater<'_> {
     Ok(())
   }
 
+  /// Begin a write transaction with `--sync-durability` while far from the
+  /// chain tip, and with `--durability` near it.
+  fn begin_write(&self, chain_tip: u64) -> Result<WriteTransaction> {
+    let mut wtx = self.index.begin_write()?;
+
+    wtx.set_durability(
+      if chain_tip.saturating_sub(self.height) > CHAIN_TIP_DISTANCE {
+        self.index.options.sync_durability
+      } else {
+        self.index.options.durability
+      }
+      .into(),
+    );
+
+    Ok(wtx)
+  }
+
   fn fetch_blocks_from(
     index: &Index,
     mut height: u64,
@@ -381,6 +411,8 @@ impl<'index> Updater<'_> {
 
     let mut height_to_block_hash = wtx.open_table(HEIGHT_TO_BLOCK_HASH)?;
     let mut height_to_inscription_id = wtx.open_multimap_table(HEIGHT_TO_INSCRIPTION_ID)?;
+    let mut height_to_new_inscription_id = wtx.open_multimap_table(HEIGHT_TO_NEW_INSCRIPTION_ID)?;
+    let mut inscription_id_to_genesis_vsize = wtx.open_table(INSCRIPTION_ID_TO_GENESIS_VSIZE)?;
     let mut inscription_id_to_inscription_entry =
       wtx.open_table(INSCRIPTION_ID_TO_INSCRIPTION_ENTRY)?;
     let mut inscription_id_to_satpoint = wtx.open_table(INSCRIPTION_ID_TO_SATPOINT)?;
@@ -404,9 +436,11 @@ impl<'index> Updater<'_> {
     let mut inscription_updater = InscriptionUpdater::new(
       self.height,
       &mut height_to_inscription_id,
+      &mut height_to_new_inscription_id,
       &mut inscription_id_to_satpoint,
       value_receiver,
       &mut inscription_id_to_inscription_entry,
+      &mut inscription_id_to_genesis_vsize,
       lost_sats,
       &mut inscription_number_to_inscription_id,
       &mut outpoint_to_value,
@@ -451,7 +485,9 @@ impl<'index> Updater<'_> {
             }
             None => match outpoint_to_sat_ranges.remove(&key)? {
               // Result<Option<AccessGuard<>>> -> Option<AccessGuard<>>
-              Some(value) => value.value().to_vec(),
+              Some(value) => {
+                load_sat_ranges(value.value(), self.compressed_sat_ranges).into_owned()
+              }
               None => {
                 if index
                   .get_outpoint_value(&input.previous_output)
@@ -510,6 +546,7 @@ impl<'index> Updater<'_> {
 
       if !coinbase_inputs.is_empty() {
         Self::mark_sats_as_lost(
+          self.compressed_sat_ranges,
           coinbase_inputs,
           &mut lost_sats,
           &mut outpoint_to_sat_ranges,
@@ -530,6 +567,10 @@ impl<'index> Updater<'_> {
       &inscription_updater.unbound_inscriptions,
     )?;
 
+    if self.index_addresses {
+      Self::index_block_addresses(wtx, &block)?;
+    }
+
     height_to_block_hash.insert(&self.height, &block.header.block_hash().store())?;
 
     self.height += 1;
@@ -543,7 +584,47 @@ impl<'index> Updater<'_> {
     Ok(())
   }
 
+  /// Move the outputs spent by `block` out of the address index, and add the
+  /// outputs it creates. OP_RETURN outputs are unspendable, so they aren't
+  /// indexed.
+  fn index_block_addresses(wtx: &WriteTransaction, block: &BlockData) -> Result {
+    let mut outpoint_to_script_pubkey = wtx.open_table(OUTPOINT_TO_SCRIPT_PUBKEY)?;
+    let mut script_pubkey_to_outpoint = wtx.open_multimap_table(SCRIPT_PUBKEY_TO_OUTPOINT)?;
+
+    for (tx, txid) in &block.txdata {
+      for input in &tx.input {
+        let key = input.previous_output.store();
+
+        let script_pubkey = outpoint_to_script_pubkey
+          .remove(&key)?
+          .map(|entry| entry.value().1.to_vec());
+
+        if let Some(script_pubkey) = script_pubkey {
+          script_pubkey_to_outpoint.remove(script_pubkey.as_slice(), &key)?;
+        }
+      }
+
+      for (vout, output) in tx.output.iter().enumerate() {
+        if output.script_pubkey.is_op_return() {
+          continue;
+        }
+
+        let key = OutPoint {
+          txid: *txid,
+          vout: vout.try_into().unwrap(),
+        }
+        .store();
+
+        outpoint_to_script_pubkey.insert(&key, (output.value, output.script_pubkey.as_bytes()))?;
+        script_pubkey_to_outpoint.insert(output.script_pubkey.as_bytes(), &key)?;
+      }
+    }
+
+    Ok(())
+  }
+
   fn mark_sats_as_lost(
+    compressed_sat_ranges: bool,
     ranges: VecDeque<(u64, u64)>,
     lost_sats: &mut u64,
     outpoint_to_sat_ranges: &mut Table<&OutPointValue, &[u8]>,
@@ -552,7 +633,7 @@ impl<'index> Updater<'_> {
   ) -> Result {
     let mut lost_sat_ranges = outpoint_to_sat_ranges
       .remove(&OutPoint::null().store())?
-      .map(|ranges| ranges.value().to_vec())
+      .map(|ranges| load_sat_ranges(ranges.value(), compressed_sat_ranges).into_owned())
       .unwrap_or_default();
 
     for (start, end) in ranges.clone() {
@@ -576,7 +657,10 @@ impl<'index> Updater<'_> {
       *lost_sats += end - start;
     }
 
-    outpoint_to_sat_ranges.insert(&OutPoint::null().store(), lost_sat_ranges.as_slice())?;
+    outpoint_to_sat_ranges.insert(
+      &OutPoint::null().store(),
+      store_sat_ranges(&lost_sat_ranges, compressed_sat_ranges).as_ref(),
+    )?;
 
     Ok(())
   }
@@ -655,6 +739,7 @@ impl<'index> Updater<'_> {
           }
 
           Self::mark_sats_as_lost(
+            self.compressed_sat_ranges,
             sats,
             lost_sats,
             outpoint_to_sat_ranges,
@@ -695,9 +780,14 @@ impl<'index> Updater<'_> {
 
       let mut sats = VecDeque::new();
       for (outpoint, sat_range) in self.range_cache.drain() {
-        let old_value = outpoint_to_sat_ranges.insert(&outpoint, sat_range.as_slice())?;
+        let old_value = outpoint_to_sat_ranges.insert(
+          &outpoint,
+          store_sat_ranges(&sat_range, self.compressed_sat_ranges).as_ref(),
+        )?;
         if let Some(old_value) = old_value {
-          for chunk in old_value.value().chunks_exact(11) {
+          for chunk in
+            load_sat_ranges(old_value.value(), self.compressed_sat_ranges).chunks_exact(11)
+          {
             let range: SatRange = SatRange::load(chunk.try_into().unwrap());
             sats.push_back(range);
           }
@@ -722,6 +812,7 @@ impl<'index> Updater<'_> {
         let mut sat_to_satpoint = wtx.open_table(SAT_TO_SATPOINT)?;
 
         Self::mark_sats_as_lost(
+          self.compressed_sat_ranges,
           sats,
           &mut lost_sats,
           &mut outpoint_to_sat_ranges,
@@ -13,6 +13,7 @@ enum Origin {
     fee: u64,
     cursed: bool,
     unbound: bool,
+    vsize: u64,
   },
   Old {
     old_satpoint: SatPoint,
@@ -23,9 +24,11 @@ pub(super) struct InscriptionUpdater<'a, 'db, 'tx> {
   flotsam: Vec<Flotsam>,
   height: u64,
   height_to_inscription_id: &'a mut MultimapTable<'db, 'tx, u64, &'static InscriptionIdValue>,
+  height_to_new_inscription_id: &'a mut MultimapTable<'db, 'tx, u64, &'static InscriptionIdValue>,
   id_to_satpoint: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, &'static SatPointValue>,
   value_receiver: &'a mut Receiver<u64>,
   id_to_entry: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, InscriptionEntryValue>,
+  id_to_genesis_vsize: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, u64>,
   pub(super) lost_sats: u64,
   next_cursed_number: i64,
   next_number: i64,
@@ -45,9 +48,11 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
   pub(super) fn new(
     height: u64,
     height_to_inscription_id: &'a mut MultimapTable<'db, 'tx, u64, &'static InscriptionIdValue>,
+    height_to_new_inscription_id: &'a mut MultimapTable<'db, 'tx, u64, &'static InscriptionIdValue>,
     id_to_satpoint: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, &'static SatPointValue>,
     value_receiver: &'a mut Receiver<u64>,
     id_to_entry: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, InscriptionEntryValue>,
+    id_to_genesis_vsize: &'a mut Table<'db, 'tx, &'static InscriptionIdValue, u64>,
     lost_sats: u64,
     number_to_id: &'a mut Table<'db, 'tx, i64, &'static InscriptionIdValue>,
     outpoint_to_value: &'a mut Table<'db, 'tx, &'static OutPointValue, u64>,
@@ -81,9 +86,11 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
       flotsam: Vec::new(),
       height,
       height_to_inscription_id,
+      height_to_new_inscription_id,
       id_to_satpoint,
       value_receiver,
       id_to_entry,
+      id_to_genesis_vsize,
       lost_sats,
       next_cursed_number,
       next_number,
@@ -235,6 +242,7 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
             fee: 0,
             cursed,
             unbound,
+            vsize: tx.vsize().try_into().unwrap(),
           },
         });
 
@@ -256,6 +264,7 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
               fee: _,
               cursed,
               unbound,
+              vsize,
             },
         } = flotsam
         {
@@ -266,6 +275,7 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
               fee: (input_value - total_output_value) / u64::from(id_counter),
               cursed,
               unbound,
+              vsize,
             },
           }
         } else {
@@ -388,6 +398,7 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
         fee,
         cursed,
         unbound,
+        vsize,
       } => {
         let number = if cursed {
           let next_cursed_number = self.next_cursed_number;
@@ -402,6 +413,9 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
         };
 
         self.number_to_id.insert(number, &inscription_id)?;
+        self
+          .height_to_new_inscription_id
+          .insert(&self.height, &inscription_id)?;
 
         let sat = if unbound {
           None
@@ -435,6 +449,8 @@ impl<'a, 'db, 'tx> InscriptionUpdater<'a, 'db, 'tx> {
           .store(),
         )?;
 
+        self.id_to_genesis_vsize.insert(&inscription_id, vsize)?;
+
         unbound
       }
     };
@@ -0,0 +1,308 @@
+use super::*;
+
+/// Reads the UTXO set snapshots written by Bitcoin Core's `dumptxoutset`.
+/// Snapshots from Bitcoin Core 28 and later start with a magic number and
+/// group coins by txid; earlier ones have neither.
+pub(super) struct UtxoSnapshot<R> {
+  reader: R,
+  pub(super) base_hash: BlockHash,
+  pub(super) coins: u64,
+  grouped: bool,
+  read: u64,
+  group: Option<(Txid, u64)>,
+}
+
+const MAGIC: [u8; 5] = *b"utxo\xff";
+
+const VERSION: u16 = 2;
+
+impl<R: Read> UtxoSnapshot<R> {
+  pub(super) fn new(mut reader: R) -> Result<Self> {
+    let mut start = [0; 5];
+    reader.read_exact(&mut start)?;
+
+    let mut base_hash = [0; 32];
+
+    let grouped = if start == MAGIC {
+      let version = u16::from_le_bytes(read_array(&mut reader)?);
+
+      if version != VERSION {
+        bail!("unsupported UTXO snapshot version {version}, only version {VERSION} and unversioned snapshots are supported");
+      }
+
+      // the network is checked by looking up the base block
+      read_array::<4>(&mut reader)?;
+
+      reader.read_exact(&mut base_hash)?;
+
+      true
+    } else {
+      base_hash[..5].copy_from_slice(&start);
+      reader.read_exact(&mut base_hash[5..])?;
+      false
+    };
+
+    Ok(Self {
+      base_hash: BlockHash::load(base_hash),
+      coins: u64::from_le_bytes(read_array(&mut reader)?),
+      reader,
+      grouped,
+      read: 0,
+      group: None,
+    })
+  }
+
+  /// The outpoint and value of the next coin, or `None` after the last.
+  pub(super) fn next_coin(&mut self) -> Result<Option<(OutPoint, u64)>> {
+    if self.read == self.coins {
+      return Ok(None);
+    }
+
+    let outpoint = if self.grouped {
+      let (txid, remaining) = match self.group {
+        Some((txid, remaining)) if remaining > 0 => (txid, remaining),
+        _ => (
+          Txid::load(read_array(&mut self.reader)?),
+          read_compact_size(&mut self.reader)?,
+        ),
+      };
+
+      self.group = Some((txid, remaining - 1));
+
+      OutPoint {
+        txid,
+        vout: read_compact_size(&mut self.reader)?.try_into()?,
+      }
+    } else {
+      OutPoint {
+        txid: Txid::load(read_array(&mut self.reader)?),
+        vout: u32::from_le_bytes(read_array(&mut self.reader)?),
+      }
+    };
+
+    // height and coinbase flag
+    read_varint(&mut self.reader)?;
+
+    let value = decompress_amount(read_varint(&mut self.reader)?);
+
+    skip_compressed_script(&mut self.reader)?;
+
+    self.read += 1;
+
+    Ok(Some((outpoint, value)))
+  }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+  let mut array = [0; N];
+  reader.read_exact(&mut array)?;
+  Ok(array)
+}
+
+fn read_compact_size(reader: &mut impl Read) -> Result<u64> {
+  Ok(match read_array::<1>(reader)?[0] {
+    0xfd => u16::from_le_bytes(read_array(reader)?).into(),
+    0xfe => u32::from_le_bytes(read_array(reader)?).into(),
+    0xff => u64::from_le_bytes(read_array(reader)?),
+    n => n.into(),
+  })
+}
+
+/// Bitcoin Core's `VARINT`, which unlike compact sizes is base 128, most
+/// significant digit first, with each continued digit offset by one.
+fn read_varint(reader: &mut impl Read) -> Result<u64> {
+  let mut n = 0u64;
+
+  loop {
+    let byte = read_array::<1>(reader)?[0];
+
+    n = n
+      .checked_mul(128)
+      .ok_or_else(|| anyhow!("UTXO snapshot varint overflows"))?
+      | u64::from(byte & 0x7f);
+
+    if byte & 0x80 == 0 {
+      return Ok(n);
+    }
+
+    n += 1;
+  }
+}
+
+/// Undo Bitcoin Core's `CompressAmount`, which stores trailing decimal zeros
+/// in the low digit.
+fn decompress_amount(x: u64) -> u64 {
+  if x == 0 {
+    return 0;
+  }
+
+  let mut x = x - 1;
+  let mut e = x % 10;
+  x /= 10;
+
+  let mut n = if e < 9 {
+    let d = x % 9 + 1;
+    x /= 9;
+    x * 10 + d
+  } else {
+    x + 1
+  };
+
+  while e > 0 {
+    n *= 10;
+    e -= 1;
+  }
+
+  n
+}
+
+/// Skip a script compressed by Bitcoin Core's `ScriptCompression`, whose
+/// sizes below six stand for common script templates.
+fn skip_compressed_script(reader: &mut impl Read) -> Result {
+  let size = match read_varint(reader)? {
+    0 | 1 => 20,
+    2..=5 => 32,
+    size => size - 6,
+  };
+
+  io::copy(&mut reader.take(size), &mut io::sink())?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn amounts_are_decompressed() {
+    assert_eq!(decompress_amount(0), 0);
+    assert_eq!(decompress_amount(0x1), 1);
+    assert_eq!(decompress_amount(0x7), 1_000_000);
+    assert_eq!(decompress_amount(0x9), 100_000_000);
+    assert_eq!(decompress_amount(0x32), 5_000_000_000);
+  }
+
+  #[test]
+  fn varints_are_read() {
+    assert_eq!(read_varint(&mut [0x00].as_slice()).unwrap(), 0);
+    assert_eq!(read_varint(&mut [0x7f].as_slice()).unwrap(), 127);
+    assert_eq!(read_varint(&mut [0x80, 0x00].as_slice()).unwrap(), 128);
+    assert_eq!(read_varint(&mut [0x80, 0x7f].as_slice()).unwrap(), 255);
+    assert_eq!(read_varint(&mut [0x81, 0x00].as_slice()).unwrap(), 256);
+  }
+
+  fn coin(value: u8) -> Vec<u8> {
+    // height 1, not coinbase; compressed amount; p2pkh script
+    let mut coin = vec![0x02, value, 0x00];
+    coin.extend([0; 20]);
+    coin
+  }
+
+  #[test]
+  fn unversioned_snapshots_are_read() {
+    let mut snapshot = vec![1; 32];
+    snapshot.extend(2u64.to_le_bytes());
+    snapshot.extend([3; 32]);
+    snapshot.extend(0u32.to_le_bytes());
+    snapshot.extend(coin(0x9));
+    snapshot.extend([4; 32]);
+    snapshot.extend(7u32.to_le_bytes());
+    snapshot.extend(coin(0x1));
+
+    let mut snapshot = UtxoSnapshot::new(snapshot.as_slice()).unwrap();
+
+    assert_eq!(snapshot.base_hash, BlockHash::load([1; 32]));
+    assert_eq!(snapshot.coins, 2);
+    assert_eq!(
+      snapshot.next_coin().unwrap(),
+      Some((
+        OutPoint {
+          txid: Txid::load([3; 32]),
+          vout: 0
+        },
+        100_000_000
+      ))
+    );
+    assert_eq!(
+      snapshot.next_coin().unwrap(),
+      Some((
+        OutPoint {
+          txid: Txid::load([4; 32]),
+          vout: 7
+        },
+        1
+      ))
+    );
+    assert_eq!(snapshot.next_coin().unwrap(), None);
+  }
+
+  #[test]
+  fn grouped_snapshots_are_read() {
+    let mut snapshot = MAGIC.to_vec();
+    snapshot.extend(VERSION.to_le_bytes());
+    snapshot.extend([0xf9, 0xbe, 0xb4, 0xd9]);
+    snapshot.extend([1; 32]);
+    snapshot.extend(3u64.to_le_bytes());
+    snapshot.extend([3; 32]);
+    snapshot.push(2);
+    snapshot.push(0);
+    snapshot.extend(coin(0x9));
+    snapshot.push(5);
+    snapshot.extend(coin(0x7));
+    snapshot.extend([4; 32]);
+    snapshot.push(1);
+    snapshot.push(1);
+    snapshot.extend(coin(0x1));
+
+    let mut snapshot = UtxoSnapshot::new(snapshot.as_slice()).unwrap();
+
+    assert_eq!(snapshot.base_hash, BlockHash::load([1; 32]));
+
+    let mut coins = Vec::new();
+    while let Some(coin) = snapshot.next_coin().unwrap() {
+      coins.push(coin);
+    }
+
+    assert_eq!(
+      coins,
+      [
+        (
+          OutPoint {
+            txid: Txid::load([3; 32]),
+            vout: 0
+          },
+          100_000_000
+        ),
+        (
+          OutPoint {
+            txid: Txid::load([3; 32]),
+            vout: 5
+          },
+          1_000_000
+        ),
+        (
+          OutPoint {
+            txid: Txid::load([4; 32]),
+            vout: 1
+          },
+          1
+        ),
+      ]
+    );
+  }
+
+  #[test]
+  fn newer_snapshot_versions_are_rejected() {
+    let mut snapshot = MAGIC.to_vec();
+    snapshot.extend(3u16.to_le_bytes());
+
+    assert_eq!(
+      UtxoSnapshot::new(snapshot.as_slice())
+        .err()
+        .unwrap()
+        .to_string(),
+      "unsupported UTXO snapshot version 3, only version 2 and unversioned snapshots are supported"
+    );
+  }
+}
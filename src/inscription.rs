@@ -3,20 +3,73 @@ use {
   bitcoin::{
     blockdata::{
       opcodes,
-      script::{self, Instruction, Instructions, PushBytesBuf},
+      script::{self, PushBytesBuf},
     },
-    taproot::TAPROOT_ANNEX_PREFIX,
     ScriptBuf, Witness,
   },
-  std::{iter::Peekable, str},
+  ord_envelope::{BODY_TAG, CONTENT_ENCODING_TAG, CONTENT_TYPE_TAG, PROTOCOL_ID},
+  std::str,
 };
 
-const PROTOCOL_ID: [u8; 3] = *b"ord";
-const BODY_TAG: [u8; 0] = [];
-const CONTENT_TYPE_TAG: [u8; 1] = [1];
 const CURSED_TAG: [u8; 1] = [66];
 const CURSED_ID: [u8; 6] = *b"cursed";
 
+/// The content type that inscriptions without one are served with.
+pub(crate) const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// An extra envelope field, given on the command line as `<N>=<HEX>`.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Tag {
+  pub(crate) number: u8,
+  pub(crate) value: Vec<u8>,
+}
+
+impl Tag {
+  pub(crate) fn cursed66() -> Self {
+    Self {
+      number: CURSED_TAG[0],
+      value: CURSED_ID.to_vec(),
+    }
+  }
+
+  pub(crate) fn is_even(&self) -> bool {
+    self.number % 2 == 0
+  }
+}
+
+impl FromStr for Tag {
+  type Err = Error;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    let (number, value) = s
+      .split_once('=')
+      .ok_or_else(|| anyhow!("tag `{s}` must be of the form <N>=<HEX>"))?;
+
+    let number = number
+      .parse::<u8>()
+      .with_context(|| format!("tag number `{number}` must be between 0 and 255"))?;
+
+    if number == CONTENT_TYPE_TAG[0] {
+      bail!("tag {number} is the content type tag");
+    }
+
+    if number == CONTENT_ENCODING_TAG[0] {
+      bail!("tag {number} is the content encoding tag");
+    }
+
+    let value = hex::decode(value).with_context(|| format!("tag value `{value}` is not hex"))?;
+
+    if value.len() > 520 {
+      bail!(
+        "tag {number} value is {} bytes, more than the 520 byte push limit",
+        value.len()
+      );
+    }
+
+    Ok(Self { number, value })
+  }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum Curse {
   NotInFirstInput,
@@ -24,10 +77,11 @@ pub(crate) enum Curse {
   Reinscription,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct Inscription {
   body: Option<Vec<u8>>,
   content_type: Option<Vec<u8>>,
+  content_encoding: Option<Vec<u8>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -40,13 +94,19 @@ pub(crate) struct TransactionInscription {
 impl Inscription {
   #[cfg(test)]
   pub(crate) fn new(content_type: Option<Vec<u8>>, body: Option<Vec<u8>>) -> Self {
-    Self { content_type, body }
+    Self {
+      content_type,
+      body,
+      content_encoding: None,
+    }
   }
 
   pub(crate) fn from_transaction(tx: &Transaction) -> Vec<TransactionInscription> {
     let mut result = Vec::new();
     for (index, tx_in) in tx.input.iter().enumerate() {
-      let Ok(inscriptions) = InscriptionParser::parse(&tx_in.witness) else { continue };
+      let Ok(inscriptions) = InscriptionParser::parse(&tx_in.witness) else {
+        continue;
+      };
 
       result.extend(
         inscriptions
@@ -68,30 +128,65 @@ impl Inscription {
     InscriptionParser::parse(witness)
   }
 
-  pub(crate) fn from_file(chain: Chain, path: impl AsRef<Path>) -> Result<Self, Error> {
+  pub(crate) fn from_file(
+    chain: Chain,
+    path: impl AsRef<Path>,
+    max_content_size: Option<usize>,
+  ) -> Result<Self, Error> {
+    Self::from_file_with_content_type(chain, path, None, max_content_size)
+  }
+
+  /// Like `from_file`, but with `content_type` instead of the content type
+  /// for the file's extension, if given.
+  ///
+  /// `max_content_size` replaces the chain's content size limit, but content
+  /// over the chain's limit is still warned about.
+  pub(crate) fn from_file_with_content_type(
+    chain: Chain,
+    path: impl AsRef<Path>,
+    content_type: Option<&str>,
+    max_content_size: Option<usize>,
+  ) -> Result<Self, Error> {
     let path = path.as_ref();
 
     let body = fs::read(path).with_context(|| format!("io error reading {}", path.display()))?;
 
+    let len = body.len();
+
+    if let Some(max) = max_content_size {
+      if len > max {
+        bail!("content size of {len} bytes exceeds maximum content size of {max} bytes");
+      }
+    }
+
     if let Some(limit) = chain.inscription_content_size_limit() {
-      let len = body.len();
       if len > limit {
-        bail!("content size of {len} bytes exceeds {limit} byte limit for {chain} inscriptions");
+        if max_content_size.is_none() {
+          bail!("content size of {len} bytes exceeds {limit} byte limit for {chain} inscriptions");
+        }
+
+        eprintln!(
+          "warning: content size of {len} bytes exceeds {limit} byte limit for {chain} inscriptions"
+        );
       }
     }
 
-    let content_type = Media::content_type_for_path(path)?;
+    let content_type = match content_type {
+      Some(content_type) => content_type,
+      None => Media::content_type_for_path(path)?,
+    };
 
     Ok(Self {
       body: Some(body),
       content_type: Some(content_type.into()),
+      content_encoding: None,
     })
   }
 
-  fn append_reveal_script_to_builder(
+  pub(crate) fn append_reveal_script_to_builder(
     &self,
     mut builder: script::Builder,
-    cursed: bool,
+    tags: &[Tag],
     end_with_1: bool,
   ) -> script::Builder {
     builder = builder
@@ -105,9 +200,17 @@ impl Inscription {
         .push_slice(PushBytesBuf::try_from(content_type).unwrap());
     }
 
-    if cursed {
-      log::info!("Appending cursed tag");
-      builder = builder.push_slice(CURSED_TAG).push_slice(CURSED_ID);
+    if let Some(content_encoding) = self.content_encoding.clone() {
+      builder = builder
+        .push_slice(CONTENT_ENCODING_TAG)
+        .push_slice(PushBytesBuf::try_from(content_encoding).unwrap());
+    }
+
+    for tag in tags {
+      log::info!("Appending tag {}", tag.number);
+      builder = builder
+        .push_slice([tag.number])
+        .push_slice(PushBytesBuf::try_from(tag.value.clone()).unwrap());
     }
 
     if let Some(body) = &self.body {
@@ -126,9 +229,14 @@ impl Inscription {
     }
   }
 
-  pub(crate) fn append_reveal_script(&self, builder: script::Builder, cursed: bool, end_with_1: bool) -> ScriptBuf {
+  pub(crate) fn append_reveal_script(
+    &self,
+    builder: script::Builder,
+    tags: &[Tag],
+    end_with_1: bool,
+  ) -> ScriptBuf {
     self
-      .append_reveal_script_to_builder(builder, cursed, end_with_1)
+      .append_reveal_script_to_builder(builder, tags, end_with_1)
       .into_script()
   }
 
@@ -144,194 +252,142 @@ impl Inscription {
     content_type.parse().unwrap_or(Media::Unknown)
   }
 
-  pub(crate) fn body(&self) -> Option<&[u8]> {
+  pub fn body(&self) -> Option<&[u8]> {
     Some(self.body.as_ref()?)
   }
 
-  pub(crate) fn into_body(self) -> Option<Vec<u8>> {
-    self.body
-  }
-
-  pub(crate) fn content_length(&self) -> Option<usize> {
-    Some(self.body()?.len())
-  }
-
-  pub(crate) fn content_type(&self) -> Option<&str> {
-    str::from_utf8(self.content_type.as_ref()?).ok()
-  }
-
-  #[cfg(test)]
-  pub(crate) fn to_witness(&self) -> Witness {
-    let builder = script::Builder::new();
-
-    let script = self.append_reveal_script(builder, false, false);
+  /// The body, decompressed if it is brotli encoded and valid.
+  pub(crate) fn into_decoded_body(self) -> Option<Vec<u8>> {
+    let body = self.body?;
 
-    let mut witness = Witness::new();
+    if self.content_encoding.as_deref() != Some(b"br") {
+      return Some(body);
+    }
 
-    witness.push(script);
-    witness.push([]);
+    let mut decoded = Vec::new();
 
-    witness
+    match brotli::BrotliDecompress(&mut body.as_slice(), &mut decoded) {
+      Ok(()) => Some(decoded),
+      Err(_) => Some(body),
+    }
   }
-}
-
-#[derive(Debug, PartialEq)]
-pub(crate) enum InscriptionError {
-  EmptyWitness,
-  InvalidInscription,
-  KeyPathSpend,
-  NoInscription,
-  Script(script::Error),
-  UnrecognizedEvenField,
-}
-
-type Result<T, E = InscriptionError> = std::result::Result<T, E>;
 
-#[derive(Debug)]
-struct InscriptionParser<'a> {
-  instructions: Peekable<Instructions<'a>>,
-}
+  /// The cheapest encoding of this inscription that is served the same way:
+  /// without a content type if it is the default, and, with `compress`, with
+  /// a brotli compressed body if that is smaller. Bodies are always pushed in
+  /// as few minimal pushes as possible, so those don't change.
+  pub(crate) fn optimize(&self, compress: bool) -> Result<Self, Error> {
+    let mut optimized = self.clone();
 
-impl<'a> InscriptionParser<'a> {
-  fn parse(witness: &Witness) -> Result<Vec<Inscription>> {
-    if witness.is_empty() {
-      return Err(InscriptionError::EmptyWitness);
+    if self.content_type() == Some(DEFAULT_CONTENT_TYPE) {
+      optimized.content_type = None;
     }
 
-    if witness.len() == 1 {
-      return Err(InscriptionError::KeyPathSpend);
-    }
+    if let (true, None, Some(body)) = (compress, &self.content_encoding, &self.body) {
+      let mut compressed = Vec::new();
+
+      brotli::BrotliCompress(
+        &mut body.as_slice(),
+        &mut compressed,
+        &brotli::enc::BrotliEncoderParams {
+          quality: 11,
+          lgwin: 24,
+          ..Default::default()
+        },
+      )?;
 
-    let annex = witness
-      .last()
-      .and_then(|element| element.first().map(|byte| *byte == TAPROOT_ANNEX_PREFIX))
-      .unwrap_or(false);
+      let compressed = Self {
+        body: Some(compressed),
+        content_encoding: Some(b"br".to_vec()),
+        ..optimized.clone()
+      };
 
-    if witness.len() == 2 && annex {
-      return Err(InscriptionError::KeyPathSpend);
+      if compressed.envelope_size() < optimized.envelope_size() {
+        optimized = compressed;
+      }
     }
 
-    let script = witness
-      .iter()
-      .nth(if annex {
-        witness.len() - 1
-      } else {
-        witness.len() - 2
-      })
-      .unwrap();
+    Ok(optimized)
+  }
 
-    InscriptionParser {
-      instructions: ScriptBuf::from(Vec::from(script)).instructions().peekable(),
-    }
-    .parse_inscriptions()
-    .into_iter()
-    .collect()
+  /// The size of the inscription's envelope, which, since it is in the
+  /// witness, is also its weight.
+  pub(crate) fn envelope_size(&self) -> usize {
+    self
+      .append_reveal_script(script::Builder::new(), &[], false)
+      .len()
   }
 
-  fn parse_inscriptions(&mut self) -> Vec<Result<Inscription>> {
-    let mut inscriptions = Vec::new();
-    loop {
-      let current = self.parse_one_inscription();
-      if current == Err(InscriptionError::NoInscription) {
-        break;
-      }
-      inscriptions.push(current);
-    }
+  pub fn content_length(&self) -> Option<usize> {
+    Some(self.body()?.len())
+  }
 
-    inscriptions
+  pub fn content_type(&self) -> Option<&str> {
+    str::from_utf8(self.content_type.as_ref()?).ok()
   }
 
-  fn parse_one_inscription(&mut self) -> Result<Inscription> {
-    self.advance_into_inscription_envelope()?;
-    let mut fields = BTreeMap::new();
+  pub fn content_encoding(&self) -> Option<&str> {
+    str::from_utf8(self.content_encoding.as_ref()?).ok()
+  }
 
-    loop {
-      match self.advance()? {
-        Instruction::PushBytes(tag) if tag.as_bytes() == BODY_TAG.as_slice() => {
-          let mut body = Vec::new();
-          while !self.accept(&Instruction::Op(opcodes::all::OP_ENDIF))? {
-            body.extend_from_slice(self.expect_push()?);
-          }
-          fields.insert(BODY_TAG.as_slice(), body);
-          break;
-        }
-        Instruction::PushBytes(tag) => {
-          if fields.contains_key(tag.as_bytes()) {
-            return Err(InscriptionError::InvalidInscription);
-          }
-          fields.insert(tag.as_bytes(), self.expect_push()?.to_vec());
-        }
-        Instruction::Op(opcodes::all::OP_ENDIF) => break,
-        _ => return Err(InscriptionError::InvalidInscription),
-      }
-    }
+  /// Whether the content type is `filter`, ignoring parameters like
+  /// `;charset=utf-8` unless `filter` has them, or, if `filter` is like
+  /// `image/*`, any subtype of it.
+  pub(crate) fn content_type_matches(&self, filter: &str) -> bool {
+    let Some(content_type) = self.content_type() else {
+      return false;
+    };
 
-    let body = fields.remove(BODY_TAG.as_slice());
-    let content_type = fields.remove(CONTENT_TYPE_TAG.as_slice());
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
 
-    for tag in fields.keys() {
-      if let Some(lsb) = tag.first() {
-        if lsb % 2 == 0 {
-          return Err(InscriptionError::UnrecognizedEvenField);
-        }
-      }
+    match filter.strip_suffix('*') {
+      Some(prefix) if prefix.ends_with('/') => essence.starts_with(prefix),
+      _ => essence == filter || content_type == filter,
     }
-
-    Ok(Inscription { body, content_type })
   }
 
-  fn advance(&mut self) -> Result<Instruction<'a>> {
-    self
-      .instructions
-      .next()
-      .ok_or(InscriptionError::NoInscription)?
-      .map_err(InscriptionError::Script)
-  }
-
-  fn advance_into_inscription_envelope(&mut self) -> Result<()> {
-    loop {
-      if self.match_instructions(&[
-        Instruction::PushBytes((&[]).into()), // represents an OF_FALSE
-        Instruction::Op(opcodes::all::OP_IF),
-        Instruction::PushBytes((&PROTOCOL_ID).into()),
-      ])? {
-        break;
-      }
-    }
+  #[cfg(test)]
+  pub(crate) fn to_witness(&self) -> Witness {
+    let builder = script::Builder::new();
 
-    Ok(())
-  }
+    let script = self.append_reveal_script(builder, &[], false);
 
-  fn match_instructions(&mut self, instructions: &[Instruction]) -> Result<bool> {
-    for instruction in instructions {
-      if &self.advance()? != instruction {
-        return Ok(false);
-      }
-    }
+    let mut witness = Witness::new();
 
-    Ok(true)
+    witness.push(script);
+    witness.push([]);
+
+    witness
   }
+}
+
+pub(crate) use ord_envelope::Error as InscriptionError;
 
-  fn expect_push(&mut self) -> Result<&'a [u8]> {
-    match self.advance()? {
-      Instruction::PushBytes(bytes) => Ok(bytes.as_bytes()),
-      _ => Err(InscriptionError::InvalidInscription),
+type Result<T, E = InscriptionError> = std::result::Result<T, E>;
+
+impl From<ord_envelope::Envelope> for Inscription {
+  fn from(envelope: ord_envelope::Envelope) -> Self {
+    Self {
+      body: envelope.body,
+      content_type: envelope.content_type,
+      content_encoding: envelope.content_encoding,
     }
   }
+}
 
-  fn accept(&mut self, instruction: &Instruction) -> Result<bool> {
-    match self.instructions.peek() {
-      Some(Ok(next)) => {
-        if next == instruction {
-          self.advance()?;
-          Ok(true)
-        } else {
-          Ok(false)
-        }
-      }
-      Some(Err(err)) => Err(InscriptionError::Script(*err)),
-      None => Ok(false),
-    }
+/// Envelopes are parsed by the `ord-envelope` crate, which has C and
+/// WebAssembly bindings, so that other programs parse them exactly the same
+/// way.
+struct InscriptionParser;
+
+impl InscriptionParser {
+  fn parse(witness: &Witness) -> Result<Vec<Inscription>> {
+    Ok(
+      ord_envelope::parse(witness)?
+        .into_iter()
+        .map(Inscription::from)
+        .collect(),
+    )
   }
 }
 
@@ -435,6 +491,7 @@ mod tests {
       Ok(vec![Inscription {
         content_type: Some(b"text/plain;charset=utf-8".to_vec()),
         body: None,
+        content_encoding: None,
       }]),
     );
   }
@@ -446,6 +503,7 @@ mod tests {
       Ok(vec![Inscription {
         content_type: None,
         body: Some(b"foo".to_vec()),
+        content_encoding: None,
       }]),
     );
   }
@@ -669,8 +727,8 @@ mod tests {
   #[test]
   fn extract_from_second_envelope() {
     let mut builder = script::Builder::new();
-    builder = inscription("foo", [1; 100]).append_reveal_script_to_builder(builder, false, false);
-    builder = inscription("bar", [1; 100]).append_reveal_script_to_builder(builder, false, false);
+    builder = inscription("foo", [1; 100]).append_reveal_script_to_builder(builder, &[], false);
+    builder = inscription("bar", [1; 100]).append_reveal_script_to_builder(builder, &[], false);
 
     let witness = Witness::from_slice(&[builder.into_script().into_bytes(), Vec::new()]);
 
@@ -707,7 +765,7 @@ mod tests {
   fn reveal_script_chunks_data() {
     assert_eq!(
       inscription("foo", [])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       7
@@ -715,7 +773,7 @@ mod tests {
 
     assert_eq!(
       inscription("foo", [0; 1])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       8
@@ -723,7 +781,7 @@ mod tests {
 
     assert_eq!(
       inscription("foo", [0; 520])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       8
@@ -731,7 +789,7 @@ mod tests {
 
     assert_eq!(
       inscription("foo", [0; 521])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       9
@@ -739,7 +797,7 @@ mod tests {
 
     assert_eq!(
       inscription("foo", [0; 1040])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       9
@@ -747,7 +805,7 @@ mod tests {
 
     assert_eq!(
       inscription("foo", [0; 1041])
-        .append_reveal_script(script::Builder::new(), false, false)
+        .append_reveal_script(script::Builder::new(), &[], false)
         .instructions()
         .count(),
       10
@@ -758,8 +816,11 @@ mod tests {
   fn chunked_data_is_parsable() {
     let mut witness = Witness::new();
 
-    witness
-      .push(&inscription("foo", [1; 1040]).append_reveal_script(script::Builder::new(), false), false);
+    witness.push(&inscription("foo", [1; 1040]).append_reveal_script(
+      script::Builder::new(),
+      &[],
+      false,
+    ));
 
     witness.push([]);
 
@@ -777,8 +838,9 @@ mod tests {
       &Inscription {
         content_type: None,
         body: None,
+        content_encoding: None,
       }
-      .append_reveal_script(script::Builder::new(), false, false),
+      .append_reveal_script(script::Builder::new(), &[], false),
     );
 
     witness.push([]);
@@ -788,6 +850,7 @@ mod tests {
       vec![Inscription {
         content_type: None,
         body: None,
+        content_encoding: None,
       }]
     );
   }
@@ -799,10 +862,61 @@ mod tests {
       Ok(vec![Inscription {
         content_type: None,
         body: None,
+        content_encoding: None,
       }]),
     );
   }
 
+  #[test]
+  fn content_encoding_is_parsed() {
+    let inscription = InscriptionParser::parse(&envelope(&[b"ord", &[9], b"br", &[], b"foo"]))
+      .unwrap()
+      .remove(0);
+
+    assert_eq!(inscription.content_encoding(), Some("br"));
+    assert_eq!(inscription.body(), Some(b"foo".as_slice()));
+  }
+
+  #[test]
+  fn optimize_strips_default_content_type() {
+    let optimized = inscription(DEFAULT_CONTENT_TYPE, "foo")
+      .optimize(false)
+      .unwrap();
+
+    assert_eq!(optimized.content_type(), None);
+    assert_eq!(optimized, Inscription::new(None, Some(b"foo".to_vec())));
+
+    let optimized = inscription("text/plain", "foo").optimize(false).unwrap();
+
+    assert_eq!(optimized.content_type(), Some("text/plain"));
+  }
+
+  #[test]
+  fn optimize_only_compresses_when_smaller() {
+    let optimized = inscription("text/plain", "foo").optimize(true).unwrap();
+
+    assert_eq!(optimized.content_encoding(), None);
+    assert_eq!(optimized.body(), Some(b"foo".as_slice()));
+
+    let original = inscription("text/plain", "foo".repeat(1000));
+    let optimized = original.optimize(true).unwrap();
+
+    assert_eq!(optimized.content_encoding(), Some("br"));
+    assert!(optimized.envelope_size() < original.envelope_size());
+
+    let mut witness = Witness::new();
+    witness.push(optimized.append_reveal_script(script::Builder::new(), &[], false));
+    witness.push([]);
+
+    assert_eq!(
+      InscriptionParser::parse(&witness)
+        .unwrap()
+        .remove(0)
+        .into_decoded_body(),
+      Some("foo".repeat(1000).into_bytes()),
+    );
+  }
+
   #[test]
   fn unknown_even_fields_are_invalid() {
     assert_eq!(
@@ -810,4 +924,53 @@ mod tests {
       Err(InscriptionError::UnrecognizedEvenField),
     );
   }
+
+  #[test]
+  fn parse_tag() {
+    assert_eq!(
+      "5=cafe".parse::<Tag>().unwrap(),
+      Tag {
+        number: 5,
+        value: vec![0xca, 0xfe],
+      }
+    );
+    assert!("5".parse::<Tag>().is_err());
+    assert!("256=00".parse::<Tag>().is_err());
+    assert!("1=00".parse::<Tag>().is_err());
+    assert!("9=6272".parse::<Tag>().is_err());
+    assert!("5=xyz".parse::<Tag>().is_err());
+  }
+
+  #[test]
+  fn extra_tags_are_appended_to_envelope() {
+    let mut witness = Witness::new();
+
+    witness.push(&inscription("foo", [1; 10]).append_reveal_script(
+      script::Builder::new(),
+      &["5=cafe".parse().unwrap()],
+      false,
+    ));
+
+    witness.push([]);
+
+    assert_eq!(
+      InscriptionParser::parse(&witness).unwrap(),
+      vec![inscription("foo", [1; 10])],
+    );
+
+    let mut witness = Witness::new();
+
+    witness.push(&inscription("foo", [1; 10]).append_reveal_script(
+      script::Builder::new(),
+      &[Tag::cursed66()],
+      false,
+    ));
+
+    witness.push([]);
+
+    assert_eq!(
+      InscriptionParser::parse(&witness),
+      Err(InscriptionError::UnrecognizedEvenField),
+    );
+  }
 }
@@ -20,8 +20,8 @@ use {
     deserialize_from_str::DeserializeFromStr,
     epoch::Epoch,
     height::Height,
-    index::{Index, List},
-    inscription::Inscription,
+    index::{Durability, Index, List},
+    inscription::{Inscription, DEFAULT_CONTENT_TYPE},
     inscription_id::InscriptionId,
     media::Media,
     options::Options,
@@ -52,7 +52,7 @@ use {
   serde::{Deserialize, Deserializer, Serialize, Serializer},
   std::{
     cmp,
-    collections::{BTreeMap, HashSet, VecDeque},
+    collections::{BTreeMap, BTreeSet, HashSet, VecDeque},
     env,
     ffi::OsString,
     fmt::{self, Display, Formatter},
@@ -97,6 +97,8 @@ macro_rules! tprintln {
     };
 }
 
+#[cfg(feature = "api")]
+pub mod api;
 mod arguments;
 mod blocktime;
 mod chain;
@@ -115,6 +117,7 @@ mod object;
 mod options;
 mod outgoing;
 mod page_config;
+mod proxy;
 pub mod rarity;
 mod representation;
 pub mod sat;
@@ -171,7 +174,7 @@ pub fn main() {
       process::exit(1);
     }
 
-    println!("Shutting down gracefully. Press <CTRL-C> again to shutdown immediately.");
+    eprintln!("Shutting down gracefully. Press <CTRL-C> again to shutdown immediately.");
 
     LISTENERS
       .lock()
@@ -74,6 +74,13 @@ impl Media {
     ))
   }
 
+  pub(crate) fn extension_for_content_type(content_type: &str) -> Option<&'static str> {
+    Self::TABLE
+      .iter()
+      .find(|(entry, _, _)| *entry == content_type)
+      .and_then(|(_, _, extensions)| extensions.first().cloned())
+  }
+
   pub(crate) fn check_mp4_codec(path: &Path) -> Result<(), Error> {
     let f = File::open(path)?;
     let size = f.metadata()?.len();
@@ -135,6 +142,17 @@ mod tests {
     );
   }
 
+  #[test]
+  fn extension_for_content_type() {
+    assert_eq!(Media::extension_for_content_type("image/jpeg"), Some("jpg"));
+    assert_eq!(
+      Media::extension_for_content_type("text/html;charset=utf-8"),
+      Some("html")
+    );
+    assert_eq!(Media::extension_for_content_type("image/avif"), None);
+    assert_eq!(Media::extension_for_content_type("foo/bar"), None);
+  }
+
   #[test]
   fn h264_in_mp4_is_allowed() {
     assert!(Media::check_mp4_codec(Path::new("examples/h264.mp4")).is_ok(),);
@@ -1,4 +1,4 @@
-use {super::*, bitcoincore_rpc::Auth};
+use {super::*, bitcoincore_rpc::Auth, proxy::ProxyTransport};
 
 #[derive(Clone, Default, Debug, Parser)]
 #[clap(group(
@@ -7,6 +7,11 @@ use {super::*, bitcoincore_rpc::Auth};
     .args(&["chain-argument", "signet", "regtest", "testnet"]),
 ))]
 pub(crate) struct Options {
+  #[clap(
+    long,
+    help = "Compact the index after `ord index run` updates it, if more than <AUTO_COMPACT_THRESHOLD> percent of the index file is fragmented. Compaction is offline: it needs exclusive access to the index, so commands that share the index, like `ord server`, never compact it."
+  )]
+  pub(crate) auto_compact_threshold: Option<f64>,
   #[clap(long, help = "Load Bitcoin Core data dir from <BITCOIN_DATA_DIR>.")]
   pub(crate) bitcoin_data_dir: Option<PathBuf>,
   #[clap(long, help = "Authenticate to Bitcoin Core RPC with <RPC_PASS>.")]
@@ -26,6 +31,11 @@ pub(crate) struct Options {
     help = "Commit changes to the index file on disk every <COMMIT> blocks."
   )]
   pub(crate) commit: usize,
+  #[clap(
+    long,
+    help = "Store sat ranges delta and varint encoded when creating a sat index, making it smaller but slower to update and query."
+  )]
+  pub(crate) compress_sat_ranges: bool,
   #[clap(long, help = "Load configuration from <CONFIG>.")]
   pub(crate) config: Option<PathBuf>,
   #[clap(long, help = "Load configuration from <CONFIG_DIR>.")]
@@ -39,6 +49,18 @@ pub(crate) struct Options {
     help = "Set index cache to <DB_CACHE_SIZE> bytes. By default takes 1/4 of available RAM."
   )]
   pub(crate) db_cache_size: Option<usize>,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "immediate",
+    help = "Commit index updates near the chain tip with <DURABILITY>."
+  )]
+  pub(crate) durability: Durability,
+  #[clap(
+    long,
+    help = "Link to inscriptions on the explorer at <EXPLORER_URL> in command output. Defaults to ordinals.com for the chain in use."
+  )]
+  pub(crate) explorer_url: Option<String>,
   #[clap(
     long,
     help = "Don't look for inscriptions below <FIRST_INSCRIPTION_HEIGHT>."
@@ -48,29 +70,71 @@ pub(crate) struct Options {
   pub(crate) height_limit: Option<u64>,
   #[clap(long, help = "Use index at <INDEX>.")]
   pub(crate) index: Option<PathBuf>,
+  #[clap(
+    long,
+    help = "Track the unspent outputs of every address, for `ord address`."
+  )]
+  pub(crate) index_addresses: bool,
+  #[clap(
+    long,
+    help = "If another ord process has the index open, wait up to <INDEX_LOCK_TIMEOUT> seconds for it to close the index."
+  )]
+  pub(crate) index_lock_timeout: Option<u64>,
   #[clap(long, help = "Track location of all satoshis.")]
   pub(crate) index_sats: bool,
+  #[clap(
+    long,
+    help = "If another ord process has the index open, query a copy of the index as it is now, without updating it. Only for commands that don't change the index. The whole index is copied, which takes as much disk space as the index and can take a long time, and the copy is only consistent if the other process doesn't update the index while it's copied, so if it keeps updating it, stop it first."
+  )]
+  pub(crate) index_snapshot: bool,
   #[clap(
     long,
     help = "Track location of all satoshis and the utxos that own them. Implies --index-sats."
   )]
   pub(crate) index_utxos: bool,
+  #[clap(
+    long,
+    help = "Allow inscription content of up to <MAX_CONTENT_SIZE> bytes, in place of the chain's limit. Content over the chain's limit is warned about."
+  )]
+  pub(crate) max_content_size: Option<usize>,
   #[clap(
     long,
     help = "Inhibit the display of the progress bar while updating the index."
   )]
   pub(crate) no_progress_bar: bool,
+  #[clap(
+    long,
+    help = "Use the index as it is, without updating it from Bitcoin Core first."
+  )]
+  pub(crate) no_sync: bool,
+  #[clap(
+    long,
+    help = "Connect to Bitcoin Core RPC through the SOCKS5 proxy at <PROXY>, like `socks5h://127.0.0.1:9050` for Tor. Host names are resolved by the proxy with `socks5h`, so onion services can be reached."
+  )]
+  pub(crate) proxy: Option<String>,
   #[clap(long, short, help = "Use regtest. Equivalent to `--chain regtest`.")]
   pub(crate) regtest: bool,
   #[clap(long, help = "Connect to Bitcoin Core RPC at <RPC_URL>.")]
   pub(crate) rpc_url: Option<String>,
   #[clap(long, short, help = "Use signet. Equivalent to `--chain signet`.")]
   pub(crate) signet: bool,
+  #[clap(
+    long,
+    help = "Use the custom signet whose block challenge is the hex script <SIGNET_CHALLENGE>. Requires `--chain signet`. Custom signets are indexed separately from the default signet."
+  )]
+  pub(crate) signet_challenge: Option<String>,
   #[clap(
     long,
     help = "Don't index any zero-sat outputs. This is new and experimental and may break your index file."
   )]
   pub(crate) skip_empty_outputs: bool,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "immediate",
+    help = "Commit index updates more than 21 blocks behind the chain tip with <SYNC_DURABILITY>. `eventual` speeds up initial sync, but a crash may lose progress since the last `immediate` or `paranoid` commit."
+  )]
+  pub(crate) sync_durability: Durability,
   #[clap(long, short, help = "Use testnet. Equivalent to `--chain testnet`.")]
   pub(crate) testnet: bool,
   #[clap(long, default_value = "ord", help = "Use wallet named <WALLET>.")]
@@ -97,7 +161,8 @@ impl Options {
   }
 
   pub(crate) fn first_inscription_height(&self) -> u64 {
-    if self.chain() == Chain::Regtest {
+    // custom signets can have inscriptions from the start
+    if self.chain() == Chain::Regtest || self.signet_challenge.is_some() {
       self.first_inscription_height.unwrap_or(0)
     } else if integration_test() {
       0
@@ -109,13 +174,31 @@ impl Options {
   }
 
   pub(crate) fn rpc_url(&self) -> String {
+    // Bitcoin Core wallet names may contain characters that aren't allowed in
+    // a URL path, such as spaces and slashes
+    let wallet = self
+      .wallet
+      .bytes()
+      .map(|byte| {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+          char::from(byte).to_string()
+        } else {
+          format!("%{byte:02X}")
+        }
+      })
+      .collect::<String>();
+
     if let Some(rpc_url) = &self.rpc_url {
-      format!("{rpc_url}/wallet/{}", self.wallet)
+      // --wallet takes precedence over a wallet in --rpc-url
+      let rpc_url = rpc_url
+        .split_once("/wallet/")
+        .map_or(rpc_url.as_str(), |(rpc_url, _wallet)| rpc_url);
+
+      format!("{rpc_url}/wallet/{wallet}")
     } else {
       format!(
-        "127.0.0.1:{}/wallet/{}",
+        "127.0.0.1:{}/wallet/{wallet}",
         self.chain().default_rpc_port(),
-        self.wallet
       )
     }
   }
@@ -150,7 +233,29 @@ impl Options {
         .join("ord"),
     };
 
-    Ok(self.chain().join_with_data_dir(&base))
+    let data_dir = self.chain().join_with_data_dir(&base);
+
+    Ok(match self.signet_challenge()? {
+      Some(challenge) => data_dir.join(format!(
+        "challenge-{}",
+        &bitcoin::hashes::sha256::Hash::hash(challenge.as_bytes()).to_string()[..16]
+      )),
+      None => data_dir,
+    })
+  }
+
+  pub(crate) fn signet_challenge(&self) -> Result<Option<ScriptBuf>> {
+    let Some(challenge) = &self.signet_challenge else {
+      return Ok(None);
+    };
+
+    if self.chain() != Chain::Signet {
+      bail!("--signet-challenge requires `--chain signet`");
+    }
+
+    Ok(Some(
+      ScriptBuf::from_hex(challenge).context("--signet-challenge is not a valid hex script")?,
+    ))
   }
 
   pub(crate) fn load_config(&self) -> Result<Config> {
@@ -223,6 +328,56 @@ impl Options {
     }
   }
 
+  pub(crate) fn explorer_url(&self) -> Result<String> {
+    let config = self.load_config()?;
+
+    Ok(
+      Options::derive_var(
+        self.explorer_url.as_deref(),
+        Some("EXPLORER_URL"),
+        config.explorer_url.as_deref(),
+        Some(self.chain().explorer_url()),
+      )?
+      .unwrap()
+      .trim_end_matches('/')
+      .into(),
+    )
+  }
+
+  pub(crate) fn max_content_size(&self) -> Result<Option<usize>> {
+    Ok(
+      self
+        .max_content_size
+        .or(self.load_config()?.max_content_size),
+    )
+  }
+
+  pub(crate) fn proxy(&self) -> Result<Option<String>> {
+    let config = self.load_config()?;
+
+    let proxy = Options::derive_var(
+      self.proxy.as_deref(),
+      Some("PROXY"),
+      config.proxy.as_deref(),
+      None,
+    )?;
+
+    if let Some(proxy) = &proxy {
+      if !proxy.starts_with("socks5://") && !proxy.starts_with("socks5h://") {
+        bail!("proxy {proxy} is not a `socks5://` or `socks5h://` URL");
+      }
+    }
+
+    Ok(proxy)
+  }
+
+  fn connect(&self, rpc_url: &str, auth: Auth) -> Result<Client> {
+    match self.proxy()? {
+      Some(proxy) => ProxyTransport::client(rpc_url, auth, &proxy),
+      None => Ok(Client::new(rpc_url, auth)?),
+    }
+  }
+
   pub(crate) fn bitcoin_rpc_client(&self) -> Result<Client> {
     let rpc_url = self.rpc_url();
 
@@ -237,12 +392,14 @@ impl Options {
       );
     }
 
-    let client = Client::new(&rpc_url, auth)
+    let client = self
+      .connect(&rpc_url, auth)
       .with_context(|| format!("failed to connect to Bitcoin Core RPC at {rpc_url}"))?;
 
     let rpc_chain = match client.get_blockchain_info()?.chain.as_str() {
       "main" => Chain::Mainnet,
       "test" => Chain::Testnet,
+      "testnet4" => Chain::Testnet4,
       "regtest" => Chain::Regtest,
       "signet" => Chain::Signet,
       other => bail!("Bitcoin RPC server on unknown chain: {other}"),
@@ -254,9 +411,32 @@ impl Options {
       bail!("Bitcoin RPC server is on {rpc_chain} but ord is on {ord_chain}");
     }
 
+    if let Some(challenge) = self.signet_challenge()? {
+      // only reported by Bitcoin Core 23.0 and later
+      if let Some(rpc_challenge) =
+        client.call::<serde_json::Value>("getblockchaininfo", &[])?["signet_challenge"].as_str()
+      {
+        if ScriptBuf::from_hex(rpc_challenge)? != challenge {
+          bail!("Bitcoin RPC server is on signet with challenge {rpc_challenge}, not the one given with --signet-challenge");
+        }
+      }
+    }
+
     Ok(client)
   }
 
+  /// A client that isn't checked against Bitcoin Core, for use with
+  /// `--no-sync`, when there may be no node to connect to.
+  pub(crate) fn unchecked_bitcoin_rpc_client(&self) -> Result<Client> {
+    let rpc_url = self.rpc_url();
+
+    Ok(
+      self
+        .connect(&rpc_url, self.auth().unwrap_or(Auth::None))
+        .or_else(|_| self.connect(&rpc_url, Auth::None))?,
+    )
+  }
+
   pub(crate) fn bitcoin_rpc_client_for_wallet_command(&self, create: bool) -> Result<Client> {
     let client = self.bitcoin_rpc_client()?;
 
@@ -488,6 +668,59 @@ mod tests {
     );
   }
 
+  #[test]
+  fn custom_signet_data_dir() {
+    let options = Arguments::try_parse_from([
+      "ord",
+      "--chain=signet",
+      "--signet-challenge=51",
+      "--data-dir",
+      "foo",
+      "index",
+      "run",
+    ])
+    .unwrap()
+    .options;
+
+    let data_dir = options.data_dir().unwrap().display().to_string();
+
+    let challenge = &bitcoin::hashes::sha256::Hash::hash(&[0x51]).to_string()[..16];
+
+    assert!(
+      data_dir.ends_with(&if cfg!(windows) {
+        format!(r"foo\signet\challenge-{challenge}")
+      } else {
+        format!("foo/signet/challenge-{challenge}")
+      }),
+      "{data_dir}"
+    );
+
+    assert_eq!(options.first_inscription_height(), 0);
+  }
+
+  #[test]
+  fn signet_challenge_requires_signet() {
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--signet-challenge=51", "index", "run"])
+        .unwrap()
+        .options
+        .data_dir()
+        .unwrap_err()
+        .to_string(),
+      "--signet-challenge requires `--chain signet`"
+    );
+
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--signet", "--signet-challenge=xyz", "index", "run"])
+        .unwrap()
+        .options
+        .data_dir()
+        .unwrap_err()
+        .to_string(),
+      "--signet-challenge is not a valid hex script"
+    );
+  }
+
   #[test]
   fn network_accepts_aliases() {
     fn check_network_alias(alias: &str, suffix: &str) {
@@ -536,6 +769,14 @@ mod tests {
         "ord/testnet3"
       },
     );
+    check_network_alias(
+      "testnet4",
+      if cfg!(windows) {
+        r"ord\testnet4"
+      } else {
+        "ord/testnet4"
+      },
+    );
   }
 
   #[test]
@@ -618,6 +859,31 @@ mod tests {
     );
   }
 
+  #[test]
+  fn rpc_url_targets_wallet() {
+    assert_eq!(
+      Arguments::try_parse_from([
+        "ord",
+        "--rpc-url=127.0.0.1:1234/wallet/foo",
+        "--wallet=bar",
+        "index",
+        "run"
+      ])
+      .unwrap()
+      .options
+      .rpc_url(),
+      "127.0.0.1:1234/wallet/bar"
+    );
+
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--wallet=my collection/1", "index", "run"])
+        .unwrap()
+        .options
+        .rpc_url(),
+      "127.0.0.1:8332/wallet/my%20collection%2F1"
+    );
+  }
+
   #[test]
   fn wallet_flag_overrides_default_name() {
     assert_eq!(
@@ -696,6 +962,79 @@ mod tests {
     );
   }
 
+  #[test]
+  fn explorer_url() {
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--chain=signet", "index", "run"])
+        .unwrap()
+        .options
+        .explorer_url()
+        .unwrap(),
+      "https://signet.ordinals.com"
+    );
+
+    let tempdir = TempDir::new().unwrap();
+    let path = tempdir.path().join("ord.yaml");
+    fs::write(&path, "hidden:\nexplorer_url: https://foo.com/").unwrap();
+
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--config", path.to_str().unwrap(), "index", "run"])
+        .unwrap()
+        .options
+        .explorer_url()
+        .unwrap(),
+      "https://foo.com"
+    );
+
+    assert_eq!(
+      Arguments::try_parse_from([
+        "ord",
+        "--config",
+        path.to_str().unwrap(),
+        "--explorer-url",
+        "https://bar.com",
+        "index",
+        "run"
+      ])
+      .unwrap()
+      .options
+      .explorer_url()
+      .unwrap(),
+      "https://bar.com"
+    );
+  }
+
+  #[test]
+  fn proxy() {
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "index", "run"])
+        .unwrap()
+        .options
+        .proxy()
+        .unwrap(),
+      None
+    );
+
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--proxy", "socks5h://127.0.0.1:9050", "index", "run"])
+        .unwrap()
+        .options
+        .proxy()
+        .unwrap(),
+      Some("socks5h://127.0.0.1:9050".into())
+    );
+
+    assert_eq!(
+      Arguments::try_parse_from(["ord", "--proxy", "http://127.0.0.1:8080", "index", "run"])
+        .unwrap()
+        .options
+        .proxy()
+        .unwrap_err()
+        .to_string(),
+      "proxy http://127.0.0.1:8080 is not a `socks5://` or `socks5h://` URL"
+    );
+  }
+
   #[test]
   fn config_is_loaded_from_config_dir_option_path() {
     let id = "8d363b28528b0cb86b5fd48615493fb175bdf132d2a3d20b4251bba3f130a5abi0"
@@ -803,6 +1142,33 @@ mod tests {
     );
   }
 
+  #[test]
+  fn no_sync_flag() {
+    let arguments = Arguments::try_parse_from(["ord", "--no-sync", "find", "0"]).unwrap();
+    assert!(arguments.options.no_sync);
+    assert!(arguments.options.unchecked_bitcoin_rpc_client().is_ok());
+  }
+
+  #[test]
+  fn durability_options() {
+    let arguments = Arguments::try_parse_from(["ord", "index", "run"]).unwrap();
+    assert_eq!(arguments.options.durability, Durability::Immediate);
+    assert_eq!(arguments.options.sync_durability, Durability::Immediate);
+
+    let arguments = Arguments::try_parse_from([
+      "ord",
+      "--durability",
+      "paranoid",
+      "--sync-durability",
+      "eventual",
+      "index",
+      "run",
+    ])
+    .unwrap();
+    assert_eq!(arguments.options.durability, Durability::Paranoid);
+    assert_eq!(arguments.options.sync_durability, Durability::Eventual);
+  }
+
   #[test]
   fn setting_db_cache_size() {
     let arguments =
@@ -4,9 +4,12 @@ use super::*;
 pub(crate) enum Outgoing {
   Amount(Amount),
   All, // send all cardinals
+  // send all cardinals except for a reserve, which stays in the wallet
+  AllMinus(Amount),
   Max, // only send cardinals that can pay for their own fees to maximize the output amount
   InscriptionId(InscriptionId),
   SatPoint(SatPoint),
+  Sat(Sat), // given by name or degree notation, and sent from wherever it is in the wallet
 }
 
 impl FromStr for Outgoing {
@@ -21,18 +24,29 @@ impl FromStr for Outgoing {
       Self::All
     } else if s == "max" {
       Self::Max
-    } else if s.contains(' ') {
-      Self::Amount(s.parse()?)
-    } else if let Some(i) = s.find(|c: char| c.is_alphabetic()) {
-      let mut s = s.to_owned();
-      s.insert(i, ' ');
-      Self::Amount(s.parse()?)
+    } else if let Some(reserve) = s.strip_prefix("all-") {
+      Self::AllMinus(parse_amount(reserve)?)
+    } else if s.contains('°') || s.chars().all(|c| c.is_ascii_lowercase()) {
+      Self::Sat(s.parse()?)
     } else {
-      Self::Amount(s.parse()?)
+      Self::Amount(parse_amount(s)?)
     })
   }
 }
 
+/// Parse an amount, with or without a space before the denomination.
+fn parse_amount(s: &str) -> Result<Amount> {
+  Ok(if s.contains(' ') {
+    s.parse()?
+  } else if let Some(i) = s.find(|c: char| c.is_alphabetic()) {
+    let mut s = s.to_owned();
+    s.insert(i, ' ');
+    s.parse()?
+  } else {
+    s.parse()?
+  })
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -71,6 +85,28 @@ mod tests {
       Outgoing::Amount("0 sat".parse().unwrap()),
     );
 
+    assert_eq!(
+      "all-0.01btc".parse::<Outgoing>().unwrap(),
+      Outgoing::AllMinus("0.01 btc".parse().unwrap()),
+    );
+
+    assert_eq!(
+      "all-1000 sat".parse::<Outgoing>().unwrap(),
+      Outgoing::AllMinus("1000 sat".parse().unwrap()),
+    );
+
+    assert!("all-".parse::<Outgoing>().is_err());
+
+    assert_eq!(
+      "nvtdijuwxlp".parse::<Outgoing>().unwrap(),
+      Outgoing::Sat(Sat(0)),
+    );
+
+    assert_eq!(
+      "0°0′0″0‴".parse::<Outgoing>().unwrap(),
+      Outgoing::Sat(Sat(0)),
+    );
+
     assert!("0".parse::<Outgoing>().is_err());
   }
 }
@@ -0,0 +1,269 @@
+use {
+  super::*,
+  base64::Engine,
+  bitcoincore_rpc::{jsonrpc, Auth},
+  reqwest::Url,
+  std::{
+    io::{Read, Write},
+    net::{IpAddr, TcpStream},
+  },
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A JSON-RPC transport that reaches Bitcoin Core through a SOCKS5 proxy,
+/// like Tor. Each request is sent over a new connection. With a `socks5h`
+/// proxy, host names are resolved by the proxy, so that onion services can be
+/// reached and no DNS queries leak.
+pub(crate) struct ProxyTransport {
+  authorization: Option<String>,
+  host: String,
+  path: String,
+  port: u16,
+  proxy: String,
+  proxy_auth: Option<(String, String)>,
+  remote_dns: bool,
+}
+
+impl ProxyTransport {
+  pub(crate) fn client(rpc_url: &str, auth: Auth, proxy: &str) -> Result<Client> {
+    Ok(Client::from_jsonrpc(jsonrpc::Client::with_transport(
+      Self::new(rpc_url, auth, proxy)?,
+    )))
+  }
+
+  fn new(rpc_url: &str, auth: Auth, proxy: &str) -> Result<Self> {
+    let url = if rpc_url.starts_with("http://") {
+      rpc_url.to_string()
+    } else {
+      format!("http://{rpc_url}")
+    };
+
+    let url = Url::parse(&url).with_context(|| format!("invalid rpc url {url}"))?;
+
+    let proxy = Url::parse(proxy).with_context(|| format!("invalid proxy url {proxy}"))?;
+
+    let proxy_auth = match (proxy.username(), proxy.password()) {
+      ("", None) => None,
+      (user, password) => {
+        let password = password.unwrap_or_default();
+
+        if user.len() > 255 || password.len() > 255 {
+          bail!("proxy user name and password must be at most 255 bytes");
+        }
+
+        Some((user.to_string(), password.to_string()))
+      }
+    };
+
+    let authorization = match auth.get_user_pass()? {
+      (Some(user), password) => Some(
+        base64::engine::general_purpose::STANDARD
+          .encode(format!("{user}:{}", password.unwrap_or_default())),
+      ),
+      (None, _) => None,
+    };
+
+    let host = url
+      .host_str()
+      .ok_or_else(|| anyhow!("rpc url {url} has no host"))?
+      .trim_start_matches('[')
+      .trim_end_matches(']')
+      .to_string();
+
+    if host.len() > 255 {
+      bail!("rpc url host {host} is longer than 255 bytes");
+    }
+
+    Ok(Self {
+      authorization,
+      host,
+      path: url.path().into(),
+      port: url.port_or_known_default().unwrap_or(80),
+      proxy: format!(
+        "{}:{}",
+        proxy
+          .host_str()
+          .ok_or_else(|| anyhow!("proxy url {proxy} has no host"))?,
+        proxy.port().unwrap_or(1080)
+      ),
+      proxy_auth,
+      remote_dns: proxy.scheme() == "socks5h",
+    })
+  }
+
+  /// Open a connection to Bitcoin Core through the proxy, per RFC 1928, with
+  /// username and password authentication per RFC 1929.
+  fn connect(&self) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(&self.proxy)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    let method = if self.proxy_auth.is_some() {
+      0x02
+    } else {
+      0x00
+    };
+
+    stream.write_all(&[0x05, 0x01, method])?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply)?;
+
+    if reply != [0x05, method] {
+      return Err(proxy_error(
+        "proxy doesn't support the requested authentication",
+      ));
+    }
+
+    if let Some((user, password)) = &self.proxy_auth {
+      let mut request = vec![0x01, user.len() as u8];
+      request.extend(user.as_bytes());
+      request.push(password.len() as u8);
+      request.extend(password.as_bytes());
+      stream.write_all(&request)?;
+
+      stream.read_exact(&mut reply)?;
+
+      if reply[1] != 0x00 {
+        return Err(proxy_error("proxy rejected user name and password"));
+      }
+    }
+
+    let ip = match self.host.parse::<IpAddr>() {
+      Ok(ip) => Some(ip),
+      Err(_) if self.remote_dns => None,
+      Err(_) => (self.host.as_str(), self.port)
+        .to_socket_addrs()?
+        .next()
+        .map(|address| address.ip()),
+    };
+
+    let mut request = vec![0x05, 0x01, 0x00];
+    match ip {
+      Some(IpAddr::V4(ip)) => {
+        request.push(0x01);
+        request.extend(ip.octets());
+      }
+      Some(IpAddr::V6(ip)) => {
+        request.push(0x04);
+        request.extend(ip.octets());
+      }
+      None => {
+        request.push(0x03);
+        request.push(self.host.len() as u8);
+        request.extend(self.host.as_bytes());
+      }
+    }
+    request.extend(self.port.to_be_bytes());
+    stream.write_all(&request)?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+
+    if reply[1] != 0x00 {
+      return Err(proxy_error(&format!(
+        "proxy failed to connect to {}:{} with reply code {}",
+        self.host, self.port, reply[1]
+      )));
+    }
+
+    // the address the proxy bound to, which isn't needed
+    let address_len = match reply[3] {
+      0x01 => 4,
+      0x03 => {
+        let mut len = [0; 1];
+        stream.read_exact(&mut len)?;
+        len[0].into()
+      }
+      0x04 => 16,
+      _ => return Err(proxy_error("proxy replied with an unknown address type")),
+    };
+
+    io::copy(&mut (&mut stream).take(address_len + 2), &mut io::sink())?;
+
+    Ok(stream)
+  }
+
+  fn exchange(&self, request: &[u8], body: &[u8]) -> io::Result<Vec<u8>> {
+    let mut stream = self.connect()?;
+    stream.write_all(request)?;
+    stream.write_all(body)?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    Ok(response)
+  }
+
+  fn post<R: for<'a> Deserialize<'a>>(&self, body: &impl Serialize) -> Result<R, jsonrpc::Error> {
+    let body = serde_json::to_vec(body)?;
+
+    let mut request = format!(
+      "POST {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n",
+      self.path,
+      self.host,
+      self.port,
+      body.len(),
+    );
+
+    if let Some(authorization) = &self.authorization {
+      request.push_str(&format!("Authorization: Basic {authorization}\r\n"));
+    }
+
+    request.push_str("\r\n");
+
+    let response = self
+      .exchange(request.as_bytes(), &body)
+      .map_err(|err| jsonrpc::Error::Transport(Box::new(err)))?;
+
+    let (status, body) = match response.windows(4).position(|window| window == b"\r\n\r\n") {
+      Some(end) => (
+        String::from_utf8_lossy(&response[..end])
+          .lines()
+          .next()
+          .unwrap_or_default()
+          .to_string(),
+        &response[end + 4..],
+      ),
+      None => {
+        return Err(jsonrpc::Error::Transport(Box::new(proxy_error(
+          "malformed HTTP response from Bitcoin Core",
+        ))))
+      }
+    };
+
+    // Bitcoin Core sends RPC errors as JSON with error status codes, so the
+    // status only matters if the body can't be parsed
+    serde_json::from_slice(body).map_err(|err| {
+      jsonrpc::Error::Transport(Box::new(proxy_error(&format!(
+        "Bitcoin Core replied with `{status}`: {err}"
+      ))))
+    })
+  }
+}
+
+fn proxy_error(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::Other, message)
+}
+
+impl jsonrpc::Transport for ProxyTransport {
+  fn send_request(&self, request: jsonrpc::Request) -> Result<jsonrpc::Response, jsonrpc::Error> {
+    self.post(&request)
+  }
+
+  fn send_batch(
+    &self,
+    requests: &[jsonrpc::Request],
+  ) -> Result<Vec<jsonrpc::Response>, jsonrpc::Error> {
+    self.post(&requests)
+  }
+
+  fn fmt_target(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(
+      f,
+      "http://{}:{}{} via socks5 proxy {}",
+      self.host, self.port, self.path, self.proxy
+    )
+  }
+}
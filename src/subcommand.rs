@@ -1,53 +1,157 @@
-use super::*;
+use {super::*, std::cell::RefCell};
 
+pub mod address;
+pub mod audit;
+pub mod audit_content;
+pub mod block;
+pub mod broadcast;
 pub mod check_index;
 pub mod compact;
+mod daemon;
 pub mod decode;
+pub mod env;
 pub mod epochs;
 pub mod find;
-mod index;
+pub mod gallery;
+pub mod index;
+#[cfg(unix)]
+pub mod indexd;
 pub mod info;
 pub mod inscriptions;
 pub mod list;
+pub mod mempool;
+pub mod optimize;
 pub mod parse;
 mod preview;
+pub mod reassemble;
+pub mod scan_mempool;
 mod server;
+pub mod snapshot;
+pub mod stats;
 pub mod subsidy;
 pub mod supply;
 pub mod traits;
 pub mod transfer;
+pub mod verify_numbers;
 pub mod wallet;
+pub mod watch;
+
+thread_local! {
+  /// Receives command output instead of stdout while `ord daemon` runs a
+  /// command for a client.
+  static OUTPUT_SINK: RefCell<Option<Box<dyn Fn(serde_json::Value)>>> = RefCell::new(None);
+
+  /// Receives progress updates while `ord daemon` runs a command for a
+  /// client. On the command line, commands report progress on stderr.
+  static PROGRESS_SINK: RefCell<Option<Box<dyn Fn(String)>>> = RefCell::new(None);
+}
+
+fn capture_output<T>(sink: impl Fn(serde_json::Value) + 'static, f: impl FnOnce() -> T) -> T {
+  OUTPUT_SINK.with(|output_sink| output_sink.replace(Some(Box::new(sink))));
+  let result = f();
+  OUTPUT_SINK.with(|output_sink| output_sink.replace(None));
+  result
+}
+
+fn capture_progress<T>(sink: impl Fn(String) + 'static, f: impl FnOnce() -> T) -> T {
+  PROGRESS_SINK.with(|progress_sink| progress_sink.replace(Some(Box::new(sink))));
+  let result = f();
+  PROGRESS_SINK.with(|progress_sink| progress_sink.replace(None));
+  result
+}
+
+fn report_progress(status: impl Display) {
+  PROGRESS_SINK.with(|progress_sink| {
+    if let Some(sink) = &*progress_sink.borrow() {
+      sink(status.to_string());
+    }
+  });
+}
+
+fn send_to_output_sink(output: impl Serialize) -> Result<bool> {
+  OUTPUT_SINK.with(|output_sink| match &*output_sink.borrow() {
+    Some(sink) => {
+      sink(serde_json::to_value(output)?);
+      Ok(true)
+    }
+    None => Ok(false),
+  })
+}
 
 fn print_json(output: impl Serialize) -> Result {
+  if send_to_output_sink(&output)? {
+    return Ok(());
+  }
+
   serde_json::to_writer_pretty(io::stdout(), &output)?;
   println!();
   Ok(())
 }
 
+fn print_line(line: impl Display) -> Result {
+  if send_to_output_sink(line.to_string())? {
+    return Ok(());
+  }
+
+  println!("{line}");
+  Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub(crate) enum Subcommand {
+  #[clap(about = "Show an address's outputs, inscriptions, and rare sats")]
+  Address(address::Address),
+  #[clap(subcommand, about = "Audit log commands")]
+  Audit(audit::AuditSubcommand),
+  #[clap(about = "Check indexed inscription content against reveal transactions")]
+  AuditContent,
+  #[clap(about = "Show the inscriptions created and transferred in a block")]
+  Block(block::Block),
+  #[clap(about = "Broadcast transactions prepared with `ord wallet inscribe --dump`")]
+  Broadcast(broadcast::Broadcast),
   #[clap(about = "Check whether the index file needs recovery without attempting recovery")]
   CheckIndex(check_index::CheckIndex),
   #[clap(about = "Compact the index file if possible")]
   Compact,
+  #[clap(about = "Serve wallet commands over HTTP with a shared index")]
+  Daemon(daemon::Daemon),
   #[clap(about = "Decode inscription data from a transaction output")]
   Decode(decode::Decode),
+  #[clap(about = "Start a regtest bitcoind with a funded ord wallet for testing")]
+  Env(env::Env),
   #[clap(about = "List the first satoshis of each reward epoch")]
   Epochs,
   #[clap(about = "Run an explorer server populated with inscriptions")]
   Preview(preview::Preview),
   #[clap(about = "Find a satoshi's current location")]
   Find(find::Find),
+  #[clap(about = "Write a static HTML gallery of inscriptions")]
+  Gallery(gallery::Gallery),
   #[clap(subcommand, about = "Index commands")]
   Index(index::IndexSubcommand),
+  #[cfg(unix)]
+  #[clap(about = "Keep the index open for other ord commands to use")]
+  Indexd(indexd::Indexd),
   #[clap(about = "Display index statistics")]
   Info(info::Info),
   #[clap(about = "List all inscriptions")]
   Inscriptions(inscriptions::Inscriptions),
   #[clap(about = "List the satoshis in an output")]
   List(list::List),
+  #[clap(about = "Summarize mempool fee rates and inscriptions")]
+  Mempool,
+  #[clap(about = "Find the cheapest inscription envelope for a file")]
+  Optimize(optimize::Optimize),
   #[clap(about = "Parse a satoshi from ordinal notation")]
   Parse(parse::Parse),
+  #[clap(about = "Reassemble a file inscribed with `ord wallet shard` from its manifest")]
+  Reassemble(reassemble::Reassemble),
+  #[clap(about = "Show inscriptions in mempool transactions as they arrive")]
+  ScanMempool(scan_mempool::ScanMempool),
+  #[clap(about = "Write the owners of a list of inscriptions to a CSV file")]
+  Snapshot(snapshot::Snapshot),
+  #[clap(about = "Show inscription counts, bytes, and fees over time")]
+  Stats(stats::Stats),
   #[clap(about = "Display information about a block's subsidy")]
   Subsidy(subsidy::Subsidy),
   #[clap(about = "Run the explorer server")]
@@ -58,24 +162,83 @@ pub(crate) enum Subcommand {
   Traits(traits::Traits),
   #[clap(about = "Modify transfer log table")]
   Transfer(transfer::Transfer),
+  #[clap(about = "Check inscription numbers against another ordinals API")]
+  VerifyNumbers(verify_numbers::VerifyNumbers),
   #[clap(subcommand, about = "Wallet commands")]
   Wallet(wallet::Wallet),
+  #[clap(
+    subcommand,
+    about = "Watch satpoints, outpoints, and inscriptions for spends and transfers"
+  )]
+  Watch(watch::Watch),
 }
 
 impl Subcommand {
+  /// Whether this command only needs a shared index, and so can be run by
+  /// `ord indexd`.
+  pub(crate) fn uses_shared_index(&self) -> bool {
+    matches!(
+      self,
+      Self::Address(_)
+        | Self::AuditContent
+        | Self::Block(_)
+        | Self::Decode(_)
+        | Self::Find(_)
+        | Self::Gallery(_)
+        | Self::Info(_)
+        | Self::Inscriptions(_)
+        | Self::List(_)
+        | Self::Reassemble(_)
+        | Self::Snapshot(_)
+        | Self::Stats(_)
+        | Self::Transfer(_)
+        | Self::VerifyNumbers(_)
+        | Self::Wallet(_)
+    )
+  }
+
+  /// Whether `ord indexd` can run this command for a client. indexd runs
+  /// commands in its own working directory, without the client's stdin or
+  /// stderr, so commands that read or write files, or that ask for
+  /// confirmation, must be run by the client.
+  pub(crate) fn can_proxy(&self) -> bool {
+    match self {
+      Self::Decode(_) | Self::Gallery(_) | Self::Reassemble(_) | Self::Snapshot(_) => false,
+      Self::Find(find) => find.file.is_empty(),
+      Self::Wallet(wallet) => wallet.can_proxy(),
+      _ => self.uses_shared_index(),
+    }
+  }
+
   pub(crate) fn run(self, options: Options) -> Result {
     match self {
+      Self::Address(address) => address.run(options),
+      Self::Audit(audit) => audit.run(options),
+      Self::AuditContent => audit_content::run(options),
+      Self::Block(block) => block.run(options),
+      Self::Broadcast(broadcast) => broadcast.run(options),
       Self::CheckIndex(check_index) => check_index.run(options),
       Self::Compact => compact::run(options),
+      Self::Daemon(daemon) => daemon.run(options),
       Self::Decode(decode) => decode.run(options),
+      Self::Env(env) => env.run(),
       Self::Epochs => epochs::run(),
       Self::Preview(preview) => preview.run(),
       Self::Find(find) => find.run(options),
+      Self::Gallery(gallery) => gallery.run(options),
       Self::Index(index) => index.run(options),
+      #[cfg(unix)]
+      Self::Indexd(indexd) => indexd.run(options),
       Self::Info(info) => info.run(options),
       Self::Inscriptions(inscriptions) => inscriptions.run(options),
       Self::List(list) => list.run(options),
+      Self::Mempool => mempool::run(options),
+      Self::Optimize(optimize) => optimize.run(options),
       Self::Parse(parse) => parse.run(),
+      Self::Reassemble(reassemble) => reassemble.run(options),
+      Self::ScanMempool(scan_mempool) => scan_mempool.run(options),
+      Self::Snapshot(snapshot) => snapshot.run(options),
+      Self::Stats(stats) => stats.run(options),
       Self::Subsidy(subsidy) => subsidy.run(),
       Self::Server(server) => {
         let index = Arc::new(Index::open(&options)?);
@@ -86,7 +249,9 @@ impl Subcommand {
       Self::Supply => supply::run(),
       Self::Traits(traits) => traits.run(),
       Self::Transfer(transfer) => transfer.run(options),
+      Self::VerifyNumbers(verify_numbers) => verify_numbers.run(options),
       Self::Wallet(wallet) => wallet.run(options),
+      Self::Watch(watch) => watch.run(options),
     }
   }
 }
@@ -0,0 +1,84 @@
+use {
+  super::*,
+  wallet::sats::{rare_sats, OutputRare},
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Address {
+  #[clap(help = "Show the outputs, inscriptions, and rare sats of <ADDRESS>.")]
+  address: bitcoin::Address<NetworkUnchecked>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub outputs: Vec<OutPoint>,
+  /// Total value of the outputs, in sats.
+  pub value: u64,
+  /// Value of the outputs holding inscriptions.
+  pub inscribed: u64,
+  /// Value of the outputs without inscriptions.
+  pub cardinal: u64,
+  pub inscriptions: Vec<InscriptionId>,
+  /// `None` without `--index-sats`.
+  pub rare_sats: Option<Vec<OutputRare>>,
+}
+
+impl Address {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let address = self.address.require_network(options.chain().network())?;
+
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let outputs = index.get_address_outputs(&address.script_pubkey())?;
+
+    let mut inscriptions = Vec::new();
+    let mut inscribed = 0;
+    let mut cardinal = 0;
+
+    for (outpoint, value) in &outputs {
+      let output_inscriptions = index.get_inscriptions_on_output(*outpoint)?;
+
+      if output_inscriptions.is_empty() {
+        cardinal += value;
+      } else {
+        inscribed += value;
+      }
+
+      inscriptions.extend(output_inscriptions);
+    }
+
+    let rare_sats = if index.has_sat_index()? {
+      let mut ranges = Vec::new();
+
+      for (outpoint, _) in &outputs {
+        if let Some(crate::index::List::Unspent(sat_ranges)) = index.list(*outpoint)? {
+          ranges.push((*outpoint, sat_ranges));
+        }
+      }
+
+      Some(
+        rare_sats(ranges)
+          .into_iter()
+          .map(|(output, sat, offset, rarity)| OutputRare {
+            sat,
+            output,
+            offset,
+            rarity,
+          })
+          .collect(),
+      )
+    } else {
+      None
+    };
+
+    print_json(Output {
+      outputs: outputs.iter().map(|(outpoint, _)| *outpoint).collect(),
+      value: inscribed + cardinal,
+      inscribed,
+      cardinal,
+      inscriptions,
+      rare_sats,
+    })
+  }
+}
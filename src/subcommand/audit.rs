@@ -0,0 +1,137 @@
+use {super::*, bitcoin::consensus, std::io::Write};
+
+/// Every transaction ord broadcasts is appended to `audit.jsonl` in the data
+/// directory, one JSON object per line, with the command line that produced
+/// it, so that operators have a trail of what was sent and why.
+#[derive(Debug, Parser)]
+pub(crate) enum AuditSubcommand {
+  #[clap(about = "Show transactions recorded in the audit log")]
+  Show(Show),
+}
+
+impl AuditSubcommand {
+  pub(crate) fn run(self, options: Options) -> Result {
+    match self {
+      Self::Show(show) => show.run(options),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+  /// Unix timestamp at which the transaction was broadcast.
+  pub timestamp: u64,
+  pub txid: Txid,
+  pub wallet: String,
+  pub command: Vec<String>,
+  pub inputs: Vec<OutPoint>,
+  pub outputs: Vec<EntryOutput>,
+  /// The fee, if the wallet knows the values of all inputs.
+  pub fee: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryOutput {
+  pub address: Option<Address<NetworkUnchecked>>,
+  pub value: u64,
+}
+
+fn path(options: &Options) -> Result<PathBuf> {
+  Ok(options.data_dir()?.join("audit.jsonl"))
+}
+
+/// Append broadcast transaction `tx` to the audit log. The transaction has
+/// already been sent, so failing to record it is a warning, not an error.
+pub(crate) fn record(options: &Options, client: &Client, tx: &[u8]) {
+  if let Err(err) = try_record(options, client, tx) {
+    eprintln!("warning: failed to record transaction in audit log: {err}");
+  }
+}
+
+fn try_record(options: &Options, client: &Client, tx: &[u8]) -> Result {
+  let tx = consensus::deserialize::<Transaction>(tx)?;
+  let txid = tx.txid();
+  let network = options.chain().network();
+
+  let entry = Entry {
+    timestamp: SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)?
+      .as_secs(),
+    txid,
+    wallet: options.wallet.clone(),
+    command: env::args().collect(),
+    inputs: tx.input.iter().map(|input| input.previous_output).collect(),
+    outputs: tx
+      .output
+      .iter()
+      .map(|output| EntryOutput {
+        address: Address::from_script(&output.script_pubkey, network)
+          .ok()
+          .map(Address::into_unchecked),
+        value: output.value,
+      })
+      .collect(),
+    fee: client
+      .get_transaction(&txid, None)
+      .ok()
+      .and_then(|tx| tx.fee)
+      .map(|fee| fee.to_sat().unsigned_abs()),
+  };
+
+  let path = path(options)?;
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  let mut file = fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)?;
+
+  // one write per entry, so concurrent ord processes don't interleave lines
+  file.write_all(format!("{}\n", serde_json::to_string(&entry)?).as_bytes())?;
+
+  Ok(())
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Show {
+  #[clap(long, help = "Only show the transaction with <TXID>.")]
+  txid: Option<Txid>,
+  #[clap(long, help = "Only show the last <LIMIT> transactions.")]
+  limit: Option<usize>,
+}
+
+impl Show {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let path = path(&options)?;
+
+    let mut entries = Vec::new();
+
+    if path.exists() {
+      for (i, line) in fs::read_to_string(&path)
+        .with_context(|| format!("io error reading {}", path.display()))?
+        .lines()
+        .enumerate()
+      {
+        if line.trim().is_empty() {
+          continue;
+        }
+
+        let entry = serde_json::from_str::<Entry>(line)
+          .with_context(|| format!("invalid entry on line {} of {}", i + 1, path.display()))?;
+
+        if self.txid.is_none() || self.txid == Some(entry.txid) {
+          entries.push(entry);
+        }
+      }
+    }
+
+    if let Some(limit) = self.limit {
+      entries.drain(..entries.len().saturating_sub(limit));
+    }
+
+    print_json(entries)
+  }
+}
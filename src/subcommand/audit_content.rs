@@ -0,0 +1,315 @@
+use {
+  super::*,
+  bitcoin::{
+    blockdata::{opcodes, script::Instruction},
+    taproot::TAPROOT_ANNEX_PREFIX,
+    Witness,
+  },
+};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Discrepancy {
+  pub inscription: InscriptionId,
+  pub number: i64,
+  pub problem: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub audited: u64,
+  pub discrepancies: Vec<Discrepancy>,
+}
+
+/// The content type and body of an envelope, as raw bytes.
+type Envelope = (Option<Vec<u8>>, Option<Vec<u8>>);
+
+pub(crate) fn run(options: Options) -> Result {
+  let index = Index::open_shared(&options)?;
+  index.update()?;
+
+  let mut audited = 0;
+  let mut discrepancies = Vec::new();
+
+  for id in index.get_inscriptions_by_inscription_number(None, None, None, None, false)? {
+    let number = index
+      .get_inscription_entry(id)?
+      .ok_or_else(|| anyhow!("inscription {id} not found"))?
+      .number;
+
+    audited += 1;
+
+    if let Some(problem) = audit(&index, id)? {
+      discrepancies.push(Discrepancy {
+        inscription: id,
+        number,
+        problem,
+      });
+    }
+  }
+
+  let count = discrepancies.len();
+
+  print_json(Output {
+    audited,
+    discrepancies,
+  })?;
+
+  if count > 0 {
+    bail!("{count} of {audited} inscriptions don't match their reveal transactions");
+  }
+
+  Ok(())
+}
+
+/// Compare what the index serves for `id` with the envelope read from its
+/// reveal transaction, returning a description of the first difference.
+fn audit(index: &Index, id: InscriptionId) -> Result<Option<String>> {
+  let Some(tx) = index.get_transaction(id.txid)? else {
+    return Ok(Some(format!("reveal transaction {} not found", id.txid)));
+  };
+
+  let Some((content_type, body)) = envelopes(&tx).into_iter().nth(id.index.try_into().unwrap())
+  else {
+    return Ok(Some(format!(
+      "reveal transaction holds no envelope {}",
+      id.index
+    )));
+  };
+
+  let Some(served) = index.get_inscription_by_id(id)? else {
+    return Ok(Some("index serves no content".into()));
+  };
+
+  let content_type =
+    content_type.map(|content_type| String::from_utf8_lossy(&content_type).into_owned());
+
+  if served.content_type() != content_type.as_deref() {
+    return Ok(Some(format!(
+      "content type is {:?}, but the envelope holds {:?}",
+      served.content_type(),
+      content_type,
+    )));
+  }
+
+  let served = served.body().unwrap_or_default();
+  let body = body.unwrap_or_default();
+
+  if served == body.as_slice() {
+    Ok(None)
+  } else if body.starts_with(served) {
+    Ok(Some(format!(
+      "body is truncated to {} of {} bytes",
+      served.len(),
+      body.len()
+    )))
+  } else {
+    Ok(Some(format!(
+      "body differs from the envelope, {} bytes served, {} bytes in the envelope",
+      served.len(),
+      body.len()
+    )))
+  }
+}
+
+/// Envelopes in `tx`, in the order inscription ids are assigned, read
+/// independently of the parser the index uses. Each input's tapscript is
+/// found per BIP 341, and envelopes may start at any instruction. Inputs
+/// with a malformed envelope hold no inscriptions.
+fn envelopes(tx: &Transaction) -> Vec<Envelope> {
+  tx.input
+    .iter()
+    .filter_map(|input| tapscript(&input.witness))
+    .filter_map(read_envelopes)
+    .flatten()
+    .collect()
+}
+
+fn tapscript(witness: &Witness) -> Option<&Script> {
+  let annex = witness.len() >= 2
+    && witness
+      .last()
+      .and_then(|element| element.first())
+      .map(|byte| *byte == TAPROOT_ANNEX_PREFIX)
+      .unwrap_or_default();
+
+  let script_position = witness.len().checked_sub(if annex { 3 } else { 2 })?;
+
+  witness.iter().nth(script_position).map(Script::from_bytes)
+}
+
+fn read_envelopes(script: &Script) -> Option<Vec<Envelope>> {
+  let instructions = script
+    .instructions()
+    .collect::<Result<Vec<Instruction>, _>>()
+    .ok()?;
+
+  let header = [
+    Instruction::PushBytes((&[]).into()),
+    Instruction::Op(opcodes::all::OP_IF),
+    Instruction::PushBytes(b"ord".into()),
+  ];
+
+  let mut envelopes = Vec::new();
+  let mut i = 0;
+  while i + header.len() <= instructions.len() {
+    if instructions[i..i + header.len()] != header {
+      i += 1;
+      continue;
+    }
+
+    i += header.len();
+
+    let mut fields = BTreeMap::<&[u8], Vec<u8>>::new();
+    let mut body = None;
+    loop {
+      match instructions.get(i) {
+        // unterminated envelopes are ignored
+        None => return Some(envelopes),
+        Some(Instruction::Op(opcodes::all::OP_ENDIF)) => {
+          i += 1;
+          break;
+        }
+        Some(Instruction::PushBytes(tag)) if tag.is_empty() => {
+          let mut content = Vec::new();
+          loop {
+            i += 1;
+            match instructions.get(i) {
+              None => return Some(envelopes),
+              Some(Instruction::Op(opcodes::all::OP_ENDIF)) => break,
+              Some(Instruction::PushBytes(data)) => content.extend_from_slice(data.as_bytes()),
+              Some(Instruction::Op(_)) => return None,
+            }
+          }
+          body = Some(content);
+          i += 1;
+          break;
+        }
+        Some(Instruction::PushBytes(tag)) => {
+          let value = match instructions.get(i + 1) {
+            None => return Some(envelopes),
+            Some(Instruction::PushBytes(value)) => value.as_bytes().to_vec(),
+            Some(Instruction::Op(_)) => return None,
+          };
+
+          if fields.insert(tag.as_bytes(), value).is_some() {
+            return None;
+          }
+
+          i += 2;
+        }
+        Some(Instruction::Op(_)) => return None,
+      }
+    }
+
+    let content_type = fields.remove([1].as_slice());
+
+    if fields
+      .keys()
+      .any(|tag| matches!(tag.first(), Some(lsb) if lsb % 2 == 0))
+    {
+      return None;
+    }
+
+    envelopes.push((content_type, body));
+  }
+
+  Some(envelopes)
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    bitcoin::{blockdata::script, locktime::absolute::LockTime},
+  };
+
+  fn reveal(witnesses: Vec<Witness>) -> Transaction {
+    Transaction {
+      version: 1,
+      lock_time: LockTime::ZERO,
+      input: witnesses
+        .into_iter()
+        .map(|witness| TxIn {
+          previous_output: OutPoint::null(),
+          script_sig: ScriptBuf::new(),
+          sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+          witness,
+        })
+        .collect(),
+      output: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn envelopes_match_parser() {
+    let tx = reveal(vec![
+      envelope(&[b"ord", &[1], b"text/plain", &[], b"foo", b"bar"]),
+      envelope(&[b"ord", &[1], b"text/plain", &[66], b"cursed"]),
+      envelope(&[b"ord", &[], b"baz"]),
+    ]);
+
+    assert_eq!(
+      envelopes(&tx),
+      vec![
+        (Some(b"text/plain".to_vec()), Some(b"foobar".to_vec())),
+        (None, Some(b"baz".to_vec())),
+      ]
+    );
+
+    assert_eq!(
+      Inscription::from_transaction(&tx)
+        .into_iter()
+        .map(|inscription| (
+          inscription.inscription.content_type().map(str::to_owned),
+          inscription.inscription.body().map(<[u8]>::to_vec),
+        ))
+        .collect::<Vec<(Option<String>, Option<Vec<u8>>)>>(),
+      vec![
+        (Some("text/plain".into()), Some(b"foobar".to_vec())),
+        (None, Some(b"baz".to_vec())),
+      ]
+    );
+  }
+
+  #[test]
+  fn tapscript_is_found_before_annex() {
+    let mut witness = envelope(&[b"ord", &[], b"foo"]);
+    witness.push([TAPROOT_ANNEX_PREFIX]);
+
+    assert_eq!(
+      envelopes(&reveal(vec![witness])),
+      vec![(None, Some(b"foo".to_vec()))]
+    );
+  }
+
+  #[test]
+  fn envelopes_start_at_any_instruction() {
+    let script = script::Builder::new()
+      .push_opcode(opcodes::OP_FALSE)
+      .push_opcode(opcodes::OP_FALSE)
+      .push_opcode(opcodes::all::OP_IF)
+      .push_slice(*b"ord")
+      .push_slice([])
+      .push_slice(*b"foo")
+      .push_opcode(opcodes::all::OP_ENDIF)
+      .into_script();
+
+    assert_eq!(
+      read_envelopes(&script),
+      Some(vec![(None, Some(b"foo".to_vec()))])
+    );
+  }
+
+  #[test]
+  fn malformed_envelopes_hide_input() {
+    let script = script::Builder::new()
+      .push_opcode(opcodes::OP_FALSE)
+      .push_opcode(opcodes::all::OP_IF)
+      .push_slice(*b"ord")
+      .push_opcode(opcodes::all::OP_CHECKSIG)
+      .push_opcode(opcodes::all::OP_ENDIF)
+      .into_script();
+
+    assert_eq!(read_envelopes(&script), None);
+  }
+}
@@ -0,0 +1,109 @@
+use {super::*, server::BlockQuery};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Block {
+  #[clap(help = "Show <BLOCK>, given as a height or a hash.")]
+  block: BlockQuery,
+  #[clap(
+    long,
+    help = "List the inscriptions created or transferred in the block."
+  )]
+  inscriptions: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Event {
+  Created,
+  Transferred,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlockInscription {
+  pub inscription: InscriptionId,
+  pub number: i64,
+  pub event: Event,
+  /// Fee paid by the reveal transaction, divided among its inscriptions.
+  /// `None` for transfers.
+  pub fee: Option<u64>,
+  /// Bytes of content.
+  pub size: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  pub height: u64,
+  pub hash: BlockHash,
+  pub created: u64,
+  pub transferred: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub inscriptions: Option<Vec<BlockInscription>>,
+}
+
+impl Block {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let (height, hash) = match self.block {
+      BlockQuery::Height(height) => (
+        height,
+        index
+          .block_hash(Some(height))?
+          .ok_or_else(|| anyhow!("block {height} not found"))?,
+      ),
+      BlockQuery::Hash(hash) => (
+        index
+          .get_block_height(hash)
+          .map_err(|_| anyhow!("block {hash} not found"))?
+          .try_into()
+          .unwrap(),
+        hash,
+      ),
+    };
+
+    let created = index.get_new_inscription_ids_by_height(height)?;
+
+    // the transfer log may have been trimmed or deleted, in which case
+    // transfers in older blocks aren't listed
+    let transferred = index.get_inscription_ids_by_height(height)?;
+
+    let inscriptions = if self.inscriptions {
+      let mut inscriptions = Vec::new();
+
+      for (inscription_id, event) in created
+        .iter()
+        .map(|id| (*id, Event::Created))
+        .chain(transferred.iter().map(|id| (*id, Event::Transferred)))
+      {
+        let entry = index
+          .get_inscription_entry(inscription_id)?
+          .ok_or_else(|| anyhow!("inscription {inscription_id} not found"))?;
+
+        let size = index
+          .get_inscription_by_id(inscription_id)?
+          .and_then(|inscription| inscription.content_length());
+
+        inscriptions.push(BlockInscription {
+          inscription: inscription_id,
+          number: entry.number,
+          fee: (event == Event::Created).then_some(entry.fee),
+          event,
+          size,
+        });
+      }
+
+      Some(inscriptions)
+    } else {
+      None
+    };
+
+    print_json(Output {
+      height,
+      hash,
+      created: created.len().try_into().unwrap(),
+      transferred: transferred.len().try_into().unwrap(),
+      inscriptions,
+    })
+  }
+}
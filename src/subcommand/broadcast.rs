@@ -0,0 +1,172 @@
+use {
+  super::*,
+  wallet::{dump, scheduled},
+};
+
+/// Broadcasts transactions signed elsewhere, so that a machine with wallet
+/// access can prepare inscriptions with `ord wallet inscribe --dump` and
+/// another can broadcast them, without the wallet.
+#[derive(Debug, Parser)]
+pub(crate) struct Broadcast {
+  #[clap(
+    help = "Broadcast the transactions in <FILE>, the output of `ord wallet inscribe --dump` or a wallet's scheduled file."
+  )]
+  file: PathBuf,
+  #[clap(
+    long,
+    default_value = "0",
+    help = "Retry reveals that fail to broadcast <RETRIES> times, a minute apart."
+  )]
+  retries: u32,
+  #[clap(
+    long,
+    help = "If Bitcoin Core won't relay a transaction, POST it as hex to <FALLBACK_BROADCAST>, like `https://mempool.space/api/tx`."
+  )]
+  fallback_broadcast: Option<String>,
+}
+
+/// `--dump` prints one object per batch, and scheduled files are arrays.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Input {
+  Dump(dump::Input),
+  Scheduled(Vec<scheduled::Broadcast>),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastCommit {
+  pub commit: Txid,
+  pub reveals: Vec<Txid>,
+  pub failed: Vec<Txid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub commits: Vec<BroadcastCommit>,
+}
+
+impl Broadcast {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let client = options.bitcoin_rpc_client()?;
+
+    let mut batches = Vec::<(String, Vec<String>)>::new();
+
+    for input in serde_json::Deserializer::from_reader(
+      File::open(&self.file).with_context(|| format!("failed to open {}", self.file.display()))?,
+    )
+    .into_iter::<Input>()
+    {
+      match input.with_context(|| format!("failed to parse {}", self.file.display()))? {
+        Input::Dump(dump) => {
+          dump.check()?;
+          batches.push((dump.commit().into(), dump.reveals()));
+        }
+        Input::Scheduled(scheduled) => batches.extend(
+          scheduled
+            .into_iter()
+            .map(|broadcast| (broadcast.raw_commit, broadcast.reveals)),
+        ),
+      }
+    }
+
+    if batches.is_empty() {
+      bail!("{} contains no transactions", self.file.display());
+    }
+
+    let mut commits = Vec::new();
+
+    let total = batches.len();
+
+    for (raw_commit, raw_reveals) in batches {
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        let remaining = total - commits.len();
+        print_json(Output { commits })?;
+        bail!(
+          "interrupted before broadcasting {remaining} of {total} commits, run `ord broadcast {}` again to broadcast the rest",
+          self.file.display()
+        );
+      }
+
+      let raw_commit = hex::decode(raw_commit)?;
+      let commit = consensus::deserialize::<Transaction>(&raw_commit)?.txid();
+
+      // a commit broadcast by an earlier run is already known
+      if client.get_raw_transaction(&commit, None).is_err() {
+        wallet::broadcast(
+          &client,
+          &options,
+          &raw_commit,
+          self.fallback_broadcast.as_deref(),
+        )
+        .context("Failed to send commit transaction")?;
+      }
+
+      let mut reveals = Vec::new();
+      let mut pending = Vec::new();
+
+      for raw_reveal in &raw_reveals {
+        let raw_reveal = hex::decode(raw_reveal)?;
+        let reveal = consensus::deserialize::<Transaction>(&raw_reveal)?.txid();
+
+        // so that running again after being interrupted skips reveals that
+        // were already broadcast
+        if client.get_raw_transaction(&reveal, None).is_ok() {
+          reveals.push(reveal);
+        } else {
+          pending.push(raw_reveal);
+        }
+      }
+
+      for attempt in 0..=self.retries {
+        if attempt > 0 {
+          if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+            break;
+          }
+
+          eprintln!(
+            "[retrying {} reveals in a minute, attempt {attempt} of {}]",
+            pending.len(),
+            self.retries
+          );
+          thread::sleep(Duration::from_secs(60));
+        }
+
+        let mut failed = Vec::new();
+
+        for raw_reveal in pending {
+          match wallet::broadcast(
+            &client,
+            &options,
+            &raw_reveal,
+            self.fallback_broadcast.as_deref(),
+          ) {
+            Ok((reveal, _)) => reveals.push(reveal),
+            Err(error) => {
+              log::warn!("failed to broadcast reveal: {error:#}");
+              failed.push(raw_reveal);
+            }
+          }
+        }
+
+        pending = failed;
+
+        if pending.is_empty() {
+          break;
+        }
+      }
+
+      commits.push(BroadcastCommit {
+        commit,
+        reveals,
+        failed: pending
+          .iter()
+          .map(|raw_reveal| -> Result<Txid> {
+            Ok(consensus::deserialize::<Transaction>(raw_reveal)?.txid())
+          })
+          .collect::<Result<Vec<Txid>>>()?,
+      });
+    }
+
+    print_json(Output { commits })
+  }
+}
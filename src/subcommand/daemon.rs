@@ -0,0 +1,657 @@
+use {
+  super::*,
+  axum::{
+    extract::{Extension, Json, Path},
+    http::{header, HeaderMap, StatusCode},
+    response::{
+      sse::{Event, Sse},
+      IntoResponse, Response,
+    },
+    routing::{get, post},
+    Router,
+  },
+  base64::Engine,
+  futures::{channel::mpsc, Stream, StreamExt},
+  std::{collections::HashMap, convert::Infallible, net::SocketAddr},
+  wallet::{inscribe::Inscribe, inscriptions::Inscriptions, send::Send as SendCommand},
+};
+
+mod grpc;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Daemon {
+  #[clap(
+    long,
+    default_value = "127.0.0.1:8090",
+    help = "Listen for requests on <ADDRESS>."
+  )]
+  address: SocketAddr,
+  #[clap(
+    long,
+    help = "Also serve the inscribe, send, list-inscriptions, and fee-estimate RPCs over gRPC on <GRPC>, streaming the progress of wallet commands."
+  )]
+  grpc: Option<SocketAddr>,
+  #[clap(
+    long,
+    help = "Require requests to carry one of the API keys in the YAML file <API_KEYS> as a bearer token. Each key has a `name`, a `key`, a `scope` of `read` or `wallet`, and optionally a `rate_limit` in requests per minute and a list of the wallet `commands` it may run."
+  )]
+  api_keys: Option<PathBuf>,
+}
+
+/// Without API keys, anyone who can reach the daemon can run wallet commands,
+/// so keys are needed to expose it to other services. `read` keys can only
+/// query, and `wallet` keys can also run the wallet commands they allow.
+#[derive(Debug)]
+struct ApiKeys {
+  keys: Vec<ApiKey>,
+  /// Start of each key's current one minute rate limit window, and the
+  /// number of requests made in it.
+  windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+/// A key's `commands` only name the wallet commands it may run. Requests
+/// can't pass flags of their own, only the fields of `InscribeRequest` and
+/// `SendRequest`, so allowing a command doesn't allow its other options.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct ApiKey {
+  name: String,
+  key: String,
+  scope: Scope,
+  rate_limit: Option<u32>,
+  commands: Option<BTreeSet<String>>,
+}
+
+/// The commands that API keys with wallet scope may be allowed to run.
+const WALLET_COMMANDS: &[&str] = &["inscribe", "send"];
+
+#[derive(Debug, Deserialize, PartialEq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+enum Scope {
+  Read,
+  Wallet,
+}
+
+/// An `ord wallet inscribe` request. Like a gRPC `InscribeRequest`, it only
+/// has fields for options that are safe to expose, and files are sent with
+/// the request instead of being read from paths on the daemon's host.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InscribeRequest {
+  files: Vec<FileRequest>,
+  fee_rate: f64,
+  commit_fee_rate: Option<f64>,
+  #[serde(default)]
+  destinations: Vec<String>,
+  postage: Option<u64>,
+  satpoint: Option<String>,
+  #[serde(default)]
+  dry_run: bool,
+  #[serde(default)]
+  no_backup: bool,
+  #[serde(default)]
+  no_limit: bool,
+  #[serde(default)]
+  wait_after_commit: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileRequest {
+  name: String,
+  /// Base64 encoded.
+  content: String,
+}
+
+/// An `ord wallet send` request, with the fields of a gRPC `SendRequest`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SendRequest {
+  address: String,
+  outgoing: String,
+  fee_rate: f64,
+  target_postage: Option<u64>,
+  max_postage: Option<u64>,
+  change: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FeeEstimate {
+  blocks: u16,
+  fee_rate: Option<f64>,
+}
+
+struct State {
+  api_keys: Option<ApiKeys>,
+  options: Options,
+  /// Wallet commands run one at a time, so that concurrent commands don't
+  /// select the same utxos.
+  wallet: Mutex<()>,
+}
+
+impl Daemon {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let api_keys = match &self.api_keys {
+      Some(path) => Some(ApiKeys::load(path)?),
+      None => None,
+    };
+
+    let index = Arc::new(Index::open(&options)?);
+    index.update()?;
+
+    Index::share(index.clone());
+
+    let index_thread = thread::spawn(move || loop {
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        break;
+      }
+      if let Err(error) = index.update() {
+        log::warn!("Updating index: {error}");
+      }
+      thread::sleep(Duration::from_millis(10000));
+    });
+    INDEXER.lock().unwrap().replace(index_thread);
+
+    let state = Arc::new(State {
+      api_keys,
+      options,
+      wallet: Mutex::new(()),
+    });
+
+    let router = Router::new()
+      .route("/fee-estimate/:blocks", get(Self::fee_estimate))
+      .route("/inscribe", post(Self::inscribe))
+      .route("/inscriptions", get(Self::inscriptions))
+      .route("/send", post(Self::send))
+      .layer(Extension(state.clone()));
+
+    let handle = axum_server::Handle::new();
+    LISTENERS.lock().unwrap().push(handle.clone());
+
+    if !integration_test() {
+      eprintln!("Listening on http://{}", self.address);
+
+      if let Some(grpc) = self.grpc {
+        eprintln!("Listening for gRPC requests on {grpc}");
+      }
+    }
+
+    Runtime::new()?.block_on(async {
+      let http = axum_server::Server::bind(self.address)
+        .handle(handle)
+        .serve(router.into_make_service());
+
+      match self.grpc {
+        Some(address) => {
+          let (http, grpc) = tokio::join!(http, grpc::serve(address, state));
+          http?;
+          grpc
+        }
+        None => Ok(http.await?),
+      }
+    })
+  }
+
+  fn estimate_fee(options: &Options, blocks: u16) -> Result<FeeEstimate> {
+    let estimate = options
+      .bitcoin_rpc_client()?
+      .estimate_smart_fee(blocks, None)?;
+
+    Ok(FeeEstimate {
+      blocks: estimate.blocks.try_into().unwrap_or(u16::MAX),
+      // Bitcoin Core estimates fee rates in BTC/kvB
+      fee_rate: estimate
+        .fee_rate
+        .map(|fee_rate| fee_rate.to_sat() as f64 / 1000.0),
+    })
+  }
+
+  fn parse<T: Parser>(name: &str, args: Vec<String>) -> Result<T, Response> {
+    T::try_parse_from(std::iter::once(name.to_string()).chain(args))
+      .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()).into_response())
+  }
+
+  /// Run a wallet command on a blocking thread, streaming its progress
+  /// updates and outputs to the client as `progress` and `output` events,
+  /// followed by a `done` or `error` event.
+  fn stream(
+    state: Arc<State>,
+    run: impl FnOnce(Options) -> Result + std::marker::Send + 'static,
+  ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    task::spawn_blocking(move || {
+      let _wallet = state.wallet.lock().unwrap();
+
+      let progress_sender = sender.clone();
+      let output_sender = sender.clone();
+      let result = capture_progress(
+        move |status| {
+          let event = Event::default().event("progress").data(status);
+          progress_sender.unbounded_send(event).ok();
+        },
+        || {
+          capture_output(
+            move |output| {
+              let event = Event::default().event("output").data(output.to_string());
+              output_sender.unbounded_send(event).ok();
+            },
+            || run(state.options.clone()),
+          )
+        },
+      );
+
+      let event = match result {
+        Ok(()) => Event::default().event("done").data(""),
+        Err(error) => Event::default().event("error").data(format!("{error:#}")),
+      };
+
+      sender.unbounded_send(event).ok();
+    });
+
+    Sse::new(receiver.map(Ok))
+  }
+
+  /// Check that the request's API key, if keys are required, may run
+  /// `command`, or only query if `command` is `None`.
+  fn authorize(state: &State, headers: &HeaderMap, command: Option<&str>) -> Result<(), Response> {
+    let Some(api_keys) = &state.api_keys else {
+      return Ok(());
+    };
+
+    let token = headers
+      .get(header::AUTHORIZATION)
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix("Bearer "));
+
+    api_keys
+      .authorize(token, command)
+      .map_err(|(status, message)| (status, message).into_response())
+  }
+
+  async fn inscribe(
+    Extension(state): Extension<Arc<State>>,
+    headers: HeaderMap,
+    Json(request): Json<InscribeRequest>,
+  ) -> Response {
+    if let Err(response) = Self::authorize(&state, &headers, Some("inscribe")) {
+      return response;
+    }
+
+    let files = match TempDir::new() {
+      Ok(files) => files,
+      Err(error) => return (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+    };
+
+    let args = match request
+      .into_proto()
+      .and_then(|request| grpc::inscribe_args(request, files.path()))
+    {
+      Ok(args) => args,
+      Err(error) => return (StatusCode::BAD_REQUEST, format!("{error:#}")).into_response(),
+    };
+
+    match Self::parse::<Inscribe>("inscribe", args) {
+      Ok(inscribe) => Self::stream(state, move |options| {
+        let result = inscribe.run(options);
+        drop(files);
+        result
+      })
+      .into_response(),
+      Err(response) => response,
+    }
+  }
+
+  async fn send(
+    Extension(state): Extension<Arc<State>>,
+    headers: HeaderMap,
+    Json(request): Json<SendRequest>,
+  ) -> Response {
+    if let Err(response) = Self::authorize(&state, &headers, Some("send")) {
+      return response;
+    }
+
+    match Self::parse::<SendCommand>("send", grpc::send_args(request.into())) {
+      Ok(send) => Self::stream(state, move |options| send.run(options)).into_response(),
+      Err(response) => response,
+    }
+  }
+
+  async fn inscriptions(Extension(state): Extension<Arc<State>>, headers: HeaderMap) -> Response {
+    if let Err(response) = Self::authorize(&state, &headers, None) {
+      return response;
+    }
+
+    let result = task::spawn_blocking(move || {
+      let inscriptions = Self::parse::<Inscriptions>("inscriptions", Vec::new())
+        .map_err(|_| anyhow!("failed to parse inscriptions command"))?;
+
+      let output = Arc::new(Mutex::new(serde_json::Value::Null));
+      let sink = output.clone();
+
+      let _wallet = state.wallet.lock().unwrap();
+
+      capture_output(
+        move |value| *sink.lock().unwrap() = value,
+        || inscriptions.run(state.options.clone()),
+      )?;
+
+      let output = output.lock().unwrap().take();
+
+      Ok::<serde_json::Value, Error>(output)
+    })
+    .await;
+
+    match result {
+      Ok(Ok(output)) => Json(output).into_response(),
+      Ok(Err(error)) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{error:#}")).into_response(),
+      Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+    }
+  }
+
+  async fn fee_estimate(
+    Extension(state): Extension<Arc<State>>,
+    headers: HeaderMap,
+    Path(blocks): Path<u16>,
+  ) -> Response {
+    if let Err(response) = Self::authorize(&state, &headers, None) {
+      return response;
+    }
+
+    let result = task::spawn_blocking(move || Self::estimate_fee(&state.options, blocks)).await;
+
+    match result {
+      Ok(Ok(estimate)) => Json(estimate).into_response(),
+      Ok(Err(error)) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{error:#}")).into_response(),
+      Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+    }
+  }
+}
+
+impl InscribeRequest {
+  fn into_proto(self) -> Result<grpc::proto::InscribeRequest> {
+    Ok(grpc::proto::InscribeRequest {
+      files: self
+        .files
+        .into_iter()
+        .map(|file| {
+          Ok(grpc::proto::File {
+            content: base64::engine::general_purpose::STANDARD
+              .decode(&file.content)
+              .with_context(|| format!("content of file `{}` is not valid base64", file.name))?,
+            name: file.name,
+          })
+        })
+        .collect::<Result<_>>()?,
+      fee_rate: self.fee_rate,
+      commit_fee_rate: self.commit_fee_rate,
+      destinations: self.destinations,
+      postage: self.postage,
+      satpoint: self.satpoint,
+      dry_run: self.dry_run,
+      no_backup: self.no_backup,
+      no_limit: self.no_limit,
+      wait_after_commit: self.wait_after_commit,
+    })
+  }
+}
+
+impl From<SendRequest> for grpc::proto::SendRequest {
+  fn from(request: SendRequest) -> Self {
+    Self {
+      address: request.address,
+      outgoing: request.outgoing,
+      fee_rate: request.fee_rate,
+      target_postage: request.target_postage,
+      max_postage: request.max_postage,
+      change: request.change,
+    }
+  }
+}
+
+impl ApiKeys {
+  fn load(path: &std::path::Path) -> Result<Self> {
+    let keys: Vec<ApiKey> = serde_yaml::from_reader(
+      File::open(path).with_context(|| format!("failed to open API keys {}", path.display()))?,
+    )
+    .with_context(|| format!("failed to parse API keys {}", path.display()))?;
+
+    let mut names = HashSet::new();
+    for key in &keys {
+      if !names.insert(&key.name) {
+        bail!("API key name `{}` is used more than once", key.name);
+      }
+
+      if key.key.is_empty() {
+        bail!("API key `{}` is empty", key.name);
+      }
+
+      for command in key.commands.iter().flatten() {
+        if !WALLET_COMMANDS.contains(&command.as_str()) {
+          bail!(
+            "API key `{}` allows unknown command `{command}`, expected one of {}",
+            key.name,
+            WALLET_COMMANDS.join(", "),
+          );
+        }
+      }
+    }
+
+    Ok(Self {
+      keys,
+      windows: Mutex::new(HashMap::new()),
+    })
+  }
+
+  fn authorize(
+    &self,
+    token: Option<&str>,
+    command: Option<&str>,
+  ) -> Result<(), (StatusCode, String)> {
+    let Some(token) = token else {
+      return Err((
+        StatusCode::UNAUTHORIZED,
+        "missing `Authorization: Bearer <KEY>` header".into(),
+      ));
+    };
+
+    let Some(key) = self.keys.iter().find(|key| key.key == token) else {
+      return Err((StatusCode::UNAUTHORIZED, "unknown API key".into()));
+    };
+
+    if let Some(command) = command {
+      if key.scope < Scope::Wallet {
+        return Err((
+          StatusCode::FORBIDDEN,
+          format!(
+            "API key `{}` has read scope and cannot run wallet commands",
+            key.name
+          ),
+        ));
+      }
+
+      if let Some(commands) = &key.commands {
+        if !commands.contains(command) {
+          return Err((
+            StatusCode::FORBIDDEN,
+            format!("API key `{}` is not allowed to run `{command}`", key.name),
+          ));
+        }
+      }
+    }
+
+    if let Some(rate_limit) = key.rate_limit {
+      let mut windows = self.windows.lock().unwrap();
+
+      let (start, requests) = windows
+        .entry(key.name.clone())
+        .or_insert((Instant::now(), 0));
+
+      if start.elapsed() >= Duration::from_secs(60) {
+        *start = Instant::now();
+        *requests = 0;
+      }
+
+      if *requests >= rate_limit {
+        return Err((
+          StatusCode::TOO_MANY_REQUESTS,
+          format!(
+            "API key `{}` is limited to {rate_limit} requests per minute",
+            key.name
+          ),
+        ));
+      }
+
+      *requests += 1;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn api_keys(yaml: &str) -> ApiKeys {
+    ApiKeys {
+      keys: serde_yaml::from_str(yaml).unwrap(),
+      windows: Mutex::new(HashMap::new()),
+    }
+  }
+
+  #[test]
+  fn inscribe_requests_carry_file_contents() {
+    let request = serde_json::from_value::<InscribeRequest>(serde_json::json!({
+      "files": [{"name": "foo.txt", "content": "Zm9v"}],
+      "fee_rate": 1.0,
+    }))
+    .unwrap()
+    .into_proto()
+    .unwrap();
+
+    assert_eq!(request.files[0].name, "foo.txt");
+    assert_eq!(request.files[0].content, b"foo");
+
+    assert_eq!(
+      serde_json::from_value::<InscribeRequest>(serde_json::json!({
+        "files": [{"name": "foo.txt", "content": "!"}],
+        "fee_rate": 1.0,
+      }))
+      .unwrap()
+      .into_proto()
+      .unwrap_err()
+      .to_string(),
+      "content of file `foo.txt` is not valid base64",
+    );
+  }
+
+  #[test]
+  fn requests_cannot_pass_arbitrary_arguments() {
+    assert!(
+      serde_json::from_value::<InscribeRequest>(serde_json::json!({
+        "files": [],
+        "fee_rate": 1.0,
+        "args": ["--dir", "/"],
+      }))
+      .is_err()
+    );
+
+    assert!(serde_json::from_value::<SendRequest>(serde_json::json!({
+      "address": "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+      "outgoing": "1000 sat",
+      "fee_rate": 1.0,
+      "args": ["--simulate"],
+    }))
+    .is_err());
+  }
+
+  #[test]
+  fn requests_without_known_key_are_unauthorized() {
+    let api_keys = api_keys("- {name: a, key: secret, scope: wallet}");
+
+    assert_eq!(
+      api_keys.authorize(None, None).unwrap_err().0,
+      StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+      api_keys.authorize(Some("wrong"), None).unwrap_err().0,
+      StatusCode::UNAUTHORIZED
+    );
+    assert!(api_keys.authorize(Some("secret"), Some("send")).is_ok());
+  }
+
+  #[test]
+  fn read_keys_cannot_run_wallet_commands() {
+    let api_keys = api_keys("- {name: a, key: secret, scope: read}");
+
+    assert!(api_keys.authorize(Some("secret"), None).is_ok());
+    assert_eq!(
+      api_keys
+        .authorize(Some("secret"), Some("send"))
+        .unwrap_err()
+        .0,
+      StatusCode::FORBIDDEN
+    );
+  }
+
+  #[test]
+  fn wallet_keys_can_only_run_allowed_commands() {
+    let api_keys = api_keys("- {name: a, key: secret, scope: wallet, commands: [send]}");
+
+    assert!(api_keys.authorize(Some("secret"), Some("send")).is_ok());
+    assert_eq!(
+      api_keys
+        .authorize(Some("secret"), Some("inscribe"))
+        .unwrap_err()
+        .0,
+      StatusCode::FORBIDDEN
+    );
+  }
+
+  #[test]
+  fn keys_can_only_allow_wallet_commands() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("api-keys.yaml");
+
+    fs::write(
+      &path,
+      "- {name: a, key: secret, scope: wallet, commands: [send, wallet]}",
+    )
+    .unwrap();
+
+    assert_eq!(
+      ApiKeys::load(&path).unwrap_err().to_string(),
+      "API key `a` allows unknown command `wallet`, expected one of inscribe, send",
+    );
+
+    fs::write(
+      &path,
+      "- {name: a, key: secret, scope: wallet, commands: [inscribe, send]}",
+    )
+    .unwrap();
+
+    assert!(ApiKeys::load(&path).is_ok());
+  }
+
+  #[test]
+  fn requests_over_rate_limit_are_rejected() {
+    let api_keys = api_keys(
+      "
+- {name: a, key: limited, scope: read, rate_limit: 2}
+- {name: b, key: unlimited, scope: read}
+",
+    );
+
+    assert!(api_keys.authorize(Some("limited"), None).is_ok());
+    assert!(api_keys.authorize(Some("limited"), None).is_ok());
+    assert_eq!(
+      api_keys.authorize(Some("limited"), None).unwrap_err().0,
+      StatusCode::TOO_MANY_REQUESTS
+    );
+
+    for _ in 0..10 {
+      assert!(api_keys.authorize(Some("unlimited"), None).is_ok());
+    }
+  }
+}
@@ -0,0 +1,592 @@
+use {
+  super::*,
+  proto::{progress::Update, wallet_server::WalletServer},
+  std::pin::Pin,
+  tonic::{Code, Request, Status},
+};
+
+pub(super) mod proto {
+  #![allow(clippy::all)]
+  tonic::include_proto!("ord");
+}
+
+type ProgressStream =
+  Pin<Box<dyn Stream<Item = Result<proto::Progress, Status>> + std::marker::Send>>;
+
+pub(super) async fn serve(address: SocketAddr, state: Arc<State>) -> Result {
+  tonic::transport::Server::builder()
+    .add_service(WalletServer::new(Service { state }))
+    .serve_with_shutdown(address, async {
+      while !SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+      }
+    })
+    .await?;
+
+  Ok(())
+}
+
+struct Service {
+  state: Arc<State>,
+}
+
+impl Service {
+  /// Check the request's API key, like `Daemon::authorize` does for HTTP
+  /// requests, with the key in `authorization` metadata.
+  fn authorize<T>(&self, request: &Request<T>, command: Option<&str>) -> Result<(), Status> {
+    let Some(api_keys) = &self.state.api_keys else {
+      return Ok(());
+    };
+
+    let token = request
+      .metadata()
+      .get("authorization")
+      .and_then(|value| value.to_str().ok())
+      .and_then(|value| value.strip_prefix("Bearer "));
+
+    api_keys
+      .authorize(token, command)
+      .map_err(|(status, message)| Status::new(code(status), message))
+  }
+
+  /// Run a wallet command on a blocking thread, streaming its progress
+  /// updates and outputs to the client. A failed command ends the stream
+  /// with an error status.
+  fn stream(
+    &self,
+    run: impl FnOnce(Options) -> Result + std::marker::Send + 'static,
+  ) -> ProgressStream {
+    let (sender, receiver) = mpsc::unbounded();
+    let state = self.state.clone();
+
+    task::spawn_blocking(move || {
+      let _wallet = state.wallet.lock().unwrap();
+
+      let progress_sender = sender.clone();
+      let output_sender = sender.clone();
+      let result = capture_progress(
+        move |status| {
+          let update = Update::Status(status);
+          progress_sender.unbounded_send(Ok(progress(update))).ok();
+        },
+        || {
+          capture_output(
+            move |output| {
+              let update = Update::Output(output.to_string());
+              output_sender.unbounded_send(Ok(progress(update))).ok();
+            },
+            || run(state.options.clone()),
+          )
+        },
+      );
+
+      if let Err(error) = result {
+        sender
+          .unbounded_send(Err(Status::internal(format!("{error:#}"))))
+          .ok();
+      }
+    });
+
+    Box::pin(receiver)
+  }
+
+  async fn blocking<T: std::marker::Send + 'static>(
+    &self,
+    f: impl FnOnce(&State) -> Result<T> + std::marker::Send + 'static,
+  ) -> Result<T, Status> {
+    let state = self.state.clone();
+
+    task::spawn_blocking(move || f(&state))
+      .await
+      .map_err(|error| Status::internal(error.to_string()))?
+      .map_err(|error| Status::internal(format!("{error:#}")))
+  }
+}
+
+#[tonic::async_trait]
+impl proto::wallet_server::Wallet for Service {
+  type InscribeStream = ProgressStream;
+  type SendStream = ProgressStream;
+
+  async fn inscribe(
+    &self,
+    request: Request<proto::InscribeRequest>,
+  ) -> Result<tonic::Response<ProgressStream>, Status> {
+    self.authorize(&request, Some("inscribe"))?;
+
+    let files = TempDir::new().map_err(|error| Status::internal(error.to_string()))?;
+
+    let args = inscribe_args(request.into_inner(), files.path())
+      .map_err(|error| Status::invalid_argument(format!("{error:#}")))?;
+
+    let inscribe = parse::<Inscribe>("inscribe", args)?;
+
+    Ok(tonic::Response::new(self.stream(move |options| {
+      let result = inscribe.run(options);
+      drop(files);
+      result
+    })))
+  }
+
+  async fn send(
+    &self,
+    request: Request<proto::SendRequest>,
+  ) -> Result<tonic::Response<ProgressStream>, Status> {
+    self.authorize(&request, Some("send"))?;
+
+    let send = parse::<SendCommand>("send", send_args(request.into_inner()))?;
+
+    Ok(tonic::Response::new(
+      self.stream(move |options| send.run(options)),
+    ))
+  }
+
+  async fn list_inscriptions(
+    &self,
+    request: Request<proto::ListInscriptionsRequest>,
+  ) -> Result<tonic::Response<proto::ListInscriptionsResponse>, Status> {
+    self.authorize(&request, None)?;
+
+    let mut args = Vec::new();
+
+    if let Some(address) = request.into_inner().address {
+      args.push(format!("--address={address}"));
+    }
+
+    let inscriptions = parse::<Inscriptions>("inscriptions", args)?;
+
+    let output = self
+      .blocking(move |state| {
+        let output = Arc::new(Mutex::new(serde_json::Value::Null));
+        let sink = output.clone();
+
+        let _wallet = state.wallet.lock().unwrap();
+
+        capture_output(
+          move |value| *sink.lock().unwrap() = value,
+          || inscriptions.run(state.options.clone()),
+        )?;
+
+        let output = output.lock().unwrap().take();
+
+        Ok(output)
+      })
+      .await?;
+
+    let inscriptions = serde_json::from_value::<Vec<ListedInscription>>(output)
+      .map_err(|error| Status::internal(error.to_string()))?
+      .into_iter()
+      .map(|inscription| proto::Inscription {
+        id: inscription.inscription.to_string(),
+        number: inscription.number,
+        location: inscription.location.to_string(),
+        postage: inscription.postage,
+        sat: inscription.sat,
+        explorer: inscription.explorer,
+      })
+      .collect();
+
+    Ok(tonic::Response::new(proto::ListInscriptionsResponse {
+      inscriptions,
+    }))
+  }
+
+  async fn estimate_fee(
+    &self,
+    request: Request<proto::EstimateFeeRequest>,
+  ) -> Result<tonic::Response<proto::EstimateFeeResponse>, Status> {
+    self.authorize(&request, None)?;
+
+    let blocks = u16::try_from(request.into_inner().blocks)
+      .map_err(|_| Status::invalid_argument("blocks must be at most 65535"))?;
+
+    let estimate = self
+      .blocking(move |state| Daemon::estimate_fee(&state.options, blocks))
+      .await?;
+
+    Ok(tonic::Response::new(proto::EstimateFeeResponse {
+      blocks: estimate.blocks.into(),
+      fee_rate: estimate.fee_rate,
+    }))
+  }
+}
+
+/// An inscription as listed by `ord wallet inscriptions`, with a sat if the
+/// index tracks sats.
+#[derive(Deserialize)]
+struct ListedInscription {
+  inscription: InscriptionId,
+  number: i64,
+  location: SatPoint,
+  explorer: String,
+  postage: u64,
+  sat: Option<u64>,
+}
+
+fn progress(update: Update) -> proto::Progress {
+  proto::Progress {
+    update: Some(update),
+  }
+}
+
+/// The gRPC equivalent of an HTTP error status returned by `ApiKeys`.
+fn code(status: StatusCode) -> Code {
+  match status {
+    StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+    StatusCode::FORBIDDEN => Code::PermissionDenied,
+    StatusCode::TOO_MANY_REQUESTS => Code::ResourceExhausted,
+    _ => Code::Internal,
+  }
+}
+
+fn parse<T: Parser>(name: &str, args: Vec<String>) -> Result<T, Status> {
+  T::try_parse_from(std::iter::once(name.to_string()).chain(args))
+    .map_err(|error| Status::invalid_argument(error.to_string()))
+}
+
+/// Write the request's files to `dir`, and return the arguments of the
+/// equivalent `ord wallet inscribe` command. Values are joined to their flags
+/// and positional arguments follow `--`, so that no value is parsed as a
+/// flag. Files are named by their position, so that they keep their order,
+/// and so that names sent by clients aren't used as paths or glob patterns.
+pub(super) fn inscribe_args(
+  request: proto::InscribeRequest,
+  dir: &std::path::Path,
+) -> Result<Vec<String>> {
+  if request.files.is_empty() {
+    bail!("no files to inscribe");
+  }
+
+  let mut args = vec![
+    format!("--fee-rate={}", request.fee_rate),
+    // there is no terminal to ask for confirmation on
+    "--yes".into(),
+  ];
+
+  if let Some(commit_fee_rate) = request.commit_fee_rate {
+    args.push(format!("--commit-fee-rate={commit_fee_rate}"));
+  }
+
+  for destination in request.destinations {
+    args.push(format!("--destination={destination}"));
+  }
+
+  if let Some(postage) = request.postage {
+    args.push(format!("--postage={postage} sat"));
+  }
+
+  if let Some(satpoint) = request.satpoint {
+    args.push(format!("--satpoint={satpoint}"));
+  }
+
+  for (flag, enabled) in [
+    ("--dry-run", request.dry_run),
+    ("--no-backup", request.no_backup),
+    ("--no-limit", request.no_limit),
+    ("--wait-after-commit", request.wait_after_commit),
+  ] {
+    if enabled {
+      args.push(flag.into());
+    }
+  }
+
+  args.push("--".into());
+
+  for (i, file) in request.files.into_iter().enumerate() {
+    let extension = std::path::Path::new(&file.name)
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .filter(|extension| extension.chars().all(|c| c.is_ascii_alphanumeric()))
+      .ok_or_else(|| anyhow!("file `{}` has no extension", file.name))?;
+
+    let path = dir.join(format!("{i}.{extension}"));
+
+    fs::write(&path, file.content)?;
+
+    args.push(path.display().to_string());
+  }
+
+  Ok(args)
+}
+
+/// Return the arguments of the `ord wallet send` command equivalent to
+/// `request`, built like those of `inscribe_args`.
+pub(super) fn send_args(request: proto::SendRequest) -> Vec<String> {
+  let mut args = vec![format!("--fee-rate={}", request.fee_rate)];
+
+  if let Some(target_postage) = request.target_postage {
+    args.push(format!("--target-postage={target_postage} sat"));
+  }
+
+  if let Some(max_postage) = request.max_postage {
+    args.push(format!("--max-postage={max_postage} sat"));
+  }
+
+  if let Some(change) = request.change {
+    args.push(format!("--change={change}"));
+  }
+
+  args.extend(["--".into(), request.address, request.outgoing]);
+
+  args
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn file(name: &str, content: &str) -> proto::File {
+    proto::File {
+      name: name.into(),
+      content: content.into(),
+    }
+  }
+
+  #[test]
+  fn inscribe_request_files_are_written_in_order() {
+    let dir = TempDir::new().unwrap();
+
+    let args = inscribe_args(
+      proto::InscribeRequest {
+        files: vec![file("b.txt", "foo"), file("a.png", "bar")],
+        fee_rate: 1.5,
+        ..Default::default()
+      },
+      dir.path(),
+    )
+    .unwrap();
+
+    let first = dir.path().join("0.txt");
+    let second = dir.path().join("1.png");
+
+    assert_eq!(
+      args,
+      [
+        "--fee-rate=1.5".into(),
+        "--yes".into(),
+        "--".into(),
+        first.display().to_string(),
+        second.display().to_string(),
+      ]
+    );
+
+    assert_eq!(fs::read_to_string(first).unwrap(), "foo");
+    assert_eq!(fs::read_to_string(second).unwrap(), "bar");
+
+    let inscribe = parse::<Inscribe>("inscribe", args).unwrap();
+    assert_eq!(inscribe.fee_rate, FeeRate::try_from(1.5).unwrap());
+    assert!(inscribe.yes);
+  }
+
+  #[test]
+  fn inscribe_request_options_become_arguments() {
+    let dir = TempDir::new().unwrap();
+
+    let args = inscribe_args(
+      proto::InscribeRequest {
+        files: vec![file("foo.txt", "foo")],
+        fee_rate: 2.0,
+        commit_fee_rate: Some(3.0),
+        destinations: vec![
+          "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+          "@cold".into(),
+        ],
+        postage: Some(546),
+        dry_run: true,
+        no_backup: true,
+        ..Default::default()
+      },
+      dir.path(),
+    )
+    .unwrap();
+
+    let inscribe = parse::<Inscribe>("inscribe", args).unwrap();
+
+    assert_eq!(
+      inscribe.commit_fee_rate,
+      Some(FeeRate::try_from(3.0).unwrap())
+    );
+    assert_eq!(inscribe.destination.len(), 2);
+    assert_eq!(inscribe.postage, Some(Amount::from_sat(546)));
+    assert!(inscribe.dry_run);
+    assert!(inscribe.no_backup);
+    assert!(!inscribe.no_limit);
+    assert!(!inscribe.wait_after_commit);
+    assert_eq!(inscribe.files, [dir.path().join("0.txt")]);
+  }
+
+  #[test]
+  fn inscribe_request_file_names_are_not_used_as_paths() {
+    let dir = TempDir::new().unwrap();
+
+    let args = inscribe_args(
+      proto::InscribeRequest {
+        files: vec![file("../../*.txt", "foo")],
+        fee_rate: 1.0,
+        ..Default::default()
+      },
+      dir.path(),
+    )
+    .unwrap();
+
+    assert_eq!(
+      args.last().unwrap(),
+      &dir.path().join("0.txt").display().to_string()
+    );
+  }
+
+  #[test]
+  fn inscribe_request_requires_files_with_extensions() {
+    let dir = TempDir::new().unwrap();
+
+    assert_eq!(
+      inscribe_args(
+        proto::InscribeRequest {
+          fee_rate: 1.0,
+          ..Default::default()
+        },
+        dir.path(),
+      )
+      .unwrap_err()
+      .to_string(),
+      "no files to inscribe",
+    );
+
+    assert_eq!(
+      inscribe_args(
+        proto::InscribeRequest {
+          files: vec![file("foo", "foo")],
+          fee_rate: 1.0,
+          ..Default::default()
+        },
+        dir.path(),
+      )
+      .unwrap_err()
+      .to_string(),
+      "file `foo` has no extension",
+    );
+  }
+
+  #[test]
+  fn send_request_becomes_send_command() {
+    let args = send_args(proto::SendRequest {
+      address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+      outgoing: "1000 sat".into(),
+      fee_rate: 1.0,
+      target_postage: Some(1000),
+      max_postage: None,
+      change: None,
+    });
+
+    assert_eq!(
+      args,
+      [
+        "--fee-rate=1",
+        "--target-postage=1000 sat",
+        "--",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        "1000 sat",
+      ]
+    );
+
+    let send = parse::<SendCommand>("send", args).unwrap();
+    assert_eq!(send.target_postage, Some(Amount::from_sat(1000)));
+  }
+
+  #[test]
+  fn invalid_requests_are_invalid_arguments() {
+    let status = parse::<SendCommand>(
+      "send",
+      send_args(proto::SendRequest {
+        address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+        outgoing: "1000 sat".into(),
+        fee_rate: -1.0,
+        ..Default::default()
+      }),
+    )
+    .unwrap_err();
+
+    assert_eq!(status.code(), Code::InvalidArgument);
+  }
+
+  #[test]
+  fn request_values_are_not_parsed_as_flags() {
+    let send = parse::<SendCommand>(
+      "send",
+      send_args(proto::SendRequest {
+        address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+        outgoing: "--simulate".into(),
+        fee_rate: 1.0,
+        ..Default::default()
+      }),
+    );
+
+    assert_eq!(send.unwrap_err().code(), Code::InvalidArgument);
+
+    let dir = TempDir::new().unwrap();
+
+    let inscribe = parse::<Inscribe>(
+      "inscribe",
+      inscribe_args(
+        proto::InscribeRequest {
+          files: vec![file("foo.txt", "foo")],
+          fee_rate: 1.0,
+          destinations: vec!["--dry-run".into()],
+          ..Default::default()
+        },
+        dir.path(),
+      )
+      .unwrap(),
+    );
+
+    assert_eq!(inscribe.unwrap_err().code(), Code::InvalidArgument);
+  }
+
+  #[test]
+  fn api_key_errors_have_grpc_codes() {
+    let api_keys = ApiKeys {
+      keys: serde_yaml::from_str("- {name: a, key: secret, scope: read, rate_limit: 1}").unwrap(),
+      windows: Mutex::new(HashMap::new()),
+    };
+
+    let error_code = |token: Option<&str>, command: Option<&str>| {
+      code(api_keys.authorize(token, command).unwrap_err().0)
+    };
+
+    assert_eq!(error_code(None, None), Code::Unauthenticated);
+    assert_eq!(
+      error_code(Some("secret"), Some("send")),
+      Code::PermissionDenied
+    );
+
+    api_keys.authorize(Some("secret"), None).unwrap();
+
+    assert_eq!(error_code(Some("secret"), None), Code::ResourceExhausted);
+  }
+
+  #[test]
+  fn listed_inscriptions_are_parsed_with_and_without_sats() {
+    let without_sat = serde_json::from_value::<ListedInscription>(serde_json::json!({
+      "number": 0,
+      "inscription": inscription_id(1),
+      "location": satpoint(1, 0),
+      "explorer": "https://ordinals.com/inscription/",
+      "postage": 10000,
+    }))
+    .unwrap();
+
+    assert_eq!(without_sat.sat, None);
+
+    let with_sat = serde_json::from_value::<ListedInscription>(serde_json::json!({
+      "sat": 5000000000u64,
+      "number": 0,
+      "inscription": inscription_id(1),
+      "location": satpoint(1, 0),
+      "explorer": "https://ordinals.com/inscription/",
+      "postage": 10000,
+    }))
+    .unwrap();
+
+    assert_eq!(with_sat.sat, Some(5000000000));
+  }
+}
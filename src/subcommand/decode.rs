@@ -14,7 +14,7 @@ pub(crate) struct Decode {
 
 impl Decode {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
 
     let inputs = &Index::get_transaction(&index, self.txid)?.unwrap().input;
     let vin = self.vin;
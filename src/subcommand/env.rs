@@ -0,0 +1,149 @@
+use {
+  super::*,
+  bitcoin::secp256k1::rand::{self, RngCore},
+  preview::KillOnDrop,
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Env {
+  #[clap(
+    default_value = "env",
+    help = "Store the regtest bitcoind and ord data in <DIRECTORY>."
+  )]
+  directory: PathBuf,
+  #[clap(
+    long,
+    default_value = "101",
+    help = "Mine <BLOCKS> blocks to the ord wallet when it is created, so that its first coinbase output is spendable."
+  )]
+  blocks: u64,
+  #[clap(
+    long,
+    help = "Mine a block to the ord wallet every <MINE_INTERVAL> seconds."
+  )]
+  mine_interval: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct Info {
+  pub bitcoin_cli_command: Vec<String>,
+  pub bitcoind_port: u16,
+  pub mnemonic: Option<Mnemonic>,
+  pub ord_wallet_command: Vec<String>,
+}
+
+impl Env {
+  pub(crate) fn run(self) -> Result {
+    fs::create_dir_all(&self.directory)?;
+
+    let directory = self.directory.canonicalize()?;
+
+    let bitcoin_data_dir = directory.join("bitcoin");
+
+    fs::create_dir_all(&bitcoin_data_dir)?;
+
+    let rpc_port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+
+    let _bitcoind = KillOnDrop(
+      Command::new("bitcoind")
+        .arg({
+          let mut arg = OsString::from("-datadir=");
+          arg.push(&bitcoin_data_dir);
+          arg
+        })
+        .arg("-regtest")
+        .arg("-txindex")
+        .arg("-listen=0")
+        .arg(format!("-rpcport={rpc_port}"))
+        .spawn()
+        .context("failed to spawn `bitcoind`")?,
+    );
+
+    let options = Options {
+      chain_argument: Chain::Regtest,
+      bitcoin_data_dir: Some(bitcoin_data_dir.clone()),
+      data_dir: Some(directory.clone()),
+      rpc_url: Some(format!("127.0.0.1:{rpc_port}")),
+      ..Options::default()
+    };
+
+    for attempt in 0.. {
+      if options.bitcoin_rpc_client().is_ok() {
+        break;
+      }
+
+      if attempt == 100 {
+        bail!("Bitcoin Core RPC did not respond");
+      }
+
+      thread::sleep(Duration::from_millis(50));
+    }
+
+    // an existing environment is reused as is, its wallet is loaded by
+    // `bitcoin_rpc_client_for_wallet_command`
+    let wallet_exists = options
+      .bitcoin_rpc_client()?
+      .list_wallet_dir()?
+      .contains(&options.wallet);
+
+    let mnemonic = if wallet_exists {
+      None
+    } else {
+      let mut entropy = [0; 16];
+      rand::thread_rng().fill_bytes(&mut entropy);
+
+      let mnemonic = Mnemonic::from_entropy(&entropy)?;
+
+      wallet::initialize_wallet(&options, mnemonic.to_seed(""))?;
+
+      Some(mnemonic)
+    };
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let address = client
+      .get_new_address(None, Some(bitcoincore_rpc::json::AddressType::Bech32m))?
+      .require_network(Network::Regtest)?;
+
+    if !wallet_exists {
+      client.generate_to_address(self.blocks, &address)?;
+    }
+
+    print_json(Info {
+      bitcoin_cli_command: vec![
+        "bitcoin-cli".into(),
+        format!("-datadir={}", bitcoin_data_dir.display()),
+        "-regtest".into(),
+        format!("-rpcport={rpc_port}"),
+      ],
+      bitcoind_port: rpc_port,
+      mnemonic,
+      ord_wallet_command: vec![
+        "ord".into(),
+        "--regtest".into(),
+        "--bitcoin-data-dir".into(),
+        bitcoin_data_dir.display().to_string(),
+        "--data-dir".into(),
+        directory.display().to_string(),
+        "--rpc-url".into(),
+        format!("127.0.0.1:{rpc_port}"),
+        "wallet".into(),
+      ],
+    })?;
+
+    let mut last_block = Instant::now();
+
+    while !SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+      if let Some(interval) = self.mine_interval {
+        if last_block.elapsed() >= Duration::from_secs(interval) {
+          client.generate_to_address(1, &address)?;
+          last_block = Instant::now();
+        }
+      }
+
+      thread::sleep(Duration::from_millis(100));
+    }
+
+    Ok(())
+  }
+}
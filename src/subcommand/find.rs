@@ -1,9 +1,15 @@
 use {
   super::*,
   chrono::NaiveDateTime,
+  clap::ValueEnum,
   std::io::{BufRead, BufReader},
 };
 
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum GroupBy {
+  Output,
+}
+
 #[derive(Debug, Parser)]
 pub(crate) struct Find {
   #[clap(long, help = "Only look in specified outpoint(s).")]
@@ -18,7 +24,7 @@ pub(crate) struct Find {
     long,
     help = "Read a list of sats and ranges to find from a file. One sat or range per line. Ranges written as <start>-<end>."
   )]
-  file: Vec<PathBuf>,
+  pub(crate) file: Vec<PathBuf>,
   #[clap(long, help = "Ignore bad sat ranges.")]
   ignore: bool,
   #[clap(long, help = "Show addresses in the results.")]
@@ -35,6 +41,19 @@ pub(crate) struct Find {
   show_time: bool,
   #[clap(long, help = "Show output values in the results.")]
   show_value: bool,
+  #[clap(
+    long,
+    value_enum,
+    help = "Group results by <GROUP_BY>. With `output`, results in the same output are merged, with contiguous results joined into single ranges."
+  )]
+  group_by: Option<GroupBy>,
+  #[clap(long, help = "Only list the outputs that contain any of the sats.")]
+  summary: bool,
+  #[clap(
+    long,
+    help = "Find where <SAT> was once block <AT_HEIGHT> had been mined, instead of where it is now."
+  )]
+  at_height: Option<u64>,
   #[clap(help = "Find output and offset of <SAT>.")]
   sat: Option<Sat>,
   #[clap(help = "Find output and offset of all sats in the range <SAT>-<END>.")]
@@ -62,12 +81,62 @@ pub struct Output {
   pub value: Option<u64>,
 }
 
+/// Where a sat was, with `--at-height`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalOutput {
+  pub sat: Sat,
+  pub height: u64,
+  /// `None` if the sat hadn't been mined yet.
+  pub satpoint: Option<SatPoint>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub address: Option<Address<NetworkUnchecked>>,
+}
+
+/// The results in one output, with `--group-by output`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputGroup {
+  pub outpoint: OutPoint,
+  /// Sats found in the output.
+  pub size: u64,
+  /// Ordered by offset.
+  pub ranges: Vec<Range>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub address: Option<Address<NetworkUnchecked>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub blockhash: Option<bitcoin::BlockHash>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub date: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub height: Option<usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub timestamp: Option<usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub value: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+  pub start: u64,
+  pub size: u64,
+  pub offset: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name: Option<String>,
+}
+
 impl Find {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
 
     index.update()?;
 
+    if self.summary && self.group_by.is_some() {
+      bail!("cannot use both --summary and --group-by");
+    }
+
+    if let Some(height) = self.at_height {
+      return self.run_at_height(&index, options.chain(), height);
+    }
+
     let mut targets = Vec::new();
     let mut results = Vec::new();
 
@@ -160,6 +229,18 @@ impl Find {
       }
     }
 
+    if self.summary {
+      let mut seen = HashSet::new();
+
+      return print_json(
+        results
+          .into_iter()
+          .map(|result| result.satpoint.outpoint)
+          .filter(|outpoint| seen.insert(*outpoint))
+          .collect::<Vec<OutPoint>>(),
+      );
+    }
+
     let mut detailed_results = Vec::new();
 
     // let gbt = options.chain().genesis_block().coinbase().unwrap().clone();
@@ -240,8 +321,99 @@ impl Find {
       detailed_results.push(result);
     }
 
-    print_json(detailed_results)?;
+    match self.group_by {
+      Some(GroupBy::Output) => print_json(Self::group_by_output(detailed_results))?,
+      None => print_json(detailed_results)?,
+    }
 
     Ok(())
   }
+
+  fn run_at_height(&self, index: &Index, chain: Chain, height: u64) -> Result {
+    let Some(sat) = self.sat else {
+      bail!("--at-height requires a <SAT>");
+    };
+
+    if self.end.is_some() || !self.file.is_empty() || !self.outpoint.is_empty() {
+      bail!("--at-height only finds a single <SAT>");
+    }
+
+    let satpoint = index.find_sat_at_height(sat, height)?;
+
+    let address = match satpoint {
+      Some(satpoint) => index
+        .get_transaction(satpoint.outpoint.txid)?
+        .and_then(|tx| {
+          tx.output
+            .into_iter()
+            .nth(satpoint.outpoint.vout.try_into().unwrap())
+        })
+        .and_then(|output| chain.address_from_script(&output.script_pubkey).ok())
+        .map(|address| address.as_unchecked().clone()),
+      None => None,
+    };
+
+    print_json(HistoricalOutput {
+      sat,
+      height,
+      satpoint,
+      address,
+    })
+  }
+
+  /// Groups are in the order their outputs were first found in.
+  fn group_by_output(results: Vec<Output>) -> Vec<OutputGroup> {
+    let mut groups = Vec::<OutputGroup>::new();
+    let mut positions = BTreeMap::new();
+
+    for result in results {
+      let position = *positions
+        .entry(result.satpoint.outpoint)
+        .or_insert_with(|| {
+          groups.push(OutputGroup {
+            outpoint: result.satpoint.outpoint,
+            size: 0,
+            ranges: Vec::new(),
+            address: result.address.clone(),
+            blockhash: result.blockhash,
+            date: result.date.clone(),
+            height: result.height,
+            timestamp: result.timestamp,
+            value: result.value,
+          });
+          groups.len() - 1
+        });
+
+      let group = &mut groups[position];
+
+      group.size += result.size;
+      group.ranges.push(Range {
+        start: result.start,
+        size: result.size,
+        offset: result.satpoint.offset,
+        name: result.name,
+      });
+    }
+
+    for group in &mut groups {
+      group.ranges.sort_by_key(|range| range.offset);
+
+      let mut merged = Vec::<Range>::new();
+
+      for range in group.ranges.drain(..) {
+        if let Some(last) = merged.last_mut() {
+          if last.offset + last.size == range.offset && last.start + last.size == range.start {
+            last.size += range.size;
+            continue;
+          }
+        }
+
+        merged.push(range);
+      }
+
+      group.ranges = merged;
+    }
+
+    groups
+  }
 }
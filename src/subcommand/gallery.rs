@@ -0,0 +1,116 @@
+use {
+  super::*,
+  crate::{
+    templates::{GalleryEntry, GalleryHtml},
+    wallet::Wallet,
+  },
+};
+
+#[derive(Debug, Parser)]
+#[clap(group(
+  ArgGroup::new("source")
+    .required(true)
+    .args(&["address", "wallet"]),
+))]
+pub(crate) struct Gallery {
+  #[clap(long, help = "Write the gallery to <OUTPUT_DIR>.")]
+  output_dir: PathBuf,
+  #[clap(long, help = "Show the wallet's inscriptions owned by <ADDRESS>.")]
+  address: Option<Address<NetworkUnchecked>>,
+  #[clap(long, help = "Show all of the wallet's inscriptions.")]
+  wallet: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub index: PathBuf,
+  pub inscriptions: u64,
+}
+
+impl Gallery {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let address = match self.address {
+      Some(address) => Some(address.require_network(options.chain().network())?),
+      None => None,
+    };
+
+    let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    let explorer = format!("{}/inscription/", options.explorer_url()?);
+
+    let content_dir = self.output_dir.join("content");
+    fs::create_dir_all(&content_dir)
+      .with_context(|| format!("failed to create {}", content_dir.display()))?;
+
+    let mut entries = Vec::new();
+    for (location, id) in index.get_inscriptions_vector(unspent_outputs)? {
+      if let Some(address) = &address {
+        let output = index
+          .get_transaction(location.outpoint.txid)?
+          .unwrap()
+          .output
+          .into_iter()
+          .nth(location.outpoint.vout.try_into().unwrap())
+          .unwrap();
+
+        if options.chain().address_from_script(&output.script_pubkey)? != *address {
+          continue;
+        }
+      }
+
+      let entry = index
+        .get_inscription_entry(id)?
+        .ok_or_else(|| anyhow!("inscription {id} not found"))?;
+
+      let inscription = index
+        .get_inscription_by_id(id)?
+        .ok_or_else(|| anyhow!("inscription {id} not found"))?;
+
+      // browsers pick how to display local files by their extension
+      let file = match inscription
+        .content_type()
+        .and_then(Media::extension_for_content_type)
+      {
+        Some(extension) => format!("content/{id}.{extension}"),
+        None => format!("content/{id}"),
+      };
+
+      fs::write(
+        self.output_dir.join(&file),
+        inscription.body().unwrap_or_default(),
+      )?;
+
+      entries.push(GalleryEntry {
+        id,
+        number: entry.number,
+        media: inscription.media(),
+        file,
+        explorer: format!("{explorer}{id}"),
+      });
+    }
+
+    entries.sort_by_key(|entry| entry.number);
+
+    let inscriptions = entries.len().try_into().unwrap();
+
+    let title = match &address {
+      Some(address) => address.to_string(),
+      None => "Wallet".into(),
+    };
+
+    let path = self.output_dir.join("index.html");
+
+    fs::write(&path, GalleryHtml { title, entries }.to_string())
+      .with_context(|| format!("failed to write {}", path.display()))?;
+
+    print_json(Output {
+      index: path,
+      inscriptions,
+    })?;
+
+    Ok(())
+  }
+}
@@ -4,6 +4,10 @@ use super::*;
 pub(crate) enum IndexSubcommand {
   #[clap(about = "Write inscription numbers and ids to a tab-separated file")]
   Export(Export),
+  #[clap(
+    about = "Seed the index with output values from a Bitcoin Core `dumptxoutset` snapshot, to skip looking them up while indexing"
+  )]
+  LoadUtxoSnapshot(LoadUtxoSnapshot),
   #[clap(about = "Update the index")]
   Run,
 }
@@ -12,6 +16,7 @@ impl IndexSubcommand {
   pub(crate) fn run(self, options: Options) -> Result {
     match self {
       Self::Export(export) => export.run(options),
+      Self::LoadUtxoSnapshot(load_utxo_snapshot) => load_utxo_snapshot.run(options),
       Self::Run => index::run(options),
     }
   }
@@ -40,10 +45,45 @@ impl Export {
   }
 }
 
+#[derive(Debug, Parser)]
+pub(crate) struct LoadUtxoSnapshot {
+  #[clap(
+    help = "Load output values from <SNAPSHOT>, written by `bitcoin-cli dumptxoutset`. Best taken at the block before the first inscription with `rollback=<HEIGHT>`, and loaded into a new index."
+  )]
+  snapshot: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoadUtxoSnapshotOutput {
+  pub base_hash: BlockHash,
+  pub base_height: u64,
+  pub coins: u64,
+}
+
+impl LoadUtxoSnapshot {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open(&options)?;
+
+    let (base_hash, base_height, coins) = index.load_utxo_snapshot(&self.snapshot)?;
+
+    print_json(LoadUtxoSnapshotOutput {
+      base_hash,
+      base_height,
+      coins,
+    })
+  }
+}
+
 pub(crate) fn run(options: Options) -> Result {
-  let index = Index::open(&options)?;
+  let mut index = Index::open(&options)?;
 
   index.update()?;
 
+  match index.auto_compact() {
+    Ok(Some(compaction)) => print_json(compaction)?,
+    Ok(None) => {}
+    Err(err) => log::warn!("Failed to compact index: {err}"),
+  }
+
   Ok(())
 }
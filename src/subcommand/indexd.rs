@@ -0,0 +1,218 @@
+use {
+  super::*,
+  std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+  },
+};
+
+/// Wallet commands run one at a time, so that concurrent commands don't
+/// select the same utxos.
+static WALLET: Mutex<()> = Mutex::new(());
+
+const CANNOT_PROXY: &str =
+  "indexd is running, and can't run commands that use files or ask for confirmation";
+
+#[derive(Debug, Parser)]
+pub(crate) struct Indexd {}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Message {
+  Output(serde_json::Value),
+  Error(String),
+  Done,
+}
+
+fn socket_path(options: &Options) -> Result<PathBuf> {
+  Ok(options.data_dir()?.join("indexd.sock"))
+}
+
+/// Run the command in `env::args`, which is `subcommand`, on a running
+/// `ord indexd` for the same data dir, printing its output here. Returns
+/// `Ok(false)` if no daemon is listening, in which case the caller should run
+/// the command itself.
+pub(crate) fn proxy(options: &Options, subcommand: &Subcommand) -> Result<bool> {
+  // indexd only serves the index in the data dir
+  if options.index.is_some() {
+    return Ok(false);
+  }
+
+  let Ok(stream) = UnixStream::connect(socket_path(options)?) else {
+    return Ok(false);
+  };
+
+  // indexd has the index open, so the command can't open it either
+  if !subcommand.can_proxy() {
+    bail!(CANNOT_PROXY);
+  }
+
+  let mut request = serde_json::to_string(&env::args().collect::<Vec<String>>())?;
+  request.push('\n');
+  (&stream).write_all(request.as_bytes())?;
+
+  for line in BufReader::new(stream).lines() {
+    match serde_json::from_str(&line?)? {
+      Message::Output(serde_json::Value::String(line)) => println!("{line}"),
+      Message::Output(output) => {
+        serde_json::to_writer_pretty(io::stdout(), &output)?;
+        println!();
+      }
+      Message::Error(error) => return Err(anyhow!(error)),
+      Message::Done => return Ok(true),
+    }
+  }
+
+  bail!("indexd closed the connection before the command finished");
+}
+
+impl Indexd {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let path = socket_path(&options)?;
+
+    // checked before opening the index, which the running indexd has open
+    if UnixStream::connect(&path).is_ok() {
+      bail!("indexd is already listening on {}", path.display());
+    }
+
+    let index = Arc::new(Index::open(&options)?);
+    index.update()?;
+
+    Index::share(index.clone());
+
+    let index_thread = thread::spawn(move || loop {
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        break;
+      }
+      if let Err(error) = index.update() {
+        log::warn!("Updating index: {error}");
+      }
+      thread::sleep(Duration::from_millis(10000));
+    });
+    INDEXER.lock().unwrap().replace(index_thread);
+
+    // left behind by a daemon that didn't shut down cleanly
+    if path.exists() {
+      fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)
+      .with_context(|| format!("failed to listen on {}", path.display()))?;
+    listener.set_nonblocking(true)?;
+
+    if !integration_test() {
+      eprintln!("Listening on {}", path.display());
+    }
+
+    let result = Self::serve(&listener);
+
+    fs::remove_file(&path).ok();
+
+    result
+  }
+
+  fn serve(listener: &UnixListener) -> Result {
+    while !SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+      match listener.accept() {
+        Ok((stream, _)) => {
+          stream.set_nonblocking(false)?;
+          thread::spawn(move || {
+            if let Err(error) = Self::handle(stream) {
+              log::warn!("Handling indexd client: {error}");
+            }
+          });
+        }
+        Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+          thread::sleep(Duration::from_millis(100));
+        }
+        Err(error) => return Err(error.into()),
+      }
+    }
+
+    Ok(())
+  }
+
+  fn handle(stream: UnixStream) -> Result {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+
+    let send = |mut stream: &UnixStream, message: Message| -> Result {
+      let mut line = serde_json::to_string(&message)?;
+      line.push('\n');
+      Ok(stream.write_all(line.as_bytes())?)
+    };
+
+    let args = serde_json::from_str::<Vec<String>>(&request)?;
+
+    let result = match Arguments::try_parse_from(args) {
+      Ok(arguments) if !arguments.subcommand.can_proxy() => Err(anyhow!(CANNOT_PROXY)),
+      Ok(arguments) => {
+        let _wallet =
+          matches!(arguments.subcommand, Subcommand::Wallet(_)).then(|| WALLET.lock().unwrap());
+
+        let output_stream = stream.try_clone()?;
+
+        capture_output(
+          move |output| {
+            send(&output_stream, Message::Output(output)).ok();
+          },
+          || arguments.subcommand.run(arguments.options),
+        )
+      }
+      Err(error) => Err(error.into()),
+    };
+
+    send(
+      &stream,
+      match result {
+        Ok(()) => Message::Done,
+        Err(error) => Message::Error(format!("{error:#}")),
+      },
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn options(data_dir: &Path) -> Options {
+    Options::try_parse_from(["ord", "--data-dir", data_dir.to_str().unwrap()]).unwrap()
+  }
+
+  #[test]
+  fn commands_are_not_proxied_without_indexd() {
+    let tempdir = TempDir::new().unwrap();
+
+    assert!(!proxy(&options(tempdir.path())).unwrap());
+  }
+
+  #[test]
+  fn commands_are_not_proxied_to_stale_socket() {
+    let tempdir = TempDir::new().unwrap();
+    let options = options(tempdir.path());
+
+    drop(UnixListener::bind(socket_path(&options).unwrap()).unwrap());
+
+    assert!(socket_path(&options).unwrap().exists());
+    assert!(!proxy(&options).unwrap());
+  }
+
+  #[test]
+  fn commands_with_index_path_are_not_proxied() {
+    let tempdir = TempDir::new().unwrap();
+
+    let options = Options::try_parse_from([
+      "ord",
+      "--data-dir",
+      tempdir.path().to_str().unwrap(),
+      "--index",
+      tempdir.path().join("other.redb").to_str().unwrap(),
+    ])
+    .unwrap();
+
+    let _listener = UnixListener::bind(socket_path(&options).unwrap()).unwrap();
+
+    assert!(!proxy(&options).unwrap());
+  }
+}
@@ -16,7 +16,7 @@ pub struct TransactionsOutput {
 
 impl Info {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
     index.update()?;
     let info = index.info()?;
 
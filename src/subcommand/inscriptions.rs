@@ -21,6 +21,11 @@ pub(crate) struct Inscriptions {
     help = "List inscriptions in order of inscribed satoshi ordinals."
   )]
   order_by_sat: bool,
+  #[clap(
+    long,
+    help = "List inscriptions on sats that were spent as fees but not claimed by the coinbase. Their location is the null outpoint."
+  )]
+  lost: bool,
 }
 
 #[derive(Serialize)]
@@ -30,6 +35,9 @@ pub struct Output {
   pub number: i64,
   pub height: u64,
   pub timestamp: u32,
+  pub genesis_fee: u64,
+  /// `None` for inscriptions indexed before the vsize was recorded.
+  pub genesis_vsize: Option<u64>,
   pub inscription: InscriptionId,
   pub location: SatPoint,
   #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -42,7 +50,7 @@ pub struct Output {
 
 impl Inscriptions {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
 
     index.update()?;
 
@@ -62,6 +70,10 @@ impl Inscriptions {
       bail!("can't specify --number and --id");
     }
 
+    if self.lost && (self.number.is_some() || self.id.is_some()) {
+      bail!("can't specify --lost with --number or --id");
+    }
+
     if self.number.is_some() || self.id.is_some() {
       let inscription = if self.number.is_some() {
         let number = self.number.unwrap();
@@ -109,6 +121,8 @@ impl Inscriptions {
         number: entry.number,
         height: entry.height,
         timestamp: entry.timestamp,
+        genesis_fee: entry.fee,
+        genesis_vsize: index.get_inscription_genesis_vsize(inscription)?,
         address,
         amount,
         content_type: Some(content_type),
@@ -117,6 +131,37 @@ impl Inscriptions {
       return Ok(());
     }
 
+    if self.lost {
+      let mut outputs = Vec::new();
+
+      for (location, inscription) in index
+        .get_lost_inscriptions()?
+        .into_iter()
+        .take(self.limit.unwrap_or(usize::MAX))
+      {
+        let entry = index
+          .get_inscription_entry(inscription)?
+          .ok_or_else(|| anyhow!("Inscription {inscription} not found"))?;
+        outputs.push(Output {
+          sat: entry.sat,
+          number: entry.number,
+          height: entry.height,
+          timestamp: entry.timestamp,
+          genesis_fee: entry.fee,
+          genesis_vsize: index.get_inscription_genesis_vsize(inscription)?,
+          inscription,
+          location,
+          address: None,
+          amount: None,
+          content_type: None,
+        });
+      }
+
+      print_json(&outputs)?;
+
+      return Ok(());
+    }
+
     let inscriptions = if self.order_by_sat {
       index.get_inscriptions_by_sat(
         // missing
@@ -150,6 +195,8 @@ impl Inscriptions {
         number: entry.number,
         height: entry.height,
         timestamp: entry.timestamp,
+        genesis_fee: entry.fee,
+        genesis_vsize: index.get_inscription_genesis_vsize(inscription)?,
         inscription,
         location,
         address: None,
@@ -19,7 +19,7 @@ pub struct Output {
 
 impl List {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
 
     index.update()?;
 
@@ -0,0 +1,96 @@
+use super::{wallet::pending, *};
+
+/// Lower bounds of the fee rate histogram's buckets, in sats/vB.
+const BUCKETS: [f64; 20] = [
+  0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 8.0, 10.0, 12.0, 15.0, 20.0, 30.0, 40.0, 50.0, 75.0, 100.0,
+  150.0, 300.0, 1000.0,
+];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+  pub min_fee_rate: f64,
+  pub transactions: u64,
+  pub vsize: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  pub transactions: u64,
+  pub vsize: u64,
+  /// Transactions with inscription envelopes in their witnesses.
+  pub inscriptions: u64,
+  /// Reveals broadcast by `ord wallet inscribe` that are in the mempool.
+  pub pending_reveals: u64,
+  /// Transactions and vsize by fee rate, omitting empty buckets.
+  pub histogram: Vec<Bucket>,
+}
+
+pub(crate) fn run(options: Options) -> Result {
+  let client = options.bitcoin_rpc_client()?;
+
+  let mempool = client.get_raw_mempool()?;
+
+  let mut histogram = BUCKETS
+    .iter()
+    .map(|min_fee_rate| Bucket {
+      min_fee_rate: *min_fee_rate,
+      transactions: 0,
+      vsize: 0,
+    })
+    .collect::<Vec<Bucket>>();
+
+  let mut vsize = 0;
+  let mut inscriptions = 0;
+
+  for txid in &mempool {
+    // transactions can leave the mempool while it's being read
+    let Ok(entry) = client.get_mempool_entry(txid) else {
+      continue;
+    };
+
+    let Ok(tx) = client.get_raw_transaction(txid, None) else {
+      continue;
+    };
+
+    let fee_rate = entry.fees.base.to_sat() as f64 / entry.vsize.max(1) as f64;
+
+    let bucket = histogram
+      .iter_mut()
+      .rev()
+      .find(|bucket| bucket.min_fee_rate <= fee_rate)
+      .unwrap();
+
+    bucket.transactions += 1;
+    bucket.vsize += entry.vsize;
+
+    vsize += entry.vsize;
+
+    if !Inscription::from_transaction(&tx).is_empty() {
+      inscriptions += 1;
+    }
+  }
+
+  let mut pending_reveals = 0;
+
+  for broadcast in pending::load(&options)? {
+    for reveal in &broadcast.reveals {
+      let reveal = consensus::deserialize::<Transaction>(&hex::decode(reveal)?)?;
+
+      if mempool.contains(&reveal.txid()) {
+        pending_reveals += 1;
+      }
+    }
+  }
+
+  histogram.retain(|bucket| bucket.transactions > 0);
+
+  print_json(Output {
+    transactions: mempool.len().try_into().unwrap(),
+    vsize,
+    inscriptions,
+    pending_reveals,
+    histogram,
+  })?;
+
+  Ok(())
+}
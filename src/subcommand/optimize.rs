@@ -0,0 +1,52 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Optimize {
+  #[clap(help = "Find the cheapest inscription envelope for <FILE>.")]
+  file: PathBuf,
+  #[clap(
+    long,
+    help = "Use <CONTENT_TYPE> instead of the content type for <FILE>'s extension."
+  )]
+  content_type: Option<String>,
+  #[clap(
+    long,
+    help = "Brotli compress the content, if that makes the envelope smaller."
+  )]
+  compress: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  /// `None` if the content type was stripped, since it's the default.
+  pub content_type: Option<String>,
+  pub content_encoding: Option<String>,
+  /// The envelope's weight before optimizing, in weight units.
+  pub weight: u64,
+  pub optimized_weight: u64,
+  pub savings: u64,
+}
+
+impl Optimize {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let inscription = Inscription::from_file_with_content_type(
+      options.chain(),
+      &self.file,
+      self.content_type.as_deref(),
+      options.max_content_size()?,
+    )?;
+
+    let optimized = inscription.optimize(self.compress)?;
+
+    let weight = u64::try_from(inscription.envelope_size()).unwrap();
+    let optimized_weight = u64::try_from(optimized.envelope_size()).unwrap();
+
+    print_json(Output {
+      content_type: optimized.content_type().map(str::to_string),
+      content_encoding: optimized.content_encoding().map(str::to_string),
+      weight,
+      optimized_weight,
+      savings: weight - optimized_weight,
+    })
+  }
+}
@@ -1,13 +1,24 @@
-use {super::*, fee_rate::FeeRate};
+use {
+  super::{
+    wallet::transaction_builder::{CoinSelection, DustPolicy},
+    *,
+  },
+  fee_rate::FeeRate,
+};
 
 #[derive(Debug, Parser)]
 pub(crate) struct Preview {
   #[clap(flatten)]
   server: super::server::Server,
+  #[clap(
+    long,
+    help = "Serve <INSCRIPTIONS> as they would render once inscribed, without starting bitcoind or inscribing them."
+  )]
+  no_bitcoind: bool,
   inscriptions: Vec<PathBuf>,
 }
 
-struct KillOnDrop(process::Child);
+pub(super) struct KillOnDrop(pub(super) process::Child);
 
 impl Drop for KillOnDrop {
   fn drop(&mut self) {
@@ -17,6 +28,28 @@ impl Drop for KillOnDrop {
 
 impl Preview {
   pub(crate) fn run(self) -> Result {
+    if self.no_bitcoind {
+      let inscriptions = self
+        .inscriptions
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+          Ok((
+            InscriptionId {
+              txid: Txid::all_zeros(),
+              index: index.try_into().unwrap(),
+            },
+            Inscription::from_file(Chain::Mainnet, path, None)?,
+          ))
+        })
+        .collect::<Result<Vec<(InscriptionId, Inscription)>>>()?;
+
+      let handle = axum_server::Handle::new();
+      LISTENERS.lock().unwrap().push(handle.clone());
+
+      return self.server.serve_inscriptions(inscriptions, handle);
+    }
+
     let tmpdir = TempDir::new()?;
 
     let rpc_port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
@@ -81,32 +114,75 @@ impl Preview {
         super::wallet::inscribe::Inscribe {
           fee_rate: FeeRate::try_from(1.0).unwrap(),
           commit_fee_rate: None,
+          reveal_fee_rate: None,
+          fee_ladder: None,
           files: self.inscriptions,
+          dir: None,
+          sort: super::wallet::inscribe::FileOrder::Name,
+          start_index: None,
+          end_index: None,
           no_backup: true,
           no_broadcast: false,
           wait_after_commit: false,
           satpoint: None,
+          sat: None,
+          include_sat_range: None,
           utxo: Vec::new(),
+          avoid_utxo: Vec::new(),
           coin_control: false,
+          funding_psbt: None,
           dry_run: false,
           dump: false,
           no_limit: false,
           destination: Vec::new(),
+          destination_script: None,
+          destination_descriptor: None,
           alignment: None,
           cursed_destination: None,
-          cursed_utxo: None,
+          cursed_utxo: Vec::new(),
           cursed: false,
           change: None,
           postage: Some(TransactionBuilder::DEFAULT_TARGET_POSTAGE),
           max_inputs: None,
           csv: None,
           cursed66: false,
+          tag: Vec::new(),
+          allow_even_tags: false,
+          optimize: false,
+          compress: false,
+          reveal_offset: None,
           no_signature: false,
           allow_reinscribe: false,
           ignore_utxo_inscriptions: false,
           single_key: false,
+          no_key_path_recovery: false,
           allow_reveal_rbf: false,
           unfunded_reveal: false,
+          op_return: None,
+          locktime: None,
+          sequence: None,
+          anchor: false,
+          anchor_value: None,
+          package_broadcast: false,
+          fallback_broadcast: None,
+          preflight: false,
+          derive_keys: false,
+          rng_seed: None,
+          no_address_reuse: false,
+          change_position: None,
+          change_split: None,
+          coin_selection: CoinSelection::Closest,
+          dust_policy: DustPolicy::Alignment,
+          avoid_unconfirmed: false,
+          min_confirmations: None,
+          simulate: false,
+          sat_flow: false,
+          protect_rarity: None,
+          allow_rare_utxo: Vec::new(),
+          yes: false,
+          broadcast_when_fee_below: None,
+          timeout: None,
+          allow_legacy_destination: false,
         },
       )),
     }
@@ -0,0 +1,62 @@
+use {
+  super::*,
+  bitcoin::hashes::{sha256, Hash},
+  std::io::Write,
+  wallet::shard::Manifest,
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Reassemble {
+  #[clap(help = "Reassemble the file described by manifest inscription <MANIFEST>.")]
+  manifest: InscriptionId,
+  #[clap(long, help = "Write the file to <OUTPUT> instead of stdout.")]
+  output: Option<PathBuf>,
+}
+
+impl Reassemble {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let manifest = index
+      .get_inscription_by_id(self.manifest)?
+      .ok_or_else(|| anyhow!("inscription {} not found", self.manifest))?;
+
+    let manifest = serde_json::from_slice::<Manifest>(manifest.body().unwrap_or_default())
+      .with_context(|| format!("inscription {} is not a shard manifest", self.manifest))?;
+
+    let mut content = Vec::new();
+
+    for (i, part) in manifest.parts.iter().enumerate() {
+      let inscription = index
+        .get_inscription_by_id(part.inscription)?
+        .ok_or_else(|| anyhow!("part {i}, inscription {}, not found", part.inscription))?;
+
+      let body = inscription.body().unwrap_or_default();
+
+      if sha256::Hash::hash(body).to_string() != part.sha256 {
+        bail!(
+          "part {i}, inscription {}, does not match the manifest's sha256",
+          part.inscription
+        );
+      }
+
+      content.extend_from_slice(body);
+    }
+
+    if u64::try_from(content.len()).unwrap() != manifest.size
+      || sha256::Hash::hash(&content).to_string() != manifest.sha256
+    {
+      bail!("reassembled file does not match the manifest's size and sha256");
+    }
+
+    match self.output {
+      Some(path) => {
+        fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?
+      }
+      None => io::stdout().write_all(&content)?,
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,130 @@
+use super::*;
+
+/// Characters of text content to show.
+const PREVIEW_CHARS: usize = 100;
+
+#[derive(Debug, Parser)]
+pub(crate) struct ScanMempool {
+  #[clap(
+    long,
+    help = "Only show inscriptions with content type <CONTENT_TYPE>, or, like `image/*`, any subtype of it."
+  )]
+  content_type: Option<String>,
+  #[clap(
+    long,
+    default_value = "0",
+    help = "Only show inscriptions with at least <MIN_SIZE> bytes of content."
+  )]
+  min_size: usize,
+  #[clap(long, help = "Scan the mempool once instead of watching it.")]
+  once: bool,
+  #[clap(
+    long,
+    default_value = "1",
+    help = "Check for new transactions every <POLL_INTERVAL> seconds."
+  )]
+  poll_interval: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope {
+  pub inscription: InscriptionId,
+  pub content_type: Option<String>,
+  pub content_length: Option<usize>,
+  /// The start of the content, if it is text.
+  pub preview: Option<String>,
+}
+
+impl ScanMempool {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let client = options.bitcoin_rpc_client()?;
+
+    let mut seen = HashSet::new();
+
+    loop {
+      for txid in client.get_raw_mempool()? {
+        if !seen.insert(txid) {
+          continue;
+        }
+
+        // transactions can leave the mempool while it's being read
+        let Ok(tx) = client.get_raw_transaction(&txid, None) else {
+          continue;
+        };
+
+        for (index, transaction_inscription) in
+          Inscription::from_transaction(&tx).into_iter().enumerate()
+        {
+          let inscription = transaction_inscription.inscription;
+
+          if !self.matches(&inscription) {
+            continue;
+          }
+
+          let preview = match inscription.media() {
+            Media::Text => inscription.body().map(|body| {
+              String::from_utf8_lossy(body)
+                .chars()
+                .take(PREVIEW_CHARS)
+                .collect()
+            }),
+            _ => None,
+          };
+
+          print_line(serde_json::to_string(&Envelope {
+            inscription: InscriptionId {
+              txid,
+              index: index.try_into().unwrap(),
+            },
+            content_type: inscription.content_type().map(str::to_string),
+            content_length: inscription.content_length(),
+            preview,
+          })?)?;
+        }
+      }
+
+      if self.once || SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        return Ok(());
+      }
+
+      thread::sleep(Duration::from_secs(self.poll_interval));
+    }
+  }
+
+  fn matches(&self, inscription: &Inscription) -> bool {
+    if inscription.content_length().unwrap_or_default() < self.min_size {
+      return false;
+    }
+
+    match &self.content_type {
+      Some(filter) => inscription.content_type_matches(filter),
+      None => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches() {
+    let scan = |content_type: Option<&str>, min_size| ScanMempool {
+      content_type: content_type.map(str::to_string),
+      min_size,
+      once: true,
+      poll_interval: 1,
+    };
+
+    let inscription = inscription("text/plain;charset=utf-8", "foo");
+
+    assert!(scan(None, 0).matches(&inscription));
+    assert!(scan(None, 3).matches(&inscription));
+    assert!(!scan(None, 4).matches(&inscription));
+    assert!(scan(Some("text/plain"), 0).matches(&inscription));
+    assert!(scan(Some("text/plain;charset=utf-8"), 0).matches(&inscription));
+    assert!(scan(Some("text/*"), 0).matches(&inscription));
+    assert!(!scan(Some("image/*"), 0).matches(&inscription));
+    assert!(!scan(Some("text/html"), 0).matches(&inscription));
+  }
+}
@@ -1,6 +1,7 @@
 use {
   self::{
     accept_json::AcceptJson,
+    content_cache::ContentCache,
     deserialize_from_str::DeserializeFromStr,
     error::{OptionExt, ServerError, ServerResult},
   },
@@ -23,6 +24,7 @@ use {
     Router, TypedHeader,
   },
   axum_server::Handle,
+  bytes::Bytes,
   rust_embed::RustEmbed,
   rustls_acme::{
     acme::{LETS_ENCRYPT_PRODUCTION_DIRECTORY, LETS_ENCRYPT_STAGING_DIRECTORY},
@@ -41,6 +43,7 @@ use {
 };
 
 mod accept_json;
+mod content_cache;
 mod error;
 
 #[derive(Clone)]
@@ -52,7 +55,8 @@ struct BlockIndexState {
   block_index: RwLock<BlockIndex>,
 }
 
-enum BlockQuery {
+#[derive(Debug, Clone)]
+pub(crate) enum BlockQuery {
   Height(u64),
   Hash(BlockHash),
 }
@@ -92,6 +96,7 @@ struct MyInscriptionJson {
   timestamp: u32,
   genesis_height: u64,
   genesis_fee: u64,
+  genesis_vsize: Option<u64>,
   genesis_transaction: Txid,
   location: String,
   output: String,
@@ -176,6 +181,12 @@ pub(crate) struct Server {
   https: bool,
   #[clap(long, help = "Redirect HTTP traffic to HTTPS.")]
   redirect_http_to_https: bool,
+  #[clap(
+    long,
+    default_value = "67108864",
+    help = "Keep up to <CONTENT_CACHE_SIZE> bytes of recently served inscription content in memory. 0 disables the cache."
+  )]
+  content_cache_size: usize,
 }
 
 impl Server {
@@ -267,6 +278,9 @@ impl Server {
         .layer(Extension(page_config))
         .layer(Extension(Arc::new(config)))
         .layer(Extension(block_index_state))
+        .layer(Extension(Arc::new(ContentCache::new(
+          self.content_cache_size,
+        ))))
         .layer(SetResponseHeaderLayer::if_not_present(
           header::CONTENT_SECURITY_POLICY,
           HeaderValue::from_static("default-src 'self'"),
@@ -380,6 +394,44 @@ impl Server {
     }))
   }
 
+  /// Serve `inscriptions` without an index, rendered exactly as they would be
+  /// by the explorer, so that files can be checked before they're inscribed.
+  pub(super) fn serve_inscriptions(
+    self,
+    inscriptions: Vec<(InscriptionId, Inscription)>,
+    handle: Handle,
+  ) -> Result {
+    Runtime::new()?.block_on(async {
+      let page_config = Arc::new(PageConfig {
+        chain: Chain::Regtest,
+        domain: None,
+      });
+
+      let router = Router::new()
+        .route("/", get(Self::local_inscriptions))
+        .route("/content/:inscription_id", get(Self::local_content))
+        .route("/inscription/:inscription_id", get(Self::local_inscription))
+        .route("/preview/:inscription_id", get(Self::local_preview))
+        .route("/static/*path", get(Self::static_asset))
+        .layer(Extension(Arc::new(inscriptions)))
+        .layer(Extension(page_config))
+        .layer(SetResponseHeaderLayer::if_not_present(
+          header::CONTENT_SECURITY_POLICY,
+          HeaderValue::from_static("default-src 'self'"),
+        ));
+
+      let port = self
+        .http_port()
+        .ok_or_else(|| anyhow!("serving files without bitcoind requires HTTP"))?;
+
+      self
+        .spawn(router, handle, port, SpawnConfig::Http)?
+        .await??;
+
+      Ok(())
+    })
+  }
+
   fn acme_cache(acme_cache: Option<&PathBuf>, options: &Options) -> Result<PathBuf> {
     let acme_cache = if let Some(acme_cache) = acme_cache {
       acme_cache.clone()
@@ -966,22 +1018,134 @@ impl Server {
   async fn content(
     Extension(index): Extension<Arc<Index>>,
     Extension(config): Extension<Arc<Config>>,
+    Extension(content_cache): Extension<Arc<ContentCache>>,
     Path(inscription_id): Path<InscriptionId>,
+    request_headers: HeaderMap,
   ) -> ServerResult<Response> {
     log::info!("GET /content/{inscription_id}");
     if config.is_hidden(inscription_id) {
       return Ok(PreviewUnknownHtml.into_response());
     }
 
-    let inscription = index
-      .get_inscription_by_id(inscription_id)?
-      .ok_or_not_found(|| format!("inscription {inscription_id}"))?;
+    // content never changes, so the inscription id is a strong etag
+    let etag = HeaderValue::from_str(&format!("\"{inscription_id}\"")).unwrap();
+
+    let content = content_cache.get(inscription_id);
+
+    if let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH) {
+      if if_none_match
+        .to_str()
+        .map(|tags| {
+          tags
+            .split(',')
+            .any(|tag| tag.trim() == "*" || tag.trim().trim_start_matches("W/") == etag)
+        })
+        .unwrap_or_default()
+        && (content.is_some()
+          || index
+            .get_inscription_satpoint_by_id(inscription_id)?
+            .is_some())
+      {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+      }
+    }
 
-    Ok(
-      Self::content_response(inscription)
-        .ok_or_not_found(|| format!("inscription {inscription_id} content"))?
+    let content = match content {
+      Some(content) => content,
+      None => {
+        let inscription = index
+          .get_inscription_by_id(inscription_id)?
+          .ok_or_not_found(|| format!("inscription {inscription_id}"))?;
+
+        let (headers, body) = Self::content_response(inscription)
+          .ok_or_not_found(|| format!("inscription {inscription_id} content"))?;
+
+        let content = Arc::new((headers, Bytes::from(body)));
+
+        content_cache.insert(inscription_id, content.clone());
+
+        content
+      }
+    };
+
+    let (headers, body) = content.as_ref();
+
+    let mut headers = headers.clone();
+    headers.insert(header::ETAG, etag);
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let range = request_headers
+      .get(header::RANGE)
+      .and_then(|range| range.to_str().ok())
+      .map(|range| Self::byte_range(range, body.len()));
+
+    Ok(match range {
+      None | Some(Ok(None)) => (headers, body.clone()).into_response(),
+      Some(Ok(Some(range))) => {
+        headers.insert(
+          header::CONTENT_RANGE,
+          HeaderValue::from_str(&format!(
+            "bytes {}-{}/{}",
+            range.start,
+            range.end - 1,
+            body.len()
+          ))
+          .unwrap(),
+        );
+        // keep the compression layer from encoding a part of the body
+        headers.insert(
+          header::CONTENT_ENCODING,
+          HeaderValue::from_static("identity"),
+        );
+        (StatusCode::PARTIAL_CONTENT, headers, body.slice(range)).into_response()
+      }
+      Some(Err(())) => (
+        StatusCode::RANGE_NOT_SATISFIABLE,
+        [(
+          header::CONTENT_RANGE,
+          HeaderValue::from_str(&format!("bytes */{}", body.len())).unwrap(),
+        )],
+      )
         .into_response(),
-    )
+    })
+  }
+
+  /// The part of a `len` byte body requested by the `Range` header `range`,
+  /// for seeking in audio and video. `None` if the header should be ignored
+  /// and the whole body served, as it is for malformed headers and requests
+  /// for more than one range, and an error if the range is unsatisfiable.
+  fn byte_range(range: &str, len: usize) -> Result<Option<std::ops::Range<usize>>, ()> {
+    let Some((start, end)) = range
+      .trim()
+      .strip_prefix("bytes=")
+      .filter(|ranges| !ranges.contains(','))
+      .and_then(|range| range.split_once('-'))
+    else {
+      return Ok(None);
+    };
+
+    let range = match (start.trim(), end.trim()) {
+      ("", "") => return Ok(None),
+      ("", suffix) => match suffix.parse::<usize>() {
+        Ok(0) => return Err(()),
+        Ok(suffix) => len.saturating_sub(suffix)..len,
+        Err(_) => return Ok(None),
+      },
+      (start, "") => match start.parse::<usize>() {
+        Ok(start) => start..len,
+        Err(_) => return Ok(None),
+      },
+      (start, end) => match (start.parse::<usize>(), end.parse::<usize>()) {
+        (Ok(start), Ok(end)) if start <= end => start..(end + 1).min(len),
+        _ => return Ok(None),
+      },
+    };
+
+    if range.start >= len {
+      return Err(());
+    }
+
+    Ok(Some(range))
   }
 
   fn content_response(inscription: Inscription) -> Option<(HeaderMap, Vec<u8>)> {
@@ -992,7 +1156,7 @@ impl Server {
       inscription
         .content_type()
         .and_then(|content_type| content_type.parse().ok())
-        .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+        .unwrap_or(HeaderValue::from_static(DEFAULT_CONTENT_TYPE)),
     );
     headers.insert(
       header::CONTENT_SECURITY_POLICY,
@@ -1003,7 +1167,9 @@ impl Server {
       HeaderValue::from_static("default-src *:*/content/ *:*/blockheight *:*/blockhash *:*/blockhash/ *:*/blocktime 'unsafe-eval' 'unsafe-inline' data: blob:"),
     );
 
-    let body = inscription.into_body();
+    // brotli encoded bodies are served decoded, and compressed again, for
+    // clients that accept it, by the compression layer
+    let body = inscription.into_decoded_body();
     let cache_control = match body {
       Some(_) => "max-age=31536000, immutable",
       None => "max-age=600",
@@ -1030,6 +1196,13 @@ impl Server {
       .get_inscription_by_id(inscription_id)?
       .ok_or_not_found(|| format!("inscription {inscription_id}"))?;
 
+    Self::preview_response(inscription_id, inscription)
+  }
+
+  fn preview_response(
+    inscription_id: InscriptionId,
+    inscription: Inscription,
+  ) -> ServerResult<Response> {
     match inscription.media() {
       Media::Audio => Ok(PreviewAudioHtml { inscription_id }.into_response()),
       Media::Iframe => Ok(
@@ -1074,6 +1247,56 @@ impl Server {
     }
   }
 
+  async fn local_inscriptions(
+    Extension(page_config): Extension<Arc<PageConfig>>,
+    Extension(inscriptions): Extension<Arc<Vec<(InscriptionId, Inscription)>>>,
+  ) -> PageHtml<InscriptionsHtml> {
+    log::info!("GET /");
+    InscriptionsHtml {
+      inscriptions: inscriptions.iter().map(|(id, _)| *id).collect(),
+      prev: None,
+      next: None,
+    }
+    .page(page_config, false)
+  }
+
+  async fn local_content(
+    Extension(inscriptions): Extension<Arc<Vec<(InscriptionId, Inscription)>>>,
+    Path(inscription_id): Path<InscriptionId>,
+  ) -> ServerResult<Response> {
+    log::info!("GET /content/{inscription_id}");
+    let (_, inscription) = inscriptions
+      .iter()
+      .find(|(id, _)| *id == inscription_id)
+      .ok_or_not_found(|| format!("inscription {inscription_id}"))?;
+
+    Ok(
+      Self::content_response(inscription.clone())
+        .ok_or_not_found(|| format!("inscription {inscription_id} content"))?
+        .into_response(),
+    )
+  }
+
+  /// Thumbnails link to inscription pages, which need an index, so show the
+  /// preview instead.
+  async fn local_inscription(Path(inscription_id): Path<InscriptionId>) -> Redirect {
+    log::info!("GET /inscription/{inscription_id}");
+    Redirect::to(&format!("/preview/{inscription_id}"))
+  }
+
+  async fn local_preview(
+    Extension(inscriptions): Extension<Arc<Vec<(InscriptionId, Inscription)>>>,
+    Path(inscription_id): Path<InscriptionId>,
+  ) -> ServerResult<Response> {
+    log::info!("GET /preview/{inscription_id}");
+    let (_, inscription) = inscriptions
+      .iter()
+      .find(|(id, _)| *id == inscription_id)
+      .ok_or_not_found(|| format!("inscription {inscription_id}"))?;
+
+    Self::preview_response(inscription_id, inscription.clone())
+  }
+
   async fn inscription(
     Extension(page_config): Extension<Arc<PageConfig>>,
     Extension(index): Extension<Arc<Index>>,
@@ -1116,6 +1339,7 @@ impl Server {
         page_config.chain,
         entry.fee,
         entry.height,
+        index.get_inscription_genesis_vsize(inscription_id)?,
         inscription,
         inscription_id,
         next,
@@ -1356,6 +1580,7 @@ impl Server {
                   timestamp: entry.timestamp,
                   genesis_height: entry.height,
                   genesis_fee: entry.fee,
+                  genesis_vsize: index.get_inscription_genesis_vsize(inscription_id)?,
                   genesis_transaction: inscription_id.txid,
                   location: satpoint.to_string() + unbound_suffix,
                   output: satpoint.outpoint.to_string() + unbound_suffix,
@@ -2590,6 +2815,20 @@ mod tests {
     assert!(body.is_empty());
   }
 
+  #[test]
+  fn content_response_decodes_brotli_content() {
+    let inscription = inscription("text/plain", "foo".repeat(1000))
+      .optimize(true)
+      .unwrap();
+
+    assert_eq!(inscription.content_encoding(), Some("br"));
+
+    let (headers, body) = Server::content_response(inscription).unwrap();
+
+    assert_eq!(headers["content-type"], "text/plain");
+    assert_eq!(body, "foo".repeat(1000).into_bytes());
+  }
+
   #[test]
   fn content_response_bad_content_type() {
     let (headers, body) = Server::content_response(Inscription::new(
@@ -2953,6 +3192,89 @@ mod tests {
     );
   }
 
+  #[test]
+  fn content_responses_have_etags_and_honor_if_none_match() {
+    let server = TestServer::new_with_regtest();
+    server.mine_blocks(1);
+
+    let txid = server.bitcoin_rpc_server.broadcast_tx(TransactionTemplate {
+      inputs: &[(1, 0, 0)],
+      witness: inscription("text/plain", "hello").to_witness(),
+      ..Default::default()
+    });
+
+    server.mine_blocks(1);
+
+    let inscription_id = InscriptionId::from(txid);
+
+    let response = server.get(format!("/content/{inscription_id}"));
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+      response.headers().get(header::ETAG).unwrap(),
+      &format!("\"{inscription_id}\"")
+    );
+
+    let response = reqwest::blocking::Client::new()
+      .get(server.join_url(&format!("/content/{inscription_id}")))
+      .header(header::IF_NONE_MATCH, format!("\"{inscription_id}\""))
+      .send()
+      .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(response.text().unwrap().is_empty());
+  }
+
+  #[test]
+  fn content_responses_serve_ranges() {
+    let server = TestServer::new_with_regtest();
+    server.mine_blocks(1);
+
+    let txid = server.bitcoin_rpc_server.broadcast_tx(TransactionTemplate {
+      inputs: &[(1, 0, 0)],
+      witness: inscription("audio/flac", "0123456789").to_witness(),
+      ..Default::default()
+    });
+
+    server.mine_blocks(1);
+
+    let url = server.join_url(&format!("/content/{}", InscriptionId::from(txid)));
+
+    let response = reqwest::blocking::Client::new()
+      .get(url.clone())
+      .header(header::RANGE, "bytes=2-5")
+      .send()
+      .unwrap();
+
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(
+      response.headers().get(header::CONTENT_RANGE).unwrap(),
+      "bytes 2-5/10"
+    );
+    assert_eq!(response.text().unwrap(), "2345");
+
+    let response = reqwest::blocking::Client::new()
+      .get(url)
+      .header(header::RANGE, "bytes=10-")
+      .send()
+      .unwrap();
+
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+  }
+
+  #[test]
+  fn byte_ranges_are_parsed() {
+    assert_eq!(Server::byte_range("bytes=0-3", 10), Ok(Some(0..4)));
+    assert_eq!(Server::byte_range("bytes=5-", 10), Ok(Some(5..10)));
+    assert_eq!(Server::byte_range("bytes=-3", 10), Ok(Some(7..10)));
+    assert_eq!(Server::byte_range("bytes=8-100", 10), Ok(Some(8..10)));
+    assert_eq!(Server::byte_range("bytes=0-1,4-5", 10), Ok(None));
+    assert_eq!(Server::byte_range("bytes=5-3", 10), Ok(None));
+    assert_eq!(Server::byte_range("lines=0-3", 10), Ok(None));
+    assert_eq!(Server::byte_range("bytes=10-", 10), Err(()));
+    assert_eq!(Server::byte_range("bytes=-0", 10), Err(()));
+  }
+
   #[test]
   fn inscriptions_page_with_no_prev_or_next() {
     TestServer::new_with_regtest_with_index_sats().assert_response_regex(
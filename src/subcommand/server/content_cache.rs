@@ -0,0 +1,117 @@
+use {super::*, lru::LruCache};
+
+/// Least recently used inscription content, so that popular inscriptions,
+/// which are immutable, aren't fetched from Bitcoin Core and parsed again on
+/// every request. Bodies are `Bytes`, so that responses share them instead of
+/// copying them.
+pub(super) struct ContentCache {
+  capacity: usize,
+  inner: Mutex<Inner>,
+}
+
+struct Inner {
+  entries: LruCache<InscriptionId, Arc<(HeaderMap, Bytes)>>,
+  size: usize,
+}
+
+impl ContentCache {
+  /// A cache holding up to `capacity` bytes of content.
+  pub(super) fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      inner: Mutex::new(Inner {
+        entries: LruCache::unbounded(),
+        size: 0,
+      }),
+    }
+  }
+
+  pub(super) fn get(&self, inscription_id: InscriptionId) -> Option<Arc<(HeaderMap, Bytes)>> {
+    self
+      .inner
+      .lock()
+      .unwrap()
+      .entries
+      .get(&inscription_id)
+      .cloned()
+  }
+
+  pub(super) fn insert(&self, inscription_id: InscriptionId, content: Arc<(HeaderMap, Bytes)>) {
+    let size = content.1.len();
+
+    if size > self.capacity {
+      return;
+    }
+
+    let mut inner = self.inner.lock().unwrap();
+
+    if let Some(replaced) = inner.entries.put(inscription_id, content) {
+      inner.size -= replaced.1.len();
+    }
+
+    inner.size += size;
+
+    while inner.size > self.capacity {
+      let Some((_, evicted)) = inner.entries.pop_lru() else {
+        break;
+      };
+
+      inner.size -= evicted.1.len();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn content(size: usize) -> Arc<(HeaderMap, Bytes)> {
+    Arc::new((HeaderMap::new(), vec![0; size].into()))
+  }
+
+  #[test]
+  fn least_recently_used_content_is_evicted() {
+    let cache = ContentCache::new(10);
+
+    let a = inscription_id(1);
+    let b = inscription_id(2);
+    let c = inscription_id(3);
+
+    cache.insert(a, content(4));
+    cache.insert(b, content(4));
+
+    assert!(cache.get(a).is_some());
+
+    cache.insert(c, content(4));
+
+    assert!(cache.get(a).is_some());
+    assert!(cache.get(b).is_none());
+    assert!(cache.get(c).is_some());
+  }
+
+  #[test]
+  fn replaced_content_is_not_counted_twice() {
+    let cache = ContentCache::new(10);
+
+    let a = inscription_id(1);
+    let b = inscription_id(2);
+
+    cache.insert(a, content(4));
+    cache.insert(a, content(4));
+    cache.insert(b, content(6));
+
+    assert!(cache.get(a).is_some());
+    assert!(cache.get(b).is_some());
+  }
+
+  #[test]
+  fn content_larger_than_cache_is_not_cached() {
+    let cache = ContentCache::new(10);
+
+    let a = inscription_id(1);
+
+    cache.insert(a, content(11));
+
+    assert!(cache.get(a).is_none());
+  }
+}
@@ -0,0 +1,199 @@
+use {super::*, std::collections::HashMap};
+
+/// Resolves the owners of a list of inscriptions, for allow-lists and
+/// airdrops. Owners at a past height are found by walking each inscription
+/// back from its current location through the blocks that the transfer log
+/// says it moved in.
+#[derive(Debug, Parser)]
+pub(crate) struct Snapshot {
+  #[clap(
+    long,
+    help = "Resolve the owners of the inscriptions in <INSCRIPTION_IDS>, one per line."
+  )]
+  inscription_ids: PathBuf,
+  #[clap(
+    long,
+    help = "Resolve owners as of the block at <AT_HEIGHT>, instead of the chain tip."
+  )]
+  at_height: Option<u64>,
+  #[clap(long, help = "Write `inscription,address` rows to <OUTPUT>.")]
+  output: PathBuf,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  pub height: u64,
+  pub inscriptions: u64,
+  /// Number of distinct owner addresses.
+  pub owners: u64,
+  /// Inscriptions that were not yet inscribed at `height`, and so have no
+  /// owner in the snapshot.
+  pub uninscribed: Vec<InscriptionId>,
+}
+
+impl Snapshot {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let tip = index
+      .block_height()?
+      .ok_or_else(|| anyhow!("index has no blocks"))?
+      .n();
+
+    let height = self.at_height.unwrap_or(tip);
+
+    if height > tip {
+      bail!("--at-height {height} is above the index height {tip}");
+    }
+
+    let inscription_ids = fs::read_to_string(&self.inscription_ids)
+      .with_context(|| format!("io error reading {}", self.inscription_ids.display()))?
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty() && !line.starts_with('#'))
+      .map(|line| {
+        line
+          .parse::<InscriptionId>()
+          .with_context(|| format!("invalid inscription id `{line}`"))
+      })
+      .collect::<Result<Vec<InscriptionId>>>()?;
+
+    let transfer_heights = if height < tip {
+      if let (_, Some(first), _) = index.show_transfer_log_stats()? {
+        if first > height + 1 {
+          eprintln!(
+            "warning: transfer log starts at height {first}, transfers after {height} and before it may have been trimmed"
+          );
+        }
+      }
+
+      index.get_transfer_heights(&inscription_ids.iter().copied().collect())?
+    } else {
+      HashMap::new()
+    };
+
+    let mut blocks = HashMap::<u64, HashMap<Txid, Transaction>>::new();
+
+    let mut csv = String::from("inscription,address\n");
+    let mut owners = HashSet::new();
+    let mut uninscribed = Vec::new();
+
+    for inscription_id in &inscription_ids {
+      let entry = index
+        .get_inscription_entry(*inscription_id)?
+        .ok_or_else(|| anyhow!("inscription {inscription_id} not found"))?;
+
+      if entry.height > height {
+        uninscribed.push(*inscription_id);
+        csv.push_str(&format!("{inscription_id},\n"));
+        continue;
+      }
+
+      let mut satpoint = index
+        .get_inscription_satpoint_by_id(*inscription_id)?
+        .ok_or_else(|| anyhow!("inscription {inscription_id} has no location"))?;
+
+      for &transfer_height in transfer_heights
+        .get(inscription_id)
+        .into_iter()
+        .flatten()
+        .rev()
+        .filter(|transfer_height| **transfer_height > height)
+      {
+        if !blocks.contains_key(&transfer_height) {
+          let block = index
+            .get_block_by_height(transfer_height)?
+            .ok_or_else(|| anyhow!("block {transfer_height} not found"))?;
+
+          blocks.insert(
+            transfer_height,
+            block.txdata.into_iter().map(|tx| (tx.txid(), tx)).collect(),
+          );
+        }
+
+        let txs = &blocks[&transfer_height];
+
+        if !txs.contains_key(&satpoint.outpoint.txid) {
+          bail!(
+            "inscription {inscription_id} is at {satpoint}, which was not created in block {transfer_height}, where the transfer log says it moved"
+          );
+        }
+
+        // an inscription can move more than once in a block
+        while let Some(tx) = txs.get(&satpoint.outpoint.txid) {
+          satpoint = Self::previous_satpoint(&index, tx, satpoint)?.ok_or_else(|| {
+            anyhow!(
+              "cannot trace inscription {inscription_id} back through coinbase transaction {} in block {transfer_height}",
+              satpoint.outpoint.txid
+            )
+          })?;
+        }
+      }
+
+      let address = if satpoint.outpoint == OutPoint::null() {
+        None
+      } else {
+        index
+          .get_transaction(satpoint.outpoint.txid)?
+          .and_then(|tx| tx.output.into_iter().nth(satpoint.outpoint.vout as usize))
+          .and_then(|output| {
+            Address::from_script(&output.script_pubkey, options.chain().network()).ok()
+          })
+          .map(|address| address.to_string())
+      };
+
+      if let Some(address) = &address {
+        owners.insert(address.clone());
+      }
+
+      csv.push_str(&format!(
+        "{inscription_id},{}\n",
+        address.unwrap_or_default()
+      ));
+    }
+
+    fs::write(&self.output, csv)
+      .with_context(|| format!("failed to write {}", self.output.display()))?;
+
+    print_json(Output {
+      height,
+      inscriptions: inscription_ids.len().try_into().unwrap(),
+      owners: owners.len().try_into().unwrap(),
+      uninscribed,
+    })
+  }
+
+  /// Where the sat at `satpoint` was before `tx` spent it, or `None` if it
+  /// came from the fees collected by a coinbase transaction.
+  fn previous_satpoint(
+    index: &Index,
+    tx: &Transaction,
+    satpoint: SatPoint,
+  ) -> Result<Option<SatPoint>> {
+    let mut offset = tx.output[..satpoint.outpoint.vout as usize]
+      .iter()
+      .map(|output| output.value)
+      .sum::<u64>()
+      + satpoint.offset;
+
+    for input in &tx.input {
+      if input.previous_output.is_null() {
+        return Ok(None);
+      }
+
+      let value = index.get_outpoint_value(&input.previous_output)?;
+
+      if offset < value {
+        return Ok(Some(SatPoint {
+          outpoint: input.previous_output,
+          offset,
+        }));
+      }
+
+      offset -= value;
+    }
+
+    Ok(None)
+  }
+}
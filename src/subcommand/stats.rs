@@ -0,0 +1,105 @@
+use {super::*, clap::ValueEnum};
+
+#[derive(Default, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Period {
+  #[default]
+  Day,
+  Block,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Stats {
+  #[clap(
+    long,
+    value_enum,
+    default_value = "day",
+    help = "Aggregate inscriptions by <BY>, the UTC day or block they were revealed in."
+  )]
+  by: Period,
+  #[clap(
+    long,
+    help = "Only count inscriptions with content type <CONTENT_TYPE>, or, like `image/*`, any subtype of it."
+  )]
+  content_type: Option<String>,
+  #[clap(long, help = "Print CSV instead of JSON.")]
+  csv: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Row {
+  /// The day, as `YYYY-MM-DD`, or the block height.
+  pub period: String,
+  pub inscriptions: u64,
+  /// Bytes of content inscribed.
+  pub bytes: u64,
+  /// Fees paid by reveal transactions, divided among their inscriptions.
+  pub fees: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub rows: Vec<Row>,
+}
+
+impl Stats {
+  /// Inscription content isn't in the index, so each inscription's reveal
+  /// transaction is fetched from Bitcoin Core.
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let mut periods = BTreeMap::<u64, Row>::new();
+
+    index.for_each_inscription_entry(|id, entry| {
+      let inscription = index
+        .get_inscription_by_id(id)?
+        .ok_or_else(|| anyhow!("inscription {id} not found"))?;
+
+      if let Some(filter) = &self.content_type {
+        if !inscription.content_type_matches(filter) {
+          return Ok(());
+        }
+      }
+
+      let (key, period) = match self.by {
+        Period::Day => (
+          u64::from(entry.timestamp) / (24 * 60 * 60),
+          timestamp(entry.timestamp).format("%Y-%m-%d").to_string(),
+        ),
+        Period::Block => (entry.height, entry.height.to_string()),
+      };
+
+      let row = periods.entry(key).or_insert_with(|| Row {
+        period,
+        inscriptions: 0,
+        bytes: 0,
+        fees: 0,
+      });
+
+      row.inscriptions += 1;
+      row.bytes += u64::try_from(inscription.content_length().unwrap_or_default()).unwrap();
+      row.fees += entry.fee;
+
+      Ok(())
+    })?;
+
+    let rows = periods.into_values().collect::<Vec<Row>>();
+
+    if self.csv {
+      print_line("period,inscriptions,bytes,fees")?;
+
+      for row in rows {
+        print_line(format!(
+          "{},{},{},{}",
+          row.period, row.inscriptions, row.bytes, row.fees
+        ))?;
+      }
+
+      return Ok(());
+    }
+
+    print_json(Output { rows })?;
+
+    Ok(())
+  }
+}
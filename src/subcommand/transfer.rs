@@ -10,7 +10,7 @@ pub(crate) struct Transfer {
 
 impl Transfer {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
     index.update()?;
 
     if self.delete && self.trim.is_some() {
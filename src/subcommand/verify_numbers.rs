@@ -0,0 +1,322 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct VerifyNumbers {
+  #[clap(
+    long,
+    help = "Compare against the ordinals API at <SOURCE>, like `https://ordinals.com`."
+  )]
+  source: String,
+  #[clap(
+    long,
+    default_value = "100",
+    help = "Check <SAMPLE> heights, evenly spaced between the first inscription height and the index height."
+  )]
+  sample: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+  pub height: u64,
+  pub problem: String,
+}
+
+/// `heights` are the heights checked, first the samples up to the first that
+/// diverges, and then the heights checked to find the first diverging height,
+/// which is the height of `first_divergence`.
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub source: String,
+  pub heights: Vec<u64>,
+  pub inscriptions: u64,
+  pub first_divergence: Option<Divergence>,
+}
+
+/// The inscriptions in a block, as served by `/inscriptions/block/<HEIGHT>`.
+/// This fork returns every inscription at once, while upstream ord returns
+/// pages of `ids`.
+#[derive(Deserialize)]
+struct BlockJson {
+  #[serde(alias = "ids")]
+  inscriptions: Vec<InscriptionId>,
+  #[serde(default)]
+  more: bool,
+}
+
+#[derive(Deserialize)]
+struct InscriptionJson {
+  number: i64,
+}
+
+impl VerifyNumbers {
+  pub(crate) fn run(self, options: Options) -> Result {
+    if self.sample == 0 {
+      bail!("--sample must be at least 1");
+    }
+
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let first = options.first_inscription_height();
+
+    let last = match index.block_height()? {
+      Some(height) if height.n() >= first => height.n(),
+      _ => bail!("index has no blocks with inscriptions to check"),
+    };
+
+    let heights = Self::sample_heights(first, last, self.sample);
+
+    let local = index.get_inscriptions_at_heights(&heights)?;
+
+    let mut client = reqwest::blocking::Client::builder();
+
+    if let Some(proxy) = options.proxy()? {
+      client = client.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    let client = client.build()?;
+
+    let source = self.source.trim_end_matches('/');
+
+    let mut checked = Vec::new();
+    let mut inscriptions = 0;
+    let mut first_divergence = None;
+
+    let mut check = |height: u64, local: Vec<(i64, InscriptionId)>| -> Result<Option<String>> {
+      checked.push(height);
+      inscriptions += u64::try_from(local.len()).unwrap();
+      let remote = Self::remote_inscriptions(&client, source, height)?;
+      Ok(Self::compare(&local, &remote))
+    };
+
+    // heights before the first inscription trivially match
+    let mut matching = first.checked_sub(1);
+
+    for (height, local) in local {
+      if let Some(problem) = check(height, local)? {
+        first_divergence = Some(Divergence { height, problem });
+        break;
+      }
+
+      matching = Some(height);
+    }
+
+    if let Some(divergence) = first_divergence {
+      first_divergence = Some(Self::bisect(
+        matching.map(|height| height + 1).unwrap_or_default(),
+        divergence,
+        |height| {
+          let local = index
+            .get_inscriptions_at_heights(&[height].into())?
+            .remove(&height)
+            .unwrap_or_default();
+
+          check(height, local)
+        },
+      )?);
+    }
+
+    let divergence = first_divergence
+      .as_ref()
+      .map(|divergence| divergence.height);
+
+    print_json(Output {
+      source: source.into(),
+      heights: checked,
+      inscriptions,
+      first_divergence,
+    })?;
+
+    if let Some(height) = divergence {
+      bail!("inscription numbers diverge from {source} at height {height}");
+    }
+
+    Ok(())
+  }
+
+  /// A divergence shifts the numbers of all later inscriptions, so the first
+  /// diverging height is found by bisecting between `unchecked`, the first
+  /// height after the last matching sample, and the first diverging sample.
+  fn bisect(
+    mut unchecked: u64,
+    mut divergence: Divergence,
+    mut check: impl FnMut(u64) -> Result<Option<String>>,
+  ) -> Result<Divergence> {
+    while unchecked < divergence.height {
+      let height = unchecked + (divergence.height - unchecked) / 2;
+
+      match check(height)? {
+        Some(problem) => divergence = Divergence { height, problem },
+        None => unchecked = height + 1,
+      }
+    }
+
+    Ok(divergence)
+  }
+
+  fn sample_heights(first: u64, last: u64, sample: u64) -> BTreeSet<u64> {
+    let span = last - first;
+
+    if sample == 1 {
+      return [last].into();
+    }
+
+    (0..sample.min(span + 1))
+      .map(|i| first + i * span / (sample.min(span + 1) - 1).max(1))
+      .collect()
+  }
+
+  fn remote_inscriptions(
+    client: &reqwest::blocking::Client,
+    source: &str,
+    height: u64,
+  ) -> Result<Vec<(i64, InscriptionId)>> {
+    let mut ids = Vec::new();
+
+    for page in 0.. {
+      let url = if page == 0 {
+        format!("{source}/inscriptions/block/{height}")
+      } else {
+        format!("{source}/inscriptions/block/{height}/{page}")
+      };
+
+      let block = Self::get::<BlockJson>(client, &url)?;
+
+      ids.extend(block.inscriptions);
+
+      if !block.more {
+        break;
+      }
+    }
+
+    let mut inscriptions = ids
+      .into_iter()
+      .map(|id| {
+        Self::get::<InscriptionJson>(client, &format!("{source}/inscription/{id}"))
+          .map(|inscription| (inscription.number, id))
+      })
+      .collect::<Result<Vec<(i64, InscriptionId)>>>()?;
+
+    inscriptions.sort_by_key(|(number, _id)| *number);
+
+    Ok(inscriptions)
+  }
+
+  fn get<T: for<'a> Deserialize<'a>>(client: &reqwest::blocking::Client, url: &str) -> Result<T> {
+    let response = client
+      .get(url)
+      .header(reqwest::header::ACCEPT, "application/json")
+      .send()
+      .with_context(|| format!("failed to fetch {url}"))?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+      bail!("{url} replied with {status}");
+    }
+
+    response
+      .json()
+      .with_context(|| format!("{url} replied with unexpected JSON"))
+  }
+
+  /// Describe the first difference between the inscriptions indexed at a
+  /// height and those reported by the source, both in number order.
+  fn compare(local: &[(i64, InscriptionId)], remote: &[(i64, InscriptionId)]) -> Option<String> {
+    for (local, remote) in local.iter().zip(remote) {
+      if local.1 != remote.1 {
+        return Some(format!(
+          "inscription {} is {} here, but the source has {} as {}",
+          local.1, local.0, remote.1, remote.0
+        ));
+      }
+
+      if local.0 != remote.0 {
+        return Some(format!(
+          "inscription {} is {} here, but {} at the source",
+          local.1, local.0, remote.0
+        ));
+      }
+    }
+
+    match local.len().cmp(&remote.len()) {
+      cmp::Ordering::Equal => None,
+      cmp::Ordering::Less => Some(format!(
+        "source has {} inscriptions, but only {} are indexed",
+        remote.len(),
+        local.len()
+      )),
+      cmp::Ordering::Greater => Some(format!(
+        "{} inscriptions are indexed, but the source has only {}",
+        local.len(),
+        remote.len()
+      )),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sample_heights() {
+    assert_eq!(
+      VerifyNumbers::sample_heights(0, 100, 5),
+      [0, 25, 50, 75, 100].into()
+    );
+    assert_eq!(
+      VerifyNumbers::sample_heights(10, 12, 100),
+      [10, 11, 12].into()
+    );
+    assert_eq!(VerifyNumbers::sample_heights(10, 10, 3), [10].into());
+    assert_eq!(VerifyNumbers::sample_heights(0, 100, 1), [100].into());
+  }
+
+  #[test]
+  fn bisect_finds_first_diverging_height() {
+    let mut checked = Vec::new();
+
+    let divergence = VerifyNumbers::bisect(
+      11,
+      Divergence {
+        height: 20,
+        problem: "sampled".into(),
+      },
+      |height| {
+        checked.push(height);
+        Ok((height >= 13).then(|| format!("at {height}")))
+      },
+    )
+    .unwrap();
+
+    assert_eq!(
+      divergence,
+      Divergence {
+        height: 13,
+        problem: "at 13".into(),
+      }
+    );
+
+    assert_eq!(checked, [15, 13, 12]);
+  }
+
+  #[test]
+  fn compare() {
+    assert_eq!(
+      VerifyNumbers::compare(&[(0, inscription_id(1))], &[(0, inscription_id(1))]),
+      None
+    );
+    assert_eq!(
+      VerifyNumbers::compare(&[(0, inscription_id(1))], &[(1, inscription_id(1))]),
+      Some(format!(
+        "inscription {} is 0 here, but 1 at the source",
+        inscription_id(1)
+      ))
+    );
+    assert_eq!(
+      VerifyNumbers::compare(&[], &[(0, inscription_id(1))]),
+      Some("source has 1 inscriptions, but only 0 are indexed".into())
+    );
+  }
+}
@@ -1,76 +1,165 @@
 use {
   super::*,
+  base64::Engine,
   bitcoin::secp256k1::{
     rand::{self, RngCore},
     All, Secp256k1,
   },
   bitcoin::{
     bip32::{ChildNumber, DerivationPath, ExtendedPrivKey, Fingerprint},
+    locktime::absolute::LockTime,
+    psbt::Psbt,
     Network,
   },
-  bitcoincore_rpc::bitcoincore_rpc_json::{ImportDescriptors, Timestamp},
-  fee_rate::FeeRate,
+  bitcoincore_rpc::bitcoincore_rpc_json::{ImportDescriptors, Timestamp, WalletProcessPsbtResult},
+  fee_rate::{FeeLadder, FeeRate},
   miniscript::descriptor::{Descriptor, DescriptorSecretKey, DescriptorXKey, Wildcard},
-  transaction_builder::TransactionBuilder,
+  std::collections::BTreeSet,
+  transaction_builder::{CoinSelection, DustPolicy, Satisfaction, TransactionBuilder},
 };
 
+pub mod airdrop;
+pub mod await_inscription;
 pub mod balance;
+pub mod bump_fee;
 pub mod cardinals;
 pub mod create;
+pub(crate) mod dump;
+pub mod export;
+pub mod failed_reveals;
+pub mod freeze;
 pub(crate) mod inscribe;
 pub mod inscriptions;
+pub mod label;
+pub mod migrate;
+pub mod mine;
 pub mod outputs;
+pub mod pending;
 pub mod receive;
 mod restore;
+pub mod sat_flow;
 pub mod sats;
+pub mod scheduled;
 pub mod send;
+pub mod shard;
+pub(crate) mod simulation;
 pub(crate) mod transaction_builder;
 pub mod transactions;
+pub mod unfreeze;
+pub mod wrap;
 
 #[derive(Debug, Parser)]
 pub(crate) enum Wallet {
+  #[clap(about = "Send many inscriptions to many recipients in batched transactions")]
+  Airdrop(airdrop::Airdrop),
+  #[clap(about = "Wait for an inscription to arrive at an address or for an inscription to move")]
+  Await(await_inscription::Await),
   #[clap(about = "Get wallet balance")]
   Balance,
+  #[clap(about = "Bump the fee of a transaction by spending its anchor output")]
+  BumpFee(bump_fee::BumpFee),
   #[clap(about = "Create new wallet")]
   Create(create::Create),
+  #[clap(about = "Export wallet inscriptions with their genesis and transfers for record keeping")]
+  Export(export::Export),
+  #[clap(
+    subcommand,
+    about = "List, retry, and back up reveals that failed to broadcast"
+  )]
+  FailedReveals(failed_reveals::FailedReveals),
+  #[clap(about = "Prevent ord and Bitcoin Core from spending outputs")]
+  Freeze(freeze::Freeze),
   #[clap(about = "Create inscription")]
   Inscribe(inscribe::Inscribe),
   #[clap(about = "List wallet inscriptions")]
   Inscriptions(inscriptions::Inscriptions),
+  #[clap(about = "Label an address or outpoint, to refer to it as @LABEL")]
+  Label(label::Label),
+  #[clap(about = "Move all inscriptions and cardinals to another wallet")]
+  Migrate(migrate::Migrate),
+  #[clap(about = "Mine blocks to the wallet on regtest, or wait for confirmations elsewhere")]
+  Mine(mine::Mine),
+  #[clap(about = "Show inscriptions awaiting confirmation and how to unstick them")]
+  Pending,
   #[clap(about = "Generate receive address")]
   Receive,
   #[clap(about = "Restore wallet")]
   Restore(restore::Restore),
   #[clap(about = "List wallet satoshis")]
   Sats(sats::Sats),
+  #[clap(
+    subcommand,
+    about = "List and resume inscriptions waiting for the fee rate to drop"
+  )]
+  Scheduled(scheduled::Scheduled),
   #[clap(about = "Send sat or inscription")]
   Send(send::Send),
+  #[clap(about = "Inscribe a large file as parts and a manifest for `ord reassemble`")]
+  Shard(shard::Shard),
   #[clap(about = "See wallet transactions")]
   Transactions(transactions::Transactions),
+  #[clap(about = "Allow spending frozen outputs again")]
+  Unfreeze(unfreeze::Unfreeze),
+  #[clap(about = "Inscribe an HTML template rendered for each item of a JSON array")]
+  Wrap(wrap::Wrap),
   #[clap(about = "List all unspent outputs in wallet")]
   Outputs,
-  #[clap(about = "List unspent cardinal outputs in wallet")]
-  Cardinals,
+  #[clap(about = "List unspent cardinal outputs that inscribe and send would spend")]
+  Cardinals(cardinals::Cardinals),
 }
 
 impl Wallet {
+  /// Whether this command only queries the wallet, and so can be run by
+  /// `ord indexd` like `Subcommand::can_proxy` describes.
+  pub(crate) fn can_proxy(&self) -> bool {
+    match self {
+      Self::Sats(sats) => sats.tsv.is_none(),
+      Self::Balance
+      | Self::Cardinals(_)
+      | Self::Inscriptions(_)
+      | Self::Outputs
+      | Self::Pending
+      | Self::Receive
+      | Self::Transactions(_) => true,
+      _ => false,
+    }
+  }
+
   pub(crate) fn run(self, options: Options) -> Result {
     match self {
+      Self::Airdrop(airdrop) => airdrop.run(options),
+      Self::Await(await_inscription) => await_inscription.run(options),
       Self::Balance => balance::run(options),
+      Self::BumpFee(bump_fee) => bump_fee.run(options),
       Self::Create(create) => create.run(options),
+      Self::Export(export) => export.run(options),
+      Self::FailedReveals(failed_reveals) => failed_reveals.run(options),
+      Self::Freeze(freeze) => freeze.run(options),
       Self::Inscribe(inscribe) => inscribe.run(options),
       Self::Inscriptions(inscriptions) => inscriptions.run(options),
+      Self::Label(label) => label.run(options),
+      Self::Migrate(migrate) => migrate.run(options),
+      Self::Mine(mine) => mine.run(options),
+      Self::Pending => pending::run(options),
       Self::Receive => receive::run(options),
       Self::Restore(restore) => restore.run(options),
       Self::Sats(sats) => sats.run(options),
+      Self::Scheduled(scheduled) => scheduled.run(options),
       Self::Send(send) => send.run(options),
+      Self::Shard(shard) => shard.run(options),
       Self::Transactions(transactions) => transactions.run(options),
+      Self::Unfreeze(unfreeze) => unfreeze.run(options),
+      Self::Wrap(wrap) => wrap.run(options),
       Self::Outputs => outputs::run(options),
-      Self::Cardinals => cardinals::run(options),
+      Self::Cardinals(cardinals) => cardinals.run(options),
     }
   }
 }
 
+/// Pay-to-anchor output script, `OP_1 <0x4e73>`, which anyone can spend with
+/// an empty witness to bump the fee of its transaction with a child.
+const P2A_SCRIPT_PUBKEY: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
 fn get_change_address(client: &Client, options: &Options) -> Result<Address> {
   Ok(
     client
@@ -80,6 +169,420 @@ fn get_change_address(client: &Client, options: &Options) -> Result<Address> {
   )
 }
 
+/// Fresh change addresses for the outputs that `--change-split <N>` adds to
+/// a transaction's change output.
+fn get_change_split_addresses(
+  client: &Client,
+  options: &Options,
+  change_split: Option<usize>,
+) -> Result<Vec<Address>> {
+  match change_split {
+    Some(0) => bail!("--change-split must be at least 1"),
+    Some(n) => (1..n)
+      .map(|_| get_change_address(client, options))
+      .collect(),
+    None => Ok(Vec::new()),
+  }
+}
+
+/// Split the change output at the end of `tx` into equal outputs to itself
+/// and to each of `addresses`, so that later transactions can spend them
+/// without chaining. The change output pays for the extra outputs at
+/// `fee_rate`.
+fn split_change(
+  tx: &mut Transaction,
+  change: &[ScriptBuf],
+  addresses: &[Address],
+  fee_rate: FeeRate,
+) -> Result {
+  if addresses.is_empty() {
+    return Ok(());
+  }
+
+  let change_value = tx
+    .output
+    .last()
+    .filter(|output| change.contains(&output.script_pubkey))
+    .ok_or_else(|| anyhow!("--change-split given but transaction has no change output"))?
+    .value;
+
+  let weight = tx.weight();
+
+  for address in addresses {
+    tx.output.push(TxOut {
+      script_pubkey: address.script_pubkey(),
+      value: 0,
+    });
+  }
+
+  let n = addresses.len() + 1;
+  let first = tx.output.len() - n;
+
+  let value = change_value
+    .checked_sub(fee_rate.fee(tx.weight() - weight).to_sat())
+    .unwrap_or_default();
+
+  let share = value / u64::try_from(n).unwrap();
+
+  if tx.output[first..]
+    .iter()
+    .any(|output| Amount::from_sat(share) < output.script_pubkey.dust_value())
+  {
+    bail!("{change_value} sats of change is too little to split into {n} outputs");
+  }
+
+  for (i, output) in tx.output[first..].iter_mut().enumerate() {
+    output.value = share;
+
+    // the remainder of the division stays with the original change output
+    if i == 0 {
+      output.value += value % u64::try_from(n).unwrap();
+    }
+  }
+
+  Ok(())
+}
+
+/// Confirmation counts of the wallet's unspent outputs, for selecting coins by
+/// age.
+fn get_utxo_confirmations(client: &Client) -> Result<BTreeMap<OutPoint, u32>> {
+  Ok(
+    client
+      .list_unspent(None, None, None, None, None)?
+      .into_iter()
+      .map(|utxo| (OutPoint::new(utxo.txid, utxo.vout), utxo.confirmations))
+      .collect(),
+  )
+}
+
+/// Predicted satisfactions of the wallet's unspent outputs, so that
+/// transactions spending non-taproot outputs are sized correctly.
+fn get_utxo_satisfactions(client: &Client) -> Result<BTreeMap<OutPoint, Satisfaction>> {
+  Ok(
+    client
+      .list_unspent(None, None, None, None, None)?
+      .into_iter()
+      .map(|utxo| {
+        (
+          OutPoint::new(utxo.txid, utxo.vout),
+          Satisfaction::predict(&utxo.script_pub_key),
+        )
+      })
+      .collect(),
+  )
+}
+
+fn get_locked_outputs(client: &Client) -> Result<BTreeSet<OutPoint>> {
+  #[derive(Deserialize)]
+  struct JsonOutPoint {
+    txid: Txid,
+    vout: u32,
+  }
+
+  Ok(
+    client
+      .call::<Vec<JsonOutPoint>>("listlockunspent", &[])?
+      .into_iter()
+      .map(|JsonOutPoint { txid, vout }| OutPoint { txid, vout })
+      .collect(),
+  )
+}
+
+fn remove_frozen_utxos(options: &Options, utxos: &mut BTreeMap<OutPoint, Amount>) -> Result {
+  for outpoint in freeze::load_frozen_outputs(options)? {
+    utxos.remove(&outpoint);
+  }
+
+  Ok(())
+}
+
+/// Remove utxos passed with `--avoid-utxo`, which can't also be passed with
+/// `--utxo`.
+fn remove_avoided_utxos(
+  utxos: &mut BTreeMap<OutPoint, Amount>,
+  avoid: &[OutPoint],
+  required: &[OutPoint],
+) -> Result {
+  for outpoint in avoid {
+    if required.contains(outpoint) {
+      bail!("cannot both spend and avoid {outpoint}");
+    }
+
+    utxos.remove(outpoint);
+  }
+
+  Ok(())
+}
+
+/// Remove utxos containing sats of `rarity` or rarer, except for those in
+/// `keep`, so that they aren't spent on fees or change by accident. Outputs
+/// whose sats aren't in the index yet, like unconfirmed change, might contain
+/// rare sats too, so they are also removed.
+fn remove_rare_sat_utxos(
+  index: &Index,
+  utxos: &mut BTreeMap<OutPoint, Amount>,
+  rarity: Rarity,
+  keep: &[OutPoint],
+) -> Result {
+  if !index.has_sat_index()? {
+    bail!("--protect-rarity requires index created with `--index-sats` flag");
+  }
+
+  let mut rare = Vec::new();
+  for outpoint in utxos.keys() {
+    if keep.contains(outpoint) {
+      continue;
+    }
+
+    let protected = match index.list(*outpoint)? {
+      Some(List::Unspent(ranges)) => ranges
+        .iter()
+        .any(|(start, _end)| Sat(*start).rarity() >= rarity),
+      _ => true,
+    };
+
+    if protected {
+      rare.push(*outpoint);
+    }
+  }
+
+  for outpoint in rare {
+    utxos.remove(&outpoint);
+  }
+
+  Ok(())
+}
+
+/// Where `sat` is, if it is in one of the wallet's unspent outputs.
+fn find_wallet_sat(index: &Index, options: &Options, sat: Sat) -> Result<Option<SatPoint>> {
+  let outpoints = index
+    .get_unspent_outputs(crate::wallet::Wallet::load(options)?)?
+    .into_keys()
+    .collect::<Vec<OutPoint>>();
+
+  Ok(
+    index
+      .find(sat, sat + 1, &outpoints, false)?
+      .unwrap_or_default()
+      .into_iter()
+      .next()
+      .map(|range| range.satpoint),
+  )
+}
+
+/// Remove outputs paying to commit tx recovery keys, except for those in
+/// `keep`. They are left by commits whose reveals haven't been mined, for
+/// example after `ord wallet inscribe` failed to broadcast them, and spending
+/// them would conflict with those reveals.
+fn remove_pending_commit_utxos(
+  client: &Client,
+  index: &Index,
+  utxos: &mut BTreeMap<OutPoint, Amount>,
+  keep: &[OutPoint],
+) -> Result {
+  for utxo in client.list_unspent(None, None, None, None, None)? {
+    let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+
+    if utxo.label.as_deref() != Some(inscribe::RECOVERY_KEY_LABEL)
+      || keep.contains(&outpoint)
+      || utxos.remove(&outpoint).is_none()
+    {
+      continue;
+    }
+
+    let commit = if utxo.confirmations == 0 {
+      format!("commit {} in the mempool", utxo.txid)
+    } else {
+      format!("commit {}", utxo.txid)
+    };
+
+    eprintln!(
+      "warning: not spending {outpoint}, which {commit} left for a reveal that hasn't been mined. Pass it with --utxo to spend it anyway."
+    );
+
+    // lines starting with `#` record broadcasts with --fallback-broadcast
+    if index
+      .get_failed_reveals(utxo.txid)?
+      .unwrap_or_default()
+      .iter()
+      .any(|line| !line.starts_with('#'))
+    {
+      eprintln!(
+        "Its reveals failed to broadcast, rebroadcast them with `ord wallet failed-reveals retry --commit {}` to finish inscribing.",
+        utxo.txid
+      );
+    }
+  }
+
+  Ok(())
+}
+
+/// Remove utxos with fewer than `min_confirmations` confirmations, except for
+/// those in `keep`. Spending unconfirmed outputs chains new transactions onto
+/// ones that may never confirm.
+fn remove_unconfirmed_utxos(
+  client: &Client,
+  utxos: &mut BTreeMap<OutPoint, Amount>,
+  min_confirmations: u32,
+  keep: &[OutPoint],
+) -> Result {
+  for (outpoint, confirmations) in get_utxo_confirmations(client)? {
+    if confirmations < min_confirmations && !keep.contains(&outpoint) {
+      utxos.remove(&outpoint);
+    }
+  }
+
+  Ok(())
+}
+
+/// Bitcoin Core's `RPC_VERIFY_ERROR` and `RPC_VERIFY_REJECTED`, returned by
+/// `sendrawtransaction` for transactions it won't accept to its mempool.
+const RELAY_ERROR_CODES: [i32; 2] = [-25, -26];
+
+/// Broadcast `tx` with Bitcoin Core. If Bitcoin Core won't relay it and
+/// `fallback` is given, `tx` is POSTed to `fallback` as hex instead, and
+/// Bitcoin Core's reason for rejecting it is returned with the txid.
+pub(crate) fn broadcast(
+  client: &Client,
+  options: &Options,
+  tx: &[u8],
+  fallback: Option<&str>,
+) -> Result<(Txid, Option<String>)> {
+  let rejection = match (client.send_raw_transaction(tx), fallback) {
+    (Ok(txid), _) => {
+      audit::record(options, client, tx);
+      return Ok((txid, None));
+    }
+    (
+      Err(bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::error::Error::Rpc(
+        bitcoincore_rpc::jsonrpc::error::RpcError { code, message, .. },
+      ))),
+      Some(_),
+    ) if RELAY_ERROR_CODES.contains(&code) => message,
+    (Err(err), _) => return Err(err.into()),
+  };
+
+  let fallback = fallback.unwrap();
+
+  let txid = fallback_broadcast(options, fallback, tx).with_context(|| {
+    format!("Bitcoin Core rejected transaction ({rejection}), and so did {fallback}")
+  })?;
+
+  audit::record(options, client, tx);
+
+  Ok((txid, Some(rejection)))
+}
+
+/// POST `tx` as hex to `url`, which replies with its txid, like
+/// mempool.space's and Esplora's `/api/tx`.
+fn fallback_broadcast(options: &Options, url: &str, tx: &[u8]) -> Result<Txid> {
+  let mut client = reqwest::blocking::Client::builder();
+
+  if let Some(proxy) = options.proxy()? {
+    client = client.proxy(reqwest::Proxy::all(proxy)?);
+  }
+
+  let response = client.build()?.post(url).body(hex::encode(tx)).send()?;
+
+  let status = response.status();
+  let text = response.text()?;
+
+  if !status.is_success() {
+    bail!("{url} replied with {status}: {}", text.trim());
+  }
+
+  text
+    .trim()
+    .parse()
+    .with_context(|| format!("{url} replied with invalid txid `{}`", text.trim()))
+}
+
+/// Transactions with a lock time in the future are rejected by the mempool.
+/// Height lock times must not exceed the current tip height, and time lock
+/// times must be before the median time past of the tip.
+fn check_lock_time(client: &Client, lock_time: LockTime) -> Result {
+  let info = client.get_blockchain_info()?;
+
+  match lock_time {
+    LockTime::Blocks(height) => {
+      if u64::from(height.to_consensus_u32()) > info.blocks {
+        bail!(
+          "--locktime {height} is above the current block height {}, the transaction would not be accepted until then",
+          info.blocks
+        );
+      }
+    }
+    LockTime::Seconds(time) => {
+      if u64::from(time.to_consensus_u32()) >= info.median_time {
+        bail!(
+          "--locktime {time} is not before the current median time past {}, the transaction would not be accepted until then",
+          info.median_time
+        );
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Many wallets and marketplaces only track inscriptions on segwit outputs,
+/// so inscriptions sent to P2PKH and P2SH outputs are easily lost.
+fn check_legacy_destination(script: &Script, network: Network, allow_legacy: bool) -> Result {
+  if !allow_legacy && (script.is_p2pkh() || script.is_p2sh()) {
+    bail!(
+      "destination {} is a legacy address, pass --allow-legacy-destination to use it anyway",
+      Address::from_script(script, network)?
+    );
+  }
+
+  Ok(())
+}
+
+/// Watch-only wallets have no private keys, so transactions they fund are
+/// printed as PSBTs to be signed elsewhere instead of being broadcast.
+fn private_keys_enabled(client: &Client) -> Result<bool> {
+  Ok(client.get_wallet_info()?.private_keys_enabled)
+}
+
+/// An unsigned PSBT for `tx`, with the wallet's UTXO and key derivation
+/// information filled in.
+fn wallet_psbt(client: &Client, tx: &Transaction) -> Result<String> {
+  let psbt = base64::engine::general_purpose::STANDARD
+    .encode(Psbt::from_unsigned_tx(tx.clone())?.serialize());
+
+  Ok(
+    client
+      .call::<WalletProcessPsbtResult>("walletprocesspsbt", &[psbt.into(), false.into()])?
+      .psbt,
+  )
+}
+
+/// A PSBT for `tx`, which spends the inputs of `funding` and possibly some of
+/// the wallet's. The funder's input information is copied over, and the
+/// wallet's inputs are signed, so that the funder can sign and broadcast it.
+fn funded_psbt(client: &Client, tx: &Transaction, funding: &Psbt) -> Result<String> {
+  let mut psbt = Psbt::from_unsigned_tx(tx.clone())?;
+
+  for (input, txin) in psbt.inputs.iter_mut().zip(&tx.input) {
+    if let Some(i) = funding
+      .unsigned_tx
+      .input
+      .iter()
+      .position(|funding_txin| funding_txin.previous_output == txin.previous_output)
+    {
+      *input = funding.inputs[i].clone();
+    }
+  }
+
+  let psbt = base64::engine::general_purpose::STANDARD.encode(psbt.serialize());
+
+  Ok(
+    client
+      .call::<WalletProcessPsbtResult>("walletprocesspsbt", &[psbt.into(), true.into()])?
+      .psbt,
+  )
+}
+
 pub(crate) fn initialize_wallet(options: &Options, seed: [u8; 64]) -> Result {
   let client = options.bitcoin_rpc_client_for_wallet_command(true)?;
   let network = options.chain().network();
@@ -0,0 +1,377 @@
+use {
+  super::*, crate::wallet::Wallet, bitcoin::Witness,
+  bitcoincore_rpc::bitcoincore_rpc_json::SignRawTransactionInput,
+};
+
+/// Sends many inscriptions to many recipients, moving several inscription
+/// utxos in each transaction instead of one `ord wallet send` per
+/// inscription. Each transaction's change funds the next, and the txid that
+/// carried each inscription is recorded in a state file as it is broadcast,
+/// so that an interrupted airdrop, or one that hits the mempool's limit on
+/// chains of unconfirmed transactions, can be resumed by running it again.
+#[derive(Debug, Parser)]
+pub(crate) struct Airdrop {
+  #[clap(
+    long,
+    help = "Send inscriptions to the recipients in <CSV>, with rows of `inscription_id,address`."
+  )]
+  csv: PathBuf,
+  #[clap(long, help = "Use fee rate of <FEE_RATE> sats/vB.")]
+  fee_rate: FeeRate,
+  #[clap(
+    long,
+    default_value = "100",
+    help = "Move at most <MAX_PER_TX> inscription utxos in each transaction."
+  )]
+  max_per_tx: usize,
+  #[clap(
+    long,
+    help = "Record sent inscriptions in <STATE>, and skip those it already records. Default `airdrop-<WALLET>.json` in the data directory."
+  )]
+  state: Option<PathBuf>,
+  #[clap(
+    long,
+    help = "Print the planned transactions without signing or broadcasting them."
+  )]
+  dry_run: bool,
+  #[clap(
+    long,
+    help = "Allow sending inscriptions to P2PKH and P2SH addresses, on which many wallets and marketplaces don't track inscriptions."
+  )]
+  allow_legacy_destination: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub transactions: Vec<PlannedTransaction>,
+  /// Inscriptions the state file records as sent by an earlier run.
+  pub skipped: Vec<InscriptionId>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlannedTransaction {
+  pub txid: Txid,
+  pub inputs: Vec<OutPoint>,
+  pub outputs: Vec<PlannedOutput>,
+  pub fee: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlannedOutput {
+  pub address: Address<NetworkUnchecked>,
+  pub value: u64,
+  pub inscriptions: Vec<InscriptionId>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+  sent: BTreeMap<InscriptionId, Txid>,
+}
+
+impl Airdrop {
+  pub(crate) fn run(self, options: Options) -> Result {
+    if self.max_per_tx == 0 {
+      bail!("--max-per-tx must be at least 1");
+    }
+
+    let network = options.chain().network();
+
+    let state_path = match &self.state {
+      Some(path) => path.clone(),
+      None => options
+        .data_dir()?
+        .join(format!("airdrop-{}.json", options.wallet)),
+    };
+
+    let mut state = if state_path.exists() {
+      serde_json::from_reader::<_, State>(File::open(&state_path)?)
+        .with_context(|| format!("failed to parse airdrop state {}", state_path.display()))?
+    } else {
+      State::default()
+    };
+
+    let mut recipients = BTreeMap::new();
+    let mut skipped = Vec::new();
+
+    for (i, line) in fs::read_to_string(&self.csv)
+      .with_context(|| format!("io error reading {}", self.csv.display()))?
+      .lines()
+      .enumerate()
+    {
+      let line = line.trim();
+
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let Some((inscription_id, address)) = line.split_once(',') else {
+        bail!(
+          "line {} of {} is not `inscription_id,address`",
+          i + 1,
+          self.csv.display()
+        );
+      };
+
+      // allow a header row
+      if i == 0 && inscription_id.trim() == "inscription_id" {
+        continue;
+      }
+
+      let inscription_id = inscription_id
+        .trim()
+        .parse::<InscriptionId>()
+        .with_context(|| format!("invalid inscription id on line {}", i + 1))?;
+
+      let address = address
+        .trim()
+        .parse::<Address<NetworkUnchecked>>()
+        .with_context(|| format!("invalid address on line {}", i + 1))?
+        .require_network(network)?;
+
+      check_legacy_destination(
+        &address.script_pubkey(),
+        network,
+        self.allow_legacy_destination,
+      )?;
+
+      if state.sent.contains_key(&inscription_id) {
+        skipped.push(inscription_id);
+        continue;
+      }
+
+      if recipients.insert(inscription_id, address).is_some() {
+        bail!("inscription {inscription_id} is listed more than once");
+      }
+    }
+
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let mut unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    remove_frozen_utxos(&options, &mut unspent_outputs)?;
+
+    for outpoint in get_locked_outputs(&client)? {
+      unspent_outputs.remove(&outpoint);
+    }
+
+    let inscriptions = index.get_inscriptions(unspent_outputs.clone())?;
+
+    let mut inscribed = BTreeMap::<OutPoint, Vec<InscriptionId>>::new();
+    for (satpoint, inscription_id) in &inscriptions {
+      inscribed
+        .entry(satpoint.outpoint)
+        .or_default()
+        .push(*inscription_id);
+    }
+
+    // an inscription utxo can only be airdropped if every inscription on it
+    // goes to the same recipient, since its sats move as one output
+    let mut inscription_utxos: Vec<(OutPoint, Amount, Address, Vec<InscriptionId>)> = Vec::new();
+    for (inscription_id, address) in &recipients {
+      let Some(satpoint) = inscriptions
+        .iter()
+        .find(|(_, id)| id == inscription_id)
+        .map(|(satpoint, _)| *satpoint)
+      else {
+        bail!("inscription {inscription_id} is not in the wallet");
+      };
+
+      if inscription_utxos
+        .iter()
+        .any(|(outpoint, _, _, _)| *outpoint == satpoint.outpoint)
+      {
+        continue;
+      }
+
+      let ids = inscribed[&satpoint.outpoint].clone();
+
+      for id in &ids {
+        if recipients.get(id) != Some(address) {
+          bail!(
+            "output {} holds inscriptions {inscription_id} and {id}, which are not listed for the same recipient",
+            satpoint.outpoint
+          );
+        }
+      }
+
+      inscription_utxos.push((
+        satpoint.outpoint,
+        unspent_outputs[&satpoint.outpoint],
+        address.clone(),
+        ids,
+      ));
+    }
+
+    let mut funding = unspent_outputs
+      .iter()
+      .filter(|(outpoint, _amount)| !inscribed.contains_key(outpoint))
+      .map(|(outpoint, amount)| (*outpoint, *amount))
+      .collect::<Vec<(OutPoint, Amount)>>();
+
+    let chunks = inscription_utxos
+      .chunks(self.max_per_tx)
+      .map(|chunk| chunk.to_vec())
+      .collect::<Vec<Vec<(OutPoint, Amount, Address, Vec<InscriptionId>)>>>();
+
+    let mut transactions = Vec::new();
+    let mut planned = Vec::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+      let mut tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: chunk
+          .iter()
+          .map(|(outpoint, _, _, _)| *outpoint)
+          .chain(funding.iter().map(|(outpoint, _)| *outpoint))
+          .map(|previous_output| TxIn {
+            previous_output,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::from_slice(&[&[0; 64]]),
+          })
+          .collect(),
+        output: Vec::new(),
+      };
+
+      let change_address = get_change_address(&client, &options)?;
+
+      // each inscription utxo goes whole to its own output, in the order
+      // they came in, so every inscription keeps its offset
+      tx.output = chunk
+        .iter()
+        .map(|(_, value, address, _)| TxOut {
+          script_pubkey: address.script_pubkey(),
+          value: value.to_sat(),
+        })
+        .chain(Some(TxOut {
+          script_pubkey: change_address.script_pubkey(),
+          value: 0,
+        }))
+        .collect();
+
+      let fee = self.fee_rate.fee(tx.weight());
+
+      let funding_value = funding.iter().map(|(_, amount)| *amount).sum::<Amount>();
+
+      let change = funding_value
+        .checked_sub(fee)
+        .filter(|change| *change >= change_address.script_pubkey().dust_value())
+        .ok_or_else(|| {
+          anyhow!(
+            "wallet does not contain enough cardinal value to pay the {fee} fee of airdrop transaction {}",
+            i + 1
+          )
+        })?;
+
+      tx.output.last_mut().unwrap().value = change.to_sat();
+
+      for input in &mut tx.input {
+        input.witness = Witness::new();
+      }
+
+      funding = vec![(
+        OutPoint {
+          txid: tx.txid(),
+          vout: (tx.output.len() - 1).try_into().unwrap(),
+        },
+        change,
+      )];
+
+      planned.push(PlannedTransaction {
+        txid: tx.txid(),
+        inputs: tx.input.iter().map(|input| input.previous_output).collect(),
+        outputs: chunk
+          .iter()
+          .map(|(_, value, address, inscriptions)| PlannedOutput {
+            address: address.clone().into_unchecked(),
+            value: value.to_sat(),
+            inscriptions: inscriptions.clone(),
+          })
+          .chain(Some(PlannedOutput {
+            address: change_address.into_unchecked(),
+            value: change.to_sat(),
+            inscriptions: Vec::new(),
+          }))
+          .collect(),
+        fee: fee.to_sat(),
+      });
+
+      transactions.push(tx);
+    }
+
+    if !self.dry_run {
+      let mut previous: Option<&Transaction> = None;
+
+      for (i, (tx, planned)) in transactions.iter().zip(&planned).enumerate() {
+        if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+          bail!(
+            "interrupted after sending {i} of {} airdrop transactions, progress was saved to {}, run the airdrop again to resume it",
+            transactions.len(),
+            state_path.display()
+          );
+        }
+
+        // the funding input of every transaction after the first spends the
+        // previous one, which the wallet may not know about yet
+        let prevtxs = previous.map(|previous| {
+          let vout = previous.output.len() - 1;
+          vec![SignRawTransactionInput {
+            txid: previous.txid(),
+            vout: vout.try_into().unwrap(),
+            script_pub_key: previous.output[vout].script_pubkey.clone(),
+            amount: Some(Amount::from_sat(previous.output[vout].value)),
+            redeem_script: None,
+          }]
+        });
+
+        let signed = client.sign_raw_transaction_with_wallet(tx, prevtxs.as_deref(), None)?;
+
+        if !signed.complete {
+          bail!("error signing airdrop transaction: {:?}", signed.errors);
+        }
+
+        let txid = client.send_raw_transaction(&signed.hex).with_context(|| {
+          format!(
+            "failed to send airdrop transaction {} of {}, run the airdrop again once earlier transactions confirm to resume it",
+            i + 1,
+            transactions.len()
+          )
+        })?;
+
+        audit::record(&options, &client, &signed.hex);
+
+        for output in &planned.outputs {
+          for inscription_id in &output.inscriptions {
+            state.sent.insert(*inscription_id, txid);
+          }
+        }
+
+        if let Some(parent) = state_path.parent() {
+          fs::create_dir_all(parent)?;
+        }
+
+        fs::write(&state_path, serde_json::to_string_pretty(&state)?)
+          .with_context(|| format!("failed to write airdrop state {}", state_path.display()))?;
+
+        eprintln!(
+          "[sent airdrop transaction {} of {}: {txid}]",
+          i + 1,
+          transactions.len()
+        );
+
+        previous = Some(tx);
+      }
+    }
+
+    print_json(Output {
+      transactions: planned,
+      skipped,
+    })?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,271 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Await {
+  #[clap(
+    help = "Wait for an inscription to arrive at <TARGET>, if it is an address, or for <TARGET> to move, if it is an inscription id."
+  )]
+  target: Object,
+  #[clap(long, help = "Give up after <TIMEOUT> seconds.")]
+  timeout: Option<u64>,
+  #[clap(
+    long,
+    default_value = "1",
+    help = "Check the mempool and for new blocks every <POLL_INTERVAL> seconds."
+  )]
+  poll_interval: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  pub inscription: InscriptionId,
+  pub txid: Txid,
+  /// Where the inscription is now, or `None` if it was spent to fees.
+  pub satpoint: Option<SatPoint>,
+  pub confirmed: bool,
+}
+
+enum Target {
+  Address(ScriptBuf),
+  Inscription(InscriptionId, SatPoint),
+}
+
+impl Await {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client()?;
+
+    let target = match &self.target {
+      Object::Address(address) => Target::Address(
+        address
+          .clone()
+          .require_network(options.chain().network())?
+          .script_pubkey(),
+      ),
+      Object::InscriptionId(inscription) => Target::Inscription(
+        *inscription,
+        index
+          .get_inscription_satpoint_by_id(*inscription)?
+          .ok_or_else(|| anyhow!("inscription {inscription} not found"))?,
+      ),
+      object => bail!("{object} is not an address or an inscription id"),
+    };
+
+    let start = Instant::now();
+    let mut height = index.block_count()?;
+    let mut seen = HashSet::new();
+
+    loop {
+      if let Some(output) = Self::check_mempool(&index, &client, &target, &mut seen)? {
+        return print_json(output);
+      }
+
+      index.update()?;
+
+      if let Some(output) = Self::check_blocks(&index, &target, height)? {
+        return print_json(output);
+      }
+
+      height = index.block_count()?;
+
+      if let Some(timeout) = self.timeout {
+        if start.elapsed() >= Duration::from_secs(timeout) {
+          bail!(
+            "timed out after {timeout} seconds waiting for {}",
+            self.target
+          );
+        }
+      }
+
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        bail!("interrupted while waiting for {}", self.target);
+      }
+
+      thread::sleep(Duration::from_secs(self.poll_interval));
+    }
+  }
+
+  fn check_mempool(
+    index: &Index,
+    client: &Client,
+    target: &Target,
+    seen: &mut HashSet<Txid>,
+  ) -> Result<Option<Output>> {
+    for txid in client.get_raw_mempool()? {
+      if !seen.insert(txid) {
+        continue;
+      }
+
+      let Some(tx) = index.get_transaction(txid)? else {
+        continue;
+      };
+
+      let arrived = match target {
+        Target::Address(script_pubkey) => {
+          Self::locate(index, &tx)?.into_iter().find(|(_, satpoint)| {
+            satpoint
+              .map(|satpoint| {
+                tx.output[satpoint.outpoint.vout as usize].script_pubkey == *script_pubkey
+              })
+              .unwrap_or_default()
+          })
+        }
+        Target::Inscription(inscription, satpoint) => {
+          if !tx
+            .input
+            .iter()
+            .any(|input| input.previous_output == satpoint.outpoint)
+          {
+            continue;
+          }
+
+          Some(
+            Self::locate(index, &tx)?
+              .into_iter()
+              .find(|(id, _)| id == inscription)
+              .unwrap_or((*inscription, None)),
+          )
+        }
+      };
+
+      if let Some((inscription, satpoint)) = arrived {
+        return Ok(Some(Output {
+          inscription,
+          txid,
+          satpoint,
+          confirmed: false,
+        }));
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Check blocks from `height` on, which the index has just caught up with.
+  fn check_blocks(index: &Index, target: &Target, height: u64) -> Result<Option<Output>> {
+    match target {
+      Target::Address(script_pubkey) => {
+        for height in height..index.block_count()? {
+          let Some(block) = index.get_block_by_height(height)? else {
+            continue;
+          };
+
+          for tx in block.txdata {
+            for (vout, output) in tx.output.iter().enumerate() {
+              if output.script_pubkey != *script_pubkey {
+                continue;
+              }
+
+              let outpoint = OutPoint::new(tx.txid(), vout.try_into().unwrap());
+
+              if let Some((satpoint, inscription)) = index
+                .get_inscriptions_on_output_with_satpoints(outpoint)?
+                .into_iter()
+                .next()
+              {
+                return Ok(Some(Output {
+                  inscription,
+                  txid: outpoint.txid,
+                  satpoint: Some(satpoint),
+                  confirmed: true,
+                }));
+              }
+            }
+          }
+        }
+      }
+      Target::Inscription(inscription, satpoint) => {
+        let current = index
+          .get_inscription_satpoint_by_id(*inscription)?
+          .ok_or_else(|| anyhow!("inscription {inscription} not found"))?;
+
+        if current != *satpoint {
+          return Ok(Some(Output {
+            inscription: *inscription,
+            txid: current.outpoint.txid,
+            satpoint: Some(current),
+            confirmed: true,
+          }));
+        }
+      }
+    }
+
+    Ok(None)
+  }
+
+  /// Where the inscriptions on the inputs of an unconfirmed transaction, and
+  /// those it reveals, will end up, or `None` for those spent to fees.
+  /// Inscriptions on unconfirmed inputs aren't indexed, and so aren't found.
+  fn locate(index: &Index, tx: &Transaction) -> Result<Vec<(InscriptionId, Option<SatPoint>)>> {
+    let txid = tx.txid();
+    let revealed = Inscription::from_transaction(tx);
+
+    let mut offsets = Vec::new();
+    let mut input_value = 0;
+
+    for (input_index, input) in tx.input.iter().enumerate() {
+      for (satpoint, inscription) in
+        index.get_inscriptions_on_output_with_satpoints(input.previous_output)?
+      {
+        offsets.push((inscription, input_value + satpoint.offset));
+      }
+
+      // new inscriptions are on the first sat of the input that reveals them
+      for (id_index, _) in revealed
+        .iter()
+        .enumerate()
+        .filter(|(_, inscription)| inscription.tx_in_index as usize == input_index)
+      {
+        offsets.push((
+          InscriptionId {
+            txid,
+            index: id_index.try_into().unwrap(),
+          },
+          input_value,
+        ));
+      }
+
+      // without the values of earlier inputs, offsets can't be known
+      let Some(value) = index
+        .get_transaction(input.previous_output.txid)?
+        .and_then(|prev| {
+          prev
+            .output
+            .get(input.previous_output.vout as usize)
+            .cloned()
+        })
+        .map(|output| output.value)
+      else {
+        return Ok(Vec::new());
+      };
+
+      input_value += value;
+    }
+
+    Ok(
+      offsets
+        .into_iter()
+        .map(|(inscription, offset)| {
+          let mut start = 0;
+
+          let satpoint = tx.output.iter().enumerate().find_map(|(vout, output)| {
+            let end = start + output.value;
+
+            let satpoint = (offset < end).then(|| SatPoint {
+              outpoint: OutPoint::new(txid, vout.try_into().unwrap()),
+              offset: offset - start,
+            });
+
+            start = end;
+
+            satpoint
+          });
+
+          (inscription, satpoint)
+        })
+        .collect(),
+    )
+  }
+}
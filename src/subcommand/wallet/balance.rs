@@ -6,7 +6,7 @@ pub struct Output {
 }
 
 pub(crate) fn run(options: Options) -> Result {
-  let index = Index::open(&options)?;
+  let index = Index::open_shared(&options)?;
   index.update()?;
 
   let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
@@ -0,0 +1,164 @@
+use {
+  super::*, crate::wallet::Wallet, bitcoin::Witness,
+  bitcoincore_rpc::bitcoincore_rpc_json::SignRawTransactionInput, std::collections::BTreeSet,
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct BumpFee {
+  #[clap(help = "Bump the fee of unconfirmed transaction <TXID>.")]
+  txid: Txid,
+  #[clap(
+    long,
+    help = "Use fee rate of <FEE_RATE> sats/vB for <TXID> and the child spending its anchor combined."
+  )]
+  fee_rate: FeeRate,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub parent: Txid,
+  pub child: Txid,
+  pub fee: u64,
+}
+
+impl BumpFee {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let parent = client.get_raw_transaction(&self.txid, None)?;
+
+    let anchor_vout = parent
+      .output
+      .iter()
+      .position(|output| output.script_pubkey.as_bytes() == P2A_SCRIPT_PUBKEY)
+      .ok_or_else(|| anyhow!("transaction {} has no anchor output", self.txid))?;
+
+    let anchor = OutPoint {
+      txid: self.txid,
+      vout: anchor_vout.try_into().unwrap(),
+    };
+
+    let anchor_value = Amount::from_sat(parent.output[anchor_vout].value);
+
+    let mempool_entry = client
+      .get_mempool_entry(&self.txid)
+      .with_context(|| format!("transaction {} is not in the mempool", self.txid))?;
+
+    let parent_fee = mempool_entry.fees.base;
+    let parent_weight = Weight::from_vb(mempool_entry.vsize).unwrap();
+
+    let mut unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    remove_frozen_utxos(&options, &mut unspent_outputs)?;
+
+    let inscribed_utxos = index
+      .get_inscriptions(unspent_outputs.clone())?
+      .keys()
+      .map(|satpoint| satpoint.outpoint)
+      .collect::<BTreeSet<OutPoint>>();
+
+    let mut cardinals = unspent_outputs
+      .into_iter()
+      .filter(|(outpoint, _amount)| !inscribed_utxos.contains(outpoint))
+      .collect::<Vec<(OutPoint, Amount)>>();
+
+    cardinals.sort_by_key(|(_outpoint, amount)| *amount);
+
+    let change = get_change_address(&client, &options)?;
+    let dust_value = change.script_pubkey().dust_value();
+
+    let mut child = Transaction {
+      version: 2,
+      lock_time: LockTime::ZERO,
+      input: vec![
+        TxIn {
+          previous_output: anchor,
+          script_sig: ScriptBuf::new(),
+          sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+          witness: Witness::new(),
+        },
+        TxIn {
+          previous_output: OutPoint::null(),
+          script_sig: ScriptBuf::new(),
+          sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+          witness: Witness::new(),
+        },
+      ],
+      output: vec![TxOut {
+        script_pubkey: change.script_pubkey(),
+        value: 0,
+      }],
+    };
+
+    let satisfactions = get_utxo_satisfactions(&client)?;
+
+    let mut largest_fee = Amount::ZERO;
+
+    // the child's weight depends on how the cardinal it spends is signed
+    let (cardinal, cardinal_value, fee) = cardinals
+      .into_iter()
+      .find_map(|(outpoint, amount)| {
+        let satisfaction = satisfactions.get(&outpoint).cloned().unwrap_or_default();
+
+        child.input[1].script_sig = satisfaction.script_sig;
+        child.input[1].witness = satisfaction.witness;
+
+        let child_weight = child.weight();
+
+        // the child must pay for its own weight, plus whatever the parent is
+        // short of the target fee rate
+        let fee = self
+          .fee_rate
+          .fee(parent_weight + child_weight)
+          .checked_sub(parent_fee)
+          .unwrap_or_default()
+          .max(self.fee_rate.fee(child_weight));
+
+        largest_fee = largest_fee.max(fee);
+
+        (amount + anchor_value >= fee + dust_value).then_some((outpoint, amount, fee))
+      })
+      .ok_or_else(|| {
+        anyhow!("wallet contains no cardinal utxo large enough to pay {largest_fee}")
+      })?;
+
+    child.input[1].previous_output = cardinal;
+    child.input[1].script_sig = ScriptBuf::new();
+    child.input[1].witness = Witness::new();
+    child.output[0].value = (cardinal_value + anchor_value - fee).to_sat();
+
+    let signed_child = client.sign_raw_transaction_with_wallet(
+      &child,
+      Some(&[SignRawTransactionInput {
+        txid: anchor.txid,
+        vout: anchor.vout,
+        script_pub_key: ScriptBuf::from_bytes(P2A_SCRIPT_PUBKEY.to_vec()),
+        amount: Some(anchor_value),
+        redeem_script: None,
+      }]),
+      None,
+    )?;
+
+    if !signed_child.complete {
+      bail!(
+        "error signing anchor spend, spending anchors requires Bitcoin Core 28.0.0 or newer: {:?}",
+        signed_child.errors
+      );
+    }
+
+    let child = client.send_raw_transaction(&signed_child.hex)?;
+
+    audit::record(&options, &client, &signed_child.hex);
+
+    print_json(Output {
+      parent: self.txid,
+      child,
+      fee: fee.to_sat(),
+    })?;
+
+    Ok(())
+  }
+}
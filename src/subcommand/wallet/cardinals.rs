@@ -1,38 +1,66 @@
 use {super::*, crate::wallet::Wallet, std::collections::BTreeSet};
 
+#[derive(Debug, Parser)]
+pub(crate) struct Cardinals {
+  #[clap(
+    long,
+    help = "Only list utxos that have at least <MIN_CONFIRMATIONS> confirmations, like `wallet inscribe` and `wallet send` with the same flag."
+  )]
+  min_confirmations: Option<u32>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Cardinal {
   pub output: OutPoint,
   pub amount: u64,
+  pub confirmations: u32,
 }
 
-pub(crate) fn run(options: Options) -> Result {
-  let index = Index::open(&options)?;
-  index.update()?;
-
-  let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
-
-  let inscribed_utxos = index
-    .get_inscriptions(unspent_outputs.clone())?
-    .keys()
-    .map(|satpoint| satpoint.outpoint)
-    .collect::<BTreeSet<OutPoint>>();
-
-  let cardinal_utxos = unspent_outputs
-    .iter()
-    .filter_map(|(output, amount)| {
-      if inscribed_utxos.contains(output) {
-        None
-      } else {
-        Some(Cardinal {
-          output: *output,
-          amount: amount.to_sat(),
-        })
-      }
-    })
-    .collect::<Vec<Cardinal>>();
-
-  print_json(cardinal_utxos)?;
-
-  Ok(())
+impl Cardinals {
+  /// The utxos that `wallet inscribe` and `wallet send` would fund
+  /// transactions with: those without inscriptions, less frozen outputs and
+  /// those left for pending reveals.
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let mut unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    remove_frozen_utxos(&options, &mut unspent_outputs)?;
+
+    if let Some(min_confirmations) = self.min_confirmations {
+      remove_unconfirmed_utxos(&client, &mut unspent_outputs, min_confirmations, &[])?;
+    }
+
+    remove_pending_commit_utxos(&client, &index, &mut unspent_outputs, &[])?;
+
+    let inscribed_utxos = index
+      .get_inscriptions(unspent_outputs.clone())?
+      .keys()
+      .map(|satpoint| satpoint.outpoint)
+      .collect::<BTreeSet<OutPoint>>();
+
+    let confirmations = get_utxo_confirmations(&client)?;
+
+    let cardinal_utxos = unspent_outputs
+      .iter()
+      .filter_map(|(output, amount)| {
+        if inscribed_utxos.contains(output) {
+          None
+        } else {
+          Some(Cardinal {
+            output: *output,
+            amount: amount.to_sat(),
+            confirmations: confirmations.get(output).copied().unwrap_or_default(),
+          })
+        }
+      })
+      .collect::<Vec<Cardinal>>();
+
+    print_json(cardinal_utxos)?;
+
+    Ok(())
+  }
 }
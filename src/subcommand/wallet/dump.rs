@@ -0,0 +1,225 @@
+use super::*;
+
+/// Version of the JSON printed by `ord wallet inscribe --dump`, increased
+/// whenever a field is renamed, moved, or removed. Dumps without a `schema`
+/// field were printed by versions of ord before the format was versioned.
+pub(crate) const SCHEMA: u32 = 1;
+
+#[derive(Serialize)]
+pub(crate) struct Output {
+  pub(crate) schema: u32,
+  pub(crate) batch: usize,
+  pub(crate) satpoint: SatPoint,
+  pub(crate) commit: Commit,
+  pub(crate) reveals: Vec<Reveal>,
+  pub(crate) recovery_descriptors: Vec<String>,
+  pub(crate) fees: u64,
+  pub(crate) dust_to_fee: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) destination_descriptors: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Commit {
+  pub(crate) txid: Txid,
+  pub(crate) weight: bitcoin::Weight,
+  pub(crate) hex: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Reveal {
+  pub(crate) inscription: InscriptionId,
+  pub(crate) filename: PathBuf,
+  pub(crate) commit_address: Address,
+  /// Index into `recovery_descriptors`, `None` with `--no-key-path-recovery`.
+  pub(crate) recovery_descriptor: Option<usize>,
+  pub(crate) weight: bitcoin::Weight,
+  pub(crate) hex: String,
+}
+
+/// A dump read back by `ord broadcast`, `ord wallet scheduled resume`, or
+/// `ord wallet failed-reveals import`. Only the fields those need are read,
+/// so that dumps of any schema up to `SCHEMA` are accepted.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Input {
+  schema: Option<u32>,
+  commit: CommitInput,
+  reveals: Vec<RevealInput>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CommitInput {
+  Record {
+    hex: String,
+  },
+  /// Unversioned dumps have the commit's hex at the top level.
+  Hex(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct RevealInput {
+  inscription: Option<InscriptionId>,
+  hex: String,
+}
+
+impl Input {
+  pub(crate) fn check(&self) -> Result {
+    match self.schema {
+      Some(schema) if schema > SCHEMA => {
+        bail!("dump has schema {schema}, but this version of ord only reads schemas up to {SCHEMA}")
+      }
+      _ => Ok(()),
+    }
+  }
+
+  pub(crate) fn commit(&self) -> &str {
+    match &self.commit {
+      CommitInput::Record { hex } | CommitInput::Hex(hex) => hex,
+    }
+  }
+
+  pub(crate) fn reveals(&self) -> Vec<String> {
+    self
+      .reveals
+      .iter()
+      .map(|reveal| reveal.hex.clone())
+      .collect()
+  }
+
+  /// Index of the inscription in each reveal, zero if the dump doesn't say.
+  pub(crate) fn index(&self) -> u32 {
+    self
+      .reveals
+      .first()
+      .and_then(|reveal| reveal.inscription)
+      .map(|inscription| inscription.index)
+      .unwrap_or_default()
+  }
+}
+
+/// Read the dumps in `path`, one JSON object per batch, as printed by
+/// `ord wallet inscribe --dump`.
+pub(crate) fn load(path: &Path) -> Result<Vec<Input>> {
+  let mut inputs = Vec::new();
+
+  for input in serde_json::Deserializer::from_reader(
+    File::open(path).with_context(|| format!("failed to open {}", path.display()))?,
+  )
+  .into_iter::<Input>()
+  {
+    let input = input.with_context(|| format!("failed to parse dump {}", path.display()))?;
+    input.check()?;
+    inputs.push(input);
+  }
+
+  if inputs.is_empty() {
+    bail!("{} contains no dumps", path.display());
+  }
+
+  Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn output_shape() {
+    let output = Output {
+      schema: SCHEMA,
+      batch: 0,
+      satpoint: satpoint(1, 0),
+      commit: Commit {
+        txid: txid(1),
+        weight: bitcoin::Weight::from_wu(400),
+        hex: "00".into(),
+      },
+      reveals: vec![
+        Reveal {
+          inscription: inscription_id(2),
+          filename: "foo.txt".into(),
+          commit_address: recipient(),
+          recovery_descriptor: Some(0),
+          weight: bitcoin::Weight::from_wu(800),
+          hex: "01".into(),
+        },
+        Reveal {
+          inscription: inscription_id(3),
+          filename: "bar.txt".into(),
+          commit_address: recipient(),
+          recovery_descriptor: None,
+          weight: bitcoin::Weight::from_wu(800),
+          hex: "02".into(),
+        },
+      ],
+      recovery_descriptors: vec!["rawtr(foo)".into()],
+      fees: 1000,
+      dust_to_fee: 0,
+      destination_descriptors: None,
+    };
+
+    pretty_assert_eq!(
+      serde_json::to_value(output).unwrap(),
+      serde_json::json!({
+        "schema": SCHEMA,
+        "batch": 0,
+        "satpoint": satpoint(1, 0).to_string(),
+        "commit": {
+          "txid": txid(1).to_string(),
+          "weight": 400,
+          "hex": "00",
+        },
+        "reveals": [
+          {
+            "inscription": inscription_id(2).to_string(),
+            "filename": "foo.txt",
+            "commit_address": recipient().to_string(),
+            "recovery_descriptor": 0,
+            "weight": 800,
+            "hex": "01",
+          },
+          {
+            "inscription": inscription_id(3).to_string(),
+            "filename": "bar.txt",
+            "commit_address": recipient().to_string(),
+            "recovery_descriptor": null,
+            "weight": 800,
+            "hex": "02",
+          },
+        ],
+        "recovery_descriptors": ["rawtr(foo)"],
+        "fees": 1000,
+        "dust_to_fee": 0,
+      }),
+    );
+  }
+
+  #[test]
+  fn unversioned_dumps_are_read() {
+    let input =
+      serde_json::from_str::<Input>(r#"{"commit": "00", "reveals": [{"hex": "01"}]}"#).unwrap();
+
+    input.check().unwrap();
+    assert_eq!(input.commit(), "00");
+    assert_eq!(input.reveals(), vec!["01".to_string()]);
+    assert_eq!(input.index(), 0);
+  }
+
+  #[test]
+  fn newer_schemas_are_rejected() {
+    let input = serde_json::from_str::<Input>(&format!(
+      r#"{{"schema": {}, "commit": {{"hex": "00"}}, "reveals": []}}"#,
+      SCHEMA + 1
+    ))
+    .unwrap();
+
+    assert_eq!(
+      input.check().unwrap_err().to_string(),
+      format!(
+        "dump has schema {}, but this version of ord only reads schemas up to {SCHEMA}",
+        SCHEMA + 1
+      )
+    );
+  }
+}
@@ -0,0 +1,121 @@
+use {super::*, crate::wallet::Wallet, std::collections::HashMap};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Export {
+  #[clap(
+    long,
+    help = "Include the heights each inscription was transferred at, from the transfer log."
+  )]
+  with_history: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Genesis {
+  pub height: u64,
+  pub timestamp: u32,
+  /// Fee paid by the reveal transaction, divided among its inscriptions.
+  pub fee: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sat: Option<Sat>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transfer {
+  pub height: u64,
+  pub blockhash: BlockHash,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+  pub inscription: InscriptionId,
+  pub number: i64,
+  pub genesis: Genesis,
+  pub location: SatPoint,
+  pub postage: u64,
+  /// Oldest first, with `--with-history`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub transfers: Option<Vec<Transfer>>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  /// The last block the export reflects.
+  pub height: u64,
+  /// The first height left in the transfer log, with `--with-history`.
+  /// Transfers before it, if it has been trimmed, are missing.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub transfer_log_start: Option<u64>,
+  pub inscriptions: Vec<Holding>,
+}
+
+impl Export {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+    let inscriptions = index.get_inscriptions_vector(unspent_outputs.clone())?;
+
+    let (transfer_log_start, mut transfer_heights) = if self.with_history {
+      (
+        index.show_transfer_log_stats()?.1,
+        index.get_transfer_heights(
+          &inscriptions
+            .iter()
+            .map(|(_, inscription)| *inscription)
+            .collect(),
+        )?,
+      )
+    } else {
+      (None, HashMap::new())
+    };
+
+    let mut holdings = Vec::new();
+
+    for (location, inscription) in inscriptions {
+      let entry = index
+        .get_inscription_entry(inscription)?
+        .ok_or_else(|| anyhow!("inscription {inscription} not found"))?;
+
+      let transfers = if self.with_history {
+        let mut transfers = Vec::new();
+
+        for height in transfer_heights.remove(&inscription).unwrap_or_default() {
+          transfers.push(Transfer {
+            height,
+            blockhash: index
+              .block_hash(Some(height))?
+              .ok_or_else(|| anyhow!("block {height} not found"))?,
+          });
+        }
+
+        Some(transfers)
+      } else {
+        None
+      };
+
+      holdings.push(Holding {
+        inscription,
+        number: entry.number,
+        genesis: Genesis {
+          height: entry.height,
+          timestamp: entry.timestamp,
+          fee: entry.fee,
+          sat: entry.sat,
+        },
+        location,
+        postage: unspent_outputs[&location.outpoint].to_sat(),
+        transfers,
+      });
+    }
+
+    print_json(Output {
+      height: index
+        .block_height()?
+        .map(|height| height.n())
+        .unwrap_or_default(),
+      transfer_log_start,
+      inscriptions: holdings,
+    })
+  }
+}
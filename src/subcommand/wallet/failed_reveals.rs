@@ -0,0 +1,254 @@
+use super::*;
+
+const FILENAME_PREFIX: &str = "failed-reveals-for-commit-";
+
+#[derive(Debug, Parser)]
+pub(crate) enum FailedReveals {
+  #[clap(about = "List reveals that failed to broadcast")]
+  List,
+  #[clap(about = "Broadcast failed reveals again")]
+  Retry(Retry),
+  #[clap(about = "Write failed reveals to files, one per commit")]
+  Export(Export),
+  #[clap(about = "Read failed reveals from files written by `export` or older versions of ord")]
+  Import(Import),
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Retry {
+  #[clap(long, help = "Only retry the reveals of <COMMIT>.")]
+  commit: Option<Txid>,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Export {
+  #[clap(
+    long,
+    default_value = ".",
+    help = "Write `failed-reveals-for-commit-<TXID>.txt` files to <DIR>."
+  )]
+  dir: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Import {
+  #[clap(
+    required = true,
+    help = "Read `failed-reveals-for-commit-<TXID>.txt` <FILES>, or `.json` output of `ord wallet inscribe --dump`."
+  )]
+  files: Vec<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct FailedCommit {
+  pub commit: Txid,
+  pub reveals: Vec<Txid>,
+  /// Comment lines, like those recording broadcasts with
+  /// `--fallback-broadcast`.
+  pub notes: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetriedCommit {
+  pub commit: Txid,
+  pub rebroadcast: Vec<Txid>,
+  pub failed: Vec<Txid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportOutput {
+  pub files: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ImportOutput {
+  pub commits: Vec<Txid>,
+}
+
+impl FailedReveals {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+
+    match self {
+      Self::List => list(&index),
+      Self::Retry(retry) => retry.run(&options, &index),
+      Self::Export(export) => export.run(&index),
+      Self::Import(import) => import.run(&index),
+    }
+  }
+}
+
+fn list(index: &Index) -> Result {
+  let mut commits = Vec::new();
+
+  for (commit, lines) in index.get_all_failed_reveals()? {
+    let (notes, reveals) = split(&lines);
+
+    commits.push(FailedCommit {
+      commit,
+      reveals: reveals
+        .iter()
+        .map(|reveal| decode(reveal).map(|tx| tx.txid()))
+        .collect::<Result<Vec<Txid>>>()?,
+      notes,
+    });
+  }
+
+  print_json(commits)
+}
+
+impl Retry {
+  fn run(self, options: &Options, index: &Index) -> Result {
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let mut retried = Vec::new();
+
+    for (commit, lines) in index.get_all_failed_reveals()? {
+      if self.commit.is_some() && self.commit != Some(commit) {
+        continue;
+      }
+
+      let (notes, reveals) = split(&lines);
+
+      let mut rebroadcast = Vec::new();
+      let mut failed = Vec::new();
+      let mut remaining = notes;
+
+      for reveal in reveals {
+        let txid = decode(&reveal)?.txid();
+
+        match client.send_raw_transaction(reveal.as_str()) {
+          Ok(_) => {
+            audit::record(options, &client, &hex::decode(&reveal)?);
+            rebroadcast.push(txid);
+          }
+          Err(err) => {
+            eprintln!("warning: failed to rebroadcast reveal {txid}: {err}");
+            failed.push(txid);
+            remaining.push(reveal);
+          }
+        }
+      }
+
+      // notes are only kept while there are reveals left to retry
+      if failed.is_empty() {
+        remaining.clear();
+      }
+
+      index.set_failed_reveals(commit, &remaining)?;
+
+      retried.push(RetriedCommit {
+        commit,
+        rebroadcast,
+        failed,
+      });
+    }
+
+    if let Some(commit) = self.commit {
+      if retried.is_empty() {
+        bail!("no failed reveals recorded for commit {commit}");
+      }
+    }
+
+    print_json(retried)
+  }
+}
+
+impl Export {
+  fn run(self, index: &Index) -> Result {
+    fs::create_dir_all(&self.dir)?;
+
+    let mut files = Vec::new();
+
+    for (commit, lines) in index.get_all_failed_reveals()? {
+      let path = self.dir.join(format!("{FILENAME_PREFIX}{commit}.txt"));
+
+      let mut contents = lines.join("\n");
+      contents.push('\n');
+
+      fs::write(&path, contents)
+        .with_context(|| format!("failed to write failed reveals to {}", path.display()))?;
+
+      files.push(path);
+    }
+
+    print_json(ExportOutput { files })
+  }
+}
+
+impl Import {
+  fn run(self, index: &Index) -> Result {
+    let mut commits = Vec::new();
+
+    for path in self.files {
+      if path.extension() == Some("json".as_ref()) {
+        for dump in dump::load(&path)? {
+          let commit = decode(dump.commit())?.txid();
+
+          let mut lines = index.get_failed_reveals(commit)?.unwrap_or_default();
+
+          for reveal in dump.reveals() {
+            if !lines.contains(&reveal) {
+              decode(&reveal).with_context(|| format!("invalid reveal in {}", path.display()))?;
+              lines.push(reveal);
+            }
+          }
+
+          index.set_failed_reveals(commit, &lines)?;
+
+          commits.push(commit);
+        }
+
+        continue;
+      }
+
+      let commit = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix(FILENAME_PREFIX))
+        .and_then(|name| name.strip_suffix(".txt"))
+        .and_then(|txid| txid.parse::<Txid>().ok())
+        .ok_or_else(|| {
+          anyhow!(
+            "{} is not named {FILENAME_PREFIX}<TXID>.txt",
+            path.display()
+          )
+        })?;
+
+      let contents =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+
+      let mut lines = index.get_failed_reveals(commit)?.unwrap_or_default();
+
+      for line in contents.lines().map(str::trim) {
+        if line.is_empty() || lines.iter().any(|existing| existing == line) {
+          continue;
+        }
+
+        if !line.starts_with('#') {
+          decode(line).with_context(|| format!("invalid reveal in {}", path.display()))?;
+        }
+
+        lines.push(line.into());
+      }
+
+      index.set_failed_reveals(commit, &lines)?;
+
+      commits.push(commit);
+    }
+
+    print_json(ImportOutput { commits })
+  }
+}
+
+/// Split recorded lines into `#` notes and signed reveals.
+fn split(lines: &[String]) -> (Vec<String>, Vec<String>) {
+  lines
+    .iter()
+    .cloned()
+    .partition(|line| line.starts_with('#'))
+}
+
+fn decode(reveal: &str) -> Result<Transaction> {
+  Ok(consensus::deserialize(&hex::decode(reveal)?)?)
+}
@@ -0,0 +1,122 @@
+use {super::*, crate::wallet::Wallet, std::collections::BTreeSet};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Freeze {
+  #[clap(
+    required = true,
+    help = "Freeze <OUTPUT>, given as an outpoint or the ID of an inscription it contains."
+  )]
+  outputs: Vec<Object>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub frozen: Vec<OutPoint>,
+}
+
+impl Freeze {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    let outputs = resolve_outputs(&index, &self.outputs)?;
+
+    for output in &outputs {
+      if !unspent_outputs.contains_key(output) {
+        bail!("output {output} is not in wallet");
+      }
+    }
+
+    let mut frozen = load_frozen_outputs(&options)?;
+
+    lock_unspent(&client, false, &outputs)?;
+
+    frozen.extend(outputs);
+
+    save_frozen_outputs(&options, &frozen)?;
+
+    print_json(Output {
+      frozen: frozen.into_iter().collect(),
+    })?;
+
+    Ok(())
+  }
+}
+
+pub(super) fn resolve_outputs(index: &Index, objects: &[Object]) -> Result<Vec<OutPoint>> {
+  objects
+    .iter()
+    .map(|object| match object {
+      Object::OutPoint(outpoint) => Ok(*outpoint),
+      Object::InscriptionId(id) => Ok(
+        index
+          .get_inscription_satpoint_by_id(*id)?
+          .ok_or_else(|| anyhow!("inscription {id} not found"))?
+          .outpoint,
+      ),
+      _ => Err(anyhow!("{object} is not an outpoint or an inscription ID")),
+    })
+    .collect()
+}
+
+/// Frozen outputs are also locked in Bitcoin Core, persistently, so that its
+/// own coin selection leaves them alone too. Outputs that are already in the
+/// requested state are skipped, since Bitcoin Core rejects them.
+pub(super) fn lock_unspent(client: &Client, unlock: bool, outputs: &[OutPoint]) -> Result {
+  let locked = get_locked_outputs(client)?;
+
+  let outputs = outputs
+    .iter()
+    .filter(|outpoint| locked.contains(outpoint) == unlock)
+    .map(|outpoint| serde_json::json!({ "txid": outpoint.txid, "vout": outpoint.vout }))
+    .collect::<Vec<serde_json::Value>>();
+
+  if outputs.is_empty() {
+    return Ok(());
+  }
+
+  if !client.call::<bool>("lockunspent", &[unlock.into(), outputs.into(), true.into()])? {
+    bail!(
+      "failed to {} outputs in Bitcoin Core",
+      if unlock { "unlock" } else { "lock" }
+    );
+  }
+
+  Ok(())
+}
+
+fn frozen_outputs_path(options: &Options) -> Result<PathBuf> {
+  Ok(
+    options
+      .data_dir()?
+      .join(format!("frozen-{}.json", options.wallet)),
+  )
+}
+
+/// Outputs frozen with `ord wallet freeze`, which ord never spends unless they
+/// are explicitly passed with `--utxo`.
+pub(crate) fn load_frozen_outputs(options: &Options) -> Result<BTreeSet<OutPoint>> {
+  let path = frozen_outputs_path(options)?;
+
+  if !path.exists() {
+    return Ok(BTreeSet::new());
+  }
+
+  serde_json::from_reader(File::open(&path)?)
+    .with_context(|| format!("failed to parse frozen outputs file {}", path.display()))
+}
+
+pub(super) fn save_frozen_outputs(options: &Options, frozen: &BTreeSet<OutPoint>) -> Result {
+  let path = frozen_outputs_path(options)?;
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  fs::write(&path, serde_json::to_string_pretty(frozen)?)
+    .with_context(|| format!("failed to write frozen outputs file {}", path.display()))
+}
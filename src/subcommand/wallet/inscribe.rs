@@ -1,8 +1,11 @@
 use {
+  super::simulation::{self, Simulation},
   super::*,
-  crate::wallet::Wallet,
+  crate::{inscription::Tag, wallet::Wallet},
   bitcoin::{
+    bip32::{ChildNumber, DerivationPath, ExtendedPrivKey},
     blockdata::{opcodes, script},
+    hashes::sha256,
     key::PrivateKey,
     key::{TapTweak, TweakedKeyPair, TweakedPublicKey, UntweakedKeyPair},
     locktime::absolute::LockTime,
@@ -17,6 +20,9 @@ use {
   bitcoincore_rpc::bitcoincore_rpc_json::{ImportDescriptors, SignRawTransactionInput, Timestamp},
   bitcoincore_rpc::Client,
   bitcoincore_rpc::RawTx,
+  clap::ValueEnum,
+  is_terminal::IsTerminal,
+  miniscript::descriptor::{Descriptor, DescriptorPublicKey, DescriptorSecretKey},
   std::collections::BTreeSet,
   std::fs::File,
   std::io::Write,
@@ -29,41 +35,235 @@ pub struct DecodeRawTransactionOutput {
   pub weight: bitcoin::Weight,
 }
 
+/// Printed instead of broadcasting when the wallet is watch-only, or when the
+/// commit tx is funded with `--funding-psbt`. The commit PSBT must be signed
+/// elsewhere and broadcast before the reveals, which are already signed, and
+/// the recovery descriptors must be backed up, since they aren't imported
+/// into the wallet.
 #[derive(Serialize)]
-struct OutputDump {
+struct WatchOnlyOutput {
+  batch: usize,
   satpoint: SatPoint,
   inscriptions: Vec<InscriptionId>,
-  filenames: Vec<PathBuf>,
-  commit: String,
-  commit_weight: bitcoin::Weight,
+  commit_psbt: String,
   reveals: Vec<String>,
-  reveal_weights: Vec<bitcoin::Weight>,
   recovery_descriptors: Vec<String>,
   fees: u64,
+  dust_to_fee: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  destination_descriptors: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+struct PreflightOutput {
+  txid: Txid,
+  allowed: bool,
+  reject_reason: Option<String>,
+  /// Why the package as a whole was rejected, in which case its txs weren't
+  /// checked individually.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  package_error: Option<String>,
+}
+
+/// A `testmempoolaccept` result. Bitcoin Core leaves out `allowed` when it
+/// rejects the whole package, for example if it's too large, and gives the
+/// reason in `package-error` instead.
+#[derive(Deserialize)]
+struct TestMempoolAcceptResult {
+  txid: Txid,
+  allowed: Option<bool>,
+  #[serde(rename = "reject-reason")]
+  reject_reason: Option<String>,
+  #[serde(rename = "package-error")]
+  package_error: Option<String>,
 }
 
 #[derive(Serialize)]
 struct Output {
+  batch: usize,
   satpoint: SatPoint,
   inscriptions: Vec<InscriptionId>,
   commit: Txid,
   reveals: Vec<Txid>,
   fees: u64,
+  dust_to_fee: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  sat_flow: Option<Vec<sat_flow::SatFlow>>,
+  /// The descriptor of each inscription's output, with `--destination-descriptor`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  destination_descriptors: Option<Vec<String>>,
+  /// How many inscriptions each `--csv` destination receives, with `--dry-run`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  destination_counts: Option<BTreeMap<String, u64>>,
+}
+
+/// The order of files enumerated from `--dir` and glob patterns. Ties are
+/// broken by name, so that the order is always the same.
+#[derive(Default, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub(crate) enum FileOrder {
+  #[default]
+  Name,
+  Size,
+  Mtime,
+}
+
+/// Label of the recovery descriptors imported into the wallet, which marks
+/// the outputs of commit transactions.
+pub(crate) const RECOVERY_KEY_LABEL: &str = "commit tx recovery key";
+
+/// The internal key of commit outputs with `--no-key-path-recovery`, BIP 341's
+/// point with no known discrete logarithm, so that there is no key path.
+fn nums_internal_key() -> XOnlyPublicKey {
+  "50929b74c1a04954b78b4b6035e97a5e078a5a0f28ec96d547bfee9ace803ac0"
+    .parse()
+    .unwrap()
+}
+
+/// A line of a `--csv` file, `destination,file[,postage[,content_type]]`.
+struct CsvRow {
+  destination: ScriptBuf,
+  file: PathBuf,
+  inscription: Inscription,
+  postage: Option<Amount>,
+}
+
+/// A sat to inscribe, given to `--sat` as a sat or a rarity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SatTarget {
+  Sat(Sat),
+  /// The first sat of this rarity in the wallet.
+  Rarity(Rarity),
+}
+
+impl FromStr for SatTarget {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    // rarities would otherwise be parsed as sat names
+    if let Ok(rarity) = s.parse::<Rarity>() {
+      return Ok(Self::Rarity(rarity));
+    }
+
+    Ok(Self::Sat(s.parse()?))
+  }
+}
+
+/// Sats from `start` up to but not including `end`, given to
+/// `--include-sat-range` as `<START>-<END>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SatRange {
+  start: Sat,
+  end: Sat,
+}
+
+impl SatRange {
+  fn size(self) -> u64 {
+    self.end.n() - self.start.n()
+  }
+}
+
+impl FromStr for SatRange {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (start, end) = s
+      .split_once('-')
+      .ok_or_else(|| anyhow!("sat range `{s}` is not of the form `<START>-<END>`"))?;
+
+    let range = Self {
+      start: start.parse()?,
+      end: end.parse()?,
+    };
+
+    if range.start >= range.end {
+      bail!("sat range `{s}` is empty");
+    }
+
+    Ok(range)
+  }
+}
+
+/// The options for one batch of inscriptions, which shares a commit tx.
+struct Batch {
+  /// The sat to inscribe, or else the first cardinal in `utxos`.
+  satpoint: Option<SatPoint>,
+  /// The inscriptions to make, one per reveal tx.
+  inscription: Vec<Inscription>,
+  /// The wallet's inscriptions, which the commit tx must not spend.
+  inscriptions: BTreeMap<SatPoint, InscriptionId>,
+  network: Network,
+  utxos: BTreeMap<OutPoint, Amount>,
+  /// Change addresses for the commit tx.
+  change: [Address; 2],
+  /// Reveal output scripts, either one for all inscriptions or one for each.
+  destinations: Vec<ScriptBuf>,
+  alignment: Option<Address>,
+  cursed_destination: Option<Address>,
+  /// With `--cursed`, cardinals for the reveal txs to spend first.
+  cursed_inputs: Option<Vec<(OutPoint, TxOut)>>,
+  cursed_split_address: Option<Address>,
+  commit_fee_rate: FeeRate,
+  reveal_fee_rates: Vec<FeeRate>,
+  max_inputs: Option<usize>,
+  no_limit: bool,
+  postages: Vec<Amount>,
+  tags: Vec<Tag>,
+  reveal_offset: u32,
+  no_signature: bool,
+  allow_reinscribe: bool,
+  ignore_utxo_inscriptions: bool,
+  single_key: bool,
+  allow_reveal_rbf: bool,
+  op_return: Option<ScriptBuf>,
+  lock_time: LockTime,
+  sequence: Sequence,
+  anchor: Option<Amount>,
+  reveal_xprv: Option<ExtendedPrivKey>,
+  change_position: Option<usize>,
+  change_split: Vec<Address>,
+  coin_selection: CoinSelection,
+  confirmations: BTreeMap<OutPoint, u32>,
+  satisfactions: BTreeMap<OutPoint, Satisfaction>,
+  dust_policy: DustPolicy,
+  no_key_path_recovery: bool,
+  /// With `--include-sat-range`, inputs the commit tx spends right after the
+  /// inscribed sat's.
+  sat_range_inputs: Vec<OutPoint>,
 }
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
 pub(crate) struct Inscribe {
   #[clap(long, help = "Inscribe <SATPOINT>")]
   pub(crate) satpoint: Option<SatPoint>,
+  #[clap(
+    long,
+    help = "Inscribe <SAT>, given as a number, name, or degree, or the first sat in the wallet of rarity <SAT>. Any sats before it in its output go to an alignment output."
+  )]
+  pub(crate) sat: Option<SatTarget>,
+  #[clap(
+    long,
+    help = "Inscribe the first sat of <INCLUDE_SAT_RANGE>, given as `<START>-<END>`, with the sats from <START> up to but not including <END> as its postage. The range may span several wallet outputs, as long as they hold it in order."
+  )]
+  pub(crate) include_sat_range: Option<SatRange>,
   #[clap(
     long,
     help = "Consider spending outpoint <UTXO>, even if it is unconfirmed or contains inscriptions"
   )]
   pub(crate) utxo: Vec<OutPoint>,
+  #[clap(
+    long,
+    help = "Don't spend outpoint <AVOID_UTXO>, even if it is cardinal."
+  )]
+  pub(crate) avoid_utxo: Vec<OutPoint>,
   #[clap(long, help = "Curse inscriptions by inscribing on the 2nd input")]
   pub(crate) cursed: bool,
   #[clap(long, help = "Only spend outpoints given with --utxo")]
   pub(crate) coin_control: bool,
+  #[clap(
+    long,
+    help = "Fund the commit tx with the inputs of <FUNDING_PSBT>, a base64 PSBT from someone else, instead of with the wallet's utxos. The commit tx is printed as a PSBT for both parties to sign instead of being broadcast. Use --change to return the funder's change."
+  )]
+  pub(crate) funding_psbt: Option<String>,
   #[clap(long, help = "Use fee rate of <FEE_RATE> sats/vB")]
   pub(crate) fee_rate: FeeRate,
   #[clap(
@@ -71,8 +271,39 @@ pub(crate) struct Inscribe {
     help = "Use <COMMIT_FEE_RATE> sats/vbyte for commit transaction.\nDefaults to <FEE_RATE> if unset."
   )]
   pub(crate) commit_fee_rate: Option<FeeRate>,
-  #[clap(help = "Inscribe sat with contents of <FILE>")]
+  #[clap(
+    long,
+    help = "Use <REVEAL_FEE_RATE> sats/vbyte for reveal transactions.\nDefaults to <FEE_RATE> if unset."
+  )]
+  pub(crate) reveal_fee_rate: Option<FeeRate>,
+  #[clap(
+    long,
+    help = "Pay <FEE_LADDER>, given as <START>:<STEP>, sats/vbyte for reveal transactions, with the last paying <START> and each earlier one paying <STEP> more, so that earlier inscriptions confirm first."
+  )]
+  pub(crate) fee_ladder: Option<FeeLadder>,
+  #[clap(
+    help = "Inscribe sat with contents of <FILE>. Glob patterns in file names, like `collection/*.png`, are expanded by ord, so they can be quoted to avoid argument length limits."
+  )]
   pub(crate) files: Vec<PathBuf>,
+  #[clap(long, help = "Inscribe the contents of every file in <DIR>.")]
+  pub(crate) dir: Option<PathBuf>,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "name",
+    help = "Inscribe files from --dir and glob patterns in <SORT> order."
+  )]
+  pub(crate) sort: FileOrder,
+  #[clap(
+    long,
+    help = "Skip files before <START_INDEX>, counting from zero, after expanding --dir and glob patterns."
+  )]
+  pub(crate) start_index: Option<usize>,
+  #[clap(
+    long,
+    help = "Skip files from <END_INDEX> on, counting from zero, after expanding --dir and glob patterns."
+  )]
+  pub(crate) end_index: Option<usize>,
   #[clap(long, help = "Do not back up recovery key.")]
   pub(crate) no_backup: bool,
   #[clap(long, help = "Do not broadcast any transactions. Implies --dump.")]
@@ -94,8 +325,21 @@ pub(crate) struct Inscribe {
     help = "Dump raw hex transactions and recovery keys to standard output."
   )]
   pub(crate) dump: bool,
-  #[clap(long, help = "Send inscription to <DESTINATION>.")]
-  pub(crate) destination: Vec<Address<NetworkUnchecked>>,
+  #[clap(
+    long,
+    help = "Send inscription to <DESTINATION>, given as an address or as @LABEL."
+  )]
+  pub(crate) destination: Vec<label::Destination>,
+  #[clap(
+    long,
+    help = "Send inscription to the raw scriptPubKey <DESTINATION_SCRIPT>, given in hex, instead of to an address."
+  )]
+  pub(crate) destination_script: Option<String>,
+  #[clap(
+    long,
+    help = "Send inscriptions to outputs described by <DESTINATION_DESCRIPTOR>, for example a multisig or timelocked script. If it has a wildcard, the nth inscription is sent to derivation index n. The descriptors used are printed, since they are needed to spend the inscriptions."
+  )]
+  pub(crate) destination_descriptor: Option<String>,
   #[clap(long, help = "Send any alignment output to <ALIGNMENT>.")]
   pub(crate) alignment: Option<Address<NetworkUnchecked>>,
   #[clap(long, help = "Send any change output to <CHANGE>.")]
@@ -107,9 +351,9 @@ pub(crate) struct Inscribe {
   pub(crate) cursed_destination: Option<Address<NetworkUnchecked>>,
   #[clap(
     long,
-    help = "Use <CURSED_UTXO> as the first input of any cursed reveal tx."
+    help = "Use <CURSED_UTXO> as the first input of a cursed reveal tx. May be given once per inscription."
   )]
-  pub(crate) cursed_utxo: Option<OutPoint>,
+  pub(crate) cursed_utxo: Vec<OutPoint>,
   #[clap(
     long,
     help = "Amount of postage to include in the inscription. Default `10000 sats`"
@@ -122,7 +366,7 @@ pub(crate) struct Inscribe {
   pub(crate) max_inputs: Option<usize>,
   #[clap(
     long,
-    help = "Location of a CSV file to use for a combination of DESTINATION and FILE NAMES.  Should be structured `destination,file`."
+    help = "Location of a CSV file to use for a combination of DESTINATION and FILE NAMES.  Should be structured `destination,file[,postage[,content_type]]`, where empty postage and content type columns use the defaults."
   )]
   pub(crate) csv: Option<PathBuf>,
   #[clap(
@@ -130,7 +374,36 @@ pub(crate) struct Inscribe {
     help = "Create a 'cursed' inscription (with an unknown even OP_66 tag)"
   )]
   pub(crate) cursed66: bool,
-  #[clap(long, help = "Don't sign the reveal tx. This lowers the miner fees but allows anyone to steal your inscription.")]
+  #[clap(
+    long,
+    help = "Add tag <N> with the hex value <HEX> to the inscription envelope, given as `<N>=<HEX>`. May be given more than once. Even tags require --allow-even-tags."
+  )]
+  pub(crate) tag: Vec<Tag>,
+  #[clap(
+    long,
+    help = "Allow even --tag numbers. Ord does not recognize inscriptions with unknown even tags, so they will be cursed or lost."
+  )]
+  pub(crate) allow_even_tags: bool,
+  #[clap(
+    long,
+    help = "Use the cheapest envelope for each inscription, leaving out the content type if it is the default. Run `ord optimize` to see what this saves."
+  )]
+  pub(crate) optimize: bool,
+  #[clap(
+    long,
+    requires = "optimize",
+    help = "Brotli compress inscription content, if that makes the envelope smaller."
+  )]
+  pub(crate) compress: bool,
+  #[clap(
+    long,
+    help = "Precede each inscription's envelope in its reveal input with <REVEAL_OFFSET> empty envelopes, so that it is cursed for not being at offset zero. Its id will end in i<REVEAL_OFFSET>."
+  )]
+  pub(crate) reveal_offset: Option<u32>,
+  #[clap(
+    long,
+    help = "Don't sign the reveal tx. This lowers the miner fees but allows anyone to steal your inscription."
+  )]
   pub(crate) no_signature: bool,
   #[clap(long, help = "Allow inscription on sats that are already inscribed.")]
   pub(crate) allow_reinscribe: bool,
@@ -138,6 +411,11 @@ pub(crate) struct Inscribe {
   pub(crate) ignore_utxo_inscriptions: bool,
   #[clap(long, help = "Use the same recovery key for all inscriptions.")]
   pub(crate) single_key: bool,
+  #[clap(
+    long,
+    help = "Build commit outputs with an unspendable internal key, so that they can only be spent by their reveal txs. No recovery keys are created, so a leaked wallet backup can't divert a pending reveal, but nor can a stuck commit output be recovered."
+  )]
+  pub(crate) no_key_path_recovery: bool,
   #[clap(
     long,
     help = "Use sighash type SinglePlusAnyoneCanPay to allow reveal txs to be RBF'ed."
@@ -148,490 +426,2250 @@ pub(crate) struct Inscribe {
     help = "Don't include fees in reveal txs, just the postage. Implies --no-broadcast and --allow-reveal-rbf."
   )]
   pub(crate) unfunded_reveal: bool,
+  #[clap(
+    long,
+    help = "Add an OP_RETURN output containing <OP_RETURN> to each reveal tx. Parsed as hex if possible, otherwise used as text."
+  )]
+  pub(crate) op_return: Option<String>,
+  #[clap(
+    long,
+    help = "Set the lock time of reveal txs to <LOCKTIME>. Values below 500000000 are block heights, others are unix timestamps."
+  )]
+  pub(crate) locktime: Option<u32>,
+  #[clap(
+    long,
+    help = "Set the sequence number of all commit and reveal tx inputs to <SEQUENCE>. Defaults to 4294967293, which signals RBF."
+  )]
+  pub(crate) sequence: Option<u32>,
+  #[clap(
+    long,
+    help = "Add a pay-to-anchor output to each reveal tx, so it can be fee bumped with `ord wallet bump-fee`."
+  )]
+  pub(crate) anchor: bool,
+  #[clap(
+    long,
+    help = "Amount of postage to put in the anchor output. Defaults to the anchor's dust value of 240 sats."
+  )]
+  pub(crate) anchor_value: Option<Amount>,
+  #[clap(
+    long,
+    help = "Broadcast the commit tx and the first reveal tx together as a package using `submitpackage`, which requires Bitcoin Core 25.0.0 or newer. This allows the commit tx fee rate to be below the minimum relay fee rate. Falls back to broadcasting them separately if `submitpackage` is unavailable."
+  )]
+  pub(crate) package_broadcast: bool,
+  #[clap(
+    long,
+    help = "If Bitcoin Core won't relay a transaction, POST it as hex to <FALLBACK_BROADCAST>, like `https://mempool.space/api/tx`."
+  )]
+  pub(crate) fallback_broadcast: Option<String>,
+  #[clap(
+    long,
+    help = "Check that Bitcoin Core would accept the commit tx and reveal txs with `testmempoolaccept` before broadcasting them, and print why any would be rejected instead of broadcasting."
+  )]
+  pub(crate) preflight: bool,
+  #[clap(
+    long,
+    help = "Derive reveal keys from the wallet's seed instead of generating random ones, so that inscriptions can be recovered with only the seed."
+  )]
+  pub(crate) derive_keys: bool,
+  #[clap(
+    long,
+    help = "UNSAFE, FOR TESTING ONLY: derive reveal keys from <RNG_SEED>, given in hex, so that the same inputs build the same commit and reveal transactions. Anyone who knows the seed can spend the commit outputs."
+  )]
+  pub(crate) rng_seed: Option<String>,
+  #[clap(
+    long,
+    help = "Fail instead of warning if the change or alignment address is also the destination of an inscription."
+  )]
+  pub(crate) no_address_reuse: bool,
+  #[clap(
+    long,
+    help = "Move the commit tx change output to output index <CHANGE_POSITION>."
+  )]
+  pub(crate) change_position: Option<usize>,
+  #[clap(
+    long,
+    help = "Split the commit tx change into <CHANGE_SPLIT> outputs of equal value, so that later inscriptions can spend them in parallel."
+  )]
+  pub(crate) change_split: Option<usize>,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "closest",
+    help = "Select cardinal utxos to fund the commit tx with <COIN_SELECTION>."
+  )]
+  pub(crate) coin_selection: CoinSelection,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "alignment",
+    help = "When commit tx change is too small for a change output, leave it to the fee, leave it in the last reveal's postage, or fail, according to <DUST_POLICY>. The sats left to the fee are reported as `dust_to_fee`."
+  )]
+  pub(crate) dust_policy: DustPolicy,
+  #[clap(long, help = "Do not fund the commit tx with unconfirmed utxos.")]
+  pub(crate) avoid_unconfirmed: bool,
+  #[clap(
+    long,
+    help = "Only fund the commit tx with utxos that have at least <MIN_CONFIRMATIONS> confirmations."
+  )]
+  pub(crate) min_confirmations: Option<u32>,
+  #[clap(
+    long,
+    help = "Don't sign or broadcast, but record the commit and reveal txs in the wallet's simulated state, which later --simulate commands build on. Delete `simulation-<WALLET>.json` in the data dir to start over."
+  )]
+  pub(crate) simulate: bool,
+  #[clap(
+    long,
+    help = "Report where each range of sats spent by the commit and reveal txs ends up, in an output, in change, or in fees. Requires an index created with `--index-sats`."
+  )]
+  pub(crate) sat_flow: bool,
+  #[clap(
+    long,
+    help = "Don't spend utxos containing sats of rarity <PROTECT_RARITY> or rarer on fees or change. Requires an index created with `--index-sats`."
+  )]
+  pub(crate) protect_rarity: Option<Rarity>,
+  #[clap(
+    long,
+    help = "Allow spending <ALLOW_RARE_UTXO> on fees or change, even though it contains sats protected by --protect-rarity."
+  )]
+  pub(crate) allow_rare_utxo: Vec<OutPoint>,
+  #[clap(
+    long,
+    help = "Don't ask for confirmation before signing, and inscribe even if --csv destinations are repeated or are legacy addresses."
+  )]
+  pub(crate) yes: bool,
+  #[clap(
+    long,
+    help = "Sign everything, but wait to broadcast until Bitcoin Core estimates a fee rate below <BROADCAST_WHEN_FEE_BELOW> sats/vB for the next block."
+  )]
+  pub(crate) broadcast_when_fee_below: Option<FeeRate>,
+  #[clap(
+    long,
+    help = "Give up waiting for --broadcast-when-fee-below after <TIMEOUT> seconds."
+  )]
+  pub(crate) timeout: Option<u64>,
+  #[clap(
+    long,
+    help = "Allow inscribing to P2PKH and P2SH destinations, on which many wallets and marketplaces don't track inscriptions."
+  )]
+  pub(crate) allow_legacy_destination: bool,
 }
 
 impl Inscribe {
-  pub(crate) fn run(self, options: Options) -> Result {
-    let mut dump = self.dump;
-    let mut no_broadcast = self.no_broadcast;
-    let mut allow_reveal_rbf = self.allow_reveal_rbf;
+  const MAX_OP_RETURN_DATA_SIZE: usize = 80;
+  const MEMPOOL_CHAIN_LIMIT: u64 = 25;
 
-    if self.unfunded_reveal {
-      no_broadcast = true;
-      allow_reveal_rbf = true;
+  fn tags(&self) -> Result<Vec<Tag>> {
+    let mut tags = Vec::new();
+
+    if self.cursed66 {
+      tags.push(Tag::cursed66());
     }
 
-    if no_broadcast {
-      dump = true;
+    for tag in &self.tag {
+      if tags.iter().any(|other: &Tag| other.number == tag.number) {
+        bail!("tag {} given more than once", tag.number);
+      }
+
+      if tag.is_even() && !self.allow_even_tags {
+        bail!(
+          "tag {} is even, so ord will not recognize the inscription, use --allow-even-tags to add it anyway",
+          tag.number
+        );
+      }
+
+      tags.push(tag.clone());
     }
 
-    let mut inscription = Vec::new();
-    let mut filenames = Vec::new();
-    let mut destinations = Vec::new();
+    Ok(tags)
+  }
 
-    let mut client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+  /// The files to inscribe, with `--dir` and glob patterns expanded, sliced
+  /// by `--start-index` and `--end-index`.
+  fn files(&self) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
 
-    if let Some(csv) = self.csv {
+    if let Some(dir) = &self.dir {
       if !self.files.is_empty() {
-        return Err(anyhow!("Cannot use both --csv and provide files"));
-      } else if !self.destination.is_empty() {
-        return Err(anyhow!("Cannot use both --csv and --destination"));
+        bail!("Cannot use both --dir and provide files");
       }
 
-      let file = File::open(&csv)?;
-      let reader = BufReader::new(file);
-      let mut line_number = 1;
-      for line in reader.lines() {
-        let line = line?;
-        let mut split = line.trim_start_matches('\u{feff}').split(',');
-        let destination = split.next().ok_or_else(|| {
-          anyhow!(
-            "Destination CSV file '{}' is not formatted correctly",
-            csv.display()
-          )
-        })?;
-        let file = split.next().ok_or_else(|| {
-          anyhow!(
-            "Destination CSV file '{}' is not formatted correctly - no comma on line {line_number}",
-            csv.display()
-          )
-        })?;
+      files = Self::list_files(dir, None, self.sort)?;
+    }
 
-        let file = PathBuf::from(file);
-        filenames.push(file.clone());
+    for file in &self.files {
+      let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
 
-        let i = Inscription::from_file(options.chain(), &file);
-        if i.is_ok() {
-          inscription.push(i?);
-        } else {
-          i.context(format!(
-            "Error with file '{}' in CSV file {} line {line_number}",
-            file.display(),
-            csv.display()
-          ))?;
-        }
+      if file.exists() || !name.contains(['*', '?', '[']) {
+        files.push(file.clone());
+        continue;
+      }
+
+      let matches = Self::list_files(
+        file.parent().unwrap_or(Path::new("")),
+        Some(&Self::glob_regex(&name)?),
+        self.sort,
+      )?;
 
-        let address = Address::from_str(destination)?;
-        destinations.push(address.require_network(options.chain().network())?);
-        line_number += 1;
+      if matches.is_empty() {
+        bail!("no files match {}", file.display());
       }
+
+      files.extend(matches);
+    }
+
+    let start = self.start_index.unwrap_or_default();
+    let end = self.end_index.unwrap_or(files.len());
+
+    if start > end || end > files.len() {
+      bail!(
+        "--start-index {start} and --end-index {end} are out of range for {} files",
+        files.len()
+      );
+    }
+
+    files.truncate(end);
+    files.drain(..start);
+
+    Ok(files)
+  }
+
+  /// Files in `dir` whose names match `pattern`, in `order`. Hidden files are
+  /// skipped, like shells do, unless the pattern starts with a dot.
+  fn list_files(dir: &Path, pattern: Option<&Regex>, order: FileOrder) -> Result<Vec<PathBuf>> {
+    let hidden = pattern.map_or(false, |pattern| pattern.as_str().starts_with("^\\."));
+
+    let mut files = Vec::new();
+
+    let read_dir = if dir.as_os_str().is_empty() {
+      Path::new(".")
     } else {
-      for file in self.files.iter() {
-        tprintln!("[open files]");
-        inscription.push(Inscription::from_file(options.chain(), file)?);
-        filenames.push(PathBuf::from(file));
+      dir
+    };
+
+    for entry in fs::read_dir(read_dir)
+      .with_context(|| format!("failed to read directory {}", read_dir.display()))?
+    {
+      let entry = entry?;
+      let name = entry.file_name();
+      let name = name.to_string_lossy();
+
+      if name.starts_with('.') && !hidden {
+        continue;
       }
-      if self.destination.is_empty() {
-        tprintln!("[get destination addresses]");
-        for (i, _) in self.files.iter().enumerate() {
-          destinations.push(get_change_address(&client, &options)?);
-          if (i + 1) % 100 == 0 {
-            tprintln!("  [{}]", i + 1);
+
+      if pattern.map_or(false, |pattern| !pattern.is_match(&name)) {
+        continue;
+      }
+
+      let metadata = fs::metadata(entry.path())?;
+
+      if !metadata.is_file() {
+        continue;
+      }
+
+      files.push((
+        dir.join(entry.file_name()),
+        metadata.len(),
+        metadata.modified()?,
+      ));
+    }
+
+    match order {
+      FileOrder::Name => files.sort_by(|a, b| a.0.cmp(&b.0)),
+      FileOrder::Size => files.sort_by(|a, b| (a.1, &a.0).cmp(&(b.1, &b.0))),
+      FileOrder::Mtime => files.sort_by(|a, b| (a.2, &a.0).cmp(&(b.2, &b.0))),
+    }
+
+    Ok(files.into_iter().map(|(path, _, _)| path).collect())
+  }
+
+  /// Translate a glob pattern for file names, with `*`, `?` and `[...]`, into
+  /// a regex.
+  fn glob_regex(glob: &str) -> Result<Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars();
+
+    while let Some(c) = chars.next() {
+      match c {
+        '*' => pattern.push_str(".*"),
+        '?' => pattern.push('.'),
+        '[' => {
+          pattern.push('[');
+          for (i, c) in chars.by_ref().enumerate() {
+            match c {
+              ']' => break,
+              '!' if i == 0 => pattern.push('^'),
+              '\\' | '[' | '^' => {
+                pattern.push('\\');
+                pattern.push(c);
+              }
+              c => pattern.push(c),
+            }
           }
+          pattern.push(']');
         }
-      } else {
-        for destination in self.destination {
-          destinations.push(destination.require_network(options.chain().network())?);
+        c => pattern.push_str(&regex::escape(&c.to_string())),
+      }
+    }
+
+    pattern.push('$');
+
+    Regex::new(&pattern).with_context(|| format!("invalid glob pattern `{glob}`"))
+  }
+
+  /// Every line of a `--csv` file is checked before any wallet work is done,
+  /// and all problems are reported at once, so that large files can be fixed
+  /// in one go.
+  fn parse_csv(
+    csv: &Path,
+    chain: Chain,
+    max_content_size: Option<usize>,
+    index: &Index,
+    no_limit: bool,
+  ) -> Result<Vec<CsvRow>> {
+    let reader = BufReader::new(
+      File::open(csv).with_context(|| format!("failed to open CSV file {}", csv.display()))?,
+    );
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    let mut files = BTreeMap::new();
+
+    for (i, line) in reader.lines().enumerate() {
+      let line_number = i + 1;
+
+      match Self::parse_csv_line(&line?, chain, max_content_size, index, no_limit) {
+        Ok(row) => {
+          if let Some(first) = files.get(&row.file) {
+            errors.push(format!(
+              "line {line_number}: file '{}' is also on line {first}",
+              row.file.display()
+            ));
+          } else {
+            files.insert(row.file.clone(), line_number);
+          }
+          rows.push(row);
         }
+        Err(error) => errors.push(format!("line {line_number}: {error:#}")),
       }
     }
 
-    if inscription.is_empty() {
-      return Err(anyhow!("Provide at least one file to inscribe"));
+    if !errors.is_empty() {
+      bail!(
+        "Destination CSV file '{}' is not valid:\n{}",
+        csv.display(),
+        errors.join("\n")
+      );
     }
 
-    if self.cursed && inscription.len() != 1 {
-      return Err(anyhow!(
-        "Currently --cursed only works on one inscription at a time"
-      ));
-    }
+    Ok(rows)
+  }
+
+  /// Warn about `--csv` destinations that receive more than one inscription,
+  /// which is often a copy and paste mistake, or that are legacy addresses,
+  /// and count the inscriptions each destination receives. Unless `proceed`,
+  /// any warning is an error.
+  fn check_csv_destinations(
+    destinations: &[ScriptBuf],
+    network: Network,
+    proceed: bool,
+  ) -> Result<BTreeMap<String, u64>> {
+    let mut scripts = BTreeMap::<&ScriptBuf, u64>::new();
+
+    for destination in destinations {
+      *scripts.entry(destination).or_default() += 1;
+    }
+
+    let mut counts = BTreeMap::new();
+    let mut warned = false;
+
+    for (script, count) in scripts {
+      let destination = Address::from_script(script, network)
+        .map(|address| address.to_string())
+        .unwrap_or_else(|_| hex::encode(script.as_bytes()));
+
+      if count > 1 {
+        eprintln!("warning: destination {destination} receives {count} inscriptions");
+        warned = true;
+      }
+
+      if script.is_p2pkh() || script.is_p2sh() {
+        eprintln!("warning: destination {destination} is a legacy address");
+        warned = true;
+      }
+
+      counts.insert(destination, count);
+    }
+
+    if warned && !proceed {
+      bail!("--csv destinations have warnings, pass --yes to inscribe anyway");
+    }
+
+    Ok(counts)
+  }
+
+  /// Witness bytes weigh one weight unit each, so a file whose envelope is
+  /// heavier than a standard tx can never be revealed. Checked as each file
+  /// is read, so that a large batch fails before any transaction is built.
+  fn check_reveal_weight(inscription: &Inscription, file: &Path) -> Result {
+    let envelope_size = inscription.envelope_size();
+
+    if envelope_size > MAX_STANDARD_TX_WEIGHT as usize {
+      bail!(
+        "file '{}' is too large for a standard reveal transaction, its envelope weighs {envelope_size} weight units, more than {MAX_STANDARD_TX_WEIGHT} (MAX_STANDARD_TX_WEIGHT)",
+        file.display()
+      );
+    }
+
+    Ok(())
+  }
+
+  fn parse_csv_line(
+    line: &str,
+    chain: Chain,
+    max_content_size: Option<usize>,
+    index: &Index,
+    no_limit: bool,
+  ) -> Result<CsvRow> {
+    let mut columns = line.trim_start_matches('\u{feff}').split(',');
+
+    let destination = columns.next().unwrap_or_default();
+
+    let Some(file) = columns.next() else {
+      bail!("no comma, expected `destination,file[,postage[,content_type]]`");
+    };
+
+    let postage = columns.next().filter(|postage| !postage.is_empty());
+
+    let content_type = columns
+      .next()
+      .filter(|content_type| !content_type.is_empty());
+
+    if columns.next().is_some() {
+      bail!("more than 4 columns, expected `destination,file[,postage[,content_type]]`");
+    }
+
+    let destination = label::Destination::from_str(destination)
+      .with_context(|| format!("invalid destination `{destination}`"))?
+      .resolve(index, chain.network())?
+      .script_pubkey();
+
+    let file = PathBuf::from(file);
+
+    let inscription =
+      Inscription::from_file_with_content_type(chain, &file, content_type, max_content_size)
+        .with_context(|| format!("error with file '{}'", file.display()))?;
+
+    if !no_limit {
+      Self::check_reveal_weight(&inscription, &file)?;
+    }
+
+    let postage = postage
+      .map(|postage| {
+        postage
+          .parse::<Amount>()
+          .with_context(|| format!("invalid postage `{postage}`"))
+      })
+      .transpose()?;
+
+    if let Some(postage) = postage {
+      if postage < destination.dust_value() {
+        bail!(
+          "postage {postage} is below the dust value of {}",
+          destination.dust_value()
+        );
+      }
+    }
+
+    Ok(CsvRow {
+      destination,
+      file,
+      inscription,
+      postage,
+    })
+  }
+
+  pub(crate) fn run(self, options: Options) -> Result {
+    self.inscribe(options, true)?;
+    Ok(())
+  }
+
+  /// Inscribe, returning the ids of the inscriptions that were broadcast, or
+  /// would be with `--dry-run` or `--simulate`. Their outputs are only
+  /// printed if `print` is set, but other output, like that of `--dump`, is
+  /// always printed.
+  pub(crate) fn inscribe(mut self, options: Options, print: bool) -> Result<Vec<InscriptionId>> {
+    let mut dump = self.dump;
+    let mut no_broadcast = self.no_broadcast;
+    let mut allow_reveal_rbf = self.allow_reveal_rbf;
+
+    if self.unfunded_reveal {
+      no_broadcast = true;
+      allow_reveal_rbf = true;
+    }
+
+    if no_broadcast {
+      dump = true;
+    }
+
+    let tags = self.tags()?;
+
+    let reveal_offset = self.reveal_offset.unwrap_or_default();
+
+    let mut inscription = Vec::new();
+    let mut filenames = Vec::new();
+    let mut destinations = Vec::new();
+    let mut destination_descriptors = Vec::new();
+    let mut postages = Vec::new();
+    let mut destination_counts = None;
+
+    let postage = self
+      .postage
+      .unwrap_or(TransactionBuilder::DEFAULT_TARGET_POSTAGE);
+
+    let mut client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    tprintln!("[update index]");
+    let mut index = Index::open_shared(&options)?;
+    index.update()?;
+
+    if let Some(sat) = self.sat {
+      if self.satpoint.is_some() {
+        return Err(anyhow!("Cannot use both --sat and --satpoint"));
+      }
+      self.satpoint = Some(Self::find_sat(&index, &options, sat)?);
+    }
+
+    let mut sat_range_inputs = Vec::new();
+
+    if let Some(sat_range) = self.include_sat_range {
+      if self.satpoint.is_some() {
+        return Err(anyhow!(
+          "Cannot use --include-sat-range with --sat or --satpoint"
+        ));
+      } else if self.postage.is_some() {
+        return Err(anyhow!("Cannot use both --include-sat-range and --postage"));
+      }
+      let (satpoint, following) = Self::find_sat_range(&index, &options, sat_range)?;
+      self.satpoint = Some(satpoint);
+      sat_range_inputs = following;
+    }
+
+    if let Some(csv) = self.csv {
+      if !self.files.is_empty() || self.dir.is_some() {
+        return Err(anyhow!("Cannot use both --csv and provide files"));
+      } else if !self.destination.is_empty() {
+        return Err(anyhow!("Cannot use both --csv and --destination"));
+      } else if self.destination_script.is_some() {
+        return Err(anyhow!("Cannot use both --csv and --destination-script"));
+      } else if self.destination_descriptor.is_some() {
+        return Err(anyhow!(
+          "Cannot use both --csv and --destination-descriptor"
+        ));
+      }
+
+      for row in Self::parse_csv(
+        &csv,
+        options.chain(),
+        options.max_content_size()?,
+        &index,
+        self.no_limit,
+      )? {
+        destinations.push(row.destination);
+        filenames.push(row.file);
+        inscription.push(row.inscription);
+        postages.push(row.postage.unwrap_or(postage));
+      }
+
+      destination_counts = Some(Self::check_csv_destinations(
+        &destinations,
+        options.chain().network(),
+        self.yes || self.dry_run,
+      )?);
+    } else {
+      let files = self.files()?;
+      for file in files.iter() {
+        tprintln!("[open files]");
+        let file_inscription =
+          Inscription::from_file(options.chain(), file, options.max_content_size()?)?;
+        if !self.no_limit {
+          Self::check_reveal_weight(&file_inscription, file)?;
+        }
+        inscription.push(file_inscription);
+        filenames.push(PathBuf::from(file));
+        postages.push(postage);
+      }
+      if let Some(destination_script) = &self.destination_script {
+        if !self.destination.is_empty() {
+          return Err(anyhow!(
+            "Cannot use both --destination and --destination-script"
+          ));
+        } else if self.destination_descriptor.is_some() {
+          return Err(anyhow!(
+            "Cannot use both --destination-script and --destination-descriptor"
+          ));
+        }
+        destinations.push(
+          ScriptBuf::from_hex(destination_script)
+            .context("--destination-script is not a valid hex script")?,
+        );
+      } else if let Some(descriptor) = &self.destination_descriptor {
+        if !self.destination.is_empty() {
+          return Err(anyhow!(
+            "Cannot use both --destination and --destination-descriptor"
+          ));
+        }
+        let descriptor = descriptor
+          .parse::<Descriptor<DescriptorPublicKey>>()
+          .context("--destination-descriptor is not a valid descriptor")?;
+        for i in 0..files.len() {
+          let descriptor = descriptor.at_derivation_index(i.try_into().unwrap())?;
+          destinations.push(descriptor.script_pubkey());
+          destination_descriptors.push(descriptor.to_string());
+        }
+      } else if self.destination.is_empty() {
+        tprintln!("[get destination addresses]");
+        for (i, _) in files.iter().enumerate() {
+          destinations.push(get_change_address(&client, &options)?.script_pubkey());
+          if (i + 1) % 100 == 0 {
+            tprintln!("  [{}]", i + 1);
+          }
+        }
+      } else {
+        for destination in self.destination {
+          destinations.push(
+            destination
+              .resolve(&index, options.chain().network())?
+              .script_pubkey(),
+          );
+        }
+      }
+    }
+
+    if inscription.is_empty() {
+      return Err(anyhow!("Provide at least one file to inscribe"));
+    }
+
+    for destination in &destinations {
+      check_legacy_destination(
+        destination,
+        options.chain().network(),
+        self.allow_legacy_destination,
+      )?;
+    }
+
+    if let Some(sat_range) = self.include_sat_range {
+      let postage = Amount::from_sat(sat_range.size());
+      if postage < destinations[0].dust_value() {
+        return Err(anyhow!(
+          "sat range of {postage} is below the dust value of {}",
+          destinations[0].dust_value()
+        ));
+      }
+      postages[0] = postage;
+    }
+
+    if self.optimize {
+      inscription = inscription
+        .iter()
+        .map(|inscription| inscription.optimize(self.compress))
+        .collect::<Result<Vec<Inscription>>>()?;
+    }
+
+    if self.derive_keys && self.rng_seed.is_some() {
+      return Err(anyhow!("Cannot use both --derive-keys and --rng-seed"));
+    }
+
+    if self.change_position.is_some() && self.change_split.is_some() {
+      return Err(anyhow!(
+        "Cannot use both --change-position and --change-split"
+      ));
+    }
+
+    let funding_psbt = self
+      .funding_psbt
+      .as_deref()
+      .map(Self::parse_funding_psbt)
+      .transpose()?;
+
+    if funding_psbt.is_some() && self.simulate {
+      return Err(anyhow!("Cannot use both --funding-psbt and --simulate"));
+    }
+
+    if self.broadcast_when_fee_below.is_some() && no_broadcast {
+      return Err(anyhow!(
+        "Cannot use both --broadcast-when-fee-below and --no-broadcast"
+      ));
+    }
+
+    if self.timeout.is_some() && self.broadcast_when_fee_below.is_none() {
+      return Err(anyhow!("--timeout requires --broadcast-when-fee-below"));
+    }
+
+    if self.package_broadcast && self.wait_after_commit {
+      return Err(anyhow!(
+        "Cannot use both --package-broadcast and --wait-after-commit"
+      ));
+    }
+
+    // a package rejected by submitpackage is never broadcast, testing a
+    // package doesn't take package fee rates into account, and the fallback
+    // takes transactions that Bitcoin Core rejects
+    if self.preflight && self.package_broadcast {
+      return Err(anyhow!(
+        "Cannot use both --preflight and --package-broadcast"
+      ));
+    }
+
+    if self.preflight && self.fallback_broadcast.is_some() {
+      return Err(anyhow!(
+        "Cannot use both --preflight and --fallback-broadcast"
+      ));
+    }
+
+    tprintln!("[get utxos]");
+    let mut utxos = if self.coin_control || funding_psbt.is_some() {
+      BTreeMap::new()
+    } else {
+      index.get_unspent_outputs(Wallet::load(&options)?)?
+    };
+
+    let mut simulation = if self.simulate {
+      Some(Simulation::load(&options)?)
+    } else {
+      None
+    };
+
+    if let Some(simulation) = &simulation {
+      simulation.apply_to_utxos(&mut utxos);
+    }
+
+    remove_frozen_utxos(&options, &mut utxos)?;
+
+    remove_avoided_utxos(&mut utxos, &self.avoid_utxo, &self.utxo)?;
+
+    let min_confirmations = self
+      .min_confirmations
+      .unwrap_or_default()
+      .max(self.avoid_unconfirmed.into());
+
+    // the inscribed sat's output and those holding the rest of its sat range
+    let keep = self
+      .satpoint
+      .map(|satpoint| satpoint.outpoint)
+      .into_iter()
+      .chain(sat_range_inputs.iter().copied())
+      .collect::<Vec<OutPoint>>();
+
+    if min_confirmations > 0 {
+      tprintln!("[remove unconfirmed utxos]");
+      remove_unconfirmed_utxos(&client, &mut utxos, min_confirmations, &keep)?;
+    }
+
+    if let Some(rarity) = self.protect_rarity {
+      tprintln!("[remove rare sat utxos]");
+      remove_rare_sat_utxos(
+        &index,
+        &mut utxos,
+        rarity,
+        &keep
+          .iter()
+          .chain(&self.allow_rare_utxo)
+          .copied()
+          .collect::<Vec<OutPoint>>(),
+      )?;
+    }
+
+    tprintln!("[remove pending commit utxos]");
+    remove_pending_commit_utxos(&client, &index, &mut utxos, &keep)?;
+
+    tprintln!("[insert utxos]");
+    for outpoint in &self.utxo {
+      utxos.insert(
+        *outpoint,
+        Amount::from_sat(
+          client.get_raw_transaction(&outpoint.txid, None)?.output[outpoint.vout as usize].value,
+        ),
+      );
+    }
+
+    let funding_utxos = match &funding_psbt {
+      Some(funding_psbt) => Self::funding_utxos(funding_psbt)?,
+      None => Vec::new(),
+    };
+
+    for (outpoint, txout) in &funding_utxos {
+      utxos.insert(*outpoint, Amount::from_sat(txout.value));
+    }
+
+    tprintln!("[get inscriptions]");
+    let mut inscriptions = index.get_inscriptions(utxos.clone())?;
+
+    if let Some(simulation) = &simulation {
+      simulation.apply_to_inscriptions(&mut inscriptions);
+    }
+
+    tprintln!("[get change]");
+    let commit_tx_change = [
+      get_change_address(&client, &options)?,
+      match self.change {
+        Some(change) => change.require_network(options.chain().network()).unwrap(),
+        None => get_change_address(&client, &options)?,
+      },
+    ];
+
+    let alignment = self.alignment.map(|alignment| {
+      alignment
+        .require_network(options.chain().network())
+        .unwrap()
+    });
+
+    let cursed_destination = self.cursed_destination.map(|cursed_destination| {
+      cursed_destination
+        .require_network(options.chain().network())
+        .unwrap()
+    });
+
+    if let Some(cursed_destination) = &cursed_destination {
+      check_legacy_destination(
+        &cursed_destination.script_pubkey(),
+        options.chain().network(),
+        self.allow_legacy_destination,
+      )?;
+    }
+
+    let (cursed_inputs, cursed_split_address, reveal_vin_from_commit) = if self.cursed {
+      if self.cursed_utxo.len() > inscription.len() {
+        bail!("more --cursed-utxo than inscriptions");
+      }
+
+      let cursed_outpoints = if self.cursed_utxo.is_empty() {
+        let inscribed_utxos = inscriptions
+          .keys()
+          .map(|satpoint| satpoint.outpoint)
+          .collect::<BTreeSet<OutPoint>>();
+
+        let mut cardinals = utxos
+          .iter()
+          .filter(|(outpoint, amount)| {
+            !inscribed_utxos.contains(outpoint)
+              && !keep.contains(outpoint)
+              && amount.to_sat() >= 546
+          })
+          .map(|(outpoint, amount)| (*outpoint, *amount))
+          .collect::<Vec<(OutPoint, Amount)>>();
+
+        cardinals.sort_by_key(|(_outpoint, amount)| *amount);
+
+        // use the smallest cardinals, but leave the largest to fund the commit
+        // tx, which splits off first inputs for any reveals left over
+        cardinals.pop();
+
+        cardinals
+          .into_iter()
+          .take(inscription.len())
+          .map(|(outpoint, _amount)| outpoint)
+          .collect()
+      } else {
+        self.cursed_utxo.clone()
+      };
+
+      let mut cursed_inputs = Vec::new();
+      for cursed_outpoint in cursed_outpoints {
+        let cursed_txout = index
+          .get_transaction(cursed_outpoint.txid)?
+          .ok_or_else(|| anyhow!("cursed utxo {cursed_outpoint} not found"))?
+          .output
+          .into_iter()
+          .nth(cursed_outpoint.vout.try_into().unwrap())
+          .ok_or_else(|| anyhow!("cursed utxo {cursed_outpoint} not found"))?;
+
+        // no batch's commit tx may spend another batch's first inputs
+        utxos.remove(&cursed_outpoint);
+
+        cursed_inputs.push((cursed_outpoint, cursed_txout));
+      }
+
+      (
+        cursed_inputs,
+        Some(get_change_address(&client, &options)?),
+        1,
+      )
+    } else {
+      (Vec::new(), None, 0)
+    };
+
+    let op_return = match &self.op_return {
+      Some(op_return) => {
+        let data = hex::decode(op_return).unwrap_or_else(|_| op_return.as_bytes().to_vec());
+
+        if !self.no_limit && data.len() > Self::MAX_OP_RETURN_DATA_SIZE {
+          bail!(
+            "--op-return data is {} bytes, more than the standard limit of {} bytes",
+            data.len(),
+            Self::MAX_OP_RETURN_DATA_SIZE
+          );
+        }
+
+        Some(ScriptBuf::new_op_return(
+          &script::PushBytesBuf::try_from(data).context("--op-return data too large")?,
+        ))
+      }
+      None => None,
+    };
+
+    let lock_time = LockTime::from_consensus(self.locktime.unwrap_or_default());
+
+    let sequence = self
+      .sequence
+      .map(Sequence)
+      .unwrap_or(Sequence::ENABLE_RBF_NO_LOCKTIME);
+
+    if self.locktime.is_some() {
+      if sequence == Sequence::MAX {
+        bail!(
+          "--locktime has no effect when --sequence is {}",
+          Sequence::MAX
+        );
+      }
+
+      if !no_broadcast {
+        check_lock_time(&client, lock_time)?;
+      }
+    }
+
+    if self.anchor_value.is_some() && !self.anchor {
+      bail!("--anchor-value requires --anchor");
+    }
+
+    let anchor = if self.anchor {
+      let anchor_script = ScriptBuf::from_bytes(P2A_SCRIPT_PUBKEY.to_vec());
+      Some(
+        self
+          .anchor_value
+          .unwrap_or_else(|| anchor_script.dust_value()),
+      )
+    } else {
+      None
+    };
+
+    if self.unfunded_reveal && (self.reveal_fee_rate.is_some() || self.fee_ladder.is_some()) {
+      bail!("Cannot use --unfunded-reveal with --reveal-fee-rate or --fee-ladder");
+    }
+
+    let reveal_fee_rates = if self.unfunded_reveal {
+      vec![FeeRate::try_from(0.0).unwrap(); inscription.len()]
+    } else if let Some(fee_ladder) = self.fee_ladder {
+      if self.reveal_fee_rate.is_some() {
+        bail!("Cannot use both --reveal-fee-rate and --fee-ladder");
+      }
+      fee_ladder.rates(inscription.len())
+    } else {
+      vec![self.reveal_fee_rate.unwrap_or(self.fee_rate); inscription.len()]
+    };
+
+    let required_utxos = self
+      .utxo
+      .iter()
+      .copied()
+      .chain(self.satpoint.map(|satpoint| satpoint.outpoint))
+      .collect::<Vec<OutPoint>>();
+
+    let max_reveals_per_commit =
+      Self::max_reveals_per_commit(&client, &mut utxos, &required_utxos, self.wait_after_commit)?;
+
+    // inscriptions that don't fit under a single commit tx are split into
+    // batches, each with its own commit tx spending different utxos
+    let destinations = (0..inscription.len())
+      .map(|i| destinations[i % destinations.len()].clone())
+      .collect::<Vec<ScriptBuf>>();
+
+    for (name, address) in [
+      ("change", Some(&commit_tx_change[1])),
+      ("alignment", alignment.as_ref()),
+    ] {
+      let Some(address) = address else {
+        continue;
+      };
+
+      if destinations.contains(&address.script_pubkey()) {
+        if self.no_address_reuse {
+          bail!("{name} address {address} is also an inscription destination");
+        }
+        eprintln!("warning: {name} address {address} is also an inscription destination");
+      }
+    }
+
+    let reveal_xprv = if self.derive_keys {
+      Some(Self::get_account_xprv(&client, options.chain().network())?)
+    } else if let Some(rng_seed) = &self.rng_seed {
+      eprintln!("warning: --rng-seed makes reveal keys predictable, don't use it with real funds");
+      Some(ExtendedPrivKey::new_master(
+        options.chain().network(),
+        &hex::decode(rng_seed).context("--rng-seed must be hex")?,
+      )?)
+    } else {
+      None
+    };
+
+    let confirmations = if self.coin_selection == CoinSelection::OldestFirst {
+      get_utxo_confirmations(&client)?
+    } else {
+      BTreeMap::new()
+    };
+
+    let watch_only = !self.dry_run && !self.simulate && !private_keys_enabled(&client)?;
+
+    if self.sat_flow {
+      if self.simulate || watch_only {
+        return Err(anyhow!(
+          "Cannot use --sat-flow with --simulate or a watch-only wallet"
+        ));
+      }
+
+      sat_flow::require_sat_index(&index)?;
+    }
+
+    let mut satisfactions = get_utxo_satisfactions(&client)?;
+
+    for (outpoint, txout) in &funding_utxos {
+      satisfactions.insert(*outpoint, Satisfaction::predict(&txout.script_pubkey));
+    }
+    let mut measured = BTreeSet::new();
+
+    let new_batch = |start: usize,
+                     end: usize,
+                     utxos: BTreeMap<OutPoint, Amount>,
+                     satisfactions: BTreeMap<OutPoint, Satisfaction>,
+                     change_split: Vec<Address>| Batch {
+      satpoint: if start == 0 { self.satpoint } else { None },
+      inscription: inscription[start..end].to_vec(),
+      inscriptions: inscriptions.clone(),
+      network: options.chain().network(),
+      utxos,
+      change: commit_tx_change.clone(),
+      destinations: destinations[start..end].to_vec(),
+      alignment: alignment.clone(),
+      cursed_destination: cursed_destination.clone(),
+      cursed_inputs: self.cursed.then(|| {
+        cursed_inputs[cmp::min(start, cursed_inputs.len())..cmp::min(end, cursed_inputs.len())]
+          .to_vec()
+      }),
+      cursed_split_address: cursed_split_address.clone(),
+      commit_fee_rate: self.commit_fee_rate.unwrap_or(self.fee_rate),
+      reveal_fee_rates: reveal_fee_rates[start..end].to_vec(),
+      max_inputs: self.max_inputs,
+      no_limit: self.no_limit,
+      postages: postages[start..end].to_vec(),
+      tags: tags.clone(),
+      reveal_offset,
+      no_signature: self.no_signature,
+      allow_reinscribe: self.allow_reinscribe,
+      ignore_utxo_inscriptions: self.ignore_utxo_inscriptions,
+      single_key: self.single_key,
+      allow_reveal_rbf,
+      op_return: op_return.clone(),
+      lock_time,
+      sequence,
+      anchor,
+      reveal_xprv,
+      change_position: self.change_position,
+      change_split,
+      coin_selection: self.coin_selection,
+      confirmations: confirmations.clone(),
+      satisfactions,
+      dust_policy: self.dust_policy,
+      no_key_path_recovery: self.no_key_path_recovery,
+      sat_range_inputs: if start == 0 {
+        sat_range_inputs.clone()
+      } else {
+        Vec::new()
+      },
+    };
+
+    // all batches are confirmed at once, before the first is broadcast
+    if !self.yes && !self.dry_run && !self.simulate && io::stdin().is_terminal() {
+      let change_split = get_change_split_addresses(&client, &options, self.change_split)?;
+      let mut utxos = utxos.clone();
+      let mut commits = 0;
+      let mut reveals = 0;
+      let mut fees = 0;
+      let mut batch_size = max_reveals_per_commit;
+      let mut start = 0;
+
+      while start < inscription.len() {
+        let end = cmp::min(start.saturating_add(batch_size), inscription.len());
+
+        let (_, unsigned_commit_tx, reveal_txs, _, _) =
+          Inscribe::create_inscription_transactions(new_batch(
+            start,
+            end,
+            utxos.clone(),
+            satisfactions.clone(),
+            change_split.clone(),
+          ))?;
+
+        if self.commit_too_heavy(end - start, &unsigned_commit_tx) {
+          batch_size = (end - start) / 2;
+          continue;
+        }
+
+        fees += Self::batch_fees(&unsigned_commit_tx, &reveal_txs, &utxos, &cursed_inputs).1;
+
+        for input in &unsigned_commit_tx.input {
+          utxos.remove(&input.previous_output);
+        }
+
+        commits += 1;
+        reveals += reveal_txs.len();
+        start = end;
+      }
+
+      Self::confirm(
+        &inscription,
+        commits,
+        reveals,
+        fees,
+        self.commit_fee_rate.unwrap_or(self.fee_rate),
+        &reveal_fee_rates,
+        &postages,
+      )?;
+    }
+
+    let mut inscribed = Vec::new();
+    let mut batch = 0;
+    let mut batch_size = max_reveals_per_commit;
+    let mut start = 0;
+    // the outputs of batches that were broadcast are printed even if a later
+    // batch fails
+    let mut outputs = Vec::new();
+
+    let result = (|| -> Result {
+      while start < inscription.len() {
+        if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+          bail!(
+            "interrupted before inscribing {} of {} inscriptions, {}",
+            inscription.len() - start,
+            inscription.len(),
+            self.resume_instructions(start)
+          );
+        }
+
+        let end = cmp::min(start.saturating_add(batch_size), inscription.len());
+        let filenames = filenames[start..end].to_vec();
+        let change_split = get_change_split_addresses(&client, &options, self.change_split)?;
+        let batch_destination_descriptors = (!destination_descriptors.is_empty())
+          .then(|| destination_descriptors[start..end].to_vec());
+
+        tprintln!("[create_inscription_transactions]");
+        let (satpoint, unsigned_commit_tx, reveal_txs, mut recovery_key_pairs, dust_to_fee) =
+          Inscribe::create_inscription_transactions(new_batch(
+            start,
+            end,
+            utxos.clone(),
+            satisfactions.clone(),
+            change_split.clone(),
+          ))?;
+
+        // a commit tx with too many outputs is nonstandard, so split the
+        // batch until each commit tx fits
+        if self.commit_too_heavy(end - start, &unsigned_commit_tx) {
+          batch_size = (end - start) / 2;
+          tprintln!("[commit tx too heavy, reducing batch size to {batch_size}]");
+          continue;
+        }
+
+        // every reveal tx has the commit tx and all of its unconfirmed
+        // ancestors as ancestors too
+        let mempool = client
+          .get_raw_mempool()?
+          .into_iter()
+          .collect::<HashSet<Txid>>();
+
+        let ancestors = Self::unconfirmed_ancestors(&unsigned_commit_tx, |txid| {
+          if mempool.contains(&txid) {
+            Ok(client.get_mempool_entry(&txid)?.ancestor_count)
+          } else {
+            Ok(0)
+          }
+        })?;
+
+        let chained = if self.wait_after_commit { 1 } else { 2 };
+
+        if ancestors + chained > Self::MEMPOOL_CHAIN_LIMIT {
+          bail!(
+            "commit tx of batch {} would have {ancestors} unconfirmed ancestors, too many for its reveal txs to fit under the mempool chain limit of {}, wait for the wallet's transactions to confirm",
+            batch + 1,
+            Self::MEMPOOL_CHAIN_LIMIT,
+          );
+        }
+
+        let (fee_utxos, fees) =
+          Self::batch_fees(&unsigned_commit_tx, &reveal_txs, &utxos, &cursed_inputs);
+
+        tprintln!("[sign commit]");
+        let signed_raw_commit_tx = if self.simulate || watch_only || funding_psbt.is_some() {
+          // the wallet can't sign for outputs that only exist in the simulation,
+          // watch-only wallets can't sign at all, and funders sign their own inputs
+          consensus::serialize(&unsigned_commit_tx)
+        } else {
+          let signed_raw_commit_tx =
+            client.sign_raw_transaction_with_wallet(&unsigned_commit_tx, None, None)?;
+
+          if !signed_raw_commit_tx.complete {
+            return Err(anyhow!(
+              "error signing commit tx: {:?}",
+              signed_raw_commit_tx.errors
+            ));
+          }
+
+          signed_raw_commit_tx.hex
+        };
+
+        // input weights are predicted from their script types until the wallet
+        // has signed them, so if their signatures turn out to be heavier or
+        // lighter, the commit tx is rebuilt to pay the requested fee rate
+        if !self.simulate && !watch_only && funding_psbt.is_none() {
+          let signed_commit_tx = consensus::deserialize::<Transaction>(&signed_raw_commit_tx)?;
+
+          let mut resize = false;
+          for (outpoint, satisfaction) in Satisfaction::from_signed_transaction(&signed_commit_tx) {
+            if measured.insert(outpoint)
+              && satisfactions
+                .get(&outpoint)
+                .cloned()
+                .unwrap_or_default()
+                .weight()
+                != satisfaction.weight()
+            {
+              resize = true;
+            }
+            satisfactions.insert(outpoint, satisfaction);
+          }
+
+          if resize {
+            tprintln!("[commit tx inputs have unexpected weight, rebuilding]");
+            continue;
+          }
+        }
+
+        batch += 1;
+
+        // later batches must not spend the same utxos
+        for input in &unsigned_commit_tx.input {
+          utxos.remove(&input.previous_output);
+        }
+
+        #[cfg(test)]
+        let commit_weight = Weight::from_wu(0);
+
+        #[cfg(not(test))]
+        let commit_weight = client
+          .call::<DecodeRawTransactionOutput>(
+            "decoderawtransaction",
+            &[signed_raw_commit_tx.raw_hex().into()],
+          )?
+          .weight;
+
+        if !self.no_limit && commit_weight > bitcoin::Weight::from_wu(MAX_STANDARD_TX_WEIGHT.into())
+        {
+          bail!(
+            "commit transaction weight greater than {MAX_STANDARD_TX_WEIGHT} (MAX_STANDARD_TX_WEIGHT): {commit_weight}"
+          );
+        }
+
+        let sat_flow = if self.sat_flow {
+          Some(sat_flow::trace(
+            &index,
+            &std::iter::once(&unsigned_commit_tx)
+              .chain(&reveal_txs)
+              .collect::<Vec<&Transaction>>(),
+            &commit_tx_change
+              .iter()
+              .chain(&change_split)
+              .map(|address| address.script_pubkey())
+              .collect::<Vec<ScriptBuf>>(),
+          )?)
+        } else {
+          None
+        };
+
+        if let Some(simulation) = &mut simulation {
+          let is_mine = simulation::is_mine(&client, options.chain().network());
+
+          for tx in std::iter::once(&unsigned_commit_tx).chain(&reveal_txs) {
+            simulation.apply(tx, &fee_utxos, &inscriptions, &is_mine)?;
+          }
+
+          simulation.save(&options)?;
+        }
+
+        if self.dry_run || self.simulate {
+          let batch_inscriptions = reveal_txs
+            .iter()
+            .map(|reveal_tx| InscriptionId {
+              txid: reveal_tx.txid(),
+              index: reveal_offset,
+            })
+            .collect::<Vec<InscriptionId>>();
+
+          inscribed.extend(&batch_inscriptions);
+
+          outputs.push(Output {
+            batch,
+            satpoint,
+            inscriptions: batch_inscriptions,
+            commit: unsigned_commit_tx.txid(),
+            reveals: reveal_txs
+              .iter()
+              .map(|reveal_tx| reveal_tx.txid())
+              .collect(),
+            fees,
+            dust_to_fee: dust_to_fee.to_sat(),
+            sat_flow,
+            destination_descriptors: batch_destination_descriptors.clone(),
+            destination_counts: destination_counts.clone(),
+          });
+        } else {
+          if self.single_key {
+            recovery_key_pairs.truncate(1);
+          }
+
+          tprintln!("[sign reveals]");
+          let mut signed_reveal_txs = Vec::new();
+          for reveal_tx in reveal_txs.iter() {
+            // nothing of this batch has been broadcast yet
+            if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+              bail!(
+                "interrupted while signing the reveals of batch {batch}, before broadcasting it, {}",
+                self.resume_instructions(start)
+              );
+            }
+
+            // the commit output, and with --cursed possibly the first input too
+            let commit_outputs = reveal_tx
+              .input
+              .iter()
+              .filter(|input| input.previous_output.txid == unsigned_commit_tx.txid())
+              .map(|input| {
+                let vout = input.previous_output.vout;
+                SignRawTransactionInput {
+                  txid: unsigned_commit_tx.txid(),
+                  vout,
+                  script_pub_key: unsigned_commit_tx.output[vout as usize]
+                    .script_pubkey
+                    .clone(),
+                  amount: Some(Amount::from_sat(
+                    unsigned_commit_tx.output[vout as usize].value,
+                  )),
+                  redeem_script: None,
+                }
+              })
+              .collect::<Vec<SignRawTransactionInput>>();
+
+            let signed_reveal_tx = if watch_only {
+              // reveal inputs spending commit outputs are signed with the reveal
+              // key, but with --cursed the first input may need the wallet
+              if reveal_tx.input.iter().any(|input| input.witness.is_empty()) {
+                bail!("reveal tx spends outputs that a watch-only wallet can't sign for");
+              }
+
+              consensus::serialize(reveal_tx)
+            } else {
+              let signed_reveal_tx =
+                client.sign_raw_transaction_with_wallet(reveal_tx, Some(&commit_outputs), None)?;
+
+              if !signed_reveal_tx.complete {
+                return Err(anyhow!(
+                  "error signing reveal tx: {:?}",
+                  signed_reveal_tx.errors
+                ));
+              }
+
+              signed_reveal_tx.hex
+            };
+
+            let reveal_weight = client
+              .call::<DecodeRawTransactionOutput>(
+                "decoderawtransaction",
+                &[signed_reveal_tx.raw_hex().into()],
+              )?
+              .weight;
+
+            if !self.no_limit
+              && reveal_weight > bitcoin::Weight::from_wu(MAX_STANDARD_TX_WEIGHT.into())
+            {
+              bail!(
+                "reveal transaction weight greater than {MAX_STANDARD_TX_WEIGHT} (MAX_STANDARD_TX_WEIGHT): {reveal_weight}"
+              );
+            }
+
+            signed_reveal_txs.push((reveal_tx, signed_reveal_tx));
+          }
+
+          if watch_only || funding_psbt.is_some() {
+            tprintln!("[watch-only psbt]");
+            print_json(WatchOnlyOutput {
+              batch,
+              satpoint,
+              inscriptions: reveal_txs
+                .iter()
+                .map(|reveal_tx| InscriptionId {
+                  txid: reveal_tx.txid(),
+                  index: reveal_offset,
+                })
+                .collect(),
+              commit_psbt: match &funding_psbt {
+                Some(funding_psbt) => funded_psbt(&client, &unsigned_commit_tx, funding_psbt)?,
+                None => wallet_psbt(&client, &unsigned_commit_tx)?,
+              },
+              reveals: signed_reveal_txs
+                .iter()
+                .map(|(_, signed_reveal_tx)| signed_reveal_tx.raw_hex())
+                .collect(),
+              recovery_descriptors: recovery_key_pairs
+                .iter()
+                .map(|recovery_key_pair| {
+                  Inscribe::get_recovery_key(&client, *recovery_key_pair, options.chain().network())
+                })
+                .collect::<Result<Vec<String>>>()?,
+              fees,
+              dust_to_fee: dust_to_fee.to_sat(),
+              destination_descriptors: batch_destination_descriptors.clone(),
+            })?;
+
+            start = end;
+            continue;
+          }
+
+          if dump {
+            tprintln!("[dump txs]");
+            let commit = signed_raw_commit_tx.raw_hex();
+
+            let mut reveals = Vec::new();
+            for (i, ((reveal_tx, signed_reveal_tx), filename)) in
+              signed_reveal_txs.iter().zip(filenames).enumerate()
+            {
+              let weight = client
+                .call::<DecodeRawTransactionOutput>(
+                  "decoderawtransaction",
+                  &[signed_reveal_tx.raw_hex().into()],
+                )?
+                .weight;
+
+              let commit_vout = reveal_tx.input[reveal_vin_from_commit].previous_output.vout;
+
+              reveals.push(dump::Reveal {
+                inscription: InscriptionId {
+                  txid: reveal_tx.txid(),
+                  index: reveal_offset,
+                },
+                filename,
+                commit_address: Address::from_script(
+                  &unsigned_commit_tx.output[commit_vout as usize].script_pubkey,
+                  options.chain().network(),
+                )?,
+                // with --single-key every reveal shares the first descriptor
+                recovery_descriptor: if self.no_key_path_recovery {
+                  None
+                } else if self.single_key {
+                  Some(0)
+                } else {
+                  Some(i)
+                },
+                weight,
+                hex: signed_reveal_tx.raw_hex(),
+              });
+            }
+
+            tprintln!("[recovery pairs]");
+            let recovery_descriptors = recovery_key_pairs
+              .iter()
+              .map(|recovery_key_pair| {
+                Inscribe::get_recovery_key(&client, *recovery_key_pair, options.chain().network())
+                  .unwrap()
+              })
+              .collect();
+
+            print_json(dump::Output {
+              schema: dump::SCHEMA,
+              batch,
+              satpoint,
+              commit: dump::Commit {
+                txid: unsigned_commit_tx.txid(),
+                weight: commit_weight,
+                hex: commit,
+              },
+              reveals,
+              recovery_descriptors,
+              fees,
+              dust_to_fee: dust_to_fee.to_sat(),
+              destination_descriptors: batch_destination_descriptors.clone(),
+            })?;
+          }
+
+          if !self.no_backup {
+            tprintln!("[backup recovery keys]");
+            for recovery_key_pair in recovery_key_pairs {
+              Inscribe::backup_recovery_key(&client, recovery_key_pair, options.chain().network())?;
+            }
+          }
+
+          if !no_broadcast {
+            if let Some(fee_rate) = self.broadcast_when_fee_below {
+              tprintln!("[wait for fee rate]");
+              scheduled::record(
+                &options,
+                scheduled::Broadcast {
+                  commit: unsigned_commit_tx.txid(),
+                  raw_commit: signed_raw_commit_tx.raw_hex(),
+                  reveals: signed_reveal_txs
+                    .iter()
+                    .map(|(_, signed_reveal_tx)| signed_reveal_tx.raw_hex())
+                    .collect(),
+                  index: reveal_offset,
+                  fee_rate_below: fee_rate.rate(),
+                },
+              )?;
+
+              scheduled::wait_for_fee_rate(&client, fee_rate.rate(), self.timeout).context(
+                "the signed transactions were saved, broadcast them once the fee rate drops with `ord wallet scheduled resume`",
+              )?;
+
+              scheduled::remove(&options, unsigned_commit_tx.txid())?;
+            }
+
+            tprintln!("[broadcast txs]");
+
+            if self.preflight {
+              Self::preflight(&client, &signed_raw_commit_tx, &signed_reveal_txs)?;
+            }
+
+            let mut package_reveal = None;
+
+            if self.package_broadcast {
+              package_reveal =
+                Self::submit_package(&client, &signed_raw_commit_tx, &signed_reveal_txs[0].1)?;
+            }
+
+            // what happened to transactions Bitcoin Core rejected, kept with the
+            // failed reveals
+            let mut fallback_results = Vec::new();
+
+            let commit = match package_reveal {
+              Some(_) => unsigned_commit_tx.txid(),
+              None => {
+                let (commit, rejection) = broadcast(
+                  &client,
+                  &options,
+                  &signed_raw_commit_tx,
+                  self.fallback_broadcast.as_deref(),
+                )
+                .context("Failed to send commit transaction")?;
+
+                if let Some(rejection) = rejection {
+                  fallback_results.push(format!(
+                    "# commit {commit} rejected by Bitcoin Core ({rejection}), broadcast with {}",
+                    self.fallback_broadcast.as_deref().unwrap_or_default(),
+                  ));
+                }
+
+                commit
+              }
+            };
+
+            report_progress(format!("broadcast commit transaction {commit}"));
+
+            if self.wait_after_commit {
+              let mut failed = false;
+              eprint!("[waiting for commit transaction {} to confirm] ", commit);
+              report_progress(format!(
+                "waiting for commit transaction {commit} to confirm"
+              ));
+              io::stdout().flush()?;
+              drop(client);
+              // waiting can take hours, so let other ord commands open the index
+              // in the meantime
+              drop(index);
+              loop {
+                thread::sleep(time::Duration::from_secs(60));
+
+                if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+                  eprintln!();
+                  Index::open_shared(&options)?.set_failed_reveals(
+                    commit,
+                    &signed_reveal_txs
+                      .iter()
+                      .map(|(_, signed_reveal_tx)| signed_reveal_tx.raw_hex())
+                      .collect::<Vec<String>>(),
+                  )?;
+                  bail!(
+                    "interrupted while waiting for commit transaction {commit} to confirm, its reveals were saved, broadcast them with `ord wallet failed-reveals retry` once it confirms{}",
+                    self.resume_later_batches(end, inscription.len())
+                  );
+                }
+
+                match options.bitcoin_rpc_client_for_wallet_command(false) {
+                  Ok(client) => {
+                    if failed {
+                      eprintln!("[reconnected]");
+                      failed = false;
+                    }
+
+                    match client.get_transaction(&commit, Some(false)) {
+                      Ok(tx) => {
+                        if tx.info.confirmations > 0 {
+                          eprintln!();
+                          eprintln!("[confirmed]");
+                          report_progress(format!("commit transaction {commit} confirmed"));
+                          break;
+                        }
+                        eprint!(".");
+                      }
+                      Err(error) => {
+                        eprintln!();
+                        eprintln!("[error: {:?}]", error);
+                        eprintln!("[trying to reconnect to bitcoin client]");
+                        failed = true;
+                      }
+                    }
+                  }
+                  Err(error) => {
+                    eprintln!();
+                    eprintln!("[failed to connect to bitcoin client: {:?}]", error);
+                    failed = true;
+                    thread::sleep(time::Duration::from_secs(60));
+                  }
+                }
+              }
+
+              index = Index::open_shared(&options)?;
+            }
+
+            client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+            let mut reveals = Vec::new();
+            let mut failed_reveals = Vec::new();
+            let mut interrupted = false;
+            for (i, (reveal_tx, signed_reveal_tx)) in signed_reveal_txs.iter().enumerate() {
+              if i == 0 {
+                if let Some(reveal) = package_reveal {
+                  report_progress(format!("broadcast reveal transaction {reveal}"));
+                  reveals.push(reveal);
+                  continue;
+                }
+              }
+
+              // the commit is out, so the rest are kept with the failed reveals
+              if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+                interrupted = true;
+                failed_reveals.push(signed_reveal_tx.raw_hex());
+                continue;
+              }
+
+              match broadcast(
+                &client,
+                &options,
+                signed_reveal_tx,
+                self.fallback_broadcast.as_deref(),
+              ) {
+                Ok((reveal, rejection)) => {
+                  if let Some(rejection) = rejection {
+                    fallback_results.push(format!(
+                      "# reveal {reveal} rejected by Bitcoin Core ({rejection}), broadcast with {}",
+                      self.fallback_broadcast.as_deref().unwrap_or_default(),
+                    ));
+                  }
+
+                  report_progress(format!("broadcast reveal transaction {reveal}"));
+                  reveals.push(reveal);
+                }
+                Err(error) => {
+                  report_progress(format!(
+                    "failed to broadcast reveal transaction {}: {error:#}",
+                    reveal_tx.txid()
+                  ));
+
+                  if self.fallback_broadcast.is_some() {
+                    fallback_results
+                      .push(format!("# reveal {} failed: {error:#}", reveal_tx.txid()));
+                  }
+
+                  failed_reveals.push(signed_reveal_tx.raw_hex());
+                }
+              };
+            }
+
+            // kept in the index rather than the current directory, so that they
+            // can be found with `ord wallet failed-reveals` from anywhere
+            index.set_failed_reveals(
+              commit,
+              &fallback_results
+                .iter()
+                .chain(&failed_reveals)
+                .cloned()
+                .collect::<Vec<String>>(),
+            )?;
+
+            pending::record(
+              &options,
+              pending::Broadcast {
+                commit,
+                reveals: signed_reveal_txs
+                  .iter()
+                  .map(|(_, signed_reveal_tx)| signed_reveal_tx.raw_hex())
+                  .collect(),
+                index: reveal_offset,
+              },
+            )?;
+
+            let batch_inscriptions = reveals
+              .iter()
+              .map(|reveal| InscriptionId {
+                txid: *reveal,
+                index: reveal_offset,
+              })
+              .collect::<Vec<InscriptionId>>();
+
+            inscribed.extend(&batch_inscriptions);
+
+            outputs.push(Output {
+              batch,
+              satpoint,
+              inscriptions: batch_inscriptions,
+              commit,
+              reveals,
+              fees,
+              dust_to_fee: dust_to_fee.to_sat(),
+              sat_flow,
+              destination_descriptors: batch_destination_descriptors.clone(),
+              destination_counts: None,
+            });
+
+            if !failed_reveals.is_empty() {
+              println!(
+                "\n{} reveal{} failed - see `ord wallet failed-reveals list`, and rebroadcast with `ord wallet failed-reveals retry`",
+                failed_reveals.len(),
+                if failed_reveals.len() == 1 { "" } else { "s" }
+              );
+            }
+
+            if interrupted {
+              bail!(
+                "interrupted while broadcasting the reveals of batch {batch}, the rest were saved, broadcast them with `ord wallet failed-reveals retry`{}",
+                self.resume_later_batches(end, inscription.len())
+              );
+            }
+          }
+        }
+
+        start = end;
+      }
+
+      Ok(())
+    })();
+
+    // earlier batches were broadcast, so only the rest should be inscribed
+    // again, and interruptions already say so
+    let result = if start > 0 && !SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+      result.with_context(|| {
+        format!(
+          "failed after inscribing {start} of {} inscriptions, {}",
+          inscription.len(),
+          self.resume_instructions(start)
+        )
+      })
+    } else {
+      result
+    };
+
+    if print && !outputs.is_empty() {
+      if outputs.len() == 1 {
+        print_json(outputs.remove(0))?;
+      } else {
+        print_json(outputs)?;
+      }
+    }
+
+    result?;
+
+    Ok(inscribed)
+  }
+
+  /// What to run to inscribe the inscriptions from `start` on, after being
+  /// interrupted.
+  fn resume_instructions(&self, start: usize) -> String {
+    if self.csv.is_some() {
+      format!(
+        "run the command again with the CSV rows after the first {start} to inscribe the rest"
+      )
+    } else {
+      format!(
+        "run the command again with `--start-index {}` to inscribe the rest",
+        self.start_index.unwrap_or_default() + start
+      )
+    }
+  }
+
+  /// `resume_instructions` for the batches after one that was interrupted
+  /// after its commit was broadcast, if there are any.
+  fn resume_later_batches(&self, end: usize, total: usize) -> String {
+    if end < total {
+      format!(", and {}", self.resume_instructions(end))
+    } else {
+      String::new()
+    }
+  }
+
+  /// Whether a commit tx of a batch of `inscriptions` is too heavy to be
+  /// standard, and the batch should be split.
+  fn commit_too_heavy(&self, inscriptions: usize, unsigned_commit_tx: &Transaction) -> bool {
+    !self.no_limit
+      && inscriptions > 1
+      && Self::estimate_signed_weight(unsigned_commit_tx)
+        > Weight::from_wu(MAX_STANDARD_TX_WEIGHT.into())
+  }
+
+  /// The fees of a batch, and the values of the outputs it spends, which are
+  /// among `utxos`, `cursed_inputs`, and the outputs of the commit tx.
+  fn batch_fees(
+    unsigned_commit_tx: &Transaction,
+    reveal_txs: &[Transaction],
+    utxos: &BTreeMap<OutPoint, Amount>,
+    cursed_inputs: &[(OutPoint, TxOut)],
+  ) -> (BTreeMap<OutPoint, Amount>, u64) {
+    let mut fee_utxos = utxos.clone();
+    fee_utxos.extend(
+      cursed_inputs
+        .iter()
+        .map(|(outpoint, txout)| (*outpoint, Amount::from_sat(txout.value))),
+    );
+
+    tprintln!("[insert values]");
+    for reveal_tx in reveal_txs {
+      for input in &reveal_tx.input {
+        if input.previous_output.txid == unsigned_commit_tx.txid() {
+          fee_utxos.insert(
+            input.previous_output,
+            Amount::from_sat(unsigned_commit_tx.output[input.previous_output.vout as usize].value),
+          );
+        }
+      }
+    }
+
+    let fees = Self::calculate_fee(unsigned_commit_tx, &fee_utxos)
+      + reveal_txs
+        .iter()
+        .map(|reveal_tx| Self::calculate_fee(reveal_tx, &fee_utxos))
+        .sum::<u64>();
+
+    (fee_utxos, fees)
+  }
+
+  /// Show what all batches will cost, and ask whether to go ahead.
+  fn confirm(
+    inscriptions: &[Inscription],
+    commits: usize,
+    reveals: usize,
+    fees: u64,
+    commit_fee_rate: FeeRate,
+    reveal_fee_rates: &[FeeRate],
+    postages: &[Amount],
+  ) -> Result {
+    eprintln!(
+      "{} files, {} bytes of content",
+      inscriptions.len(),
+      inscriptions
+        .iter()
+        .map(|inscription| inscription.body().map(<[u8]>::len).unwrap_or_default())
+        .sum::<usize>()
+    );
+    eprintln!("{commits} commit and {reveals} reveal transactions");
+
+    let (lowest, highest) = reveal_fee_rates.iter().map(FeeRate::rate).fold(
+      (f64::INFINITY, f64::NEG_INFINITY),
+      |(lowest, highest), rate| (lowest.min(rate), highest.max(rate)),
+    );
 
-    tprintln!("[update index]");
-    let index = Index::open(&options)?;
-    index.update()?;
+    eprintln!(
+      "{fees} sats in fees, at {} sats/vB for commits and {} sats/vB for reveals",
+      commit_fee_rate.rate(),
+      if lowest == highest {
+        lowest.to_string()
+      } else {
+        format!("{lowest} to {highest}")
+      },
+    );
+    eprintln!(
+      "{} sats of postage",
+      postages.iter().map(|postage| postage.to_sat()).sum::<u64>()
+    );
+    eprint!("Sign and broadcast? [y/N] ");
 
-    tprintln!("[get utxos]");
-    let mut utxos = if self.coin_control {
-      BTreeMap::new()
-    } else {
-      index.get_unspent_outputs(Wallet::load(&options)?)?
-    };
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
 
-    tprintln!("[insert utxos]");
-    for outpoint in &self.utxo {
-      utxos.insert(
-        *outpoint,
-        Amount::from_sat(
-          client.get_raw_transaction(&outpoint.txid, None)?.output[outpoint.vout as usize].value,
-        ),
-      );
+    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+      bail!("inscribing cancelled");
     }
 
-    tprintln!("[get inscriptions]");
-    let inscriptions = index.get_inscriptions(utxos.clone())?;
+    Ok(())
+  }
 
-    tprintln!("[get change]");
-    let commit_tx_change = [
-      get_change_address(&client, &options)?,
-      match self.change {
-        Some(change) => change.require_network(options.chain().network()).unwrap(),
-        None => get_change_address(&client, &options)?,
-      },
-    ];
+  /// Weight of an unsigned tx once its inputs are signed, assuming they are
+  /// all taproot key path spends, like those of commit txs.
+  fn estimate_signed_weight(tx: &Transaction) -> Weight {
+    let mut tx = tx.clone();
 
-    let alignment = self.alignment.map(|alignment| {
-      alignment
-        .require_network(options.chain().network())
-        .unwrap()
-    });
+    for input in &mut tx.input {
+      input.witness = Witness::from_slice(&[[0; SCHNORR_SIGNATURE_SIZE]]);
+    }
 
-    let cursed_destination = self.cursed_destination.map(|cursed_destination| {
-      cursed_destination
-        .require_network(options.chain().network())
-        .unwrap()
-    });
+    tx.weight()
+  }
 
-    let (cursed_outpoint, cursed_txout, reveal_vin_from_commit) = if self.cursed {
-      let inscribed_utxos = inscriptions
-        .keys()
-        .map(|satpoint| satpoint.outpoint)
-        .collect::<BTreeSet<OutPoint>>();
+  fn find_sat(index: &Index, options: &Options, target: SatTarget) -> Result<SatPoint> {
+    if !index.has_sat_index()? {
+      bail!("--sat requires index created with `--index-sats` flag");
+    }
 
-      let mut smallest_value = 0;
-      let mut cursed_outpoint = None;
-      if let Some(cursed_utxo) = self.cursed_utxo {
-        cursed_outpoint = Some(cursed_utxo);
-      } else {
-        for outpoint in utxos.keys().filter(|outpoint| {
-          !inscribed_utxos.contains(outpoint)
-            && (self.satpoint.is_none() || **outpoint != self.satpoint.unwrap().outpoint)
-            && utxos[outpoint].to_sat() >= 546
-        }) {
-          if smallest_value == 0 || utxos[outpoint].to_sat() < smallest_value {
-            smallest_value = utxos[outpoint].to_sat();
-            cursed_outpoint = Some(*outpoint);
+    match target {
+      SatTarget::Sat(sat) => {
+        find_wallet_sat(index, options, sat)?.ok_or_else(|| anyhow!("sat {sat} is not in wallet"))
+      }
+      SatTarget::Rarity(rarity) => {
+        for outpoint in index
+          .get_unspent_outputs(Wallet::load(options)?)?
+          .into_keys()
+        {
+          let Some(List::Unspent(ranges)) = index.list(outpoint)? else {
+            continue;
+          };
+
+          // only the first sat of a range can be rare, since each range is
+          // within the sats of a single block
+          let mut offset = 0;
+          for (start, end) in ranges {
+            if Sat(start).rarity() == rarity {
+              return Ok(SatPoint { outpoint, offset });
+            }
+            offset += end - start;
           }
         }
 
-        if smallest_value == 0 {
-          return Err(anyhow!("wallet contains no cardinal utxos"));
-        }
+        bail!("no {rarity} sat in wallet")
       }
+    }
+  }
 
-      let cursed_txout = index
-        .get_transaction(cursed_outpoint.unwrap().txid)?
-        .expect("not found")
-        .output
-        .into_iter()
-        .nth(cursed_outpoint.unwrap().vout.try_into().unwrap())
-        .expect("current transaction output");
+  /// The satpoint of the first sat of `sat_range`, and the outputs after its
+  /// own holding the rest of the range, which must follow on from each other
+  /// so that spending them in order keeps the range's sats together.
+  fn find_sat_range(
+    index: &Index,
+    options: &Options,
+    sat_range: SatRange,
+  ) -> Result<(SatPoint, Vec<OutPoint>)> {
+    if !index.has_sat_index()? {
+      bail!("--include-sat-range requires index created with `--index-sats` flag");
+    }
 
-      (cursed_outpoint, Some(cursed_txout), 1)
-    } else {
-      (None, None, 0)
-    };
+    let unspent_outputs = index.get_unspent_outputs(Wallet::load(options)?)?;
 
-    let reveal_fee_rate = if self.unfunded_reveal {
-      FeeRate::try_from(0.0).unwrap()
-    } else {
-      self.fee_rate
-    };
+    let mut pieces = index
+      .find(
+        sat_range.start,
+        sat_range.end,
+        &unspent_outputs.keys().copied().collect(),
+        false,
+      )?
+      .unwrap_or_default();
 
-    tprintln!("[create_inscription_transactions]");
-    let (satpoint, unsigned_commit_tx, reveal_txs, mut recovery_key_pairs) =
-      Inscribe::create_inscription_transactions(
-        self.satpoint,
-        inscription,
-        inscriptions,
-        options.chain().network(),
-        utxos.clone(),
-        commit_tx_change,
-        destinations,
-        alignment,
-        cursed_destination,
-        cursed_outpoint,
-        cursed_txout,
-        self.commit_fee_rate.unwrap_or(self.fee_rate),
-        reveal_fee_rate,
-        self.max_inputs,
-        self.no_limit,
-        match self.postage {
-          Some(postage) => postage,
-          _ => TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-        },
-        self.cursed66,
-        self.no_signature,
-        self.allow_reinscribe,
-        self.ignore_utxo_inscriptions,
-        self.single_key,
-        allow_reveal_rbf,
-      )?;
+    if pieces.iter().map(|piece| piece.size).sum::<u64>() != sat_range.size() {
+      bail!(
+        "sats {}-{} are not all in wallet",
+        sat_range.start,
+        sat_range.end
+      );
+    }
 
-    tprintln!("[sign commit]");
-    let signed_raw_commit_tx =
-      client.sign_raw_transaction_with_wallet(&unsigned_commit_tx, None, None)?;
+    pieces.sort_by_key(|piece| piece.start);
 
-    if !signed_raw_commit_tx.complete {
-      return Err(anyhow!(
-        "error signing commit tx: {:?}",
-        signed_raw_commit_tx.errors
-      ));
-    }
+    let mut following = Vec::new();
 
-    let signed_raw_commit_tx = signed_raw_commit_tx.hex;
+    for (previous, piece) in pieces.iter().zip(pieces.iter().skip(1)) {
+      let previous_end = previous.satpoint.offset + previous.size;
 
-    #[cfg(test)]
-    let commit_weight = Weight::from_wu(0);
+      if piece.satpoint.outpoint == previous.satpoint.outpoint
+        && piece.satpoint.offset == previous_end
+      {
+        continue;
+      }
 
-    #[cfg(not(test))]
-    let commit_weight = client
-      .call::<DecodeRawTransactionOutput>(
-        "decoderawtransaction",
-        &[signed_raw_commit_tx.raw_hex().into()],
-      )?
-      .weight;
+      if piece.satpoint.offset != 0
+        || previous_end != unspent_outputs[&previous.satpoint.outpoint].to_sat()
+        || piece.satpoint.outpoint == pieces[0].satpoint.outpoint
+        || following.contains(&piece.satpoint.outpoint)
+      {
+        bail!(
+          "sats {}-{} are not held in order by the wallet's outputs",
+          sat_range.start,
+          sat_range.end
+        );
+      }
 
-    if !self.no_limit && commit_weight > bitcoin::Weight::from_wu(MAX_STANDARD_TX_WEIGHT.into()) {
-      bail!(
-        "commit transaction weight greater than {MAX_STANDARD_TX_WEIGHT} (MAX_STANDARD_TX_WEIGHT): {commit_weight}"
-      );
+      following.push(piece.satpoint.outpoint);
     }
 
-    tprintln!("[insert values]");
-    for reveal_tx in reveal_txs.clone() {
-      utxos.insert(
-        reveal_tx.input[reveal_vin_from_commit].previous_output,
-        Amount::from_sat(
-          unsigned_commit_tx.output
-            [reveal_tx.input[reveal_vin_from_commit].previous_output.vout as usize]
-            .value,
-        ),
-      );
+    Ok((pieces[0].satpoint, following))
+  }
+
+  /// A funding PSBT only contributes inputs, since the commit tx's outputs
+  /// are built by the wallet.
+  fn parse_funding_psbt(funding_psbt: &str) -> Result<Psbt> {
+    let psbt = Psbt::deserialize(
+      &base64::engine::general_purpose::STANDARD
+        .decode(funding_psbt)
+        .context("--funding-psbt is not valid base64")?,
+    )
+    .context("--funding-psbt is not a valid PSBT")?;
+
+    if psbt.unsigned_tx.input.is_empty() {
+      bail!("funding PSBT has no inputs");
     }
 
-    let fees = Self::calculate_fee(&unsigned_commit_tx, &utxos)
-      + reveal_txs
-        .iter()
-        .map(|reveal_tx| Self::calculate_fee(reveal_tx, &utxos))
-        .sum::<u64>();
+    if !psbt.unsigned_tx.output.is_empty() {
+      bail!("funding PSBT has outputs, use --change to return the funder's change instead");
+    }
 
-    if self.dry_run {
-      print_json(Output {
-        satpoint,
-        inscriptions: reveal_txs
-          .iter()
-          .map(|reveal_tx| reveal_tx.txid().into())
-          .collect(),
-        commit: unsigned_commit_tx.txid(),
-        reveals: reveal_txs
-          .iter()
-          .map(|reveal_tx| reveal_tx.txid())
-          .collect(),
-        fees,
-      })?;
-    } else {
-      if self.single_key {
-        recovery_key_pairs = [recovery_key_pairs[0]].to_vec();
-      }
+    Ok(psbt)
+  }
 
-      tprintln!("[sign reveals]");
-      let mut signed_reveal_txs = Vec::new();
-      for reveal_tx in reveal_txs.iter() {
-        let commit_output = reveal_tx.input[reveal_vin_from_commit].previous_output;
-        let vout = commit_output.vout;
-        let signed_reveal_tx = client.sign_raw_transaction_with_wallet(
-          reveal_tx,
-          Some(&[SignRawTransactionInput {
-            txid: unsigned_commit_tx.txid(),
-            vout,
-            script_pub_key: unsigned_commit_tx.output[vout as usize]
-              .script_pubkey
-              .clone(),
-            amount: Some(Amount::from_sat(
-              unsigned_commit_tx.output[vout as usize].value,
-            )),
-            redeem_script: None,
-          }]),
-          None,
-        )?;
-
-        if !signed_reveal_tx.complete {
-          return Err(anyhow!(
-            "error signing reveal tx: {:?}",
-            signed_reveal_tx.errors
-          ));
-        }
+  fn funding_utxos(funding_psbt: &Psbt) -> Result<Vec<(OutPoint, TxOut)>> {
+    funding_psbt
+      .unsigned_tx
+      .input
+      .iter()
+      .zip(&funding_psbt.inputs)
+      .map(|(txin, input)| {
+        let outpoint = txin.previous_output;
+
+        let txout = input
+          .witness_utxo
+          .clone()
+          .or_else(|| {
+            input
+              .non_witness_utxo
+              .as_ref()
+              .and_then(|tx| tx.output.get(outpoint.vout as usize).cloned())
+          })
+          .ok_or_else(|| anyhow!("funding PSBT input {outpoint} has no utxo information"))?;
 
-        let reveal_weight = client
-          .call::<DecodeRawTransactionOutput>(
-            "decoderawtransaction",
-            &[signed_reveal_tx.hex.raw_hex().into()],
-          )?
-          .weight;
+        Ok((outpoint, txout))
+      })
+      .collect()
+  }
 
-        if !self.no_limit && reveal_weight > bitcoin::Weight::from_wu(MAX_STANDARD_TX_WEIGHT.into())
-        {
+  /// Bitcoin Core's default mempool policy allows at most 25 txs in a chain
+  /// of unconfirmed ancestors, and at most 25 txs in a chain of unconfirmed
+  /// descendants. Every reveal tx descends from its commit tx, and from any
+  /// unconfirmed txs the commit tx spends from. Utxos whose unconfirmed txs
+  /// leave no room for a commit tx and a reveal tx are excluded, and the
+  /// number of reveal txs a single commit tx can have is returned.
+  ///
+  /// If the commit tx confirms before the reveal txs are broadcast, only the
+  /// commit tx is chained to unconfirmed txs, and the number of reveal txs is
+  /// unlimited.
+  ///
+  /// A commit tx spending utxos of several unconfirmed txs has all of their
+  /// ancestors, which is only known once its inputs are selected, and is
+  /// checked with `unconfirmed_ancestors`.
+  fn max_reveals_per_commit(
+    client: &Client,
+    utxos: &mut BTreeMap<OutPoint, Amount>,
+    required_utxos: &[OutPoint],
+    commit_confirms_first: bool,
+  ) -> Result<usize> {
+    let chained = if commit_confirms_first { 1 } else { 2 };
+
+    let mempool = client
+      .get_raw_mempool()?
+      .into_iter()
+      .collect::<HashSet<Txid>>();
+
+    let unconfirmed = utxos
+      .keys()
+      .map(|outpoint| outpoint.txid)
+      .filter(|txid| mempool.contains(txid))
+      .collect::<BTreeSet<Txid>>();
+
+    let mut descendants = 0;
+    for txid in unconfirmed {
+      let entry = client.get_mempool_entry(&txid)?;
+
+      if entry.ancestor_count + chained > Self::MEMPOOL_CHAIN_LIMIT
+        || entry.descendant_count + chained > Self::MEMPOOL_CHAIN_LIMIT
+      {
+        if let Some(outpoint) = required_utxos.iter().find(|outpoint| outpoint.txid == txid) {
           bail!(
-            "reveal transaction weight greater than {MAX_STANDARD_TX_WEIGHT} (MAX_STANDARD_TX_WEIGHT): {reveal_weight}"
+            "utxo {outpoint} has too many unconfirmed ancestors or descendants to inscribe without exceeding the mempool chain limit of {}",
+            Self::MEMPOOL_CHAIN_LIMIT,
           );
         }
 
-        signed_reveal_txs.push((reveal_tx, signed_reveal_tx.hex));
+        tprintln!("excluding utxos of unconfirmed tx {txid}");
+        utxos.retain(|outpoint, _amount| outpoint.txid != txid);
+        continue;
       }
 
-      if dump {
-        tprintln!("[dump txs]");
-        let commit = signed_raw_commit_tx.raw_hex();
-
-        let mut reveals = Vec::new();
-        let mut reveal_weights = Vec::new();
-        let mut inscriptions = Vec::new();
-        for (reveal_tx, signed_reveal_tx) in signed_reveal_txs.iter() {
-          let reveal_weight = client
-            .call::<DecodeRawTransactionOutput>(
-              "decoderawtransaction",
-              &[signed_reveal_tx.raw_hex().into()],
-            )?
-            .weight;
-
-          reveal_weights.push(reveal_weight);
-          reveals.push(signed_reveal_tx.raw_hex());
-          inscriptions.push(reveal_tx.txid().into());
-        }
+      descendants = descendants.max(entry.descendant_count);
+    }
 
-        tprintln!("[recovery pairs]");
-        let recovery_descriptors = recovery_key_pairs
-          .iter()
-          .map(|recovery_key_pair| {
-            Inscribe::get_recovery_key(&client, *recovery_key_pair, options.chain().network())
-              .unwrap()
-          })
-          .collect();
-
-        print_json(OutputDump {
-          satpoint,
-          inscriptions,
-          filenames,
-          commit,
-          commit_weight,
-          reveals,
-          reveal_weights,
-          recovery_descriptors,
-          fees,
-        })?;
-      }
+    if commit_confirms_first {
+      return Ok(usize::MAX);
+    }
 
-      if !self.no_backup {
-        tprintln!("[backup recovery keys]");
-        for recovery_key_pair in recovery_key_pairs {
-          Inscribe::backup_recovery_key(&client, recovery_key_pair, options.chain().network())?;
-        }
-      }
+    Ok(usize::try_from(Self::MEMPOOL_CHAIN_LIMIT - 1 - descendants).unwrap())
+  }
 
-      if !no_broadcast {
-        tprintln!("[broadcast txs]");
+  /// Number of unconfirmed ancestors `tx` would have, given the number of
+  /// unconfirmed txs each of its parents and their ancestors make up.
+  /// Ancestors that parents share are counted for each, so this may
+  /// overestimate, but never underestimates.
+  fn unconfirmed_ancestors(
+    tx: &Transaction,
+    mut ancestor_count: impl FnMut(Txid) -> Result<u64>,
+  ) -> Result<u64> {
+    let parents = tx
+      .input
+      .iter()
+      .map(|input| input.previous_output.txid)
+      .collect::<BTreeSet<Txid>>();
 
-        // make sure before sending the commit tx that we can write to a file in the event that any of the reveals fail
-        let failed_reveals_filename = format!(
-          "failed-reveals-for-commit-{}.txt",
-          unsigned_commit_tx.txid()
-        );
-        let file = fs::OpenOptions::new()
-          .create(true)
-          .write(true)
-          .open(&failed_reveals_filename);
+    let mut ancestors = 0;
 
-        if file.is_err() {
-          return Err(anyhow!("cannot write to the current directory"));
-        }
+    for parent in parents {
+      ancestors += ancestor_count(parent)?;
+    }
 
-        let commit = client
-          .send_raw_transaction(&signed_raw_commit_tx)
-          .context("Failed to send commit transaction")?;
-
-        if self.wait_after_commit {
-          let mut failed = false;
-          drop(index);
-          eprint!("[waiting for commit transaction {} to confirm] ", commit);
-          io::stdout().flush()?;
-          drop(client);
-          loop {
-            thread::sleep(time::Duration::from_secs(60));
-            match options.bitcoin_rpc_client_for_wallet_command(false) {
-              Ok(client) => {
-                if failed {
-                  eprintln!("[reconnected]");
-                  failed = false;
-                }
+    Ok(ancestors)
+  }
 
-                match client.get_transaction(&commit, Some(false)) {
-                  Ok(tx) => {
-                    if tx.info.confirmations > 0 {
-                      eprintln!();
-                      eprintln!("[confirmed]");
-                      break;
-                    }
-                    eprint!(".");
-                  }
-                  Err(error) => {
-                    eprintln!();
-                    eprintln!("[error: {:?}]", error);
-                    eprintln!("[trying to reconnect to bitcoin client]");
-                    failed = true;
-                  }
-                }
-              }
-              Err(error) => {
-                eprintln!();
-                eprintln!("[failed to connect to bitcoin client: {:?}]", error);
-                failed = true;
-                thread::sleep(time::Duration::from_secs(60));
-              }
-            }
-          }
-        }
+  /// Check that the commit tx and the reveal txs would be accepted to the
+  /// mempool with `testmempoolaccept`, printing the result for each tx and
+  /// returning an error if any would be rejected. Packages are limited to 25
+  /// txs and 101 kvB, so only the reveal txs that fit in a package with the
+  /// commit tx are checked.
+  fn preflight(
+    client: &Client,
+    signed_commit_tx: &[u8],
+    signed_reveal_txs: &[(&Transaction, Vec<u8>)],
+  ) -> Result {
+    const MAX_PACKAGE_COUNT: usize = 25;
+    const MAX_PACKAGE_VSIZE: usize = 101_000;
 
-        let mut file = file?;
-        client = options.bitcoin_rpc_client_for_wallet_command(false)?;
-        let mut reveals = Vec::new();
-        let mut failed_reveals = Vec::new();
-        for (_i, (reveal_tx, signed_reveal_tx)) in signed_reveal_txs.iter().enumerate() {
-          match client.send_raw_transaction(signed_reveal_tx) {
-            Ok(reveal) => {
-              reveals.push(reveal);
-            }
-            Err(_error) => {
-              failed_reveals.push(reveal_tx.raw_hex());
-            }
-          };
-        }
+    tprintln!("[preflight]");
+    let vsize =
+      |tx: &[u8]| -> Result<usize> { Ok(consensus::deserialize::<Transaction>(tx)?.vsize()) };
 
-        print_json(Output {
-          satpoint,
-          inscriptions: reveals.iter().map(|reveal| (*reveal).into()).collect(),
-          commit,
-          reveals,
-          fees,
-        })?;
+    let mut rawtxs = vec![signed_commit_tx.raw_hex()];
+    let mut package_vsize = vsize(signed_commit_tx)?;
 
-        if failed_reveals.is_empty() {
-          drop(file);
-          fs::remove_file(failed_reveals_filename)?;
-        } else {
-          for tx in &failed_reveals {
-            writeln!(file, "{tx}")?;
-          }
+    for (_reveal_tx, signed_reveal_tx) in signed_reveal_txs {
+      package_vsize += vsize(signed_reveal_tx)?;
 
-          println!(
-            "\n{} reveal{} failed - see {failed_reveals_filename}",
-            failed_reveals.len(),
-            if failed_reveals.len() == 1 { "" } else { "s" }
-          );
+      if rawtxs.len() == MAX_PACKAGE_COUNT || package_vsize > MAX_PACKAGE_VSIZE {
+        break;
+      }
+
+      rawtxs.push(signed_reveal_tx.raw_hex());
+    }
+
+    let results = client
+      .call::<Vec<TestMempoolAcceptResult>>("testmempoolaccept", &[rawtxs.into()])
+      .context("Failed to check transactions with testmempoolaccept")?;
+
+    if results.iter().all(|result| result.allowed == Some(true)) {
+      return Ok(());
+    }
+
+    print_json(
+      results
+        .into_iter()
+        .map(|result| PreflightOutput {
+          txid: result.txid,
+          allowed: result.allowed.unwrap_or_default(),
+          reject_reason: result.reject_reason,
+          package_error: result.package_error,
+        })
+        .collect::<Vec<PreflightOutput>>(),
+    )?;
+
+    bail!("transactions would be rejected by the mempool, not broadcasting");
+  }
+
+  /// Submit the commit tx and a reveal tx as a package, returning the txid of
+  /// the reveal tx, or `None` if this version of Bitcoin Core can't accept
+  /// packages, in which case they should be broadcast one at a time.
+  ///
+  /// Bitcoin Core only accepts child-with-parents packages, a single child
+  /// and its unconfirmed parents, so the package can't hold more than one
+  /// reveal tx. Once the package is accepted, the commit tx is in the mempool
+  /// and the other reveal txs can be broadcast on their own.
+  ///
+  /// `submitpackage` was added in Bitcoin Core 25.0, so older versions fail
+  /// with a method not found error, and versions before 27.0 only allow it
+  /// on regtest, failing with a miscellaneous error elsewhere.
+  fn submit_package(
+    client: &Client,
+    signed_commit_tx: &[u8],
+    signed_reveal_tx: &[u8],
+  ) -> Result<Option<Txid>> {
+    const RPC_METHOD_NOT_FOUND: i32 = -32601;
+    const RPC_MISC_ERROR: i32 = -1;
+
+    let result = client.call::<serde_json::Value>(
+      "submitpackage",
+      &[vec![signed_commit_tx.raw_hex(), signed_reveal_tx.raw_hex()].into()],
+    );
+
+    let result = match result {
+      Ok(result) => result,
+      Err(bitcoincore_rpc::Error::JsonRpc(bitcoincore_rpc::jsonrpc::error::Error::Rpc(
+        bitcoincore_rpc::jsonrpc::error::RpcError { code, message, .. },
+      )))
+        if code == RPC_METHOD_NOT_FOUND || code == RPC_MISC_ERROR =>
+      {
+        eprintln!("submitpackage unavailable ({message}), broadcasting transactions separately");
+        return Ok(None);
+      }
+      Err(err) => return Err(err).context("Failed to submit commit and reveal package"),
+    };
+
+    let mut errors = Vec::new();
+    if let Some(tx_results) = result["tx-results"].as_object() {
+      for tx_result in tx_results.values() {
+        if let Some(error) = tx_result["error"].as_str() {
+          errors.push(format!("{}: {error}", tx_result["txid"]));
         }
       }
     }
 
-    Ok(())
+    if let Some(package_msg) = result["package_msg"].as_str() {
+      if package_msg != "success" && errors.is_empty() {
+        errors.push(package_msg.into());
+      }
+    }
+
+    if !errors.is_empty() {
+      bail!("package rejected: {}", errors.join(", "));
+    }
+
+    let reveal_tx: Transaction = consensus::deserialize(signed_reveal_tx)?;
+
+    Ok(Some(reveal_tx.txid()))
   }
 
   fn calculate_fee(tx: &Transaction, utxos: &BTreeMap<OutPoint, Amount>) -> u64 {
@@ -670,29 +2708,53 @@ impl Inscribe {
   }
 
   fn create_inscription_transactions(
-    satpoint: Option<SatPoint>,
-    inscription: Vec<Inscription>,
-    inscriptions: BTreeMap<SatPoint, InscriptionId>,
-    network: Network,
-    utxos: BTreeMap<OutPoint, Amount>,
-    change: [Address; 2],
-    destinations: Vec<Address>,
-    alignment: Option<Address>,
-    cursed_destination: Option<Address>,
-    cursed_outpoint: Option<OutPoint>,
-    cursed_txout: Option<TxOut>,
-    commit_fee_rate: FeeRate,
-    reveal_fee_rate: FeeRate,
-    max_inputs: Option<usize>,
-    no_limit: bool,
-    postage: Amount,
-    cursed66: bool,
-    no_signature: bool,
-    allow_reinscribe: bool,
-    ignore_utxo_inscriptions: bool,
-    single_key: bool,
-    allow_reveal_rbf: bool,
-  ) -> Result<(SatPoint, Transaction, Vec<Transaction>, Vec<TweakedKeyPair>)> {
+    batch: Batch,
+  ) -> Result<(
+    SatPoint,
+    Transaction,
+    Vec<Transaction>,
+    Vec<TweakedKeyPair>,
+    Amount,
+  )> {
+    let Batch {
+      satpoint,
+      inscription,
+      inscriptions,
+      network,
+      utxos,
+      change,
+      destinations,
+      alignment,
+      cursed_destination,
+      cursed_inputs,
+      cursed_split_address,
+      commit_fee_rate,
+      reveal_fee_rates,
+      max_inputs,
+      no_limit,
+      postages,
+      tags,
+      reveal_offset,
+      no_signature,
+      allow_reinscribe,
+      ignore_utxo_inscriptions,
+      single_key,
+      allow_reveal_rbf,
+      op_return,
+      lock_time,
+      sequence,
+      anchor,
+      reveal_xprv,
+      change_position,
+      change_split,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      dust_policy,
+      no_key_path_recovery,
+      sat_range_inputs,
+    } = batch;
+
     let satpoint = if let Some(satpoint) = satpoint {
       satpoint
     } else {
@@ -705,7 +2767,10 @@ impl Inscribe {
         .keys()
         .find(|outpoint| {
           !inscribed_utxos.contains(outpoint)
-            && (cursed_outpoint.is_none() || **outpoint != cursed_outpoint.unwrap())
+            && !cursed_inputs
+              .iter()
+              .flatten()
+              .any(|(cursed_outpoint, _)| cursed_outpoint == *outpoint)
         })
         .map(|outpoint| SatPoint {
           outpoint: *outpoint,
@@ -727,7 +2792,42 @@ impl Inscribe {
       }
     }
 
-    let reveal_vout_postage = if cursed_outpoint.is_some() { 1 } else { 0 };
+    let cursed = cursed_inputs.is_some();
+    let cursed_inputs = cursed_inputs.unwrap_or_default();
+
+    // with --cursed every reveal tx spends a cardinal as its first input, one
+    // of `cursed_inputs` if there are enough, otherwise an output split off by
+    // the commit tx
+    let cursed_splits = if cursed {
+      inscription.len().saturating_sub(cursed_inputs.len())
+    } else {
+      0
+    };
+
+    let cursed_split_txout = cursed_split_address.as_ref().map(|address| TxOut {
+      script_pubkey: address.script_pubkey(),
+      value: address.script_pubkey().dust_value().to_sat(),
+    });
+
+    if cursed_splits > 0 && cursed_split_txout.is_none() {
+      bail!(
+        "not enough cursed utxos for {} inscriptions",
+        inscription.len()
+      );
+    }
+
+    let cursed_txout = |i: usize| -> Option<TxOut> {
+      if !cursed {
+        return None;
+      }
+
+      Some(match cursed_inputs.get(i) {
+        Some((_, txout)) => txout.clone(),
+        None => cursed_split_txout.clone().unwrap(),
+      })
+    };
+
+    let reveal_vout_postage = if cursed { 1 } else { 0 };
 
     let mut commit_tx_addresses = Vec::new();
     let mut reveal_fees = Vec::new();
@@ -739,7 +2839,15 @@ impl Inscribe {
     tprintln!("[make reveals]");
 
     let secp256k1 = Secp256k1::new();
-    let mut key_pair = UntweakedKeyPair::new(&secp256k1, &mut rand::thread_rng());
+
+    let new_key_pair = |i: usize| -> Result<UntweakedKeyPair> {
+      match reveal_xprv {
+        Some(xprv) => Self::derive_reveal_key_pair(&secp256k1, xprv, satpoint.outpoint, i),
+        None => Ok(UntweakedKeyPair::new(&secp256k1, &mut rand::thread_rng())),
+      }
+    };
+
+    let mut key_pair = new_key_pair(0)?;
 
     // let key = secp256k1::SecretKey::from_str("0000000000000000000000000000000000000000000000000000000000000001").unwrap();
     // let mut key_pair = secp256k1::KeyPair::from_secret_key(&secp256k1, &key);
@@ -748,22 +2856,32 @@ impl Inscribe {
 
     for (i, inscription) in inscription.iter().enumerate() {
       if !single_key && i != 0 {
-        key_pair = UntweakedKeyPair::new(&secp256k1, &mut rand::thread_rng());
+        key_pair = new_key_pair(i)?;
         (public_key, _parity) = XOnlyPublicKey::from_keypair(&key_pair);
       }
       key_pairs.push(key_pair);
 
       let mut builder = ScriptBuf::builder();
-      let reveal_script = inscription.append_reveal_script(
-        builder,
-        cursed66,
-        no_signature,
-      );
+
+      // the indexer numbers inscriptions within an input by envelope, and
+      // curses any that isn't the first
+      for _ in 0..reveal_offset {
+        builder = Inscription::default().append_reveal_script_to_builder(builder, &[], false);
+      }
+
+      let reveal_script = inscription.append_reveal_script(builder, &tags, no_signature);
 
       let taproot_spend_info = TaprootBuilder::new()
         .add_leaf(0, reveal_script.clone())
         .expect("adding leaf should work")
-        .finalize(&secp256k1, public_key)
+        .finalize(
+          &secp256k1,
+          if no_key_path_recovery {
+            nums_internal_key()
+          } else {
+            public_key
+          },
+        )
         .expect("finalizing taproot builder should work");
 
       let control_block = taproot_spend_info
@@ -778,13 +2896,12 @@ impl Inscribe {
 
       let mut inputs = vec![OutPoint::null()];
       let mut outputs = vec![TxOut {
-        script_pubkey: destinations[i % destinations.len()].script_pubkey(),
+        script_pubkey: destinations[i % destinations.len()].clone(),
         value: 0,
       }];
 
-      if let Some(cursed_outpoint) = cursed_outpoint {
-        let cursed_txout = cursed_txout.as_ref().unwrap();
-        inputs.insert(0, cursed_outpoint);
+      if let Some(cursed_txout) = cursed_txout(i) {
+        inputs.insert(0, OutPoint::null());
         outputs.insert(
           0,
           TxOut {
@@ -797,53 +2914,118 @@ impl Inscribe {
         );
       }
 
+      if let Some(anchor) = anchor {
+        outputs.push(TxOut {
+          script_pubkey: ScriptBuf::from_bytes(P2A_SCRIPT_PUBKEY.to_vec()),
+          value: anchor.to_sat(),
+        });
+      }
+
       let (_, reveal_fee) = Self::build_reveal_transaction(
         &control_block,
-        reveal_fee_rate,
+        reveal_fee_rates[i % reveal_fee_rates.len()],
         reveal_vout_postage,
         inputs,
         outputs,
         &reveal_script,
         no_signature,
+        op_return.as_deref(),
+        lock_time,
+        sequence,
       );
       reveal_scripts.push(reveal_script);
       control_blocks.push(control_block);
-      reveal_fees.push(reveal_fee + postage);
+      reveal_fees.push(reveal_fee + postages[i % postages.len()] + anchor.unwrap_or_default());
     }
 
     let mut utxos_clone = utxos.clone();
-    if let Some(cursed_outpoint) = cursed_outpoint {
-      utxos_clone.remove(&cursed_outpoint);
+    for (cursed_outpoint, _) in &cursed_inputs {
+      utxos_clone.remove(cursed_outpoint);
+    }
+
+    let mut commit_recipients = commit_tx_addresses.clone();
+    let mut commit_values = reveal_fees;
+    if let Some(cursed_split_address) = &cursed_split_address {
+      for _ in 0..cursed_splits {
+        commit_recipients.push(cursed_split_address.clone());
+        commit_values.push(cursed_split_address.script_pubkey().dust_value());
+      }
     }
 
     tprintln!("[make commit]");
-    let unsigned_commit_tx = TransactionBuilder::build_transaction_with_values(
+    let (mut unsigned_commit_tx, dust_to_fee) = TransactionBuilder::build_transaction_with_values(
       satpoint,
       inscriptions,
       utxos_clone,
-      commit_tx_addresses.clone(),
+      commit_recipients,
       alignment,
-      change,
+      change.clone(),
       commit_fee_rate,
-      reveal_fees,
+      commit_values,
       max_inputs,
       ignore_utxo_inscriptions,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      dust_policy,
+      sat_range_inputs,
     )?;
 
+    for input in &mut unsigned_commit_tx.input {
+      input.sequence = sequence;
+    }
+
     let mut reveal_txs = Vec::new();
     let mut recovery_key_pairs = Vec::new();
 
-    // search the commit tx for the output that sends to the first reveal tx's taproot address, to use as an index
-    let (first_vout, _output) = unsigned_commit_tx
-      .output
-      .iter()
-      .enumerate()
-      .find(|(_vout, output)| output.script_pubkey == commit_tx_addresses[0].script_pubkey())
-      .expect("should find sat commit/inscription output");
+    // search the commit tx for the output that sends to the first reveal tx's taproot address, to use as an index
+    let (first_vout, _output) = unsigned_commit_tx
+      .output
+      .iter()
+      .enumerate()
+      .find(|(_vout, output)| output.script_pubkey == commit_tx_addresses[0].script_pubkey())
+      .expect("should find sat commit/inscription output");
+
+    // the change output is always last, so moving it only shifts the outputs
+    // at or after its new position
+    if let Some(position) = change_position {
+      let last = unsigned_commit_tx.output.len() - 1;
+
+      if !change
+        .iter()
+        .any(|change| change.script_pubkey() == unsigned_commit_tx.output[last].script_pubkey)
+      {
+        bail!("--change-position given but commit tx has no change output");
+      }
+
+      if position > last {
+        bail!("--change-position {position} is out of range, commit tx has {last} other outputs");
+      }
+
+      let change_output = unsigned_commit_tx.output.remove(last);
+      unsigned_commit_tx.output.insert(position, change_output);
+    }
+
+    split_change(
+      &mut unsigned_commit_tx,
+      &change
+        .iter()
+        .map(|address| address.script_pubkey())
+        .collect::<Vec<ScriptBuf>>(),
+      &change_split,
+      commit_fee_rate,
+    )?;
+
+    // commit outputs are in recipient order, reveals first and then any cursed
+    // splits, shifted by the change output if it was moved in front of them
+    let commit_vout = |k: usize| match change_position {
+      Some(position) if k + first_vout >= position => k + first_vout + 1,
+      _ => k + first_vout,
+    };
 
     tprintln!("[remake reveals]");
     for (i, key_pair) in key_pairs.iter().enumerate() {
-      let vout = i + first_vout;
+      let vout = commit_vout(i);
       let output = &unsigned_commit_tx.output[vout];
       let reveal_script = &reveal_scripts[i];
 
@@ -852,12 +3034,20 @@ impl Inscribe {
         vout: vout.try_into().unwrap(),
       }];
       let mut outputs = vec![TxOut {
-        script_pubkey: destinations[i % destinations.len()].script_pubkey(),
+        script_pubkey: destinations[i % destinations.len()].clone(),
         value: output.value,
       }];
 
-      if let Some(cursed_outpoint) = cursed_outpoint {
-        let cursed_txout = cursed_txout.as_ref().unwrap();
+      if let Some(cursed_txout) = cursed_txout(i) {
+        let cursed_outpoint = match cursed_inputs.get(i) {
+          Some((cursed_outpoint, _)) => *cursed_outpoint,
+          None => OutPoint {
+            txid: unsigned_commit_tx.txid(),
+            vout: commit_vout(key_pairs.len() + i - cursed_inputs.len())
+              .try_into()
+              .unwrap(),
+          },
+        };
         inputs.insert(0, cursed_outpoint);
         outputs.insert(
           0,
@@ -871,19 +3061,29 @@ impl Inscribe {
         );
       }
 
+      if let Some(anchor) = anchor {
+        outputs.push(TxOut {
+          script_pubkey: ScriptBuf::from_bytes(P2A_SCRIPT_PUBKEY.to_vec()),
+          value: anchor.to_sat(),
+        });
+      }
+
       let (mut reveal_tx, fee) = Self::build_reveal_transaction(
         &control_blocks[i],
-        reveal_fee_rate,
+        reveal_fee_rates[i % reveal_fee_rates.len()],
         reveal_vout_postage,
         inputs,
         outputs,
         reveal_script,
         no_signature,
+        op_return.as_deref(),
+        lock_time,
+        sequence,
       );
 
       reveal_tx.output[reveal_vout_postage].value = reveal_tx.output[reveal_vout_postage]
         .value
-        .checked_sub(fee.to_sat())
+        .checked_sub((fee + anchor.unwrap_or_default()).to_sat())
         .context("reveal transaction output value insufficient to pay transaction fee")?;
 
       if reveal_tx.output[reveal_vout_postage].value
@@ -903,7 +3103,7 @@ impl Inscribe {
           Prevouts::One(reveal_vout_postage, output),
           TapSighashType::SinglePlusAnyoneCanPay,
         )
-      } else if cursed_outpoint.is_some() {
+      } else if cursed {
         (
           Prevouts::One(reveal_vout_postage, output),
           TapSighashType::AllPlusAnyoneCanPay,
@@ -921,18 +3121,23 @@ impl Inscribe {
         )
         .expect("signature hash should compute");
 
-      let signature = secp256k1.sign_schnorr(
-        &secp256k1::Message::from_slice(signature_hash.as_ref())
-          .expect("should be cryptographically secure hash"),
-        key_pair,
-      );
+      let message = secp256k1::Message::from_slice(signature_hash.as_ref())
+        .expect("should be cryptographically secure hash");
+
+      // reveals signed with derived keys are reproducible, since their
+      // signatures don't use auxiliary randomness
+      let signature = if reveal_xprv.is_some() {
+        secp256k1.sign_schnorr_no_aux_rand(&message, key_pair)
+      } else {
+        secp256k1.sign_schnorr(&message, key_pair)
+      };
 
       let witness = sighash_cache
         .witness_mut(reveal_vout_postage)
         .expect("getting mutable witness reference should work");
 
       if !no_signature {
-        if allow_reveal_rbf || cursed_outpoint.is_some() {
+        if allow_reveal_rbf || cursed {
           let mut signature = signature.as_ref().to_vec();
           signature.push(hash_ty as u8);
           witness.push(signature);
@@ -944,17 +3149,20 @@ impl Inscribe {
       witness.push(reveal_script);
       witness.push(control_blocks[i].serialize());
 
-      let recovery_key_pair = key_pair.tap_tweak(&secp256k1, taproot_spend_infos[i].merkle_root());
-      recovery_key_pairs.push(recovery_key_pair);
-
-      let (x_only_pub_key, _parity) = recovery_key_pair.to_inner().x_only_public_key();
-      assert_eq!(
-        Address::p2tr_tweaked(
-          TweakedPublicKey::dangerous_assume_tweaked(x_only_pub_key),
-          network,
-        ),
-        commit_tx_addresses[i]
-      );
+      if !no_key_path_recovery {
+        let recovery_key_pair =
+          key_pair.tap_tweak(&secp256k1, taproot_spend_infos[i].merkle_root());
+        recovery_key_pairs.push(recovery_key_pair);
+
+        let (x_only_pub_key, _parity) = recovery_key_pair.to_inner().x_only_public_key();
+        assert_eq!(
+          Address::p2tr_tweaked(
+            TweakedPublicKey::dangerous_assume_tweaked(x_only_pub_key),
+            network,
+          ),
+          commit_tx_addresses[i]
+        );
+      }
 
       let reveal_weight = reveal_tx.weight();
       reveal_txs.push(reveal_tx);
@@ -969,7 +3177,74 @@ impl Inscribe {
       }
     }
 
-    Ok((satpoint, unsigned_commit_tx, reveal_txs, recovery_key_pairs))
+    Ok((
+      satpoint,
+      unsigned_commit_tx,
+      reveal_txs,
+      recovery_key_pairs,
+      dust_to_fee,
+    ))
+  }
+
+  /// The wallet's account level extended private key, m/86'/<COIN>'/0', taken
+  /// from its active receive descriptor.
+  fn get_account_xprv(client: &Client, network: Network) -> Result<ExtendedPrivKey> {
+    let secp256k1 = Secp256k1::new();
+
+    for descriptor in client.list_descriptors(Some(true))?.descriptors {
+      if !descriptor.desc.starts_with("tr(") || descriptor.internal == Some(true) {
+        continue;
+      }
+
+      let (_, key_map) =
+        Descriptor::<DescriptorPublicKey>::parse_descriptor(&secp256k1, &descriptor.desc)?;
+
+      for secret_key in key_map.values() {
+        if let DescriptorSecretKey::XPrv(xkey) = secret_key {
+          // extended keys only distinguish mainnet from the test networks,
+          // so a regtest or signet tprv decodes as testnet
+          if (xkey.xkey.network == Network::Bitcoin) != (network == Network::Bitcoin) {
+            bail!(
+              "wallet descriptor is for {}, not {network}",
+              xkey.xkey.network
+            );
+          }
+          return Ok(xkey.xkey);
+        }
+      }
+    }
+
+    Err(anyhow!(
+      "wallet has no extended private key to derive reveal keys from"
+    ))
+  }
+
+  /// Reveal key `index` of the commit tx spending `outpoint` is derived from
+  /// the account key at `2'/<H>'/<index>'`, where `H` is the first four bytes
+  /// of the SHA256 of the consensus encoded outpoint with the high bit
+  /// cleared. The outpoint is always an input of the commit tx, so the keys
+  /// can be rederived from the seed and the commit tx alone.
+  fn derive_reveal_key_pair(
+    secp256k1: &Secp256k1<secp256k1::All>,
+    account_xprv: ExtendedPrivKey,
+    outpoint: OutPoint,
+    index: usize,
+  ) -> Result<UntweakedKeyPair> {
+    let hash = sha256::Hash::hash(&bitcoin::consensus::serialize(&outpoint));
+
+    let outpoint_index =
+      u32::from_be_bytes(hash.to_byte_array()[..4].try_into().unwrap()) & 0x7fff_ffff;
+
+    let path = DerivationPath::master()
+      .child(ChildNumber::from_hardened_idx(2)?)
+      .child(ChildNumber::from_hardened_idx(outpoint_index)?)
+      .child(ChildNumber::from_hardened_idx(index.try_into()?)?);
+
+    Ok(
+      account_xprv
+        .derive_priv(secp256k1, &path)?
+        .to_keypair(secp256k1),
+    )
   }
 
   fn get_recovery_key(
@@ -1002,7 +3277,7 @@ impl Inscribe {
       range: None,
       next_index: None,
       internal: Some(false),
-      label: Some("commit tx recovery key".to_string()),
+      label: Some(RECOVERY_KEY_LABEL.to_string()),
     })?;
 
     for result in response {
@@ -1019,10 +3294,22 @@ impl Inscribe {
     fee_rate: FeeRate,
     reveal_vout_postage: usize,
     inputs: Vec<OutPoint>,
-    outputs: Vec<TxOut>,
+    mut outputs: Vec<TxOut>,
     script: &Script,
     no_signature: bool,
+    op_return: Option<&Script>,
+    lock_time: LockTime,
+    sequence: Sequence,
   ) -> (Transaction, Amount) {
+    // OP_RETURN outputs are exempt from the dust limit, so they carry no value
+    // and only add to the weight the fee is calculated from
+    if let Some(op_return) = op_return {
+      outputs.push(TxOut {
+        script_pubkey: op_return.into(),
+        value: 0,
+      });
+    }
+
     let reveal_tx = Transaction {
       input: inputs
         .iter()
@@ -1030,11 +3317,11 @@ impl Inscribe {
           previous_output: *outpoint,
           script_sig: script::Builder::new().into_script(),
           witness: Witness::new(),
-          sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+          sequence,
         })
         .collect(),
       output: outputs,
-      lock_time: LockTime::ZERO,
+      lock_time,
       version: 1,
     };
 
@@ -1045,11 +3332,11 @@ impl Inscribe {
         // add dummy inscription witness for reveal input/commit output
         if current_index == reveal_vout_postage {
           if !no_signature {
-          txin.witness.push(
-            Signature::from_slice(&[0; SCHNORR_SIGNATURE_SIZE])
-              .unwrap()
-              .as_ref(),
-          );
+            txin.witness.push(
+              Signature::from_slice(&[0; SCHNORR_SIGNATURE_SIZE])
+                .unwrap()
+                .as_ref(),
+            );
           }
           txin.witness.push(script);
           txin.witness.push(&control_block.serialize());
@@ -1069,38 +3356,57 @@ impl Inscribe {
 mod tests {
   use super::*;
 
+  fn batch(inscription: Vec<Inscription>, utxos: BTreeMap<OutPoint, Amount>) -> Batch {
+    Batch {
+      satpoint: None,
+      inscription,
+      inscriptions: BTreeMap::new(),
+      network: Network::Bitcoin,
+      utxos,
+      change: [change(0), change(1)],
+      destinations: vec![recipient().script_pubkey()],
+      alignment: None,
+      cursed_destination: None,
+      cursed_inputs: None,
+      cursed_split_address: None,
+      commit_fee_rate: FeeRate::try_from(1.0).unwrap(),
+      reveal_fee_rates: vec![FeeRate::try_from(1.0).unwrap()],
+      max_inputs: None,
+      no_limit: false,
+      postages: vec![TransactionBuilder::DEFAULT_TARGET_POSTAGE],
+      tags: Vec::new(),
+      reveal_offset: 0,
+      no_signature: false,
+      allow_reinscribe: false,
+      ignore_utxo_inscriptions: false,
+      single_key: false,
+      allow_reveal_rbf: false,
+      op_return: None,
+      lock_time: LockTime::ZERO,
+      sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+      anchor: None,
+      reveal_xprv: None,
+      change_position: None,
+      change_split: Vec::new(),
+      coin_selection: CoinSelection::Closest,
+      confirmations: BTreeMap::new(),
+      satisfactions: BTreeMap::new(),
+      dust_policy: DustPolicy::Alignment,
+      no_key_path_recovery: false,
+      sat_range_inputs: Vec::new(),
+    }
+  }
+
   #[test]
   fn reveal_transaction_pays_fee() {
     let utxos = vec![(outpoint(1), Amount::from_sat(20000))];
     let inscription = inscription("text/plain", "ord");
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
 
-    let (_satpoint, commit_tx, reveal_tx, _private_key) =
-      Inscribe::create_inscription_transactions(
-        Some(satpoint(1, 0)),
-        vec![inscription],
-        BTreeMap::new(),
-        Network::Bitcoin,
-        utxos.into_iter().collect(),
-        [commit_address, change(1)],
-        reveal_address,
-        None,
-        None,
-        None,
-        None,
-        FeeRate::try_from(1.0).unwrap(),
-        FeeRate::try_from(1.0).unwrap(),
-        None,
-        false,
-        TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-        false,
-        false,
-        false,
-        false,
-        false,
-        false,
-      )
+    let (_satpoint, commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
       .unwrap();
 
     #[allow(clippy::cast_possible_truncation)]
@@ -1113,43 +3419,196 @@ mod tests {
     );
   }
 
+  #[test]
+  fn commits_without_key_path_recovery_use_nums_internal_key() {
+    let utxos = vec![(outpoint(1), Amount::from_sat(20000))];
+
+    let (_satpoint, _commit_tx, reveal_tx, recovery_key_pairs, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        no_key_path_recovery: true,
+        ..batch(
+          vec![inscription("text/plain", "ord")],
+          utxos.into_iter().collect(),
+        )
+      })
+      .unwrap();
+
+    assert!(recovery_key_pairs.is_empty());
+
+    let control_block =
+      ControlBlock::decode(reveal_tx[0].input[0].witness.last().unwrap()).unwrap();
+
+    assert_eq!(control_block.internal_key, nums_internal_key());
+  }
+
+  #[test]
+  fn reveal_transaction_with_op_return_pays_fee() {
+    let utxos = vec![(outpoint(1), Amount::from_sat(20000))];
+    let inscription = inscription("text/plain", "ord");
+    let op_return = ScriptBuf::new_op_return(&[1u8, 2, 3]);
+
+    let (_satpoint, commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        op_return: Some(op_return.clone()),
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
+      .unwrap();
+
+    assert_eq!(reveal_tx[0].output.len(), 2);
+    assert_eq!(reveal_tx[0].output[1].script_pubkey, op_return);
+    assert_eq!(reveal_tx[0].output[1].value, 0);
+
+    let fee = Amount::from_sat(reveal_tx[0].weight().to_vbytes_ceil());
+
+    assert_eq!(
+      reveal_tx[0].output[0].value,
+      20000 - fee.to_sat() - (20000 - commit_tx.output[0].value),
+    );
+  }
+
+  #[test]
+  fn change_position_moves_commit_change_output() {
+    let utxos = vec![(outpoint(1), Amount::from_sat(50_000))];
+
+    let (_satpoint, commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        change_position: Some(0),
+        ..batch(
+          vec![inscription("text/plain", "ord")],
+          utxos.into_iter().collect(),
+        )
+      })
+      .unwrap();
+
+    assert_eq!(commit_tx.output.len(), 2);
+    assert_eq!(commit_tx.output[0].script_pubkey, change(1).script_pubkey());
+    assert_eq!(reveal_tx[0].input[0].previous_output.vout, 1);
+  }
+
+  #[test]
+  fn reveal_offset_precedes_inscription_with_empty_envelopes() {
+    let utxos = vec![(outpoint(1), Amount::from_sat(50_000))];
+
+    let (_satpoint, _commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        reveal_offset: 2,
+        ..batch(
+          vec![inscription("text/plain", "ord")],
+          utxos.into_iter().collect(),
+        )
+      })
+      .unwrap();
+
+    let inscriptions = Inscription::from_transaction(&reveal_tx[0]);
+
+    assert_eq!(inscriptions.len(), 3);
+    assert_eq!(inscriptions[0].inscription, Inscription::default());
+    assert_eq!(
+      inscriptions[2].inscription,
+      inscription("text/plain", "ord")
+    );
+    assert_eq!(inscriptions[2].tx_in_offset, 2);
+  }
+
+  #[test]
+  fn derived_reveal_keys_are_deterministic() {
+    let secp256k1 = Secp256k1::new();
+    let xprv = ExtendedPrivKey::new_master(Network::Bitcoin, &[0; 64]).unwrap();
+
+    let key_pair = |outpoint, index| {
+      Inscribe::derive_reveal_key_pair(&secp256k1, xprv, outpoint, index).unwrap()
+    };
+
+    assert_eq!(key_pair(outpoint(1), 0), key_pair(outpoint(1), 0));
+    assert_ne!(key_pair(outpoint(1), 0), key_pair(outpoint(1), 1));
+    assert_ne!(key_pair(outpoint(1), 0), key_pair(outpoint(2), 0));
+  }
+
+  #[test]
+  fn reveals_with_derived_keys_are_reproducible() {
+    let create = |reveal_xprv| {
+      let (_satpoint, commit_tx, reveal_txs, _, _) =
+        Inscribe::create_inscription_transactions(Batch {
+          satpoint: Some(satpoint(1, 0)),
+          reveal_xprv,
+          ..batch(
+            vec![inscription("text/plain", "ord")],
+            [(outpoint(1), Amount::from_sat(20000))]
+              .into_iter()
+              .collect(),
+          )
+        })
+        .unwrap();
+
+      (commit_tx, reveal_txs)
+    };
+
+    let seeded = || Some(ExtendedPrivKey::new_master(Network::Bitcoin, &[1; 32]).unwrap());
+
+    assert_eq!(create(seeded()), create(seeded()));
+    assert_ne!(create(None), create(None));
+  }
+
   #[test]
   fn inscript_tansactions_opt_in_to_rbf() {
     let utxos = vec![(outpoint(1), Amount::from_sat(20000))];
     let inscription = inscription("text/plain", "ord");
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
-
-    let (_satpoint, commit_tx, reveal_tx, _) = Inscribe::create_inscription_transactions(
-      Some(satpoint(1, 0)),
-      vec![inscription],
-      BTreeMap::new(),
-      Network::Bitcoin,
-      utxos.into_iter().collect(),
-      [commit_address, change(1)],
-      reveal_address,
-      None,
-      None,
-      None,
-      None,
-      FeeRate::try_from(1.0).unwrap(),
-      FeeRate::try_from(1.0).unwrap(),
-      None,
-      false,
-      TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-      false,
-      false,
-      false,
-      false,
-      false,
-      false,
-    )
-    .unwrap();
+
+    let (_satpoint, commit_tx, reveal_tx, _, _) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
+      .unwrap();
 
     assert!(commit_tx.is_explicitly_rbf());
     assert!(reveal_tx[0].is_explicitly_rbf());
   }
 
+  #[test]
+  fn dust_policy_decides_where_dust_commit_change_goes() {
+    let create = |dust_policy| {
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        dust_policy,
+        ..batch(
+          vec![inscription("text/plain", "ord")],
+          [(outpoint(1), Amount::from_sat(10_400))].into(),
+        )
+      })
+    };
+
+    let (_satpoint, _commit_tx, reveal_tx, _private_key, dust_to_fee) =
+      create(DustPolicy::Fee).unwrap();
+
+    assert!(dust_to_fee > Amount::ZERO);
+    assert_eq!(
+      reveal_tx[0].output[0].value,
+      TransactionBuilder::DEFAULT_TARGET_POSTAGE.to_sat()
+    );
+
+    let (_satpoint, _commit_tx, reveal_tx, _private_key, no_dust_to_fee) =
+      create(DustPolicy::Alignment).unwrap();
+
+    assert_eq!(no_dust_to_fee, Amount::ZERO);
+    assert_eq!(
+      reveal_tx[0].output[0].value,
+      (TransactionBuilder::DEFAULT_TARGET_POSTAGE + dust_to_fee).to_sat()
+    );
+
+    assert_eq!(
+      create(DustPolicy::Error).unwrap_err().to_string(),
+      format!(
+        "{} sats of change is too small for a change output",
+        dust_to_fee.to_sat()
+      ),
+    );
+  }
+
   #[test]
   fn inscribe_with_no_satpoint_and_no_cardinal_utxos() {
     let utxos = vec![(outpoint(1), Amount::from_sat(1000))];
@@ -1164,33 +3623,12 @@ mod tests {
 
     let inscription = inscription("text/plain", "ord");
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
 
-    let error = Inscribe::create_inscription_transactions(
+    let error = Inscribe::create_inscription_transactions(Batch {
       satpoint,
-      vec![inscription],
       inscriptions,
-      Network::Bitcoin,
-      utxos.into_iter().collect(),
-      [commit_address, change(1)],
-      reveal_address,
-      None,
-      None,
-      None,
-      None,
-      FeeRate::try_from(1.0).unwrap(),
-      FeeRate::try_from(1.0).unwrap(),
-      None,
-      false,
-      TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-      false,
-      false,
-      false,
-      false,
-      false,
-      false,
-    )
+      ..batch(vec![inscription], utxos.into_iter().collect())
+    })
     .unwrap_err()
     .to_string();
 
@@ -1218,33 +3656,12 @@ mod tests {
 
     let inscription = inscription("text/plain", "ord");
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
 
-    assert!(Inscribe::create_inscription_transactions(
+    assert!(Inscribe::create_inscription_transactions(Batch {
       satpoint,
-      vec![inscription],
       inscriptions,
-      Network::Bitcoin,
-      utxos.into_iter().collect(),
-      [commit_address, change(1)],
-      reveal_address,
-      None,
-      None,
-      None,
-      None,
-      FeeRate::try_from(1.0).unwrap(),
-      FeeRate::try_from(1.0).unwrap(),
-      None,
-      false,
-      TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-      false,
-      false,
-      false,
-      false,
-      false,
-      false,
-    )
+      ..batch(vec![inscription], utxos.into_iter().collect())
+    })
     .is_ok())
   }
 
@@ -1265,35 +3682,17 @@ mod tests {
 
     let inscription = inscription("text/plain", "ord");
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
     let fee_rate = 3.3;
 
-    let (_satpoint, commit_tx, reveal_tx, _private_key) =
-      Inscribe::create_inscription_transactions(
+    let (_satpoint, commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
         satpoint,
-        vec![inscription],
         inscriptions,
-        bitcoin::Network::Signet,
-        utxos.into_iter().collect(),
-        [commit_address, change(1)],
-        reveal_address,
-        None,
-        None,
-        None,
-        None,
-        FeeRate::try_from(fee_rate).unwrap(),
-        FeeRate::try_from(fee_rate).unwrap(),
-        None,
-        false,
-        TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-        false,
-        false,
-        false,
-        false,
-        false,
-        false,
-      )
+        network: bitcoin::Network::Signet,
+        commit_fee_rate: FeeRate::try_from(fee_rate).unwrap(),
+        reveal_fee_rates: vec![FeeRate::try_from(fee_rate).unwrap()],
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
       .unwrap();
 
     let sig_vbytes = 17.0;
@@ -1341,36 +3740,18 @@ mod tests {
 
     let inscription = inscription("text/plain", "ord");
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
     let commit_fee_rate = 3.3;
     let fee_rate = 1.0;
 
-    let (_satpoint, commit_tx, reveal_tx, _private_key) =
-      Inscribe::create_inscription_transactions(
+    let (_satpoint, commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
         satpoint,
-        vec![inscription],
         inscriptions,
-        bitcoin::Network::Signet,
-        utxos.into_iter().collect(),
-        [commit_address, change(1)],
-        reveal_address,
-        None,
-        None,
-        None,
-        None,
-        FeeRate::try_from(commit_fee_rate).unwrap(),
-        FeeRate::try_from(fee_rate).unwrap(),
-        None,
-        false,
-        TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-        false,
-        false,
-        false,
-        false,
-        false,
-        false,
-      )
+        network: bitcoin::Network::Signet,
+        commit_fee_rate: FeeRate::try_from(commit_fee_rate).unwrap(),
+        reveal_fee_rates: vec![FeeRate::try_from(fee_rate).unwrap()],
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
       .unwrap();
 
     let sig_vbytes = 17;
@@ -1405,33 +3786,11 @@ mod tests {
 
     let inscription = inscription("text/plain", [0; MAX_STANDARD_TX_WEIGHT as usize]);
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
 
-    let error = Inscribe::create_inscription_transactions(
+    let error = Inscribe::create_inscription_transactions(Batch {
       satpoint,
-      vec![inscription],
-      BTreeMap::new(),
-      Network::Bitcoin,
-      utxos.into_iter().collect(),
-      [commit_address, change(1)],
-      reveal_address,
-      None,
-      None,
-      None,
-      None,
-      FeeRate::try_from(1.0).unwrap(),
-      FeeRate::try_from(1.0).unwrap(),
-      None,
-      false,
-      TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-      false,
-      false,
-      false,
-      false,
-      false,
-      false,
-    )
+      ..batch(vec![inscription], utxos.into_iter().collect())
+    })
     .unwrap_err()
     .to_string();
 
@@ -1448,36 +3807,287 @@ mod tests {
 
     let inscription = inscription("text/plain", [0; MAX_STANDARD_TX_WEIGHT as usize]);
     let satpoint = None;
-    let commit_address = change(0);
-    let reveal_address = vec![recipient()];
 
-    let (_satpoint, _commit_tx, reveal_tx, _private_key) =
-      Inscribe::create_inscription_transactions(
+    let (_satpoint, _commit_tx, reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
         satpoint,
-        vec![inscription],
-        BTreeMap::new(),
-        Network::Bitcoin,
-        utxos.into_iter().collect(),
-        [commit_address, change(1)],
-        reveal_address,
-        None,
-        None,
-        None,
-        None,
-        FeeRate::try_from(1.0).unwrap(),
-        FeeRate::try_from(1.0).unwrap(),
-        None,
-        true,
-        TransactionBuilder::DEFAULT_TARGET_POSTAGE,
-        false,
-        false,
-        false,
-        false,
-        false,
-        false,
-      )
+        no_limit: true,
+        ..batch(vec![inscription], utxos.into_iter().collect())
+      })
       .unwrap();
 
     assert!(reveal_tx[0].size() >= MAX_STANDARD_TX_WEIGHT as usize);
   }
+
+  /// The commit tx for an inscription on a 1,000 sat utxo, which needs more
+  /// cardinals to pay for the reveal tx and postage.
+  fn commit_tx_with_coin_selection(
+    coin_selection: CoinSelection,
+    utxos: &[(OutPoint, u64)],
+    confirmations: BTreeMap<OutPoint, u32>,
+  ) -> Transaction {
+    let (_satpoint, commit_tx, _reveal_tx, _private_key, _dust_to_fee) =
+      Inscribe::create_inscription_transactions(Batch {
+        satpoint: Some(satpoint(1, 0)),
+        coin_selection,
+        confirmations,
+        ..batch(
+          vec![inscription("text/plain", "ord")],
+          [(outpoint(1), 1_000)]
+            .iter()
+            .chain(utxos)
+            .map(|(outpoint, value)| (*outpoint, Amount::from_sat(*value)))
+            .collect(),
+        )
+      })
+      .unwrap();
+
+    commit_tx
+  }
+
+  fn inputs(tx: &Transaction) -> Vec<OutPoint> {
+    tx.input.iter().map(|txin| txin.previous_output).collect()
+  }
+
+  fn cardinals() -> Vec<(OutPoint, u64)> {
+    vec![
+      (outpoint(2), 20_000),
+      (outpoint(3), 12_000),
+      (outpoint(4), 50_000),
+    ]
+  }
+
+  fn has_change_output(tx: &Transaction) -> bool {
+    tx.output.iter().any(|output| {
+      output.script_pubkey == change(0).script_pubkey()
+        || output.script_pubkey == change(1).script_pubkey()
+    })
+  }
+
+  #[test]
+  fn branch_and_bound_coin_selection_avoids_change() {
+    let utxos = (0..40)
+      .map(|vout| {
+        (
+          OutPoint {
+            txid: txid(2),
+            vout,
+          },
+          400,
+        )
+      })
+      .chain([(outpoint(3), 15_000)])
+      .collect::<Vec<(OutPoint, u64)>>();
+
+    let closest = commit_tx_with_coin_selection(CoinSelection::Closest, &utxos, BTreeMap::new());
+
+    assert_eq!(inputs(&closest), [outpoint(1), outpoint(3)]);
+    assert!(has_change_output(&closest));
+
+    let commit_tx =
+      commit_tx_with_coin_selection(CoinSelection::BranchAndBound, &utxos, BTreeMap::new());
+
+    assert!(commit_tx.input.len() > 2);
+    assert!(!inputs(&commit_tx).contains(&outpoint(3)));
+    assert!(!has_change_output(&commit_tx));
+  }
+
+  #[test]
+  fn largest_first_coin_selection_spends_biggest_cardinal() {
+    assert_eq!(
+      inputs(&commit_tx_with_coin_selection(
+        CoinSelection::LargestFirst,
+        &cardinals(),
+        BTreeMap::new(),
+      )),
+      [outpoint(1), outpoint(4)],
+    );
+  }
+
+  #[test]
+  fn oldest_first_coin_selection_spends_most_confirmed_cardinal() {
+    assert_eq!(
+      inputs(&commit_tx_with_coin_selection(
+        CoinSelection::OldestFirst,
+        &cardinals(),
+        [(outpoint(2), 100), (outpoint(3), 10), (outpoint(4), 1)].into(),
+      )),
+      [outpoint(1), outpoint(2)],
+    );
+  }
+
+  #[test]
+  fn single_coin_selection_spends_smallest_sufficient_cardinal() {
+    assert_eq!(
+      inputs(&commit_tx_with_coin_selection(
+        CoinSelection::Single,
+        &cardinals(),
+        BTreeMap::new(),
+      )),
+      [outpoint(1), outpoint(3)],
+    );
+  }
+
+  /// Broadcast a tx with unspent outputs and `children` unconfirmed txs
+  /// spending its other outputs.
+  fn unconfirmed_parent(
+    rpc_server: &test_bitcoincore_rpc::Handle,
+    client: &Client,
+    children: u32,
+  ) -> Txid {
+    let parent = rpc_server.broadcast_tx(TransactionTemplate {
+      inputs: &[(1, 0, 0)],
+      outputs: 25,
+      ..Default::default()
+    });
+
+    for vout in 0..children {
+      client
+        .send_raw_transaction(&Transaction {
+          version: 2,
+          lock_time: LockTime::ZERO,
+          input: vec![TxIn {
+            previous_output: OutPoint { txid: parent, vout },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::new(),
+          }],
+          output: vec![TxOut {
+            value: 1000,
+            script_pubkey: ScriptBuf::new(),
+          }],
+        })
+        .unwrap();
+    }
+
+    parent
+  }
+
+  #[test]
+  fn max_reveals_per_commit_leaves_room_for_unconfirmed_descendants() {
+    let rpc_server = test_bitcoincore_rpc::spawn();
+    rpc_server.mine_blocks(1);
+    let client = Client::new(&rpc_server.url(), bitcoincore_rpc::Auth::None).unwrap();
+
+    let parent = unconfirmed_parent(&rpc_server, &client, 3);
+    let outpoint = OutPoint {
+      txid: parent,
+      vout: 24,
+    };
+
+    let mut utxos = [(outpoint, Amount::from_sat(1000))]
+      .into_iter()
+      .collect::<BTreeMap<OutPoint, Amount>>();
+
+    assert_eq!(
+      Inscribe::max_reveals_per_commit(&client, &mut utxos, &[], false).unwrap(),
+      20
+    );
+    assert!(utxos.contains_key(&outpoint));
+
+    assert_eq!(
+      Inscribe::max_reveals_per_commit(&client, &mut utxos, &[], true).unwrap(),
+      usize::MAX
+    );
+    assert!(utxos.contains_key(&outpoint));
+  }
+
+  #[test]
+  fn max_reveals_per_commit_excludes_utxos_at_mempool_chain_limit() {
+    let rpc_server = test_bitcoincore_rpc::spawn();
+    rpc_server.mine_blocks(1);
+    let client = Client::new(&rpc_server.url(), bitcoincore_rpc::Auth::None).unwrap();
+
+    let parent = unconfirmed_parent(&rpc_server, &client, 23);
+    let outpoint = OutPoint {
+      txid: parent,
+      vout: 24,
+    };
+
+    let utxos = [(outpoint, Amount::from_sat(1000))]
+      .into_iter()
+      .collect::<BTreeMap<OutPoint, Amount>>();
+
+    // the commit tx and a reveal tx would be the 25th and 26th descendants
+    let mut chained = utxos.clone();
+    assert_eq!(
+      Inscribe::max_reveals_per_commit(&client, &mut chained, &[], false).unwrap(),
+      24
+    );
+    assert!(chained.is_empty());
+
+    assert_eq!(
+      Inscribe::max_reveals_per_commit(&client, &mut utxos.clone(), &[outpoint], false)
+        .unwrap_err()
+        .to_string(),
+      format!("utxo {outpoint} has too many unconfirmed ancestors or descendants to inscribe without exceeding the mempool chain limit of 25"),
+    );
+
+    // only the commit tx is chained if it confirms before the reveal txs are
+    // broadcast
+    let mut confirms_first = utxos.clone();
+    assert_eq!(
+      Inscribe::max_reveals_per_commit(&client, &mut confirms_first, &[], true).unwrap(),
+      usize::MAX
+    );
+    assert_eq!(confirms_first, utxos);
+  }
+
+  #[test]
+  fn unconfirmed_ancestors_of_all_parents_are_counted() {
+    let input = |txid, vout| TxIn {
+      previous_output: OutPoint { txid, vout },
+      script_sig: ScriptBuf::new(),
+      sequence: Sequence::MAX,
+      witness: Witness::new(),
+    };
+
+    let commit = Transaction {
+      version: 2,
+      lock_time: LockTime::ZERO,
+      input: vec![input(txid(1), 0), input(txid(1), 1), input(txid(2), 0)],
+      output: Vec::new(),
+    };
+
+    assert_eq!(
+      Inscribe::unconfirmed_ancestors(&commit, |parent| Ok(if parent == txid(1) {
+        12
+      } else {
+        11
+      }))
+      .unwrap(),
+      23
+    );
+  }
+
+  #[test]
+  fn glob_patterns_match_file_names() {
+    let regex = Inscribe::glob_regex("*.png").unwrap();
+    assert!(regex.is_match("foo.png"));
+    assert!(!regex.is_match("foo.png.txt"));
+    assert!(!regex.is_match("foopng"));
+
+    let regex = Inscribe::glob_regex("item-?[0-9].[!t]xt").unwrap();
+    assert!(regex.is_match("item-a1.jxt"));
+    assert!(!regex.is_match("item-a1.txt"));
+    assert!(!regex.is_match("item-ab1.jxt"));
+  }
+
+  #[test]
+  fn sat_range_from_str() {
+    assert_eq!(
+      "100-200".parse::<SatRange>().unwrap(),
+      SatRange {
+        start: Sat(100),
+        end: Sat(200),
+      }
+    );
+    assert_eq!(
+      "9.0-10.0".parse::<SatRange>().unwrap().size(),
+      50 * COIN_VALUE
+    );
+    assert!("100".parse::<SatRange>().is_err());
+    assert!("200-100".parse::<SatRange>().is_err());
+    assert!("100-100".parse::<SatRange>().is_err());
+  }
 }
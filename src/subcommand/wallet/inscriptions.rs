@@ -27,7 +27,7 @@ pub(crate) struct Inscriptions {
 
 impl Inscriptions {
   pub(crate) fn run(self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
     index.update()?;
 
     let index_has_sats = index.has_sat_index()?;
@@ -35,12 +35,7 @@ impl Inscriptions {
     let unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
     let inscriptions = index.get_inscriptions_vector(unspent_outputs.clone())?;
 
-    let explorer = match options.chain() {
-      Chain::Mainnet => "https://ordinals.com/inscription/",
-      Chain::Regtest => "http://localhost/inscription/",
-      Chain::Signet => "https://signet.ordinals.com/inscription/",
-      Chain::Testnet => "https://testnet.ordinals.com/inscription/",
-    };
+    let explorer = format!("{}/inscription/", options.explorer_url()?);
 
     let mut output_with_sat = Vec::new();
     let mut output_without_sat = Vec::new();
@@ -0,0 +1,112 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Label {
+  #[clap(help = "Label <DESTINATION>, given as an address or an outpoint.")]
+  destination: Object,
+  #[clap(help = "Refer to the destination as @<LABEL>.")]
+  label: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub label: String,
+  pub destination: String,
+}
+
+impl Label {
+  pub(crate) fn run(self, options: Options) -> Result {
+    if self.label.is_empty() || self.label.starts_with('@') || self.label.contains(',') {
+      bail!(
+        "invalid label `{}`, labels must not be empty, start with `@`, or contain commas",
+        self.label
+      );
+    }
+
+    let destination = match self.destination {
+      Object::Address(address) => {
+        let address = address.require_network(options.chain().network())?;
+
+        // mirrored to Bitcoin Core, so that its own commands show the label too
+        options
+          .bitcoin_rpc_client_for_wallet_command(false)?
+          .call::<serde_json::Value>(
+            "setlabel",
+            &[address.to_string().into(), self.label.clone().into()],
+          )?;
+
+        address.to_string()
+      }
+      Object::OutPoint(outpoint) => outpoint.to_string(),
+      object => bail!("{object} is not an address or an outpoint"),
+    };
+
+    Index::open_shared(&options)?.set_label(&self.label, &destination)?;
+
+    print_json(Output {
+      label: self.label,
+      destination,
+    })?;
+
+    Ok(())
+  }
+}
+
+/// An address, or `@LABEL` for an address labeled with `ord wallet label`.
+#[derive(Clone, Debug)]
+pub(crate) enum Destination {
+  Address(Address<NetworkUnchecked>),
+  Label(String),
+}
+
+impl FromStr for Destination {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s.strip_prefix('@') {
+      Some(label) => Ok(Self::Label(label.into())),
+      None => Ok(Self::Address(s.parse()?)),
+    }
+  }
+}
+
+impl Destination {
+  pub(crate) fn resolve(&self, index: &Index, network: Network) -> Result<Address> {
+    let address = match self {
+      Self::Address(address) => address.clone(),
+      Self::Label(label) => {
+        let destination = index
+          .get_label(label)?
+          .ok_or_else(|| anyhow!("no destination is labeled @{label}"))?;
+
+        destination
+          .parse::<Address<NetworkUnchecked>>()
+          .map_err(|_| anyhow!("@{label} labels {destination}, which is not an address"))?
+      }
+    };
+
+    Ok(address.require_network(network)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_destination() {
+    assert!(matches!(
+      "@alice".parse::<Destination>().unwrap(),
+      Destination::Label(label) if label == "alice"
+    ));
+
+    assert!(matches!(
+      "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        .parse::<Destination>()
+        .unwrap(),
+      Destination::Address(_)
+    ));
+
+    assert!("alice".parse::<Destination>().is_err());
+  }
+}
@@ -0,0 +1,502 @@
+use {
+  super::*, crate::wallet::Wallet, bitcoin::Witness,
+  bitcoincore_rpc::bitcoincore_rpc_json::SignRawTransactionInput,
+  miniscript::descriptor::DescriptorPublicKey,
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Migrate {
+  #[clap(
+    long,
+    required = true,
+    help = "Move inscriptions and cardinals to <TO>, either one or more addresses, which are used in turn, or a single output descriptor, from which successive addresses are derived."
+  )]
+  to: Vec<String>,
+  #[clap(long, help = "Use fee rate of <FEE_RATE> sats/vB.")]
+  fee_rate: FeeRate,
+  #[clap(
+    long,
+    help = "Trim inscription outputs worth more than 20000 sats down to <POSTAGE>, if their inscriptions allow it. Default `10000 sats`"
+  )]
+  postage: Option<Amount>,
+  #[clap(
+    long,
+    help = "Print the planned transactions without signing or broadcasting them."
+  )]
+  dry_run: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub transactions: Vec<PlannedTransaction>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlannedTransaction {
+  pub txid: Txid,
+  pub inputs: Vec<OutPoint>,
+  pub outputs: Vec<PlannedOutput>,
+  pub fee: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlannedOutput {
+  pub address: Address<NetworkUnchecked>,
+  pub value: u64,
+  pub inscriptions: Vec<InscriptionId>,
+}
+
+enum Destinations {
+  Addresses(Vec<Address>, usize),
+  Descriptor(Descriptor<DescriptorPublicKey>, u32),
+}
+
+impl Destinations {
+  fn next(&mut self, network: Network) -> Result<Address> {
+    match self {
+      Self::Addresses(addresses, i) => {
+        let address = addresses[*i % addresses.len()].clone();
+        *i += 1;
+        Ok(address)
+      }
+      Self::Descriptor(descriptor, i) => {
+        let address = descriptor.at_derivation_index(*i)?.address(network)?;
+        *i += 1;
+        Ok(address)
+      }
+    }
+  }
+}
+
+impl Migrate {
+  /// Inscription utxos are moved in chunks, so that each transaction stays
+  /// well below the standard weight limit.
+  const MAX_INSCRIPTION_UTXOS_PER_TX: usize = 100;
+
+  /// Bitcoin Core's default mempool policy allows at most 25 txs in a chain
+  /// of unconfirmed txs. Every migration tx after the first spends the
+  /// previous one, so a migration tx that would make the chain any longer
+  /// waits for the previous one to confirm.
+  const MEMPOOL_CHAIN_LIMIT: u64 = 25;
+
+  pub(crate) fn run(self, options: Options) -> Result {
+    let network = options.chain().network();
+
+    let mut destinations = self.destinations(network)?;
+
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let mut unspent_outputs = index.get_unspent_outputs(Wallet::load(&options)?)?;
+
+    remove_frozen_utxos(&options, &mut unspent_outputs)?;
+
+    let inscriptions = index.get_inscriptions(unspent_outputs.clone())?;
+
+    let mut inscription_utxos: Vec<(OutPoint, Amount, Vec<(u64, InscriptionId)>)> = Vec::new();
+    for (satpoint, inscription_id) in &inscriptions {
+      let Some(value) = unspent_outputs.get(&satpoint.outpoint) else {
+        continue;
+      };
+
+      match inscription_utxos.last_mut() {
+        Some((outpoint, _, ids)) if *outpoint == satpoint.outpoint => {
+          ids.push((satpoint.offset, *inscription_id))
+        }
+        _ => inscription_utxos.push((
+          satpoint.outpoint,
+          *value,
+          vec![(satpoint.offset, *inscription_id)],
+        )),
+      }
+    }
+
+    let mut funding = unspent_outputs
+      .iter()
+      .filter(|(outpoint, _amount)| {
+        !inscription_utxos
+          .iter()
+          .any(|(inscribed, _, _)| inscribed == *outpoint)
+      })
+      .map(|(outpoint, amount)| (*outpoint, *amount))
+      .collect::<Vec<(OutPoint, Amount)>>();
+
+    if inscription_utxos.is_empty() && funding.is_empty() {
+      bail!("wallet contains no outputs to migrate");
+    }
+
+    let postage = self
+      .postage
+      .unwrap_or(TransactionBuilder::DEFAULT_TARGET_POSTAGE);
+
+    let chunks = if inscription_utxos.is_empty() {
+      vec![Vec::new()]
+    } else {
+      inscription_utxos
+        .chunks(Self::MAX_INSCRIPTION_UTXOS_PER_TX)
+        .map(|chunk| chunk.to_vec())
+        .collect()
+    };
+
+    let mut transactions = Vec::new();
+    let mut planned = Vec::new();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+      let last = i == chunks.len() - 1;
+
+      let mut tx = Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: chunk
+          .iter()
+          .map(|(outpoint, _, _)| *outpoint)
+          .chain(funding.iter().map(|(outpoint, _)| *outpoint))
+          .map(|previous_output| TxIn {
+            previous_output,
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::from_slice(&[&[0; 64]]),
+          })
+          .collect(),
+        output: Vec::new(),
+      };
+
+      let mut outputs = Vec::new();
+
+      // each inscription utxo's sats go to outputs in the same order they came
+      // in, so every inscription lands in its own utxo's first output
+      for (_outpoint, value, ids) in chunk {
+        let address = destinations.next(network)?;
+
+        let max_offset = ids.iter().map(|(offset, _)| *offset).max().unwrap();
+
+        let kept = if *value > TransactionBuilder::DEFAULT_MAX_POSTAGE
+          && max_offset < postage.to_sat()
+          && *value - postage >= address.script_pubkey().dust_value()
+        {
+          postage
+        } else {
+          *value
+        };
+
+        outputs.push((
+          address,
+          kept,
+          ids
+            .iter()
+            .map(|(_, id)| *id)
+            .collect::<Vec<InscriptionId>>(),
+        ));
+
+        if kept < *value {
+          outputs.push((destinations.next(network)?, *value - kept, Vec::new()));
+        }
+      }
+
+      let funding_value = funding.iter().map(|(_, amount)| *amount).sum::<Amount>();
+
+      // cardinals only leave the wallet in the last transaction, until then
+      // they fund the next one
+      let cardinal_address = if last {
+        destinations.next(network)?
+      } else {
+        get_change_address(&client, &options)?
+      };
+
+      tx.output = outputs
+        .iter()
+        .map(|(address, value, _)| TxOut {
+          script_pubkey: address.script_pubkey(),
+          value: value.to_sat(),
+        })
+        .chain(Some(TxOut {
+          script_pubkey: cardinal_address.script_pubkey(),
+          value: 0,
+        }))
+        .collect();
+
+      let fee = self.fee_rate.fee(tx.weight());
+
+      let cardinal_value = funding_value.checked_sub(fee).ok_or_else(|| {
+        anyhow!(
+          "wallet does not contain enough cardinal value to pay the {fee} fee of migration transaction {}",
+          i + 1
+        )
+      })?;
+
+      let fee = if cardinal_value >= cardinal_address.script_pubkey().dust_value() {
+        tx.output.last_mut().unwrap().value = cardinal_value.to_sat();
+        outputs.push((cardinal_address, cardinal_value, Vec::new()));
+        fee
+      } else if last {
+        tx.output.pop();
+        funding_value
+      } else {
+        bail!(
+          "wallet does not contain enough cardinal value to fund migration transaction {}",
+          i + 2
+        );
+      };
+
+      for input in &mut tx.input {
+        input.witness = Witness::new();
+      }
+
+      funding = if last {
+        Vec::new()
+      } else {
+        vec![(
+          OutPoint {
+            txid: tx.txid(),
+            vout: (tx.output.len() - 1).try_into().unwrap(),
+          },
+          cardinal_value,
+        )]
+      };
+
+      planned.push(PlannedTransaction {
+        txid: tx.txid(),
+        inputs: tx.input.iter().map(|input| input.previous_output).collect(),
+        outputs: outputs
+          .into_iter()
+          .map(|(address, value, inscriptions)| PlannedOutput {
+            address: address.into_unchecked(),
+            value: value.to_sat(),
+            inscriptions,
+          })
+          .collect(),
+        fee: fee.to_sat(),
+      });
+
+      transactions.push(tx);
+    }
+
+    if !self.dry_run {
+      let mut mempool = Self::mempool(&client)?;
+      let mut previous: Option<(&Transaction, u64)> = None;
+
+      for (i, tx) in transactions.iter().enumerate() {
+        let mut ancestors = Self::unconfirmed_ancestors(
+          tx,
+          previous.map(|(previous, chain)| (previous.txid(), chain)),
+          |txid| Self::ancestor_count(&client, &mempool, txid),
+        )?;
+
+        if ancestors >= Self::MEMPOOL_CHAIN_LIMIT {
+          if let Some((previous, _)) = previous {
+            Self::wait_for_confirmation(&client, previous.txid())?;
+
+            mempool = Self::mempool(&client)?;
+
+            ancestors = Self::unconfirmed_ancestors(tx, None, |txid| {
+              Self::ancestor_count(&client, &mempool, txid)
+            })?;
+          }
+
+          if ancestors >= Self::MEMPOOL_CHAIN_LIMIT {
+            bail!(
+              "migration transaction {} would have {ancestors} unconfirmed ancestors, more than the mempool chain limit of {} allows, wait for the wallet's transactions to confirm and run `ord wallet migrate` again",
+              i + 1,
+              Self::MEMPOOL_CHAIN_LIMIT,
+            );
+          }
+        }
+
+        // the funding input of every transaction after the first spends the
+        // previous one, which the wallet may not know about yet
+        let prevtxs = previous.map(|(previous, _)| {
+          let vout = previous.output.len() - 1;
+          vec![SignRawTransactionInput {
+            txid: previous.txid(),
+            vout: vout.try_into().unwrap(),
+            script_pub_key: previous.output[vout].script_pubkey.clone(),
+            amount: Some(Amount::from_sat(previous.output[vout].value)),
+            redeem_script: None,
+          }]
+        });
+
+        let signed = client.sign_raw_transaction_with_wallet(tx, prevtxs.as_deref(), None)?;
+
+        if !signed.complete {
+          bail!("error signing migration transaction: {:?}", signed.errors);
+        }
+
+        client.send_raw_transaction(&signed.hex)?;
+
+        audit::record(&options, &client, &signed.hex);
+
+        previous = Some((tx, ancestors + 1));
+      }
+    }
+
+    print_json(Output {
+      transactions: planned,
+    })?;
+
+    Ok(())
+  }
+
+  fn mempool(client: &Client) -> Result<HashSet<Txid>> {
+    Ok(client.get_raw_mempool()?.into_iter().collect())
+  }
+
+  /// Number of unconfirmed txs that `txid` and its ancestors make up.
+  fn ancestor_count(client: &Client, mempool: &HashSet<Txid>, txid: Txid) -> Result<u64> {
+    if mempool.contains(&txid) {
+      Ok(client.get_mempool_entry(&txid)?.ancestor_count)
+    } else {
+      Ok(0)
+    }
+  }
+
+  /// Number of unconfirmed ancestors `tx` would have. `previous` is the
+  /// previous migration tx, which may not be in the mempool yet, and the
+  /// length of the chain it ends. Ancestors that parents share are counted
+  /// for each, so this may overestimate, but never underestimates.
+  fn unconfirmed_ancestors(
+    tx: &Transaction,
+    previous: Option<(Txid, u64)>,
+    mut ancestor_count: impl FnMut(Txid) -> Result<u64>,
+  ) -> Result<u64> {
+    let parents = tx
+      .input
+      .iter()
+      .map(|input| input.previous_output.txid)
+      .collect::<BTreeSet<Txid>>();
+
+    let mut ancestors = 0;
+
+    for parent in parents {
+      ancestors += match previous {
+        Some((txid, chain)) if txid == parent => chain,
+        _ => ancestor_count(parent)?,
+      };
+    }
+
+    Ok(ancestors)
+  }
+
+  fn wait_for_confirmation(client: &Client, txid: Txid) -> Result {
+    eprint!("[waiting for migration transaction {txid} to confirm] ");
+
+    loop {
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        eprintln!();
+        bail!(
+          "interrupted while waiting for migration transaction {txid} to confirm, run `ord wallet migrate` again once it confirms to migrate the rest"
+        );
+      }
+
+      if client
+        .get_transaction(&txid, Some(false))?
+        .info
+        .confirmations
+        > 0
+      {
+        eprintln!("[confirmed]");
+        return Ok(());
+      }
+
+      eprint!(".");
+
+      thread::sleep(if integration_test() {
+        Duration::from_millis(100)
+      } else {
+        Duration::from_secs(60)
+      });
+    }
+  }
+
+  fn destinations(&self, network: Network) -> Result<Destinations> {
+    let addresses = self
+      .to
+      .iter()
+      .map(|to| {
+        to.parse::<Address<NetworkUnchecked>>()
+          .ok()
+          .map(|address| address.require_network(network))
+          .transpose()
+      })
+      .collect::<Result<Vec<Option<Address>>, bitcoin::address::Error>>()?;
+
+    if addresses.iter().all(Option::is_some) {
+      return Ok(Destinations::Addresses(
+        addresses.into_iter().flatten().collect(),
+        0,
+      ));
+    }
+
+    if self.to.len() != 1 {
+      bail!("--to must be one or more addresses, or a single descriptor");
+    }
+
+    let descriptor = self.to[0]
+      .parse::<Descriptor<DescriptorPublicKey>>()
+      .with_context(|| format!("--to {} is not an address or a descriptor", self.to[0]))?;
+
+    Ok(Destinations::Descriptor(descriptor, 0))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spending(parents: &[Txid]) -> Transaction {
+    Transaction {
+      version: 2,
+      lock_time: LockTime::ZERO,
+      input: parents
+        .iter()
+        .enumerate()
+        .map(|(vout, txid)| TxIn {
+          previous_output: OutPoint {
+            txid: *txid,
+            vout: vout.try_into().unwrap(),
+          },
+          script_sig: ScriptBuf::new(),
+          sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+          witness: Witness::new(),
+        })
+        .collect(),
+      output: Vec::new(),
+    }
+  }
+
+  #[test]
+  fn confirmed_parents_are_not_ancestors() {
+    assert_eq!(
+      Migrate::unconfirmed_ancestors(&spending(&[txid(1), txid(2)]), None, |_| Ok(0)).unwrap(),
+      0
+    );
+  }
+
+  #[test]
+  fn ancestors_of_each_unconfirmed_parent_are_counted_once() {
+    assert_eq!(
+      Migrate::unconfirmed_ancestors(&spending(&[txid(1), txid(1), txid(2)]), None, |parent| {
+        Ok(if parent == txid(1) { 3 } else { 2 })
+      })
+      .unwrap(),
+      5
+    );
+  }
+
+  #[test]
+  fn previous_migration_tx_counts_as_its_chain() {
+    assert_eq!(
+      Migrate::unconfirmed_ancestors(
+        &spending(&[txid(1), txid(2)]),
+        Some((txid(1), 24)),
+        |parent| {
+          assert_eq!(parent, txid(2));
+          Ok(0)
+        }
+      )
+      .unwrap(),
+      24
+    );
+  }
+}
@@ -0,0 +1,87 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) struct Mine {
+  #[clap(
+    long,
+    default_value = "1",
+    help = "Mine <BLOCKS> blocks to the wallet on regtest, or wait for <BLOCKS> new blocks on other chains."
+  )]
+  blocks: u64,
+  #[clap(
+    long,
+    help = "Mine or wait until <TXID> has <BLOCKS> confirmations instead."
+  )]
+  txid: Option<Txid>,
+  #[clap(
+    long,
+    default_value = "10",
+    help = "Check for new blocks every <POLL_INTERVAL> seconds while waiting."
+  )]
+  poll_interval: u64,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Output {
+  pub blocks: Vec<BlockHash>,
+  pub height: u64,
+}
+
+impl Mine {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let address = if options.chain() == Chain::Regtest {
+      Some(
+        client
+          .get_new_address(None, Some(bitcoincore_rpc::json::AddressType::Bech32m))?
+          .require_network(Network::Regtest)?,
+      )
+    } else {
+      None
+    };
+
+    let start = client.get_block_count()?;
+
+    loop {
+      let done = match self.txid {
+        Some(txid) => {
+          let confirmations = client.get_transaction(&txid, None)?.info.confirmations;
+
+          if confirmations < 0 {
+            bail!("transaction {txid} conflicts with a confirmed transaction");
+          }
+
+          confirmations.unsigned_abs() >= self.blocks.try_into().unwrap_or(u32::MAX)
+        }
+        None => client.get_block_count()? >= start + self.blocks,
+      };
+
+      if done {
+        break;
+      }
+
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        bail!("interrupted while waiting for blocks");
+      }
+
+      match &address {
+        Some(address) => {
+          client.generate_to_address(1, address)?;
+        }
+        None => thread::sleep(Duration::from_secs(self.poll_interval)),
+      }
+    }
+
+    let height = client.get_block_count()?;
+
+    print_json(Output {
+      blocks: (start + 1..=height)
+        .map(|height| client.get_block_hash(height))
+        .collect::<Result<Vec<BlockHash>, bitcoincore_rpc::Error>>()?,
+      height,
+    })?;
+
+    Ok(())
+  }
+}
@@ -7,7 +7,7 @@ pub struct Output {
 }
 
 pub(crate) fn run(options: Options) -> Result {
-  let index = Index::open(&options)?;
+  let index = Index::open_shared(&options)?;
   index.update()?;
 
   let mut outputs = Vec::new();
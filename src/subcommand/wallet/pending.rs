@@ -0,0 +1,164 @@
+use super::*;
+
+/// A commit and its reveals, as broadcast by `ord wallet inscribe`. Kept in
+/// the wallet's pending file until the reveals confirm.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Broadcast {
+  pub(crate) commit: Txid,
+  /// Signed reveals, so that those that fail can be broadcast again.
+  pub(crate) reveals: Vec<String>,
+  /// Index of the inscription in each reveal.
+  pub(crate) index: u32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+  /// The reveal is in the mempool.
+  Unconfirmed,
+  /// The reveal isn't in the mempool, but its commit is in the mempool or
+  /// confirmed.
+  Failed,
+  /// Neither the reveal nor its commit are in the mempool.
+  Dropped,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+  /// Spend the reveal's anchor output with `ord wallet bump-fee`.
+  Bump,
+  /// Broadcast `raw_reveal` again.
+  Rebroadcast,
+  /// Spend the commit output back to the wallet by passing it with `--utxo`,
+  /// which the wallet can sign for with the recovery key.
+  Recover,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingInscription {
+  pub inscription: InscriptionId,
+  pub commit: Txid,
+  pub reveal: Txid,
+  pub status: Status,
+  pub actions: Vec<Action>,
+  pub raw_reveal: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub pending: Vec<PendingInscription>,
+}
+
+pub(crate) fn run(options: Options) -> Result {
+  let index = Index::open_shared(&options)?;
+  index.update()?;
+
+  let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+  let mut pending = Vec::new();
+  let mut unconfirmed = Vec::new();
+
+  for broadcast in load(&options)? {
+    let commit_confirmations = client
+      .get_transaction(&broadcast.commit, Some(false))
+      .map(|tx| tx.info.confirmations)
+      .unwrap_or_default();
+
+    // the commit conflicts with a confirmed transaction, so its reveals can
+    // never confirm
+    if commit_confirmations < 0 {
+      continue;
+    }
+
+    let commit_available =
+      commit_confirmations > 0 || client.get_mempool_entry(&broadcast.commit).is_ok();
+
+    let count = pending.len();
+
+    for raw_reveal in &broadcast.reveals {
+      let reveal = consensus::deserialize::<Transaction>(&hex::decode(raw_reveal)?)?;
+
+      let inscription = InscriptionId {
+        txid: reveal.txid(),
+        index: broadcast.index,
+      };
+
+      if index.get_inscription_entry(inscription)?.is_some() {
+        continue;
+      }
+
+      let (status, actions) = if client.get_mempool_entry(&inscription.txid).is_ok() {
+        let anchor = reveal
+          .output
+          .iter()
+          .any(|output| output.script_pubkey.as_bytes() == P2A_SCRIPT_PUBKEY);
+
+        let actions = anchor.then_some(Action::Bump).into_iter().collect();
+
+        (Status::Unconfirmed, actions)
+      } else if commit_available {
+        (Status::Failed, vec![Action::Rebroadcast, Action::Recover])
+      } else {
+        (Status::Dropped, Vec::new())
+      };
+
+      pending.push(PendingInscription {
+        inscription,
+        commit: broadcast.commit,
+        reveal: inscription.txid,
+        raw_reveal: (status == Status::Failed).then(|| raw_reveal.clone()),
+        status,
+        actions,
+      });
+    }
+
+    if pending.len() > count {
+      unconfirmed.push(broadcast);
+    }
+  }
+
+  save(&options, &unconfirmed)?;
+
+  print_json(Output { pending })?;
+
+  Ok(())
+}
+
+/// Record a broadcast, so that `ord wallet pending` can follow it until its
+/// reveals confirm.
+pub(crate) fn record(options: &Options, broadcast: Broadcast) -> Result {
+  let mut broadcasts = load(options)?;
+  broadcasts.push(broadcast);
+  save(options, &broadcasts)
+}
+
+pub(crate) fn load(options: &Options) -> Result<Vec<Broadcast>> {
+  let path = path(options)?;
+
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+
+  serde_json::from_reader(File::open(&path)?)
+    .with_context(|| format!("failed to parse pending file {}", path.display()))
+}
+
+fn save(options: &Options, broadcasts: &[Broadcast]) -> Result {
+  let path = path(options)?;
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  fs::write(&path, serde_json::to_string_pretty(broadcasts)?)
+    .with_context(|| format!("failed to write pending file {}", path.display()))
+}
+
+fn path(options: &Options) -> Result<PathBuf> {
+  Ok(
+    options
+      .data_dir()?
+      .join(format!("pending-{}.json", options.wallet)),
+  )
+}
@@ -0,0 +1,241 @@
+use super::*;
+
+/// A range of sats spent by a transaction, and where it ends up.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SatFlow {
+  pub start: u64,
+  pub end: u64,
+  /// The rarity of the first sat in the range, the only one that can be rare.
+  pub rarity: Rarity,
+  pub input: SatPoint,
+  /// `None` if the range is paid to miners as fee.
+  pub output: Option<SatPoint>,
+  pub change: bool,
+}
+
+/// Fail early, before anything is broadcast, if the sat flow of a
+/// transaction can't be traced.
+pub(crate) fn require_sat_index(index: &Index) -> Result {
+  if !index.has_sat_index()? {
+    bail!("--sat-flow requires index created with `--index-sats` flag");
+  }
+
+  Ok(())
+}
+
+/// Trace the sats spent by `transactions`, each of which may spend outputs
+/// of earlier ones, to the outputs they are sent to, or to fees. Outputs
+/// paying to `change` are marked as change.
+pub(crate) fn trace(
+  index: &Index,
+  transactions: &[&Transaction],
+  change: &[ScriptBuf],
+) -> Result<Vec<SatFlow>> {
+  trace_with(transactions, change, |outpoint| {
+    match index.list(outpoint)? {
+      Some(List::Unspent(ranges)) => Ok(ranges),
+      Some(List::Spent) => bail!("input {outpoint} has already been spent"),
+      None => bail!("input {outpoint} is not in the index"),
+    }
+  })
+}
+
+fn trace_with(
+  transactions: &[&Transaction],
+  change: &[ScriptBuf],
+  input_ranges: impl Fn(OutPoint) -> Result<Vec<(u64, u64)>>,
+) -> Result<Vec<SatFlow>> {
+  let mut output_ranges = BTreeMap::<OutPoint, Vec<(u64, u64)>>::new();
+  let mut flows = Vec::new();
+
+  for tx in transactions {
+    let txid = tx.txid();
+
+    let mut pending = VecDeque::new();
+    for input in &tx.input {
+      let outpoint = input.previous_output;
+
+      let ranges = match output_ranges.remove(&outpoint) {
+        Some(ranges) => ranges,
+        None => input_ranges(outpoint)?,
+      };
+
+      let mut offset = 0;
+      for (start, end) in ranges {
+        pending.push_back((SatPoint { outpoint, offset }, start, end));
+        offset += end - start;
+      }
+    }
+
+    for (vout, output) in tx.output.iter().enumerate() {
+      let outpoint = OutPoint {
+        txid,
+        vout: vout.try_into().unwrap(),
+      };
+
+      let mut ranges = Vec::new();
+      let mut offset = 0;
+      while offset < output.value {
+        let (input, start, end) = pending
+          .pop_front()
+          .ok_or_else(|| anyhow!("{txid} outputs more sats than it spends"))?;
+
+        let end_here = cmp::min(end, start + output.value - offset);
+
+        if end_here < end {
+          pending.push_front((
+            SatPoint {
+              outpoint: input.outpoint,
+              offset: input.offset + end_here - start,
+            },
+            end_here,
+            end,
+          ));
+        }
+
+        flows.push(SatFlow {
+          start,
+          end: end_here,
+          rarity: Sat(start).rarity(),
+          input,
+          output: Some(SatPoint { outpoint, offset }),
+          change: change.contains(&output.script_pubkey),
+        });
+
+        ranges.push((start, end_here));
+        offset += end_here - start;
+      }
+
+      output_ranges.insert(outpoint, ranges);
+    }
+
+    for (input, start, end) in pending {
+      flows.push(SatFlow {
+        start,
+        end,
+        rarity: Sat(start).rarity(),
+        input,
+        output: None,
+        change: false,
+      });
+    }
+  }
+
+  Ok(flows)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sats_flow_to_outputs_then_fees() {
+    let change = change(0).script_pubkey();
+
+    let tx = Transaction {
+      version: 1,
+      lock_time: LockTime::ZERO,
+      input: vec![tx_in(outpoint(1)), tx_in(outpoint(2))],
+      output: vec![
+        TxOut {
+          value: 150,
+          script_pubkey: recipient().script_pubkey(),
+        },
+        TxOut {
+          value: 40,
+          script_pubkey: change.clone(),
+        },
+      ],
+    };
+
+    let txid = tx.txid();
+
+    let flows = trace_with(&[&tx], &[change], |previous_output| {
+      Ok(if previous_output == outpoint(1) {
+        vec![(0, 100)]
+      } else {
+        vec![(1000, 1100)]
+      })
+    })
+    .unwrap();
+
+    let output = |vout, offset| {
+      Some(SatPoint {
+        outpoint: OutPoint { txid, vout },
+        offset,
+      })
+    };
+
+    pretty_assert_eq!(
+      flows,
+      vec![
+        SatFlow {
+          start: 0,
+          end: 100,
+          rarity: Rarity::Mythic,
+          input: satpoint(1, 0),
+          output: output(0, 0),
+          change: false,
+        },
+        SatFlow {
+          start: 1000,
+          end: 1050,
+          rarity: Rarity::Common,
+          input: satpoint(2, 0),
+          output: output(0, 100),
+          change: false,
+        },
+        SatFlow {
+          start: 1050,
+          end: 1090,
+          rarity: Rarity::Common,
+          input: satpoint(2, 50),
+          output: output(1, 0),
+          change: true,
+        },
+        SatFlow {
+          start: 1090,
+          end: 1100,
+          rarity: Rarity::Common,
+          input: satpoint(2, 90),
+          output: None,
+          change: false,
+        },
+      ],
+    );
+  }
+
+  #[test]
+  fn sats_flow_through_chained_transactions() {
+    let parent = Transaction {
+      version: 1,
+      lock_time: LockTime::ZERO,
+      input: vec![tx_in(outpoint(1))],
+      output: vec![TxOut {
+        value: 90,
+        script_pubkey: recipient().script_pubkey(),
+      }],
+    };
+
+    let child = Transaction {
+      version: 1,
+      lock_time: LockTime::ZERO,
+      input: vec![tx_in(OutPoint {
+        txid: parent.txid(),
+        vout: 0,
+      })],
+      output: vec![TxOut {
+        value: 80,
+        script_pubkey: recipient().script_pubkey(),
+      }],
+    };
+
+    let flows = trace_with(&[&parent, &child], &[], |_| Ok(vec![(500, 600)])).unwrap();
+
+    assert_eq!(flows.len(), 4);
+    assert_eq!((flows[2].start, flows[2].end), (500, 580));
+    assert_eq!(flows[2].input.outpoint.txid, parent.txid());
+    assert_eq!((flows[3].start, flows[3].end), (580, 590));
+    assert_eq!(flows[3].output, None);
+  }
+}
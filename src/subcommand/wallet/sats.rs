@@ -6,7 +6,7 @@ pub(crate) struct Sats {
     long,
     help = "Find satoshis listed in first column of tab-separated value file <TSV>."
   )]
-  tsv: Option<PathBuf>,
+  pub(crate) tsv: Option<PathBuf>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,7 +25,7 @@ pub struct OutputRare {
 
 impl Sats {
   pub(crate) fn run(&self, options: Options) -> Result {
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
     index.update()?;
 
     let utxos = index.get_unspent_output_ranges(Wallet::load(&options)?)?;
@@ -60,7 +60,9 @@ impl Sats {
   }
 }
 
-fn rare_sats(utxos: Vec<(OutPoint, Vec<(u64, u64)>)>) -> Vec<(OutPoint, Sat, u64, Rarity)> {
+pub(crate) fn rare_sats(
+  utxos: Vec<(OutPoint, Vec<(u64, u64)>)>,
+) -> Vec<(OutPoint, Sat, u64, Rarity)> {
   utxos
     .into_iter()
     .flat_map(|(outpoint, sat_ranges)| {
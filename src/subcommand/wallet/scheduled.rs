@@ -0,0 +1,231 @@
+use super::*;
+
+/// Seconds between fee estimates while waiting for the fee rate to drop.
+const POLL_INTERVAL: u64 = 10;
+
+/// A commit and its reveals, signed by `ord wallet inscribe
+/// --broadcast-when-fee-below` and waiting for the fee rate to drop. Kept in
+/// the wallet's scheduled file until the wait is over, so that it can be
+/// resumed with `ord wallet scheduled resume` if ord is stopped.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Broadcast {
+  pub(crate) commit: Txid,
+  pub(crate) raw_commit: String,
+  pub(crate) reveals: Vec<String>,
+  /// Index of the inscription in each reveal.
+  pub(crate) index: u32,
+  /// In sats/vB.
+  pub(crate) fee_rate_below: f64,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) enum Scheduled {
+  #[clap(about = "List inscriptions waiting for the fee rate to drop")]
+  List,
+  #[clap(about = "Wait for the fee rate to drop, and broadcast scheduled inscriptions")]
+  Resume(Resume),
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Resume {
+  #[clap(long, help = "Give up after <TIMEOUT> seconds.")]
+  timeout: Option<u64>,
+  #[clap(
+    long,
+    requires = "fee_rate_below",
+    help = "Schedule the transactions in <DUMP>, the output of `ord wallet inscribe --dump`, before resuming."
+  )]
+  dump: Option<PathBuf>,
+  #[clap(
+    long,
+    requires = "dump",
+    help = "Broadcast the transactions in <DUMP> once the fee rate drops below <FEE_RATE_BELOW> sats/vB."
+  )]
+  fee_rate_below: Option<FeeRate>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledCommit {
+  pub commit: Txid,
+  pub reveals: Vec<Txid>,
+  pub fee_rate_below: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub scheduled: Vec<ScheduledCommit>,
+}
+
+impl Scheduled {
+  pub(crate) fn run(self, options: Options) -> Result {
+    match self {
+      Self::List => print_json(Output {
+        scheduled: load(&options)?
+          .iter()
+          .map(ScheduledCommit::from_broadcast)
+          .collect::<Result<Vec<ScheduledCommit>>>()?,
+      }),
+      Self::Resume(resume) => resume.run(options),
+    }
+  }
+}
+
+impl ScheduledCommit {
+  fn from_broadcast(broadcast: &Broadcast) -> Result<Self> {
+    Ok(Self {
+      commit: broadcast.commit,
+      reveals: broadcast
+        .reveals
+        .iter()
+        .map(|reveal| -> Result<Txid> {
+          Ok(consensus::deserialize::<Transaction>(&hex::decode(reveal)?)?.txid())
+        })
+        .collect::<Result<Vec<Txid>>>()?,
+      fee_rate_below: broadcast.fee_rate_below,
+    })
+  }
+}
+
+impl Resume {
+  fn run(self, options: Options) -> Result {
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    if let (Some(path), Some(fee_rate_below)) = (&self.dump, self.fee_rate_below) {
+      for dump in dump::load(path)? {
+        let raw_commit = dump.commit().to_string();
+
+        record(
+          &options,
+          Broadcast {
+            commit: consensus::deserialize::<Transaction>(&hex::decode(&raw_commit)?)?.txid(),
+            raw_commit,
+            reveals: dump.reveals(),
+            index: dump.index(),
+            fee_rate_below: fee_rate_below.rate(),
+          },
+        )?;
+      }
+    }
+
+    let start = Instant::now();
+
+    let mut broadcast_commits = Vec::new();
+
+    for broadcast in load(&options)? {
+      wait_for_fee_rate(
+        &client,
+        broadcast.fee_rate_below,
+        self
+          .timeout
+          .map(|timeout| timeout.saturating_sub(start.elapsed().as_secs())),
+      )?;
+
+      broadcast_commits.push(ScheduledCommit::from_broadcast(&broadcast)?);
+
+      remove(&options, broadcast.commit)?;
+
+      super::broadcast(
+        &client,
+        &options,
+        &hex::decode(&broadcast.raw_commit)?,
+        None,
+      )
+      .context("Failed to send commit transaction")?;
+
+      for reveal in &broadcast.reveals {
+        super::broadcast(&client, &options, &hex::decode(reveal)?, None)
+          .context("Failed to send reveal transaction")?;
+      }
+
+      pending::record(
+        &options,
+        pending::Broadcast {
+          commit: broadcast.commit,
+          reveals: broadcast.reveals,
+          index: broadcast.index,
+        },
+      )?;
+    }
+
+    print_json(Output {
+      scheduled: broadcast_commits,
+    })
+  }
+}
+
+/// Wait until Bitcoin Core estimates a fee rate for confirmation in the next
+/// block below `fee_rate_below` sats/vB, or `timeout` seconds have passed.
+pub(crate) fn wait_for_fee_rate(
+  client: &Client,
+  fee_rate_below: f64,
+  timeout: Option<u64>,
+) -> Result {
+  let start = Instant::now();
+
+  loop {
+    // without enough data, Bitcoin Core returns no estimate
+    if let Some(fee_rate) = client.estimate_smart_fee(1, None)?.fee_rate {
+      let fee_rate = fee_rate.to_sat() as f64 / 1000.0;
+
+      if fee_rate < fee_rate_below {
+        return Ok(());
+      }
+
+      eprintln!("[fee rate is {fee_rate} sats/vB, waiting for it to drop below {fee_rate_below}]");
+    }
+
+    if let Some(timeout) = timeout {
+      if start.elapsed() >= Duration::from_secs(timeout) {
+        bail!("fee rate did not drop below {fee_rate_below} sats/vB within {timeout} seconds");
+      }
+    }
+
+    if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+      bail!("interrupted while waiting for the fee rate to drop below {fee_rate_below} sats/vB");
+    }
+
+    thread::sleep(Duration::from_secs(POLL_INTERVAL));
+  }
+}
+
+pub(crate) fn record(options: &Options, broadcast: Broadcast) -> Result {
+  let mut broadcasts = load(options)?;
+  broadcasts.push(broadcast);
+  save(options, &broadcasts)
+}
+
+pub(crate) fn remove(options: &Options, commit: Txid) -> Result {
+  let mut broadcasts = load(options)?;
+  broadcasts.retain(|broadcast| broadcast.commit != commit);
+  save(options, &broadcasts)
+}
+
+fn load(options: &Options) -> Result<Vec<Broadcast>> {
+  let path = path(options)?;
+
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+
+  serde_json::from_reader(File::open(&path)?)
+    .with_context(|| format!("failed to parse scheduled file {}", path.display()))
+}
+
+fn save(options: &Options, broadcasts: &[Broadcast]) -> Result {
+  let path = path(options)?;
+
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+
+  fs::write(&path, serde_json::to_string_pretty(broadcasts)?)
+    .with_context(|| format!("failed to write scheduled file {}", path.display()))
+}
+
+fn path(options: &Options) -> Result<PathBuf> {
+  Ok(
+    options
+      .data_dir()?
+      .join(format!("scheduled-{}.json", options.wallet)),
+  )
+}
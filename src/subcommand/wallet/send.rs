@@ -1,14 +1,30 @@
-use {super::*, crate::wallet::Wallet};
+use {
+  super::simulation::{self, Simulation},
+  super::*,
+  crate::wallet::Wallet,
+};
 
 #[derive(Debug, Parser)]
+#[clap(allow_missing_positional = true)]
 pub(crate) struct Send {
-  address: Address<NetworkUnchecked>,
+  #[clap(required_unless_present = "destination-script")]
+  address: Option<label::Destination>,
   outgoing: Outgoing,
+  #[clap(
+    long,
+    help = "Send to the raw scriptPubKey <DESTINATION_SCRIPT>, given in hex, instead of to an address. Only works when sending inscriptions or satpoints."
+  )]
+  destination_script: Option<String>,
   #[clap(
     long,
     help = "Consider spending outpoint <UTXO>, even if it is unconfirmed or contains inscriptions"
   )]
   utxo: Vec<OutPoint>,
+  #[clap(
+    long,
+    help = "Don't spend outpoint <AVOID_UTXO>, even if it is cardinal. Only works when sending inscriptions or satpoints."
+  )]
+  avoid_utxo: Vec<OutPoint>,
   #[clap(
     long,
     help = "Only spend outpoints given with --utxo when sending inscriptions or satpoints"
@@ -35,30 +51,248 @@ pub(crate) struct Send {
     help = "Use at most <MAX_INPUTS> inputs to build the transaction sending a satpoint or an inscription."
   )]
   pub(crate) max_inputs: Option<usize>,
+  #[clap(
+    long,
+    help = "Set the lock time of the transaction to <LOCKTIME>. Values below 500000000 are block heights, others are unix timestamps. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) locktime: Option<u32>,
+  #[clap(
+    long,
+    help = "Set the sequence number of all transaction inputs to <SEQUENCE>. Defaults to 4294967293, which signals RBF. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) sequence: Option<u32>,
+  #[clap(
+    long,
+    arg_enum,
+    default_value = "closest",
+    help = "Select cardinal utxos to fund the transaction with <COIN_SELECTION>. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) coin_selection: CoinSelection,
+  #[clap(
+    long,
+    help = "Do not fund the transaction with unconfirmed utxos. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) avoid_unconfirmed: bool,
+  #[clap(
+    long,
+    help = "Only fund the transaction with utxos that have at least <MIN_CONFIRMATIONS> confirmations. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) min_confirmations: Option<u32>,
+  #[clap(
+    long,
+    help = "Unlock the inscription outputs locked while sending cardinals once the transaction is sent, restoring the previous lock state."
+  )]
+  pub(crate) unlock_after: bool,
+  #[clap(
+    long,
+    help = "Don't sign or broadcast, but record the transaction in the wallet's simulated state, which later --simulate commands build on. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) simulate: bool,
+  #[clap(
+    long,
+    help = "Print the txid along with where each range of sats spent by the transaction ends up, in an output, in change, or in fees. Requires an index created with `--index-sats`."
+  )]
+  pub(crate) sat_flow: bool,
+  #[clap(
+    long,
+    help = "Don't spend utxos containing sats of rarity <PROTECT_RARITY> or rarer on fees or change. Requires an index created with `--index-sats`."
+  )]
+  pub(crate) protect_rarity: Option<Rarity>,
+  #[clap(
+    long,
+    help = "Allow spending <ALLOW_RARE_UTXO> on fees or change, even though it contains sats protected by --protect-rarity."
+  )]
+  pub(crate) allow_rare_utxo: Vec<OutPoint>,
+  #[clap(
+    long,
+    help = "Keep <RESERVE> in the wallet, in a new change output, when sending all or max, like `all-<RESERVE>`."
+  )]
+  pub(crate) reserve: Option<Amount>,
+  #[clap(
+    long,
+    help = "Split change into <CHANGE_SPLIT> outputs of equal value, so that later transactions can spend them in parallel. Only works when sending inscriptions or satpoints."
+  )]
+  pub(crate) change_split: Option<usize>,
+  #[clap(
+    long,
+    help = "Allow sending inscriptions and satpoints to P2PKH and P2SH addresses, on which many wallets and marketplaces don't track inscriptions."
+  )]
+  pub(crate) allow_legacy_destination: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Output {
   pub transaction: Txid,
+  pub locked: Vec<OutPoint>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SatFlowOutput {
+  pub txid: Txid,
+  pub sat_flow: Vec<sat_flow::SatFlow>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SendAllOutput {
   pub txid: Txid,
   pub complete: bool,
+  #[serde(default)]
+  pub locked: Vec<OutPoint>,
 }
 
 impl Send {
-  pub(crate) fn run(self, options: Options) -> Result {
-    let address = self
-      .address
-      .clone()
-      .require_network(options.chain().network())?;
+  pub(crate) fn run(mut self, options: Options) -> Result {
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
 
-    let index = Index::open(&options)?;
+    let index = Index::open_shared(&options)?;
     index.update()?;
 
-    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+    if let Outgoing::Sat(sat) = self.outgoing {
+      self.outgoing = Outgoing::SatPoint(Self::find_sat(&index, &options, sat)?);
+    }
+
+    let destination_script = match &self.destination_script {
+      Some(destination_script) => {
+        if self.address.is_some() {
+          bail!("Cannot use both an address and --destination-script");
+        }
+
+        if !matches!(
+          self.outgoing,
+          Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+        ) {
+          bail!("--destination-script only works when sending inscriptions or satpoints");
+        }
+
+        Some(
+          ScriptBuf::from_hex(destination_script)
+            .context("--destination-script is not a valid hex script")?,
+        )
+      }
+      None => None,
+    };
+
+    // when sending to a raw script the transaction is built paying to a wallet
+    // address, whose output script is swapped for the requested one afterwards
+    let address = match &self.address {
+      Some(address) => address.resolve(&index, options.chain().network())?,
+      None => get_change_address(&client, &options)?,
+    };
+
+    if matches!(
+      self.outgoing,
+      Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+    ) {
+      check_legacy_destination(
+        destination_script
+          .as_deref()
+          .unwrap_or(&address.script_pubkey()),
+        options.chain().network(),
+        self.allow_legacy_destination,
+      )?;
+    }
+
+    if (self.locktime.is_some() || self.sequence.is_some())
+      && !matches!(
+        self.outgoing,
+        Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+      )
+    {
+      bail!("--locktime and --sequence only work when sending inscriptions or satpoints");
+    }
+
+    let min_confirmations = self
+      .min_confirmations
+      .unwrap_or_default()
+      .max(self.avoid_unconfirmed.into());
+
+    if min_confirmations > 0
+      && !matches!(
+        self.outgoing,
+        Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+      )
+    {
+      bail!(
+        "--avoid-unconfirmed and --min-confirmations only work when sending inscriptions or satpoints"
+      );
+    }
+
+    if self.simulate
+      && !matches!(
+        self.outgoing,
+        Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+      )
+    {
+      bail!("--simulate only works when sending inscriptions or satpoints");
+    }
+
+    if self.change_split.is_some()
+      && !matches!(
+        self.outgoing,
+        Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+      )
+    {
+      bail!("--change-split only works when sending inscriptions or satpoints");
+    }
+
+    if self.protect_rarity.is_some()
+      && !matches!(
+        self.outgoing,
+        Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+      )
+    {
+      bail!("--protect-rarity only works when sending inscriptions or satpoints");
+    }
+
+    if self.sat_flow {
+      if self.simulate
+        || !matches!(
+          self.outgoing,
+          Outgoing::SatPoint(_) | Outgoing::InscriptionId(_)
+        )
+        || !private_keys_enabled(&client)?
+      {
+        bail!("--sat-flow only works when signing and broadcasting inscriptions or satpoints");
+      }
+
+      sat_flow::require_sat_index(&index)?;
+    }
+
+    let reserve = match self.outgoing {
+      Outgoing::AllMinus(reserve) => {
+        if self.reserve.is_some() {
+          bail!("Cannot use both all-<RESERVE> and --reserve");
+        }
+
+        Some(reserve)
+      }
+      Outgoing::All | Outgoing::Max => self.reserve,
+      _ => {
+        if self.reserve.is_some() {
+          bail!("--reserve only works when sending all or max");
+        }
+
+        None
+      }
+    };
+
+    let lock_time = LockTime::from_consensus(self.locktime.unwrap_or_default());
+
+    let sequence = self
+      .sequence
+      .map(Sequence)
+      .unwrap_or(Sequence::ENABLE_RBF_NO_LOCKTIME);
+
+    if self.locktime.is_some() {
+      if sequence == Sequence::MAX {
+        bail!(
+          "--locktime has no effect when --sequence is {}",
+          Sequence::MAX
+        );
+      }
+
+      check_lock_time(&client, lock_time)?;
+    }
 
     let mut unspent_outputs = if self.coin_control {
       BTreeMap::new()
@@ -66,6 +300,29 @@ impl Send {
       index.get_unspent_outputs(Wallet::load(&options)?)?
     };
 
+    let simulation = if self.simulate {
+      Some(Simulation::load(&options)?)
+    } else {
+      None
+    };
+
+    if let Some(simulation) = &simulation {
+      simulation.apply_to_utxos(&mut unspent_outputs);
+    }
+
+    remove_frozen_utxos(&options, &mut unspent_outputs)?;
+
+    if !self.avoid_utxo.is_empty()
+      && matches!(
+        self.outgoing,
+        Outgoing::Amount(_) | Outgoing::All | Outgoing::AllMinus(_) | Outgoing::Max
+      )
+    {
+      bail!("--avoid-utxo doesn't work when sending cardinals");
+    }
+
+    remove_avoided_utxos(&mut unspent_outputs, &self.avoid_utxo, &self.utxo)?;
+
     for outpoint in &self.utxo {
       unspent_outputs.insert(
         *outpoint,
@@ -75,7 +332,11 @@ impl Send {
       );
     }
 
-    let inscriptions = index.get_inscriptions(unspent_outputs.clone())?;
+    let mut inscriptions = index.get_inscriptions(unspent_outputs.clone())?;
+
+    if let Some(simulation) = &simulation {
+      simulation.apply_to_inscriptions(&mut inscriptions);
+    }
 
     let satpoint = match self.outgoing {
       Outgoing::SatPoint(satpoint) => {
@@ -86,33 +347,51 @@ impl Send {
         }
         satpoint
       }
-      Outgoing::InscriptionId(id) => index
-        .get_inscription_satpoint_by_id(id)?
-        .ok_or_else(|| anyhow!("Inscription {id} not found"))?,
+      Outgoing::InscriptionId(id) => match simulation
+        .as_ref()
+        .and_then(|simulation| simulation.inscription_satpoint(id))
+      {
+        Some(satpoint) => satpoint,
+        None => index
+          .get_inscription_satpoint_by_id(id)?
+          .ok_or_else(|| anyhow!("Inscription {id} not found"))?,
+      },
       Outgoing::Amount(amount) => {
         if self.coin_control || !self.utxo.is_empty() {
           bail!("--coin_control and --utxo don't work when sending cardinals");
         }
 
-        self.send_amount(address, amount, &client, inscriptions, unspent_outputs)?;
+        self.send_amount(
+          &options,
+          address,
+          amount,
+          &client,
+          inscriptions,
+          unspent_outputs,
+        )?;
         return Ok(());
       }
-      Outgoing::All => {
+      Outgoing::All | Outgoing::AllMinus(_) | Outgoing::Max => {
         if self.coin_control || !self.utxo.is_empty() {
           bail!("--coin_control and --utxo don't work when sending cardinals");
         }
 
-        self.send_all_or_max(&client, address, inscriptions, unspent_outputs)?;
-        return Ok(());
-      }
-      Outgoing::Max => {
-        if self.coin_control || !self.utxo.is_empty() {
-          bail!("--coin_control and --utxo don't work when sending cardinals");
-        }
+        let reserve = match reserve {
+          Some(reserve) => Some((get_change_address(&client, &options)?, reserve)),
+          None => None,
+        };
 
-        self.send_all_or_max(&client, address, inscriptions, unspent_outputs)?;
+        self.send_all_or_max(
+          &options,
+          &client,
+          address,
+          reserve,
+          inscriptions,
+          unspent_outputs,
+        )?;
         return Ok(());
       }
+      Outgoing::Sat(_) => unreachable!("sats are resolved to satpoints"),
     };
 
     let change = [
@@ -129,10 +408,40 @@ impl Send {
         .unwrap()
     });
 
-    let unsigned_transaction = TransactionBuilder::build_transaction_with_postage(
+    let mut keep = vec![satpoint.outpoint];
+    keep.extend(&self.utxo);
+
+    remove_pending_commit_utxos(&client, &index, &mut unspent_outputs, &keep)?;
+
+    if min_confirmations > 0 {
+      remove_unconfirmed_utxos(&client, &mut unspent_outputs, min_confirmations, &keep)?;
+    }
+
+    if let Some(rarity) = self.protect_rarity {
+      keep.extend(&self.allow_rare_utxo);
+      remove_rare_sat_utxos(&index, &mut unspent_outputs, rarity, &keep)?;
+    }
+
+    let confirmations = if self.coin_selection == CoinSelection::OldestFirst {
+      get_utxo_confirmations(&client)?
+    } else {
+      BTreeMap::new()
+    };
+
+    let stand_in_script = address.script_pubkey();
+
+    let change_split_addresses = get_change_split_addresses(&client, &options, self.change_split)?;
+
+    let change_scripts = change
+      .iter()
+      .chain(&change_split_addresses)
+      .map(|address| address.script_pubkey())
+      .collect::<Vec<ScriptBuf>>();
+
+    let mut unsigned_transaction = TransactionBuilder::build_transaction_with_postage(
       satpoint,
-      inscriptions,
-      unspent_outputs,
+      inscriptions.clone(),
+      unspent_outputs.clone(),
       address,
       alignment,
       change,
@@ -146,29 +455,142 @@ impl Send {
         Some(max_postage) => max_postage,
         _ => TransactionBuilder::DEFAULT_MAX_POSTAGE,
       },
+      self.coin_selection,
+      confirmations,
+      get_utxo_satisfactions(&client)?,
     )?;
 
+    unsigned_transaction.lock_time = lock_time;
+
+    for input in &mut unsigned_transaction.input {
+      input.sequence = sequence;
+    }
+
+    if let Some(destination_script) = destination_script {
+      Self::replace_output_script(
+        &mut unsigned_transaction,
+        &stand_in_script,
+        destination_script,
+        self.fee_rate,
+      )?;
+    }
+
+    split_change(
+      &mut unsigned_transaction,
+      &change_scripts,
+      &change_split_addresses,
+      self.fee_rate,
+    )?;
+
+    if let Some(mut simulation) = simulation {
+      simulation.apply(
+        &unsigned_transaction,
+        &unspent_outputs,
+        &inscriptions,
+        simulation::is_mine(&client, options.chain().network()),
+      )?;
+
+      simulation.save(&options)?;
+
+      print_line(unsigned_transaction.txid())?;
+
+      return Ok(());
+    }
+
+    if !private_keys_enabled(&client)? {
+      print_line(wallet_psbt(&client, &unsigned_transaction)?)?;
+      return Ok(());
+    }
+
     let signed_tx = client
       .sign_raw_transaction_with_wallet(&unsigned_transaction, None, None)?
       .hex;
 
+    let sat_flow = if self.sat_flow {
+      Some(sat_flow::trace(
+        &index,
+        &[&consensus::deserialize(&signed_tx)?],
+        &change_scripts,
+      )?)
+    } else {
+      None
+    };
+
     let txid = client.send_raw_transaction(&signed_tx)?;
 
-    println!("{txid}");
+    audit::record(&options, &client, &signed_tx);
+
+    match sat_flow {
+      Some(sat_flow) => print_json(SatFlowOutput { txid, sat_flow })?,
+      None => print_line(txid)?,
+    }
+
+    Ok(())
+  }
+
+  /// Find `sat` in the wallet's unspent outputs with the sat index.
+  fn find_sat(index: &Index, options: &Options, sat: Sat) -> Result<SatPoint> {
+    if !index.has_sat_index()? {
+      bail!("sending a sat by name or degree requires index created with `--index-sats` flag");
+    }
+
+    find_wallet_sat(index, options, sat)?.ok_or_else(|| anyhow!("sat {sat} is not in wallet"))
+  }
+
+  fn replace_output_script(
+    transaction: &mut Transaction,
+    stand_in_script: &Script,
+    destination_script: ScriptBuf,
+    fee_rate: FeeRate,
+  ) -> Result {
+    let weight = transaction.weight();
+
+    let output = transaction
+      .output
+      .iter_mut()
+      .find(|output| output.script_pubkey.as_script() == stand_in_script)
+      .ok_or_else(|| anyhow!("could not find recipient output"))?;
+
+    let dust_value = destination_script.dust_value();
+    output.script_pubkey = destination_script;
+
+    if Amount::from_sat(output.value) < dust_value {
+      bail!(
+        "output value is below dust value for --destination-script: {} < {dust_value}",
+        Amount::from_sat(output.value)
+      );
+    }
+
+    // the fee was paid by the last output, so it pays for any extra weight too
+    if let Some(extra_weight) = transaction.weight().checked_sub(weight) {
+      let extra_fee = fee_rate.fee(extra_weight).to_sat();
+      let last = transaction
+        .output
+        .last_mut()
+        .expect("transaction has outputs");
+
+      last.value = last
+        .value
+        .checked_sub(extra_fee)
+        .filter(|value| Amount::from_sat(*value) >= last.script_pubkey.dust_value())
+        .ok_or_else(|| anyhow!("last output cannot pay fee for --destination-script"))?;
+    }
 
     Ok(())
   }
 
   fn send_amount(
     self,
+    options: &Options,
     address: Address,
     amount: Amount,
     client: &Client,
     inscriptions: BTreeMap<SatPoint, InscriptionId>,
     unspent_outputs: BTreeMap<bitcoin::OutPoint, bitcoin::Amount>,
   ) -> Result {
-    Self::lock_inscriptions(client, inscriptions, unspent_outputs)?;
-    let txid = client.call(
+    let (inscription_outputs, locked) =
+      Self::lock_inscriptions(client, inscriptions, unspent_outputs)?;
+    let txid = client.call::<Txid>(
       "sendtoaddress",
       &[
         address.to_string().into(),  //  1. address
@@ -182,23 +604,47 @@ impl Send {
         serde_json::Value::Null,     //  9. avoid_reuse
         self.fee_rate.rate().into(), // 10. fee_rate - in sat/vB
       ],
+    );
+    self.after_send(
+      options,
+      client,
+      txid.as_ref().ok(),
+      &inscription_outputs,
+      &locked,
     )?;
-    print_json(Output { transaction: txid })?;
+    print_json(Output {
+      transaction: txid?,
+      locked,
+    })?;
     Ok(())
   }
 
+  /// `sendall` pays recipients with amounts first, and splits what's left
+  /// between those without, so a reserve is paid to a change address with an
+  /// amount.
   fn send_all_or_max(
     self,
+    options: &Options,
     client: &Client,
     address: Address,
+    reserve: Option<(Address, Amount)>,
     inscriptions: BTreeMap<SatPoint, InscriptionId>,
     unspent_outputs: BTreeMap<bitcoin::OutPoint, bitcoin::Amount>,
   ) -> Result {
-    Self::lock_inscriptions(client, inscriptions, unspent_outputs)?;
-    let result: SendAllOutput = client.call(
+    let mut recipients = vec![serde_json::to_value(address.to_string())?];
+
+    if let Some((change, reserve)) = reserve {
+      let mut recipient = serde_json::Map::new();
+      recipient.insert(change.to_string(), reserve.to_btc().into());
+      recipients.push(recipient.into());
+    }
+
+    let (inscription_outputs, locked) =
+      Self::lock_inscriptions(client, inscriptions, unspent_outputs)?;
+    let result = client.call::<SendAllOutput>(
       "sendall",
       &[
-        vec![serde_json::to_value((address).to_string())?].into(), //       1. recipients
+        recipients.into(), //                                               1. recipients
         serde_json::Value::Null, //                                         2. conf_target
         serde_json::Value::Null, //                                         3. estimate_mode
         self.fee_rate.rate().into(), //                                     4. fee_rate - in sat/vB
@@ -208,16 +654,63 @@ impl Send {
           "{\"send_max\": false}"
         })?,
       ],
+    );
+    self.after_send(
+      options,
+      client,
+      result
+        .as_ref()
+        .ok()
+        .filter(|result| result.complete)
+        .map(|result| &result.txid),
+      &inscription_outputs,
+      &locked,
     )?;
-    print_json(result)?;
+    print_json(SendAllOutput { locked, ..result? })?;
+    Ok(())
+  }
+
+  /// Check that the transaction sent by Bitcoin Core, if any, didn't spend
+  /// inscriptions, record it in the audit log, and undo the locks taken by
+  /// `lock_inscriptions` if asked.
+  fn after_send(
+    &self,
+    options: &Options,
+    client: &Client,
+    txid: Option<&Txid>,
+    inscription_outputs: &[OutPoint],
+    locked: &[OutPoint],
+  ) -> Result {
+    if self.unlock_after && !locked.is_empty() && !client.unlock_unspent(locked)? {
+      bail!("failed to unlock ordinal UTXOs");
+    }
+
+    if let Some(txid) = txid {
+      let tx = client.get_raw_transaction(txid, None)?;
+
+      audit::record(options, client, &consensus::serialize(&tx));
+
+      for input in tx.input {
+        if inscription_outputs.contains(&input.previous_output) {
+          bail!(
+            "transaction {txid} spent inscription output {}",
+            input.previous_output
+          );
+        }
+      }
+    }
+
     Ok(())
   }
 
+  /// Lock the wallet's inscription outputs so that Bitcoin Core doesn't spend
+  /// them, returning all inscription outputs and the ones that weren't
+  /// already locked.
   fn lock_inscriptions(
     client: &Client,
     inscriptions: BTreeMap<SatPoint, InscriptionId>,
     unspent_outputs: BTreeMap<bitcoin::OutPoint, bitcoin::Amount>,
-  ) -> Result {
+  ) -> Result<(Vec<OutPoint>, Vec<OutPoint>)> {
     let all_inscription_outputs = inscriptions
       .keys()
       .map(|satpoint| satpoint.outpoint)
@@ -229,10 +722,18 @@ impl Send {
       .cloned()
       .collect::<Vec<OutPoint>>();
 
-    if !client.lock_unspent(&wallet_inscription_outputs)? {
+    let already_locked = get_locked_outputs(client)?;
+
+    let locked = wallet_inscription_outputs
+      .iter()
+      .filter(|utxo| !already_locked.contains(utxo))
+      .cloned()
+      .collect::<Vec<OutPoint>>();
+
+    if !client.lock_unspent(&locked)? {
       bail!("failed to lock ordinal UTXOs");
     }
 
-    Ok(())
+    Ok((wallet_inscription_outputs, locked))
   }
 }
@@ -0,0 +1,154 @@
+use {
+  super::*,
+  bitcoin::hashes::{sha256, Hash},
+  inscribe::Inscribe,
+};
+
+/// Inscribes a file too large for one inscription as parts, and then a
+/// manifest of the parts, which `ord reassemble` puts back together. Parts
+/// are sent to the wallet, and the manifest to `--destination`, if given.
+#[derive(Debug, Parser)]
+pub(crate) struct Shard {
+  #[clap(
+    long,
+    help = "Split the file into parts of at most <CHUNK_SIZE> bytes."
+  )]
+  chunk_size: usize,
+  #[clap(flatten)]
+  inscribe: Inscribe,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Part {
+  pub inscription: InscriptionId,
+  /// Hex SHA-256 of the part's content.
+  pub sha256: String,
+}
+
+/// The content of a manifest inscription.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+  /// Content type of the whole file.
+  pub content_type: String,
+  pub size: u64,
+  /// Hex SHA-256 of the whole file.
+  pub sha256: String,
+  pub parts: Vec<Part>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+  pub manifest: InscriptionId,
+  pub parts: Vec<InscriptionId>,
+}
+
+impl Shard {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let mut inscribe = self.inscribe;
+
+    if self.chunk_size == 0 {
+      bail!("--chunk-size must be greater than zero");
+    }
+
+    if inscribe.files.len() != 1 || inscribe.dir.is_some() || inscribe.csv.is_some() {
+      bail!("provide exactly one file to shard");
+    }
+
+    if inscribe.satpoint.is_some() || inscribe.sat.is_some() || inscribe.include_sat_range.is_some()
+    {
+      bail!("cannot shard onto a particular sat");
+    }
+
+    let file = inscribe.files[0].clone();
+
+    let content =
+      fs::read(&file).with_context(|| format!("io error reading {}", file.display()))?;
+
+    if content.is_empty() {
+      bail!("{} is empty", file.display());
+    }
+
+    let content_type = Media::content_type_for_path(&file)?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let tempdir = TempDir::new()?;
+
+    // parts are inscribed from a CSV file, since it can give them a content
+    // type other than that of their file extension
+    let mut csv = String::new();
+
+    let chunks = content.chunks(self.chunk_size).collect::<Vec<&[u8]>>();
+
+    for (i, chunk) in chunks.iter().enumerate() {
+      let path = tempdir.path().join(format!("part-{i}"));
+      fs::write(&path, chunk)?;
+
+      csv.push_str(&format!(
+        "{},{},,{DEFAULT_CONTENT_TYPE}\n",
+        get_change_address(&client, &options)?,
+        path.display(),
+      ));
+    }
+
+    let csv_path = tempdir.path().join("parts.csv");
+    fs::write(&csv_path, csv)?;
+
+    let mut parts = inscribe.clone();
+    parts.files = Vec::new();
+    parts.csv = Some(csv_path);
+    parts.destination = Vec::new();
+    parts.destination_script = None;
+    parts.destination_descriptor = None;
+
+    let part_ids = parts.inscribe(options.clone(), false)?;
+
+    if part_ids.len() != chunks.len() {
+      bail!(
+        "only {} of {} parts were inscribed, so the manifest was not",
+        part_ids.len(),
+        chunks.len()
+      );
+    }
+
+    let manifest = Manifest {
+      content_type: content_type.into(),
+      size: content.len().try_into().unwrap(),
+      sha256: sha256::Hash::hash(&content).to_string(),
+      parts: part_ids
+        .iter()
+        .zip(&chunks)
+        .map(|(inscription, chunk)| Part {
+          inscription: *inscription,
+          sha256: sha256::Hash::hash(chunk).to_string(),
+        })
+        .collect(),
+    };
+
+    let manifest_path = tempdir.path().join("manifest.json");
+    fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+
+    inscribe.files = vec![manifest_path];
+
+    // the parts' reveal outputs aren't indexed until they are mined, so they
+    // must be avoided explicitly
+    inscribe.avoid_utxo.extend(
+      part_ids
+        .iter()
+        .map(|inscription| OutPoint::new(inscription.txid, 0)),
+    );
+
+    let manifest_id = inscribe
+      .inscribe(options, false)?
+      .into_iter()
+      .next()
+      .ok_or_else(|| anyhow!("the parts were inscribed, but the manifest was not"))?;
+
+    print_json(Output {
+      manifest: manifest_id,
+      parts: part_ids,
+    })?;
+
+    Ok(())
+  }
+}
@@ -0,0 +1,258 @@
+use super::*;
+
+/// Wallet state left behind by commands run with `--simulate`, which build
+/// transactions without broadcasting them. It is stored in the data dir and
+/// applied on top of the index, so later simulated commands see the outputs
+/// and inscriptions created by earlier ones.
+#[derive(Default, Serialize, Deserialize)]
+pub(crate) struct Simulation {
+  transactions: Vec<Txid>,
+  spent: BTreeSet<OutPoint>,
+  outputs: BTreeMap<OutPoint, u64>,
+  wallet_outputs: BTreeSet<OutPoint>,
+  inscriptions: BTreeMap<SatPoint, InscriptionId>,
+}
+
+impl Simulation {
+  fn path(options: &Options) -> Result<PathBuf> {
+    Ok(
+      options
+        .data_dir()?
+        .join(format!("simulation-{}.json", options.wallet)),
+    )
+  }
+
+  pub(crate) fn load(options: &Options) -> Result<Self> {
+    let path = Self::path(options)?;
+
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    serde_json::from_reader(File::open(&path)?)
+      .with_context(|| format!("failed to parse simulation file {}", path.display()))
+  }
+
+  pub(crate) fn save(&self, options: &Options) -> Result {
+    let path = Self::path(options)?;
+
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&path, serde_json::to_string_pretty(self)?)
+      .with_context(|| format!("failed to write simulation file {}", path.display()))
+  }
+
+  pub(crate) fn apply_to_utxos(&self, utxos: &mut BTreeMap<OutPoint, Amount>) {
+    utxos.retain(|outpoint, _| !self.spent.contains(outpoint));
+    utxos.extend(
+      self
+        .wallet_outputs
+        .iter()
+        .map(|outpoint| (*outpoint, Amount::from_sat(self.outputs[outpoint]))),
+    );
+  }
+
+  pub(crate) fn apply_to_inscriptions(&self, inscriptions: &mut BTreeMap<SatPoint, InscriptionId>) {
+    inscriptions.retain(|satpoint, _| !self.spent.contains(&satpoint.outpoint));
+    inscriptions.extend(
+      self
+        .inscriptions
+        .iter()
+        .filter(|(satpoint, _)| self.wallet_outputs.contains(&satpoint.outpoint)),
+    );
+  }
+
+  pub(crate) fn inscription_satpoint(&self, inscription_id: InscriptionId) -> Option<SatPoint> {
+    self
+      .inscriptions
+      .iter()
+      .find(|(_, id)| **id == inscription_id)
+      .map(|(satpoint, _)| *satpoint)
+  }
+
+  /// Spend the inputs of `tx` and create its outputs, moving inscriptions
+  /// along with their sats. Values of inputs not created by earlier simulated
+  /// transactions are looked up in `utxos`.
+  pub(crate) fn apply(
+    &mut self,
+    tx: &Transaction,
+    utxos: &BTreeMap<OutPoint, Amount>,
+    inscriptions: &BTreeMap<SatPoint, InscriptionId>,
+    is_mine: impl Fn(&Script) -> Result<bool>,
+  ) -> Result {
+    let txid = tx.txid();
+
+    let mut inscriptions = inscriptions.clone();
+    inscriptions.extend(&self.inscriptions);
+
+    let envelopes = Inscription::from_transaction(tx);
+
+    let mut flotsam = Vec::new();
+    let mut offset = 0;
+
+    for (vin, input) in tx.input.iter().enumerate() {
+      let outpoint = input.previous_output;
+
+      if self.spent.contains(&outpoint) {
+        bail!("simulated transaction {txid} spends {outpoint}, which was already spent by an earlier simulated transaction");
+      }
+
+      // new inscriptions are made on the first sat of their input
+      for (index, envelope) in envelopes.iter().enumerate() {
+        if usize::try_from(envelope.tx_in_index).unwrap() == vin {
+          flotsam.push((
+            offset,
+            InscriptionId {
+              txid,
+              index: index.try_into().unwrap(),
+            },
+          ));
+        }
+      }
+
+      for (satpoint, inscription_id) in &inscriptions {
+        if satpoint.outpoint == outpoint {
+          flotsam.push((offset + satpoint.offset, *inscription_id));
+        }
+      }
+
+      offset += match self.outputs.get(&outpoint) {
+        Some(value) => *value,
+        None => utxos
+          .get(&outpoint)
+          .ok_or_else(|| anyhow!("simulated transaction {txid} spends unknown output {outpoint}"))?
+          .to_sat(),
+      };
+    }
+
+    for input in &tx.input {
+      let outpoint = input.previous_output;
+      self.spent.insert(outpoint);
+      self.outputs.remove(&outpoint);
+      self.wallet_outputs.remove(&outpoint);
+      self
+        .inscriptions
+        .retain(|satpoint, _| satpoint.outpoint != outpoint);
+    }
+
+    // inscriptions on sats that go to fees are no longer tracked
+    let mut start = 0;
+    for (vout, output) in tx.output.iter().enumerate() {
+      let outpoint = OutPoint {
+        txid,
+        vout: vout.try_into().unwrap(),
+      };
+
+      let end = start + output.value;
+
+      for (offset, inscription_id) in &flotsam {
+        if (start..end).contains(offset) {
+          self.inscriptions.insert(
+            SatPoint {
+              outpoint,
+              offset: offset - start,
+            },
+            *inscription_id,
+          );
+        }
+      }
+
+      self.outputs.insert(outpoint, output.value);
+
+      if is_mine(&output.script_pubkey)? {
+        self.wallet_outputs.insert(outpoint);
+      }
+
+      start = end;
+    }
+
+    self.transactions.push(txid);
+
+    Ok(())
+  }
+}
+
+/// Whether Bitcoin Core's wallet owns the address of an output script.
+pub(crate) fn is_mine(client: &Client, network: Network) -> impl Fn(&Script) -> Result<bool> + '_ {
+  move |script| {
+    let Ok(address) = Address::from_script(script, network) else {
+      return Ok(false);
+    };
+
+    Ok(
+      client.call::<serde_json::Value>("getaddressinfo", &[address.to_string().into()])?["ismine"]
+        .as_bool()
+        .unwrap_or_default(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn inscriptions_follow_their_sats() {
+    let mut simulation = Simulation::default();
+
+    let tx = Transaction {
+      version: 2,
+      lock_time: LockTime::ZERO,
+      input: vec![tx_in(outpoint(1)), tx_in(outpoint(2))],
+      output: vec![tx_out(10_000, recipient()), tx_out(15_000, change(0))],
+    };
+
+    simulation
+      .apply(
+        &tx,
+        &[
+          (outpoint(1), Amount::from_sat(10_000)),
+          (outpoint(2), Amount::from_sat(20_000)),
+        ]
+        .into_iter()
+        .collect(),
+        &[(satpoint(2, 1_000), inscription_id(1))]
+          .into_iter()
+          .collect(),
+        |script| Ok(script == change(0).script_pubkey().as_script()),
+      )
+      .unwrap();
+
+    let change_output = OutPoint {
+      txid: tx.txid(),
+      vout: 1,
+    };
+
+    let mut utxos = [(outpoint(1), Amount::from_sat(10_000))]
+      .into_iter()
+      .collect();
+    simulation.apply_to_utxos(&mut utxos);
+    assert_eq!(
+      utxos,
+      [(change_output, Amount::from_sat(15_000))]
+        .into_iter()
+        .collect()
+    );
+
+    let mut inscriptions = BTreeMap::new();
+    simulation.apply_to_inscriptions(&mut inscriptions);
+    assert_eq!(
+      inscriptions,
+      [(
+        SatPoint {
+          outpoint: change_output,
+          offset: 1_000,
+        },
+        inscription_id(1)
+      )]
+      .into_iter()
+      .collect()
+    );
+
+    assert!(simulation
+      .apply(&tx, &utxos, &inscriptions, |_| Ok(true))
+      .is_err());
+  }
+}
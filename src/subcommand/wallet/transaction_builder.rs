@@ -38,6 +38,7 @@ use {
     blockdata::{locktime::absolute::LockTime, witness::Witness},
     Amount, ScriptBuf,
   },
+  clap::ValueEnum,
   std::{
     cmp::{max, min},
     collections::{BTreeMap, BTreeSet},
@@ -51,6 +52,7 @@ pub enum Error {
     output_value: Amount,
     dust_value: Amount,
   },
+  DustChange(Amount),
   MaxPostageLessThanTarget {
     max_postage: Amount,
     target_postage: Amount,
@@ -80,6 +82,7 @@ impl fmt::Display for Error {
         output_value,
         dust_value,
       } => write!(f, "output value is below dust value: {output_value} < {dust_value}"),
+      Error::DustChange(change) => write!(f, "{} sats of change is too small for a change output", change.to_sat()),
       Error::MaxPostageLessThanTarget {max_postage, target_postage} => write!(f, "max postage {} sats is less than target postage {} sats", max_postage.to_sat(), target_postage.to_sat()),
       Error::NotInWallet(outgoing_satpoint) => write!(f, "outgoing satpoint {outgoing_satpoint} not in wallet"),
       Error::OutOfRange(outgoing_satpoint, maximum) => write!(f, "outgoing satpoint {outgoing_satpoint} offset higher than maximum {maximum}"),
@@ -124,6 +127,124 @@ pub struct TransactionBuilder {
   current_output: usize,
   padding_outputs: usize,
   ignore_utxo_inscriptions: bool,
+  coin_selection: CoinSelection,
+  confirmations: BTreeMap<OutPoint, u32>,
+  satisfactions: BTreeMap<OutPoint, Satisfaction>,
+  dust_policy: DustPolicy,
+  dust_change: Amount,
+  pending_selection: Vec<OutPoint>,
+  /// Inputs spent right after the outgoing sat's, in order, so that the sats
+  /// after it come from them.
+  following: Vec<OutPoint>,
+}
+
+/// The script sig and witness that spend an input, or stand-ins of the same
+/// size, used to estimate the weight of a transaction before it is signed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Satisfaction {
+  pub script_sig: ScriptBuf,
+  pub witness: Witness,
+}
+
+impl Default for Satisfaction {
+  /// We initialize wallets with taproot descriptors only, whose outputs are
+  /// spent with a single Schnorr signature.
+  fn default() -> Self {
+    Self {
+      script_sig: ScriptBuf::new(),
+      witness: Witness::from_slice(&[&[0; TransactionBuilder::SCHNORR_SIGNATURE_SIZE]]),
+    }
+  }
+}
+
+impl Satisfaction {
+  /// The actual satisfactions of the inputs of a signed transaction.
+  pub fn from_signed_transaction(tx: &Transaction) -> BTreeMap<OutPoint, Self> {
+    tx.input
+      .iter()
+      .map(|input| {
+        (
+          input.previous_output,
+          Self {
+            script_sig: input.script_sig.clone(),
+            witness: input.witness.clone(),
+          },
+        )
+      })
+      .collect()
+  }
+
+  /// Stand-ins for the satisfaction of an output with `script_pubkey`, with
+  /// the largest signatures the wallet makes. Bitcoin Core wallets nest
+  /// segwit outputs as P2SH-P2WPKH, and anything unrecognized is assumed to
+  /// be taproot.
+  pub fn predict(script_pubkey: &Script) -> Self {
+    let ecdsa = || {
+      Witness::from_slice(&[
+        [0; TransactionBuilder::ECDSA_SIGNATURE_SIZE].as_slice(),
+        &[0; TransactionBuilder::COMPRESSED_PUBLIC_KEY_SIZE],
+      ])
+    };
+
+    if script_pubkey.is_v0_p2wpkh() {
+      Self {
+        script_sig: ScriptBuf::new(),
+        witness: ecdsa(),
+      }
+    } else if script_pubkey.is_p2sh() {
+      // a push of the 22 byte P2WPKH redeem script
+      Self {
+        script_sig: ScriptBuf::from_bytes(vec![0; 1 + 22]),
+        witness: ecdsa(),
+      }
+    } else if script_pubkey.is_p2pkh() {
+      // pushes of the signature and public key
+      Self {
+        script_sig: ScriptBuf::from_bytes(vec![
+          0;
+          1 + TransactionBuilder::ECDSA_SIGNATURE_SIZE
+            + 1
+            + TransactionBuilder::COMPRESSED_PUBLIC_KEY_SIZE
+        ]),
+        witness: Witness::new(),
+      }
+    } else {
+      Self::default()
+    }
+  }
+
+  pub fn weight(&self) -> Weight {
+    Weight::from_wu((self.script_sig.len() * 4 + self.witness.serialized_len()) as u64)
+  }
+}
+
+/// How cardinal utxos are chosen when a transaction needs more value.
+#[derive(Default, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum CoinSelection {
+  /// The utxo with the value closest to what is needed.
+  #[default]
+  Closest,
+  /// A set of utxos that covers what is needed without a change output, or
+  /// else the closest utxo.
+  BranchAndBound,
+  /// The biggest utxos, to use as few inputs as possible.
+  LargestFirst,
+  /// The utxos with the most confirmations, to clean up old outputs.
+  OldestFirst,
+  /// The smallest single utxo that covers what is needed.
+  Single,
+}
+
+/// What to do with change that is too small to be worth a change output.
+#[derive(Default, ValueEnum, Copy, Clone, Debug, PartialEq)]
+pub enum DustPolicy {
+  /// Leave it to the miner as extra fee.
+  Fee,
+  /// Leave it in the last recipient output, behind the aligned outgoing sat.
+  #[default]
+  Alignment,
+  /// Fail instead of spending it on either.
+  Error,
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -133,6 +254,8 @@ impl TransactionBuilder {
   const ADDITIONAL_INPUT_WEIGHT: Weight = Weight::from_wu((57.5 * 4.0) as u64);
   const ADDITIONAL_OUTPUT_WEIGHT: Weight = Weight::from_wu(43 * 4);
   const SCHNORR_SIGNATURE_SIZE: usize = 64;
+  const ECDSA_SIGNATURE_SIZE: usize = 72;
+  const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
   pub(crate) const DEFAULT_MAX_POSTAGE: Amount = Amount::from_sat(2 * 10_000);
   pub(crate) const DEFAULT_TARGET_POSTAGE: Amount = Amount::from_sat(10_000);
 
@@ -147,6 +270,9 @@ impl TransactionBuilder {
     max_inputs: Option<usize>,
     target_postage: Amount,
     max_postage: Amount,
+    coin_selection: CoinSelection,
+    confirmations: BTreeMap<OutPoint, u32>,
+    satisfactions: BTreeMap<OutPoint, Satisfaction>,
   ) -> Result<Transaction> {
     if max_postage < target_postage {
       return Err(Error::MaxPostageLessThanTarget {
@@ -168,8 +294,13 @@ impl TransactionBuilder {
       target_postage,
       max_postage,
       false,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      DustPolicy::Alignment,
     )?
     .build_transaction()
+    .map(|(transaction, _dust_to_fee)| transaction)
   }
 
   pub fn build_transaction_with_value(
@@ -182,6 +313,9 @@ impl TransactionBuilder {
     fee_rate: FeeRate,
     max_inputs: Option<usize>,
     output_value: Amount,
+    coin_selection: CoinSelection,
+    confirmations: BTreeMap<OutPoint, u32>,
+    satisfactions: BTreeMap<OutPoint, Satisfaction>,
   ) -> Result<Transaction> {
     let dust_value = recipient.script_pubkey().dust_value();
 
@@ -205,8 +339,13 @@ impl TransactionBuilder {
       Amount::from_sat(0),
       Amount::from_sat(0),
       false,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      DustPolicy::Alignment,
     )?
     .build_transaction()
+    .map(|(transaction, _dust_to_fee)| transaction)
   }
 
   pub fn build_transaction_with_values(
@@ -220,7 +359,12 @@ impl TransactionBuilder {
     output_value: Vec<Amount>,
     max_inputs: Option<usize>,
     ignore_utxo_inscriptions: bool,
-  ) -> Result<Transaction> {
+    coin_selection: CoinSelection,
+    confirmations: BTreeMap<OutPoint, u32>,
+    satisfactions: BTreeMap<OutPoint, Satisfaction>,
+    dust_policy: DustPolicy,
+    following: Vec<OutPoint>,
+  ) -> Result<(Transaction, Amount)> {
     for (recipient, output_value) in recipient.iter().zip(output_value.clone()) {
       let dust_value = recipient.script_pubkey().dust_value();
 
@@ -248,19 +392,37 @@ impl TransactionBuilder {
       Amount::from_sat(0),
       Amount::from_sat(0),
       ignore_utxo_inscriptions,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      dust_policy,
     )?
+    .follow_outgoing_with(following)
     .build_transaction()
   }
 
-  fn build_transaction(self) -> Result<Transaction> {
+  fn follow_outgoing_with(mut self, following: Vec<OutPoint>) -> Self {
+    self.following = following;
     self
+  }
+
+  /// Build the transaction, returning it along with the sats of change that
+  /// were left to the fee because they were too small for a change output.
+  fn build_transaction(self) -> Result<(Transaction, Amount)> {
+    let builder = self
       .select_outgoing()?
       .align_outgoing()
       .pad_alignment_output()?
       .add_value()?
-      .strip_value()
-      .deduct_fee()
-      .build()
+      .strip_value();
+
+    if builder.dust_policy == DustPolicy::Error && builder.dust_change > Amount::ZERO {
+      return Err(Error::DustChange(builder.dust_change));
+    }
+
+    let dust_to_fee = builder.dust_to_fee();
+
+    Ok((builder.deduct_fee().build()?, dust_to_fee))
   }
 
   fn new(
@@ -276,6 +438,10 @@ impl TransactionBuilder {
     target_postage: Amount,
     max_postage: Amount,
     ignore_utxo_inscriptions: bool,
+    coin_selection: CoinSelection,
+    confirmations: BTreeMap<OutPoint, u32>,
+    satisfactions: BTreeMap<OutPoint, Satisfaction>,
+    dust_policy: DustPolicy,
   ) -> Result<Self> {
     for recipient in recipient.clone() {
       if change.contains(&recipient) {
@@ -312,6 +478,13 @@ impl TransactionBuilder {
       current_output: 0,
       padding_outputs: 0,
       ignore_utxo_inscriptions,
+      coin_selection,
+      confirmations,
+      satisfactions,
+      dust_policy,
+      dust_change: Amount::ZERO,
+      pending_selection: Vec::new(),
+      following: Vec::new(),
     })
   }
 
@@ -351,6 +524,35 @@ impl TransactionBuilder {
     self.utxos.remove(&self.outgoing.outpoint);
     self.inputs.push(self.outgoing.outpoint);
     let mut available = amount;
+
+    for outpoint in self.following.clone() {
+      if !self.ignore_utxo_inscriptions {
+        if let Some((inscribed_satpoint, inscription_id)) = self
+          .inscriptions
+          .iter()
+          .find(|(inscribed_satpoint, _)| inscribed_satpoint.outpoint == outpoint)
+        {
+          return Err(Error::UtxoContainsAdditionalInscription {
+            outgoing_satpoint: self.outgoing,
+            inscribed_satpoint: *inscribed_satpoint,
+            inscription_id: *inscription_id,
+          });
+        }
+      }
+
+      let amount = *self
+        .amounts
+        .get(&outpoint)
+        .ok_or(Error::NotInWallet(SatPoint {
+          outpoint,
+          offset: 0,
+        }))?;
+
+      self.utxos.remove(&outpoint);
+      self.inputs.push(outpoint);
+      available += amount;
+    }
+
     for (recipient, target) in self.recipient.iter().zip(self.target.iter()) {
       let value = match target {
         Target::Postage => recipient.script_pubkey().dust_value(),
@@ -494,7 +696,7 @@ impl TransactionBuilder {
         let (utxo, value) = self.select_cardinal_utxo(needed, false)?;
 
         let benefit = value
-          .checked_sub(additional_fee)
+          .checked_sub(self.input_fee(utxo))
           .ok_or(Error::NotEnoughCardinalUtxos)?;
 
         self.inputs.push(utxo);
@@ -583,6 +785,16 @@ impl TransactionBuilder {
         self
           .outputs
           .push((self.unused_change_addresses[1].clone(), value - target));
+      } else if excess > max {
+        self.dust_change = excess - target;
+
+        if self.dust_policy == DustPolicy::Fee {
+          tprintln!(
+            "left {} sats of dust change to the fee",
+            self.dust_change.to_sat()
+          );
+          self.outputs.last_mut().expect("no outputs found").1 -= self.dust_change;
+        }
       }
     }
 
@@ -622,13 +834,23 @@ impl TransactionBuilder {
     self
   }
 
-  /// Estimate the size in virtual bytes of the transaction under construction.
-  /// We initialize wallets with taproot descriptors only, so we know that all
-  /// inputs are taproot key path spends, which allows us to know that witnesses
-  /// will all consist of single Schnorr signatures.
+  fn dust_to_fee(&self) -> Amount {
+    if self.dust_policy == DustPolicy::Fee {
+      self.dust_change
+    } else {
+      Amount::ZERO
+    }
+  }
+
+  /// Estimate the weight of the transaction under construction, with the
+  /// satisfactions of its inputs, if known, or else taproot key path spends.
   fn estimate_weight(&self) -> Weight {
     Self::estimate_weight_with(
-      self.inputs.len(),
+      self
+        .inputs
+        .iter()
+        .map(|outpoint| self.satisfaction(*outpoint))
+        .collect(),
       self
         .outputs
         .iter()
@@ -638,16 +860,34 @@ impl TransactionBuilder {
     )
   }
 
-  fn estimate_weight_with(inputs: usize, outputs: Vec<Address>) -> Weight {
+  fn satisfaction(&self, outpoint: OutPoint) -> Satisfaction {
+    self
+      .satisfactions
+      .get(&outpoint)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  /// The fee for adding `outpoint` as an input, which depends on the
+  /// weight of its satisfaction.
+  fn input_fee(&self, outpoint: OutPoint) -> Amount {
+    self.fee_rate.fee(
+      Self::ADDITIONAL_INPUT_WEIGHT - Satisfaction::default().weight()
+        + self.satisfaction(outpoint).weight(),
+    )
+  }
+
+  fn estimate_weight_with(inputs: Vec<Satisfaction>, outputs: Vec<Address>) -> Weight {
     let t = Transaction {
       version: 1,
       lock_time: LockTime::ZERO,
-      input: (0..inputs)
-        .map(|_| TxIn {
+      input: inputs
+        .into_iter()
+        .map(|satisfaction| TxIn {
           previous_output: OutPoint::null(),
-          script_sig: ScriptBuf::new(),
+          script_sig: satisfaction.script_sig,
           sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
-          witness: Witness::from_slice(&[&[0; TransactionBuilder::SCHNORR_SIGNATURE_SIZE]]),
+          witness: satisfaction.witness,
         })
         .collect(),
       output: outputs
@@ -744,20 +984,18 @@ impl TransactionBuilder {
       }
     }
     assert!(found, "invariant: outgoing sat is found in outputs");
-/*
     // this invarient failed if we use --single-key to inscribe the same file multiple times using the same key
-    for recipient in &self.recipient {
-      assert_eq!(
-        transaction
-          .output
-          .iter()
-          .filter(|tx_out| tx_out.script_pubkey == recipient.script_pubkey())
-          .count(),
-        1,
-        "invariant: recipient address appears exactly once in outputs",
-      );
-    }
-*/
+    // for recipient in &self.recipient {
+    //   assert_eq!(
+    //     transaction
+    //       .output
+    //       .iter()
+    //       .filter(|tx_out| tx_out.script_pubkey == recipient.script_pubkey())
+    //       .count(),
+    //     1,
+    //     "invariant: recipient address appears exactly once in outputs",
+    //   );
+    // }
     assert!(
       self
         .change_addresses
@@ -841,12 +1079,15 @@ impl TransactionBuilder {
 
     let mut modified_tx = transaction.clone();
     for input in &mut modified_tx.input {
-      input.witness = Witness::from_slice(&[&[0; 64]]);
+      let satisfaction = self.satisfaction(input.previous_output);
+      input.script_sig = satisfaction.script_sig;
+      input.witness = satisfaction.witness;
     }
     let expected_fee = self.fee_rate.fee(modified_tx.weight());
 
     assert_eq!(
-      actual_fee, expected_fee,
+      actual_fee,
+      expected_fee + self.dust_to_fee(),
       "invariant: fee estimation is correct",
     );
 
@@ -897,12 +1138,77 @@ impl TransactionBuilder {
       .map(|satpoint| satpoint.outpoint)
       .collect::<BTreeSet<OutPoint>>();
 
-    let mut best_match = None;
-    for utxo in &self.utxos {
-      if inscribed_utxos.contains(utxo) {
-        continue;
+    if let Some(utxo) = self.pending_selection.pop() {
+      let value = self.amounts[&utxo];
+      self.utxos.remove(&utxo);
+      tprintln!(
+        "found cardinal worth {} from branch and bound selection",
+        value
+      );
+      return Ok((utxo, value));
+    }
+
+    let cardinals = self
+      .utxos
+      .iter()
+      .filter(|utxo| !inscribed_utxos.contains(utxo))
+      .copied()
+      .collect::<Vec<OutPoint>>();
+
+    let best_match = match self.coin_selection {
+      CoinSelection::Closest => self.closest_cardinal_utxo(&cardinals, target_value, prefer_under),
+      CoinSelection::BranchAndBound => {
+        let selection = if prefer_under {
+          None
+        } else {
+          self.branch_and_bound(&cardinals, target_value)
+        };
+
+        match selection {
+          Some(mut selection) => {
+            let utxo = selection.remove(0);
+            self.pending_selection = selection;
+            Some((utxo, self.amounts[&utxo]))
+          }
+          None => self.closest_cardinal_utxo(&cardinals, target_value, prefer_under),
+        }
       }
+      CoinSelection::LargestFirst => cardinals
+        .iter()
+        .max_by_key(|utxo| self.amounts[utxo])
+        .map(|utxo| (*utxo, self.amounts[utxo])),
+      CoinSelection::OldestFirst => cardinals
+        .iter()
+        .max_by_key(|utxo| {
+          (
+            self.confirmations.get(utxo).copied().unwrap_or_default(),
+            self.amounts[utxo],
+          )
+        })
+        .map(|utxo| (*utxo, self.amounts[utxo])),
+      CoinSelection::Single => cardinals
+        .iter()
+        .filter(|utxo| self.amounts[utxo] >= target_value)
+        .min_by_key(|utxo| self.amounts[utxo])
+        .map(|utxo| (*utxo, self.amounts[utxo])),
+    };
 
+    let (utxo, value) = best_match.ok_or(Error::NotEnoughCardinalUtxos)?;
+
+    self.utxos.remove(&utxo);
+    tprintln!("found cardinal worth {}", value);
+
+    Ok((utxo, value))
+  }
+
+  fn closest_cardinal_utxo(
+    &self,
+    cardinals: &[OutPoint],
+    target_value: Amount,
+    prefer_under: bool,
+  ) -> Option<(OutPoint, Amount)> {
+    let mut best_match = None;
+    for utxo in cardinals {
       let current_value = self.amounts[utxo];
 
       let (_, best_value) = match best_match {
@@ -933,12 +1239,112 @@ impl TransactionBuilder {
       }
     }
 
-    let (utxo, value) = best_match.ok_or(Error::NotEnoughCardinalUtxos)?;
+    best_match
+  }
 
-    self.utxos.remove(&utxo);
-    tprintln!("found cardinal worth {}", value);
+  /// Search for a set of cardinals whose value, net of the fee for spending
+  /// them, covers `target_value` without leaving enough excess to be worth a
+  /// change output. `target_value` includes the fee for one input.
+  fn branch_and_bound(
+    &self,
+    cardinals: &[OutPoint],
+    target_value: Amount,
+  ) -> Option<Vec<OutPoint>> {
+    const MAX_TRIES: usize = 100_000;
+
+    fn search(
+      candidates: &[(OutPoint, u64)],
+      index: usize,
+      sum: u64,
+      remaining: u64,
+      (target, upper): (u64, u64),
+      selection: &mut Vec<OutPoint>,
+      tries: &mut usize,
+    ) -> bool {
+      if sum > upper {
+        return false;
+      }
 
-    Ok((utxo, value))
+      if sum >= target {
+        return true;
+      }
+
+      if index == candidates.len() || sum + remaining < target || *tries == 0 {
+        return false;
+      }
+
+      *tries -= 1;
+
+      let (utxo, value) = candidates[index];
+
+      selection.push(utxo);
+      if search(
+        candidates,
+        index + 1,
+        sum + value,
+        remaining - value,
+        (target, upper),
+        selection,
+        tries,
+      ) {
+        return true;
+      }
+      selection.pop();
+
+      search(
+        candidates,
+        index + 1,
+        sum,
+        remaining - value,
+        (target, upper),
+        selection,
+        tries,
+      )
+    }
+
+    let input_fee = self.fee_rate.fee(Self::ADDITIONAL_INPUT_WEIGHT);
+
+    let target = target_value.checked_sub(input_fee)?.to_sat();
+
+    let cost_of_change = self
+      .unused_change_addresses
+      .last()
+      .unwrap()
+      .script_pubkey()
+      .dust_value()
+      + self.fee_rate.fee(Self::ADDITIONAL_OUTPUT_WEIGHT);
+
+    let mut candidates = cardinals
+      .iter()
+      .filter_map(|utxo| {
+        self.amounts[utxo]
+          .checked_sub(self.input_fee(*utxo))
+          .filter(|value| *value > Amount::ZERO)
+          .map(|value| (*utxo, value.to_sat()))
+      })
+      .collect::<Vec<(OutPoint, u64)>>();
+
+    candidates.sort_by_key(|(_utxo, value)| std::cmp::Reverse(*value));
+
+    let mut selection = Vec::new();
+    let mut tries = MAX_TRIES;
+
+    if !search(
+      &candidates,
+      0,
+      0,
+      candidates.iter().map(|(_utxo, value)| value).sum(),
+      (target, target + cost_of_change.to_sat()),
+      &mut selection,
+      &mut tries,
+    ) {
+      return None;
+    }
+
+    // the rest of the selection is popped from the back
+    selection[1..].reverse();
+
+    Some(selection)
   }
 }
 
@@ -967,6 +1373,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1017,6 +1427,13 @@ mod tests {
       current_output: 0,
       padding_outputs: 0,
       ignore_utxo_inscriptions: false,
+      coin_selection: CoinSelection::Closest,
+      confirmations: BTreeMap::new(),
+      satisfactions: BTreeMap::new(),
+      dust_policy: DustPolicy::Alignment,
+      dust_change: Amount::ZERO,
+      pending_selection: Vec::new(),
+      following: Vec::new(),
     };
 
     pretty_assert_eq!(
@@ -1049,6 +1466,9 @@ mod tests {
       None,
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
     )
     .unwrap()
     .is_explicitly_rbf())
@@ -1070,6 +1490,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1098,6 +1521,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1126,6 +1553,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1152,6 +1582,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos),
     )
@@ -1176,6 +1609,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos),
     )
@@ -1200,6 +1636,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1235,6 +1674,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .build()
@@ -1259,6 +1702,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .build()
@@ -1283,6 +1730,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .build()
@@ -1307,6 +1758,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1338,6 +1793,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1364,6 +1823,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1398,6 +1860,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1422,6 +1888,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1451,6 +1920,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1479,6 +1951,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1512,6 +1988,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1543,6 +2023,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1571,6 +2055,10 @@ mod tests {
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -1611,6 +2099,13 @@ mod tests {
       current_output: 0,
       padding_outputs: 0,
       ignore_utxo_inscriptions: false,
+      coin_selection: CoinSelection::Closest,
+      confirmations: BTreeMap::new(),
+      satisfactions: BTreeMap::new(),
+      dust_policy: DustPolicy::Alignment,
+      dust_change: Amount::ZERO,
+      pending_selection: Vec::new(),
+      following: Vec::new(),
     }
     .build()
     .unwrap();
@@ -1647,6 +2142,13 @@ mod tests {
       current_output: 0,
       padding_outputs: 0,
       ignore_utxo_inscriptions: false,
+      coin_selection: CoinSelection::Closest,
+      confirmations: BTreeMap::new(),
+      satisfactions: BTreeMap::new(),
+      dust_policy: DustPolicy::Alignment,
+      dust_change: Amount::ZERO,
+      pending_selection: Vec::new(),
+      following: Vec::new(),
     }
     .build()
     .unwrap();
@@ -1671,6 +2173,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos)
     )
@@ -1692,6 +2197,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::UtxoContainsAdditionalInscription {
         outgoing_satpoint: satpoint(1, 0),
@@ -1718,6 +2226,9 @@ mod tests {
       None,
       TransactionBuilder::DEFAULT_TARGET_POSTAGE,
       TransactionBuilder::DEFAULT_MAX_POSTAGE,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
     )
     .unwrap();
 
@@ -1750,7 +2261,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(1.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1778,7 +2292,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(1.0).unwrap(),
         None,
-        Amount::from_sat(1500)
+        Amount::from_sat(1500),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1803,7 +2320,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(1.0).unwrap(),
         None,
-        Amount::from_sat(1)
+        Amount::from_sat(1),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::Dust {
         output_value: Amount::from_sat(1),
@@ -1829,7 +2349,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(1.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos),
     )
@@ -1852,7 +2375,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(4.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos),
     )
@@ -1860,16 +2386,101 @@ mod tests {
 
   #[test]
   fn additional_input_size_is_correct() {
-    let before = TransactionBuilder::estimate_weight_with(1, Vec::new());
-    let after = TransactionBuilder::estimate_weight_with(2, Vec::new());
+    let before =
+      TransactionBuilder::estimate_weight_with(vec![Satisfaction::default()], Vec::new());
+    let after =
+      TransactionBuilder::estimate_weight_with(vec![Satisfaction::default(); 2], Vec::new());
     assert_eq!(after - before, TransactionBuilder::ADDITIONAL_INPUT_WEIGHT);
   }
 
+  #[test]
+  fn predicted_input_sizes_are_correct() {
+    for (address, weight) in [
+      ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", 272),
+      ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", 364),
+      ("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", 593),
+      (
+        "bc1pxwww0ct9ue7e8tdnlmug5m2tamfn7q06sahstg39ys4c9f3340qqxrdu9k",
+        230,
+      ),
+    ] {
+      let satisfaction = Satisfaction::predict(
+        &address
+          .parse::<Address<NetworkUnchecked>>()
+          .unwrap()
+          .assume_checked()
+          .script_pubkey(),
+      );
+
+      let before =
+        TransactionBuilder::estimate_weight_with(vec![Satisfaction::default()], Vec::new());
+      let after = TransactionBuilder::estimate_weight_with(
+        vec![Satisfaction::default(), satisfaction.clone()],
+        Vec::new(),
+      );
+
+      assert_eq!(after - before, Weight::from_wu(weight), "{address}");
+      assert_eq!(
+        after - before,
+        TransactionBuilder::ADDITIONAL_INPUT_WEIGHT - Satisfaction::default().weight()
+          + satisfaction.weight(),
+        "{address}",
+      );
+    }
+  }
+
+  #[test]
+  fn heavier_inputs_pay_more_fee() {
+    let utxos = vec![(outpoint(1), Amount::from_sat(5_000))];
+
+    let build = |satisfactions| {
+      TransactionBuilder::build_transaction_with_postage(
+        satpoint(1, 0),
+        BTreeMap::new(),
+        utxos.clone().into_iter().collect(),
+        recipient(),
+        None,
+        [change(0), change(1)],
+        FeeRate::try_from(10.0).unwrap(),
+        None,
+        TransactionBuilder::DEFAULT_TARGET_POSTAGE,
+        TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        satisfactions,
+      )
+      .unwrap()
+    };
+
+    let taproot = build(BTreeMap::new());
+    let p2wpkh = build(
+      [(
+        outpoint(1),
+        Satisfaction::predict(
+          &"bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+            .parse::<Address<NetworkUnchecked>>()
+            .unwrap()
+            .assume_checked()
+            .script_pubkey(),
+        ),
+      )]
+      .into(),
+    );
+
+    assert_eq!(
+      taproot.output[0].value - p2wpkh.output[0].value,
+      FeeRate::try_from(10.0)
+        .unwrap()
+        .fee(Weight::from_wu(272 - 230))
+        .to_sat(),
+    );
+  }
+
   #[test]
   fn additional_output_size_is_correct() {
-    let before = TransactionBuilder::estimate_weight_with(0, Vec::new());
+    let before = TransactionBuilder::estimate_weight_with(Vec::new(), Vec::new());
     let after = TransactionBuilder::estimate_weight_with(
-      0,
+      Vec::new(),
       vec![
         "bc1pxwww0ct9ue7e8tdnlmug5m2tamfn7q06sahstg39ys4c9f3340qqxrdu9k"
           .parse::<Address<NetworkUnchecked>>()
@@ -1894,7 +2505,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(1.0).unwrap(),
         None,
-        Amount::from_sat(707)
+        Amount::from_sat(707),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1921,6 +2535,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1945,7 +2562,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(5.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -1970,7 +2590,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(6.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::NotEnoughCardinalUtxos)
     );
@@ -1990,7 +2613,10 @@ mod tests {
         [recipient(), change(1)],
         FeeRate::try_from(0.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::DuplicateAddress(recipient()))
     );
@@ -2010,7 +2636,10 @@ mod tests {
         [change(0), change(0)],
         FeeRate::try_from(0.0).unwrap(),
         None,
-        Amount::from_sat(1000)
+        Amount::from_sat(1000),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Err(Error::DuplicateAddress(change(0)))
     );
@@ -2030,7 +2659,10 @@ mod tests {
         [change(0), change(1)],
         FeeRate::try_from(2.0).unwrap(),
         None,
-        Amount::from_sat(1500)
+        Amount::from_sat(1500),
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -2041,6 +2673,95 @@ mod tests {
     );
   }
 
+  #[test]
+  fn dust_policy_decides_where_dust_change_goes() {
+    let build = |dust_policy| {
+      TransactionBuilder::build_transaction_with_values(
+        satpoint(1, 0),
+        BTreeMap::new(),
+        vec![(outpoint(1), Amount::from_sat(2000))]
+          .into_iter()
+          .collect(),
+        vec![recipient()],
+        alignment(),
+        [change(0), change(1)],
+        FeeRate::try_from(2.0).unwrap(),
+        vec![Amount::from_sat(1500)],
+        None,
+        false,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
+        dust_policy,
+        Vec::new(),
+      )
+    };
+
+    pretty_assert_eq!(
+      build(DustPolicy::Alignment),
+      Ok((
+        Transaction {
+          version: 1,
+          lock_time: LockTime::ZERO,
+          input: vec![tx_in(outpoint(1))],
+          output: vec![tx_out(1802, recipient())],
+        },
+        Amount::ZERO,
+      )),
+    );
+
+    pretty_assert_eq!(
+      build(DustPolicy::Fee),
+      Ok((
+        Transaction {
+          version: 1,
+          lock_time: LockTime::ZERO,
+          input: vec![tx_in(outpoint(1))],
+          output: vec![tx_out(1500, recipient())],
+        },
+        Amount::from_sat(302),
+      )),
+    );
+
+    pretty_assert_eq!(
+      build(DustPolicy::Error),
+      Err(Error::DustChange(Amount::from_sat(302))),
+    );
+  }
+
+  #[test]
+  fn following_inputs_are_spent_after_outgoing() {
+    pretty_assert_eq!(
+      TransactionBuilder::build_transaction_with_values(
+        satpoint(1, 1000),
+        BTreeMap::new(),
+        vec![
+          (outpoint(1), Amount::from_sat(1500)),
+          (outpoint(2), Amount::from_sat(20_000)),
+          (outpoint(3), Amount::from_sat(1000)),
+        ]
+        .into_iter()
+        .collect(),
+        vec![recipient()],
+        alignment(),
+        [change(0), change(1)],
+        FeeRate::try_from(1.0).unwrap(),
+        vec![Amount::from_sat(1200)],
+        None,
+        false,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
+        DustPolicy::Alignment,
+        vec![outpoint(3)],
+      )
+      .unwrap()
+      .0
+      .input,
+      vec![tx_in(outpoint(1)), tx_in(outpoint(3))],
+    );
+  }
+
   #[test]
   fn output_over_max_postage_because_fees_prevent_excess_value_stripping() {
     pretty_assert_eq!(
@@ -2057,6 +2778,9 @@ mod tests {
         None,
         TransactionBuilder::DEFAULT_TARGET_POSTAGE,
         TransactionBuilder::DEFAULT_MAX_POSTAGE,
+        CoinSelection::Closest,
+        BTreeMap::new(),
+        BTreeMap::new(),
       ),
       Ok(Transaction {
         version: 1,
@@ -2091,6 +2815,10 @@ mod tests {
       Amount::from_sat(0),
       Amount::from_sat(0),
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -2141,6 +2869,10 @@ mod tests {
       Amount::from_sat(0),
       Amount::from_sat(0),
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap()
     .select_outgoing()
@@ -2198,6 +2930,10 @@ mod tests {
       Amount::from_sat(0),
       Amount::from_sat(0),
       false,
+      CoinSelection::Closest,
+      BTreeMap::new(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
     )
     .unwrap();
 
@@ -2238,4 +2974,76 @@ mod tests {
       Amount::from_sat(20_000),
     );
   }
+
+  fn select_cardinal_utxos_with(
+    coin_selection: CoinSelection,
+    target_value: Amount,
+    count: usize,
+  ) -> Vec<Amount> {
+    let utxos = vec![
+      (outpoint(1), Amount::from_sat(20_000)),
+      (outpoint(2), Amount::from_sat(105)),
+      (outpoint(3), Amount::from_sat(10_000)),
+      (outpoint(4), Amount::from_sat(50_000)),
+    ];
+
+    let mut tx_builder = TransactionBuilder::new(
+      satpoint(0, 0),
+      BTreeMap::new(),
+      utxos.into_iter().collect(),
+      vec![recipient()],
+      None,
+      [change(0), change(1)],
+      FeeRate::try_from(1.0).unwrap(),
+      None,
+      vec![Target::Value(Amount::from_sat(10_000))],
+      Amount::from_sat(0),
+      Amount::from_sat(0),
+      false,
+      coin_selection,
+      vec![(outpoint(2), 10), (outpoint(3), 100), (outpoint(4), 1)]
+        .into_iter()
+        .collect(),
+      BTreeMap::new(),
+      DustPolicy::Alignment,
+    )
+    .unwrap();
+
+    (0..count)
+      .map(|_| {
+        tx_builder
+          .select_cardinal_utxo(target_value, false)
+          .unwrap()
+          .1
+      })
+      .collect()
+  }
+
+  #[test]
+  fn coin_selection_strategies() {
+    assert_eq!(
+      select_cardinal_utxos_with(CoinSelection::Closest, Amount::from_sat(15_000), 1),
+      [Amount::from_sat(20_000)]
+    );
+
+    assert_eq!(
+      select_cardinal_utxos_with(CoinSelection::LargestFirst, Amount::from_sat(15_000), 1),
+      [Amount::from_sat(50_000)]
+    );
+
+    assert_eq!(
+      select_cardinal_utxos_with(CoinSelection::OldestFirst, Amount::from_sat(15_000), 1),
+      [Amount::from_sat(10_000)]
+    );
+
+    assert_eq!(
+      select_cardinal_utxos_with(CoinSelection::Single, Amount::from_sat(30_000), 1),
+      [Amount::from_sat(50_000)]
+    );
+
+    assert_eq!(
+      select_cardinal_utxos_with(CoinSelection::BranchAndBound, Amount::from_sat(29_942), 2),
+      [Amount::from_sat(20_000), Amount::from_sat(10_000)]
+    );
+  }
 }
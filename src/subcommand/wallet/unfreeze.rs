@@ -0,0 +1,47 @@
+use {
+  super::{
+    freeze::{self, load_frozen_outputs, lock_unspent, resolve_outputs, save_frozen_outputs},
+    *,
+  },
+  crate::wallet::Wallet,
+};
+
+#[derive(Debug, Parser)]
+pub(crate) struct Unfreeze {
+  #[clap(
+    required = true,
+    help = "Unfreeze <OUTPUT>, given as an outpoint or the ID of an inscription it contains."
+  )]
+  outputs: Vec<Object>,
+}
+
+impl Unfreeze {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let index = Index::open_shared(&options)?;
+    index.update()?;
+
+    Wallet::load(&options)?;
+
+    let client = options.bitcoin_rpc_client_for_wallet_command(false)?;
+
+    let outputs = resolve_outputs(&index, &self.outputs)?;
+
+    let mut frozen = load_frozen_outputs(&options)?;
+
+    for output in &outputs {
+      if !frozen.remove(output) {
+        bail!("output {output} is not frozen");
+      }
+    }
+
+    lock_unspent(&client, true, &outputs)?;
+
+    save_frozen_outputs(&options, &frozen)?;
+
+    print_json(freeze::Output {
+      frozen: frozen.into_iter().collect(),
+    })?;
+
+    Ok(())
+  }
+}
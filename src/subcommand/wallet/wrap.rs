@@ -0,0 +1,120 @@
+use {super::*, inscribe::Inscribe};
+
+/// Renders an HTML template once for each item of a JSON array, and inscribes
+/// the results in one batch, for collections of recursive inscriptions that
+/// load shared assets from `/content/<INSCRIPTION_ID>`.
+#[derive(Debug, Parser)]
+pub(crate) struct Wrap {
+  #[clap(
+    long,
+    help = "Render <TEMPLATE>, replacing each `{{FIELD}}` with the item's value of FIELD."
+  )]
+  template: PathBuf,
+  #[clap(
+    long,
+    help = "Render the template for each object in JSON array <DATA>."
+  )]
+  data: PathBuf,
+  #[clap(flatten)]
+  inscribe: Inscribe,
+}
+
+impl Wrap {
+  pub(crate) fn run(self, options: Options) -> Result {
+    let mut inscribe = self.inscribe;
+
+    if !inscribe.files.is_empty() || inscribe.dir.is_some() || inscribe.csv.is_some() {
+      bail!("Cannot use both --template and provide files");
+    }
+
+    let template = fs::read_to_string(&self.template)
+      .with_context(|| format!("io error reading {}", self.template.display()))?;
+
+    let items = serde_json::from_reader::<_, Vec<serde_json::Map<String, serde_json::Value>>>(
+      File::open(&self.data)
+        .with_context(|| format!("io error reading {}", self.data.display()))?,
+    )
+    .with_context(|| format!("{} is not a JSON array of objects", self.data.display()))?;
+
+    if items.is_empty() {
+      bail!("{} has no items", self.data.display());
+    }
+
+    let tempdir = TempDir::new()?;
+
+    let width = (items.len() - 1).to_string().len();
+
+    for (i, item) in items.iter().enumerate() {
+      let html = Self::render(&template, item).with_context(|| format!("item {i}"))?;
+
+      // zero padded, so that names sort in item order
+      let path = tempdir.path().join(format!("{i:0width$}.html"));
+      fs::write(&path, html)?;
+
+      inscribe.files.push(path);
+    }
+
+    inscribe.run(options)
+  }
+
+  /// Replace each `{{FIELD}}` in `template` with the value of `FIELD`.
+  /// Strings are inserted as they are, and other values as JSON.
+  fn render(template: &str, item: &serde_json::Map<String, serde_json::Value>) -> Result<String> {
+    let mut html = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+      html.push_str(&rest[..start]);
+
+      let end = rest[start..]
+        .find("}}")
+        .ok_or_else(|| anyhow!("unclosed `{{{{` in template"))?;
+
+      let field = rest[start + 2..start + end].trim();
+
+      match item.get(field) {
+        Some(serde_json::Value::String(value)) => html.push_str(value),
+        Some(value) => html.push_str(&value.to_string()),
+        None => bail!("no field `{field}`"),
+      }
+
+      rest = &rest[start + end + 2..];
+    }
+
+    html.push_str(rest);
+
+    Ok(html)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render() {
+    let item = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(
+      r#"{"name": "foo", "number": 1}"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      Wrap::render("<p>{{name}} #{{ number }}</p>", &item).unwrap(),
+      "<p>foo #1</p>"
+    );
+
+    assert_eq!(
+      Wrap::render("<p>{{bar}}</p>", &item)
+        .unwrap_err()
+        .to_string(),
+      "no field `bar`"
+    );
+
+    assert_eq!(
+      Wrap::render("<p>{{name</p>", &item)
+        .unwrap_err()
+        .to_string(),
+      "unclosed `{{` in template"
+    );
+  }
+}
@@ -0,0 +1,328 @@
+use super::*;
+
+#[derive(Debug, Parser)]
+pub(crate) enum Watch {
+  #[clap(about = "Watch a satpoint, outpoint, or inscription")]
+  Add(Add),
+  #[clap(about = "Stop watching a satpoint, outpoint, or inscription")]
+  Remove(Remove),
+  #[clap(about = "List watched items")]
+  List,
+  #[clap(about = "Emit events as watched items are spent or transferred")]
+  Run(Run),
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Add {
+  #[clap(help = "Watch <ITEM>, a satpoint, outpoint, or inscription id.")]
+  item: Object,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Remove {
+  #[clap(help = "Stop watching <ITEM>.")]
+  item: Object,
+}
+
+#[derive(Debug, Parser)]
+pub(crate) struct Run {
+  #[clap(long, help = "POST each event as JSON to <WEBHOOK>.")]
+  webhook: Option<String>,
+  #[clap(
+    long,
+    default_value = "10",
+    help = "Check for new blocks every <POLL_INTERVAL> seconds."
+  )]
+  poll_interval: u64,
+  #[clap(long, help = "Exit after checking the blocks indexed so far.")]
+  once: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Item {
+  Inscription(InscriptionId),
+  Outpoint(OutPoint),
+  Satpoint(SatPoint),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+  /// A watched outpoint or satpoint was spent.
+  Spent,
+  /// A watched inscription was transferred.
+  Transferred,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+  pub item: Item,
+  pub kind: Kind,
+  pub height: u64,
+  pub txid: Txid,
+  /// Where a transferred inscription is now.
+  pub satpoint: Option<SatPoint>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Output {
+  pub items: Vec<Item>,
+  pub next_height: Option<u64>,
+}
+
+/// The watch list, kept in the data dir. Spent outpoints and satpoints are
+/// removed, since they can't be spent again.
+#[derive(Default, Serialize, Deserialize)]
+struct WatchList {
+  items: Vec<Item>,
+  /// The first block that hasn't been checked for events.
+  next_height: Option<u64>,
+}
+
+impl Watch {
+  pub(crate) fn run(self, options: Options) -> Result {
+    match self {
+      Self::Add(add) => add.run(options),
+      Self::Remove(remove) => remove.run(options),
+      Self::List => {
+        let watch_list = WatchList::load(&options)?;
+
+        print_json(Output {
+          items: watch_list.items,
+          next_height: watch_list.next_height,
+        })
+      }
+      Self::Run(run) => run.run(options),
+    }
+  }
+}
+
+impl Item {
+  fn from_object(object: Object) -> Result<Self> {
+    match object {
+      Object::InscriptionId(inscription) => Ok(Self::Inscription(inscription)),
+      Object::OutPoint(outpoint) => Ok(Self::Outpoint(outpoint)),
+      Object::SatPoint(satpoint) => Ok(Self::Satpoint(satpoint)),
+      object => bail!("{object} is not a satpoint, outpoint, or inscription id"),
+    }
+  }
+}
+
+impl Add {
+  fn run(self, options: Options) -> Result {
+    let item = Item::from_object(self.item)?;
+
+    let index = Index::open(&options)?;
+    index.update()?;
+
+    if let Item::Inscription(inscription) = item {
+      if index.get_inscription_entry(inscription)?.is_none() {
+        bail!("inscription {inscription} not found");
+      }
+    }
+
+    let mut watch_list = WatchList::load(&options)?;
+
+    if !watch_list.items.contains(&item) {
+      watch_list.items.push(item);
+    }
+
+    // events are only emitted for blocks after the first item was added
+    if watch_list.next_height.is_none() {
+      watch_list.next_height = Some(index.block_count()?);
+    }
+
+    watch_list.save(&options)?;
+
+    print_json(Output {
+      items: watch_list.items,
+      next_height: watch_list.next_height,
+    })
+  }
+}
+
+impl Remove {
+  fn run(self, options: Options) -> Result {
+    let description = self.item.to_string();
+
+    let item = Item::from_object(self.item)?;
+
+    let mut watch_list = WatchList::load(&options)?;
+
+    let len = watch_list.items.len();
+
+    watch_list.items.retain(|watched| *watched != item);
+
+    if watch_list.items.len() == len {
+      bail!("{description} is not watched");
+    }
+
+    watch_list.save(&options)?;
+
+    print_json(Output {
+      items: watch_list.items,
+      next_height: watch_list.next_height,
+    })
+  }
+}
+
+impl Run {
+  fn run(self, options: Options) -> Result {
+    let index = Index::open(&options)?;
+
+    let webhook = match &self.webhook {
+      Some(url) => {
+        let mut client = reqwest::blocking::Client::builder();
+
+        if let Some(proxy) = options.proxy()? {
+          client = client.proxy(reqwest::Proxy::all(proxy)?);
+        }
+
+        Some((client.build()?, url))
+      }
+      None => None,
+    };
+
+    loop {
+      index.update()?;
+
+      let mut watch_list = WatchList::load(&options)?;
+
+      let block_count = index.block_count()?;
+
+      for height in watch_list.next_height.unwrap_or(block_count)..block_count {
+        for event in watch_list.check(&index, height)? {
+          print_line(serde_json::to_string(&event)?)?;
+
+          if let Some((client, url)) = &webhook {
+            if let Err(err) = client
+              .post(url.as_str())
+              .json(&event)
+              .send()
+              .and_then(|response| response.error_for_status())
+            {
+              log::warn!("failed to POST event to {url}: {err}");
+            }
+          }
+        }
+
+        // saved after every block, so that a restarted watcher resumes
+        // without repeating or missing events
+        watch_list.next_height = Some(height + 1);
+        watch_list.save(&options)?;
+      }
+
+      if watch_list.next_height.is_none() {
+        watch_list.next_height = Some(block_count);
+        watch_list.save(&options)?;
+      }
+
+      if self.once {
+        return Ok(());
+      }
+
+      if SHUTTING_DOWN.load(atomic::Ordering::Relaxed) {
+        return Ok(());
+      }
+
+      thread::sleep(Duration::from_secs(self.poll_interval));
+    }
+  }
+}
+
+impl WatchList {
+  /// Events at `height`. Transfers of inscriptions come from the transfer
+  /// log, and spends of outpoints and satpoints from the block's inputs.
+  fn check(&mut self, index: &Index, height: u64) -> Result<Vec<Event>> {
+    let mut events = Vec::new();
+
+    let transferred = index.get_inscription_ids_by_height(height)?;
+
+    for item in &self.items {
+      if let Item::Inscription(inscription) = item {
+        if !transferred.contains(inscription) {
+          continue;
+        }
+
+        let satpoint = index
+          .get_inscription_satpoint_by_id(*inscription)?
+          .ok_or_else(|| anyhow!("inscription {inscription} not found"))?;
+
+        events.push(Event {
+          item: *item,
+          kind: Kind::Transferred,
+          height,
+          txid: satpoint.outpoint.txid,
+          satpoint: Some(satpoint),
+        });
+      }
+    }
+
+    if self
+      .items
+      .iter()
+      .any(|item| !matches!(item, Item::Inscription(_)))
+    {
+      let block = index
+        .get_block_by_height(height)?
+        .ok_or_else(|| anyhow!("block {height} not found"))?;
+
+      for tx in &block.txdata {
+        for input in &tx.input {
+          for item in &self.items {
+            let outpoint = match item {
+              Item::Inscription(_) => continue,
+              Item::Outpoint(outpoint) => *outpoint,
+              Item::Satpoint(satpoint) => satpoint.outpoint,
+            };
+
+            if input.previous_output == outpoint {
+              events.push(Event {
+                item: *item,
+                kind: Kind::Spent,
+                height,
+                txid: tx.txid(),
+                satpoint: None,
+              });
+            }
+          }
+        }
+      }
+
+      self.items.retain(|item| {
+        !events
+          .iter()
+          .any(|event| event.kind == Kind::Spent && event.item == *item)
+      });
+    }
+
+    Ok(events)
+  }
+
+  fn load(options: &Options) -> Result<Self> {
+    let path = Self::path(options)?;
+
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    serde_json::from_reader(File::open(&path)?)
+      .with_context(|| format!("failed to parse watch list {}", path.display()))
+  }
+
+  fn save(&self, options: &Options) -> Result {
+    let path = Self::path(options)?;
+
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+
+    fs::write(&path, serde_json::to_string_pretty(self)?)
+      .with_context(|| format!("failed to write watch list {}", path.display()))
+  }
+
+  fn path(options: &Options) -> Result<PathBuf> {
+    Ok(options.data_dir()?.join("watch.json"))
+  }
+}
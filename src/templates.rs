@@ -3,6 +3,7 @@ use {super::*, boilerplate::Boilerplate};
 pub(crate) use {
   block::BlockHtml,
   clock::ClockSvg,
+  gallery::{GalleryEntry, GalleryHtml},
   home::HomeHtml,
   iframe::Iframe,
   input::InputHtml,
@@ -22,6 +23,7 @@ pub(crate) use {
 
 mod block;
 mod clock;
+mod gallery;
 mod home;
 mod iframe;
 mod input;
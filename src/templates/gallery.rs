@@ -0,0 +1,56 @@
+use super::*;
+
+pub(crate) struct GalleryEntry {
+  pub(crate) id: InscriptionId,
+  pub(crate) number: i64,
+  pub(crate) media: Media,
+  /// Path of the content file, relative to the gallery.
+  pub(crate) file: String,
+  pub(crate) explorer: String,
+}
+
+#[derive(Boilerplate)]
+pub(crate) struct GalleryHtml {
+  pub(crate) title: String,
+  pub(crate) entries: Vec<GalleryEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn gallery() {
+    assert_regex_match!(
+      GalleryHtml {
+        title: "Wallet".into(),
+        entries: vec![GalleryEntry {
+          id: inscription_id(1),
+          number: 7,
+          media: Media::Image,
+          file: format!("content/{}.png", inscription_id(1)),
+          explorer: format!("https://ordinals.com/inscription/{}", inscription_id(1)),
+        }],
+      },
+      "
+        <!doctype html>
+        <html lang=en>
+        .*<title>Wallet</title>
+        .*<h1>Wallet</h1>
+        .*<div class=entry>
+          <img src=content/1{64}i1.png loading=lazy>
+          <dl>
+            <dt>id</dt>
+            <dd>1{64}i1</dd>
+            <dt>number</dt>
+            <dd>7</dd>
+          </dl>
+          <a href=content/1{64}i1.png>content</a>
+          <a href=https://ordinals.com/inscription/1{64}i1>explorer</a>
+        </div>
+        .*
+      "
+      .unindent()
+    );
+  }
+}
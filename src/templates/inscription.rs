@@ -22,6 +22,8 @@ pub struct InscriptionJson {
   pub number: i64,
   pub genesis_height: u64,
   pub genesis_fee: u64,
+  /// `None` for inscriptions indexed before the vsize was recorded.
+  pub genesis_vsize: Option<u64>,
   pub output_value: Option<u64>,
   pub address: Option<String>,
   pub sat: Option<Sat>,
@@ -38,6 +40,7 @@ impl InscriptionJson {
     chain: Chain,
     genesis_fee: u64,
     genesis_height: u64,
+    genesis_vsize: Option<u64>,
     inscription: Inscription,
     inscription_id: InscriptionId,
     next: Option<InscriptionId>,
@@ -53,6 +56,7 @@ impl InscriptionJson {
       number,
       genesis_height,
       genesis_fee,
+      genesis_vsize,
       output_value: output.as_ref().map(|o| o.value),
       address: output
         .as_ref()
@@ -54,13 +54,32 @@ pub trait Api {
   fn sign_raw_transaction_with_wallet(
     &self,
     tx: String,
-    utxos: Option<()>,
+    utxos: Option<Vec<Value>>,
     sighash_type: Option<()>,
   ) -> Result<Value, jsonrpc_core::Error>;
 
   #[rpc(name = "sendrawtransaction")]
   fn send_raw_transaction(&self, tx: String) -> Result<String, jsonrpc_core::Error>;
 
+  #[rpc(name = "getrawmempool")]
+  fn get_raw_mempool(&self) -> Result<Vec<Txid>, jsonrpc_core::Error>;
+
+  #[rpc(name = "getmempoolentry")]
+  fn get_mempool_entry(&self, txid: Txid) -> Result<Value, jsonrpc_core::Error>;
+
+  #[rpc(name = "estimatesmartfee")]
+  fn estimate_smart_fee(
+    &self,
+    conf_target: u16,
+    estimate_mode: Option<EstimateMode>,
+  ) -> Result<Value, jsonrpc_core::Error>;
+
+  #[rpc(name = "testmempoolaccept")]
+  fn test_mempool_accept(&self, rawtxs: Vec<String>) -> Result<Vec<Value>, jsonrpc_core::Error>;
+
+  #[rpc(name = "submitpackage")]
+  fn submit_package(&self, rawtxs: Vec<String>) -> Result<Value, jsonrpc_core::Error>;
+
   #[rpc(name = "sendtoaddress")]
   fn send_to_address(
     &self,
@@ -123,6 +142,13 @@ pub trait Api {
     req: Vec<ImportDescriptors>,
   ) -> Result<Vec<ImportMultiResult>, jsonrpc_core::Error>;
 
+  #[rpc(name = "generatetoaddress")]
+  fn generate_to_address(
+    &self,
+    nblocks: u64,
+    address: String,
+  ) -> Result<Vec<BlockHash>, jsonrpc_core::Error>;
+
   #[rpc(name = "getnewaddress")]
   fn get_new_address(
     &self,
@@ -144,14 +170,30 @@ pub trait Api {
     &self,
     unlock: bool,
     outputs: Vec<JsonOutPoint>,
+    persistent: Option<bool>,
   ) -> Result<bool, jsonrpc_core::Error>;
 
+  #[rpc(name = "setlabel")]
+  fn set_label(&self, address: String, label: String) -> Result<(), jsonrpc_core::Error>;
+
   #[rpc(name = "listdescriptors")]
-  fn list_descriptors(&self) -> Result<ListDescriptorsResult, jsonrpc_core::Error>;
+  fn list_descriptors(
+    &self,
+    private: Option<bool>,
+  ) -> Result<ListDescriptorsResult, jsonrpc_core::Error>;
 
   #[rpc(name = "loadwallet")]
   fn load_wallet(&self, wallet: String) -> Result<LoadWalletResult, jsonrpc_core::Error>;
 
   #[rpc(name = "listwallets")]
   fn list_wallets(&self) -> Result<Vec<String>, jsonrpc_core::Error>;
+
+  #[rpc(name = "walletprocesspsbt")]
+  fn wallet_process_psbt(
+    &self,
+    psbt: String,
+    sign: Option<bool>,
+    sighash_type: Option<()>,
+    bip32derivs: Option<bool>,
+  ) -> Result<Value, jsonrpc_core::Error>;
 }
@@ -13,8 +13,8 @@ use {
     hashes::Hash,
     locktime::absolute::LockTime,
     pow::CompactTarget,
-    Amount, Block, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
-    Wtxid,
+    Amount, Block, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid,
+    WPubkeyHash, Witness, Wtxid,
   },
   bitcoincore_rpc::json::{
     Bip125Replaceable, CreateRawTransactionInput, Descriptor, EstimateMode, GetBalancesResult,
@@ -210,6 +210,10 @@ impl Handle {
     self.state().mempool().to_vec()
   }
 
+  pub fn remove_from_mempool(&self, txid: Txid) {
+    self.state().mempool.retain(|tx| tx.txid() != txid);
+  }
+
   pub fn descriptors(&self) -> Vec<String> {
     self.state().descriptors.clone()
   }
@@ -239,6 +243,31 @@ impl Handle {
   pub fn loaded_wallets(&self) -> BTreeSet<String> {
     self.state().loaded_wallets.clone()
   }
+
+  pub fn disable_private_keys(&self) {
+    self.state().private_keys_enabled = false;
+  }
+
+  /// Mine coinbase outputs to a P2WPKH script, and sign inputs with
+  /// witnesses the size of P2WPKH spends instead of taproot key path spends.
+  pub fn use_p2wpkh_wallet(&self) {
+    self.state().p2wpkh_wallet = true;
+  }
+
+  pub fn labels(&self) -> BTreeMap<String, String> {
+    self.state().labels.clone()
+  }
+
+  /// Set the fee rate returned by `estimatesmartfee`, in sats/vB.
+  pub fn set_fee_estimate(&self, fee_rate: f64) {
+    self.state().fee_estimate = Some(fee_rate);
+  }
+
+  /// Make `testmempoolaccept` reject packages as a whole with `error`, like
+  /// Bitcoin Core does for packages that are too large.
+  pub fn reject_packages(&self, error: &str) {
+    self.state().package_error = Some(error.into());
+  }
 }
 
 impl Drop for Handle {
@@ -1,8 +1,9 @@
 use {
   super::*,
   bitcoin::{
+    key::TweakedPublicKey,
     secp256k1::{rand, KeyPair, Secp256k1, XOnlyPublicKey},
-    Witness,
+    PrivateKey, Witness,
   },
   bitcoincore_rpc::RawTx,
 };
@@ -25,6 +26,20 @@ impl Server {
   fn not_found() -> jsonrpc_core::Error {
     jsonrpc_core::Error::new(jsonrpc_core::types::error::ErrorCode::ServerError(-8))
   }
+
+  /// The address of a `rawtr(<WIF>)` descriptor, like the recovery
+  /// descriptors imported by `ord wallet inscribe`.
+  fn rawtr_address(descriptor: &str, network: Network) -> Option<Address> {
+    let key = descriptor.strip_prefix("rawtr(")?.split(')').next()?;
+    let key = PrivateKey::from_wif(key).ok()?;
+    let (public_key, _parity) =
+      XOnlyPublicKey::from_keypair(&KeyPair::from_secret_key(&Secp256k1::new(), &key.inner));
+
+    Some(Address::p2tr_tweaked(
+      TweakedPublicKey::dangerous_assume_tweaked(public_key),
+      network,
+    ))
+  }
 }
 
 impl Api for Server {
@@ -44,6 +59,18 @@ impl Api for Server {
   }
 
   fn get_blockchain_info(&self) -> Result<GetBlockchainInfoResult, jsonrpc_core::Error> {
+    let state = self.state();
+
+    let mut times = state
+      .hashes
+      .iter()
+      .rev()
+      .take(11)
+      .map(|hash| state.blocks[hash].header.time)
+      .collect::<Vec<u32>>();
+
+    times.sort();
+
     Ok(GetBlockchainInfoResult {
       chain: String::from(match self.network {
         Network::Bitcoin => "main",
@@ -52,11 +79,11 @@ impl Api for Server {
         Network::Regtest => "regtest",
         _ => panic!(),
       }),
-      blocks: 0,
+      blocks: (state.hashes.len() - 1).try_into().unwrap(),
       headers: 0,
-      best_block_hash: self.state().hashes[0],
+      best_block_hash: state.hashes[0],
       difficulty: 0.0,
-      median_time: 0,
+      median_time: times[times.len() / 2].into(),
       verification_progress: 0.0,
       initial_block_download: false,
       chain_work: Vec::new(),
@@ -176,7 +203,7 @@ impl Api for Server {
         keypool_size: 0,
         keypool_size_hd_internal: 0,
         pay_tx_fee: Amount::from_sat(0),
-        private_keys_enabled: false,
+        private_keys_enabled: self.state().private_keys_enabled,
         scanning: None,
         tx_count: 0,
         unconfirmed_balance: Amount::from_sat(0),
@@ -241,21 +268,50 @@ impl Api for Server {
   fn sign_raw_transaction_with_wallet(
     &self,
     tx: String,
-    utxos: Option<()>,
+    utxos: Option<Vec<Value>>,
     sighash_type: Option<()>,
   ) -> Result<Value, jsonrpc_core::Error> {
-    assert_eq!(utxos, None, "utxos param not supported");
     assert_eq!(sighash_type, None, "sighash_type param not supported");
 
     let mut transaction: Transaction = deserialize(&hex::decode(tx).unwrap()).unwrap();
-    for input in &mut transaction.input {
-      input.witness = Witness::from_slice(&[&[0; 64]]);
+
+    // pay-to-anchor outputs are spent with an empty witness
+    let anchors = utxos
+      .unwrap_or_default()
+      .into_iter()
+      .filter(|utxo| utxo["scriptPubKey"] == "51024e73")
+      .map(|utxo| {
+        OutPoint::new(
+          utxo["txid"].as_str().unwrap().parse().unwrap(),
+          utxo["vout"].as_u64().unwrap().try_into().unwrap(),
+        )
+      })
+      .collect::<Vec<OutPoint>>();
+
+    let state = self.state();
+
+    let complete = state.private_keys_enabled;
+
+    if complete {
+      for input in &mut transaction.input {
+        // inputs that are already signed, like reveal inputs signed by ord,
+        // are left alone
+        if !input.witness.is_empty() || anchors.contains(&input.previous_output) {
+          continue;
+        }
+
+        input.witness = if state.p2wpkh_wallet {
+          Witness::from_slice(&[[0; 71].as_slice(), &[0; 33]])
+        } else {
+          Witness::from_slice(&[&[0; 64]])
+        };
+      }
     }
 
     Ok(
       serde_json::to_value(SignRawTransactionResult {
         hex: hex::decode(transaction.raw_hex()).unwrap(),
-        complete: true,
+        complete,
         errors: None,
       })
       .unwrap(),
@@ -269,6 +325,145 @@ impl Api for Server {
     Ok(tx.txid().to_string())
   }
 
+  fn estimate_smart_fee(
+    &self,
+    _conf_target: u16,
+    _estimate_mode: Option<EstimateMode>,
+  ) -> Result<Value, jsonrpc_core::Error> {
+    // like Bitcoin Core, in BTC/kvB
+    Ok(match self.state().fee_estimate {
+      Some(fee_rate) => serde_json::json!({
+        "feerate": fee_rate / 100_000.0,
+        "blocks": 1,
+      }),
+      None => serde_json::json!({
+        "errors": ["Insufficient data or no feerate found"],
+        "blocks": 0,
+      }),
+    })
+  }
+
+  fn get_raw_mempool(&self) -> Result<Vec<Txid>, jsonrpc_core::Error> {
+    Ok(self.state().mempool.iter().map(|tx| tx.txid()).collect())
+  }
+
+  fn get_mempool_entry(&self, txid: Txid) -> Result<Value, jsonrpc_core::Error> {
+    let state = self.state();
+
+    let tx = state
+      .mempool
+      .iter()
+      .find(|tx| tx.txid() == txid)
+      .ok_or_else(Self::not_found)?;
+
+    let ancestors = tx
+      .input
+      .iter()
+      .filter(|input| {
+        state
+          .mempool
+          .iter()
+          .any(|tx| tx.txid() == input.previous_output.txid)
+      })
+      .count();
+
+    let descendants = state
+      .mempool
+      .iter()
+      .filter(|descendant| {
+        descendant
+          .input
+          .iter()
+          .any(|input| input.previous_output.txid == txid)
+      })
+      .count();
+
+    Ok(serde_json::json!({
+      "vsize": tx.vsize(),
+      "weight": tx.weight().to_wu(),
+      "time": 0,
+      "height": 0,
+      "descendantcount": descendants + 1,
+      "descendantsize": tx.vsize(),
+      "ancestorcount": ancestors + 1,
+      "ancestorsize": tx.vsize(),
+      "wtxid": tx.wtxid(),
+      "fees": {
+        "base": 0.0,
+        "modified": 0.0,
+        "ancestor": 0.0,
+        "descendant": 0.0,
+      },
+      "depends": [],
+      "spentby": [],
+      "bip125-replaceable": true,
+      "unbroadcast": false,
+    }))
+  }
+
+  fn test_mempool_accept(&self, rawtxs: Vec<String>) -> Result<Vec<Value>, jsonrpc_core::Error> {
+    let package_error = self.state().package_error.clone();
+
+    Ok(
+      rawtxs
+        .into_iter()
+        .map(|tx| {
+          let tx: Transaction = deserialize(&hex::decode(tx).unwrap()).unwrap();
+          match &package_error {
+            Some(package_error) => serde_json::json!({
+              "txid": tx.txid(),
+              "wtxid": tx.wtxid(),
+              "package-error": package_error,
+            }),
+            None => serde_json::json!({
+              "txid": tx.txid(),
+              "wtxid": tx.wtxid(),
+              "allowed": true,
+            }),
+          }
+        })
+        .collect(),
+    )
+  }
+
+  fn submit_package(&self, rawtxs: Vec<String>) -> Result<Value, jsonrpc_core::Error> {
+    let mut state = self.state();
+
+    if state.version < 250000 {
+      return Err(jsonrpc_core::Error::method_not_found());
+    }
+
+    if state.version < 270000 && state.network != Network::Regtest {
+      return Err(jsonrpc_core::Error {
+        code: jsonrpc_core::types::error::ErrorCode::ServerError(-1),
+        message: "submitpackage is for regression testing (-regtest mode) only".into(),
+        data: None,
+      });
+    }
+
+    let mut tx_results = serde_json::Map::new();
+
+    for rawtx in rawtxs {
+      let tx: Transaction = deserialize(&hex::decode(rawtx).unwrap()).unwrap();
+
+      tx_results.insert(
+        tx.wtxid().to_string(),
+        serde_json::json!({
+          "txid": tx.txid(),
+          "vsize": tx.vsize(),
+        }),
+      );
+
+      state.mempool.push(tx);
+    }
+
+    Ok(serde_json::json!({
+      "package_msg": "success",
+      "tx-results": tx_results,
+      "replaced-transactions": [],
+    }))
+  }
+
   fn send_to_address(
     &self,
     address: Address<NetworkUnchecked>,
@@ -301,11 +496,20 @@ impl Api for Server {
       locked,
     });
 
-    Ok(
-      "0000000000000000000000000000000000000000000000000000000000000000"
-        .parse()
-        .unwrap(),
-    )
+    let txid = Txid::all_zeros();
+
+    // stand-in for the wallet transaction, which spends nothing
+    state.transactions.insert(
+      txid,
+      Transaction {
+        version: 2,
+        lock_time: LockTime::ZERO,
+        input: Vec::new(),
+        output: Vec::new(),
+      },
+    );
+
+    Ok(txid)
   }
 
   fn get_transaction(
@@ -313,12 +517,21 @@ impl Api for Server {
     txid: Txid,
     _include_watchonly: Option<bool>,
   ) -> Result<Value, jsonrpc_core::Error> {
-    match self.state.lock().unwrap().transactions.get(&txid) {
+    let state = self.state();
+
+    let confirmations = state
+      .hashes
+      .iter()
+      .position(|hash| state.blocks[hash].txdata.iter().any(|tx| tx.txid() == txid))
+      .map(|height| state.hashes.len() - height)
+      .unwrap_or_default();
+
+    match state.transactions.get(&txid) {
       Some(tx) => Ok(
         serde_json::to_value(GetTransactionResult {
           info: WalletTxInfo {
             txid,
-            confirmations: 0,
+            confirmations: confirmations.try_into().unwrap(),
             time: 0,
             timereceived: 0,
             blockhash: None,
@@ -372,7 +585,13 @@ impl Api for Server {
         None => Err(Self::not_found()),
       }
     } else {
-      match self.state().transactions.get(&txid) {
+      let state = self.state();
+
+      match state
+        .transactions
+        .get(&txid)
+        .or_else(|| state.mempool.iter().find(|tx| tx.txid() == txid))
+      {
         Some(tx) => Ok(Value::String(hex::encode(serialize(tx)))),
         None => Err(Self::not_found()),
       }
@@ -400,20 +619,28 @@ impl Api for Server {
         .utxos
         .iter()
         .filter(|(outpoint, _amount)| !state.locked.contains(outpoint))
-        .map(|(outpoint, &amount)| ListUnspentResultEntry {
-          txid: outpoint.txid,
-          vout: outpoint.vout,
-          address: None,
-          label: None,
-          redeem_script: None,
-          witness_script: None,
-          script_pub_key: ScriptBuf::new(),
-          amount,
-          confirmations: 0,
-          spendable: true,
-          solvable: true,
-          descriptor: None,
-          safe: true,
+        .map(|(outpoint, &amount)| {
+          let script_pub_key = state.transactions[&outpoint.txid].output[outpoint.vout as usize]
+            .script_pubkey
+            .clone();
+
+          ListUnspentResultEntry {
+            txid: outpoint.txid,
+            vout: outpoint.vout,
+            address: None,
+            label: Address::from_script(&script_pub_key, self.network)
+              .ok()
+              .and_then(|address| state.labels.get(&address.to_string()).cloned()),
+            redeem_script: None,
+            witness_script: None,
+            script_pub_key,
+            amount,
+            confirmations: 0,
+            spendable: true,
+            solvable: true,
+            descriptor: None,
+            safe: true,
+          }
         })
         .collect(),
     )
@@ -459,10 +686,17 @@ impl Api for Server {
     &self,
     req: Vec<ImportDescriptors>,
   ) -> Result<Vec<ImportMultiResult>, jsonrpc_core::Error> {
-    self
-      .state()
-      .descriptors
-      .extend(req.into_iter().map(|params| params.descriptor));
+    let mut state = self.state();
+
+    for params in req {
+      if let Some(label) = params.label {
+        if let Some(address) = Self::rawtr_address(&params.descriptor, self.network) {
+          state.labels.insert(address.to_string(), label);
+        }
+      }
+
+      state.descriptors.push(params.descriptor);
+    }
 
     Ok(vec![ImportMultiResult {
       success: true,
@@ -471,6 +705,19 @@ impl Api for Server {
     }])
   }
 
+  fn generate_to_address(
+    &self,
+    nblocks: u64,
+    _address: String,
+  ) -> Result<Vec<BlockHash>, jsonrpc_core::Error> {
+    let mut state = self.state();
+    Ok(
+      (0..nblocks)
+        .map(|_| state.push_block(50 * COIN_VALUE).block_hash())
+        .collect(),
+    )
+  }
+
   fn get_new_address(
     &self,
     _label: Option<String>,
@@ -532,9 +779,8 @@ impl Api for Server {
     &self,
     unlock: bool,
     outputs: Vec<JsonOutPoint>,
+    _persistent: Option<bool>,
   ) -> Result<bool, jsonrpc_core::Error> {
-    assert!(!unlock);
-
     let mut state = self.state();
 
     if state.fail_lock_unspent {
@@ -546,14 +792,26 @@ impl Api for Server {
         vout: output.vout,
         txid: output.txid,
       };
-      assert!(state.utxos.contains_key(&output));
-      state.locked.insert(output);
+      if unlock {
+        assert!(state.locked.remove(&output));
+      } else {
+        assert!(state.utxos.contains_key(&output));
+        state.locked.insert(output);
+      }
     }
 
     Ok(true)
   }
 
-  fn list_descriptors(&self) -> Result<ListDescriptorsResult, jsonrpc_core::Error> {
+  fn set_label(&self, address: String, label: String) -> Result<(), jsonrpc_core::Error> {
+    self.state().labels.insert(address, label);
+    Ok(())
+  }
+
+  fn list_descriptors(
+    &self,
+    _private: Option<bool>,
+  ) -> Result<ListDescriptorsResult, jsonrpc_core::Error> {
     Ok(ListDescriptorsResult {
       wallet_name: "ord".into(),
       descriptors: self
@@ -594,4 +852,20 @@ impl Api for Server {
         .collect::<Vec<String>>(),
     )
   }
+
+  fn wallet_process_psbt(
+    &self,
+    psbt: String,
+    _sign: Option<bool>,
+    sighash_type: Option<()>,
+    _bip32derivs: Option<bool>,
+  ) -> Result<Value, jsonrpc_core::Error> {
+    // psbts are returned unsigned, since the mock has no keys
+    assert_eq!(sighash_type, None, "sighash_type param not supported");
+
+    Ok(serde_json::json!({
+      "psbt": psbt,
+      "complete": false,
+    }))
+  }
 }
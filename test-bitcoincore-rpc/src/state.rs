@@ -4,12 +4,19 @@ pub(crate) struct State {
   pub(crate) blocks: BTreeMap<BlockHash, Block>,
   pub(crate) descriptors: Vec<String>,
   pub(crate) fail_lock_unspent: bool,
+  /// In sats/vB.
+  pub(crate) fee_estimate: Option<f64>,
   pub(crate) hashes: Vec<BlockHash>,
+  pub(crate) labels: BTreeMap<String, String>,
   pub(crate) loaded_wallets: BTreeSet<String>,
   pub(crate) locked: BTreeSet<OutPoint>,
   pub(crate) mempool: Vec<Transaction>,
   pub(crate) network: Network,
   pub(crate) nonce: u32,
+  pub(crate) p2wpkh_wallet: bool,
+  /// Returned by `testmempoolaccept` instead of checking txs individually.
+  pub(crate) package_error: Option<String>,
+  pub(crate) private_keys_enabled: bool,
   pub(crate) sent: Vec<Sent>,
   pub(crate) transactions: BTreeMap<Txid, Transaction>,
   pub(crate) utxos: BTreeMap<OutPoint, Amount>,
@@ -31,11 +38,16 @@ impl State {
       blocks,
       descriptors: Vec::new(),
       fail_lock_unspent,
+      fee_estimate: None,
       hashes,
+      labels: BTreeMap::new(),
       locked: BTreeSet::new(),
       mempool: Vec::new(),
       network,
       nonce: 0,
+      p2wpkh_wallet: false,
+      package_error: None,
+      private_keys_enabled: true,
       sent: Vec::new(),
       transactions: BTreeMap::new(),
       utxos: BTreeMap::new(),
@@ -78,7 +90,11 @@ impl State {
               fee
             })
             .sum::<u64>(),
-        script_pubkey: ScriptBuf::new(),
+        script_pubkey: if self.p2wpkh_wallet {
+          ScriptBuf::new_v0_p2wpkh(&WPubkeyHash::all_zeros())
+        } else {
+          ScriptBuf::new()
+        },
       }],
     };
 
@@ -0,0 +1,51 @@
+use {super::*, ord::subcommand::address::Output};
+
+#[test]
+fn address_shows_outputs_and_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe {
+    inscriptions,
+    reveals,
+    ..
+  } = CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --destination bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  rpc_server.mine_blocks(1);
+
+  let output =
+    CommandBuilder::new("--index-addresses address bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Output>();
+
+  assert_eq!(output.outputs, [OutPoint::new(reveals[0], 0)]);
+  assert_eq!(output.value, 10_000);
+  assert_eq!(output.inscribed, 10_000);
+  assert_eq!(output.cardinal, 0);
+  assert_eq!(
+    output
+      .inscriptions
+      .iter()
+      .map(ToString::to_string)
+      .collect::<Vec<String>>(),
+    inscriptions
+  );
+  assert!(output.rare_sats.is_none());
+}
+
+#[test]
+fn address_requires_address_index() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  CommandBuilder::new("address bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: address requires index created with `--index-addresses` flag\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
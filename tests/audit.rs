@@ -0,0 +1,63 @@
+use {super::*, ord::subcommand::audit::Entry};
+
+#[test]
+fn sent_transactions_are_recorded_in_audit_log() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let txid = CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {inscription}"
+  ))
+  .temp_dir(tempdir.clone())
+  .rpc_server(&rpc_server)
+  .stdout_regex(r".*")
+  .run_and_extract_stdout();
+
+  let entries = CommandBuilder::new("audit show")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<Entry>>();
+
+  assert_eq!(entries.len(), 1);
+
+  let entry = &entries[0];
+
+  assert_eq!(entry.txid.to_string(), txid.trim());
+  assert_eq!(
+    entry.inputs,
+    rpc_server.mempool()[0]
+      .input
+      .iter()
+      .map(|input| input.previous_output)
+      .collect::<Vec<OutPoint>>()
+  );
+  assert_eq!(
+    entry.outputs[0].address,
+    Some(
+      "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+        .parse::<Address<NetworkUnchecked>>()
+        .unwrap()
+    )
+  );
+  assert!(entry.command.iter().any(|arg| arg == "send"));
+}
+
+#[test]
+fn audit_log_is_empty_before_anything_is_sent() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  assert_eq!(
+    CommandBuilder::new("audit show")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Vec<Entry>>(),
+    Vec::new()
+  );
+}
@@ -0,0 +1,17 @@
+use {super::*, ord::subcommand::audit_content::Output};
+
+#[test]
+fn indexed_content_matches_reveal_transactions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  inscribe(&rpc_server);
+  inscribe(&rpc_server);
+
+  let output = CommandBuilder::new("audit-content")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.audited, 2);
+  assert!(output.discrepancies.is_empty());
+}
@@ -0,0 +1,38 @@
+use {
+  super::*,
+  ord::subcommand::block::{Event, Output},
+};
+
+#[test]
+fn block_lists_created_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let inscribe = inscribe(&rpc_server);
+
+  let output = CommandBuilder::new("block 2 --inscriptions")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.height, 2);
+  assert_eq!(output.created, 1);
+  assert_eq!(output.transferred, 0);
+
+  let inscriptions = output.inscriptions.unwrap();
+
+  assert_eq!(inscriptions.len(), 1);
+  assert_eq!(
+    inscriptions[0].inscription.to_string(),
+    inscribe.inscriptions[0]
+  );
+  assert_eq!(inscriptions[0].event, Event::Created);
+  assert_eq!(inscriptions[0].size, Some(3));
+  assert!(inscriptions[0].fee.unwrap() > 0);
+
+  let output = CommandBuilder::new(format!("block {}", output.hash))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.height, 2);
+  assert_eq!(output.inscriptions, None);
+}
@@ -70,3 +70,61 @@ fn preview() {
     format!(".*(<a href=/inscription/.*){{{}}}.*", examples.len())
   );
 }
+
+#[test]
+fn preview_without_bitcoind() {
+  let port = TcpListener::bind("127.0.0.1:0")
+    .unwrap()
+    .local_addr()
+    .unwrap()
+    .port();
+
+  let builder = CommandBuilder::new(format!(
+    "preview --no-bitcoind --http-port {port} foo.txt bar.html"
+  ))
+  .write("foo.txt", "FOO")
+  .write("bar.html", "<p>BAR</p>");
+
+  let _child = KillOnDrop(builder.command().spawn().unwrap());
+
+  let id = |index| format!("{}i{index}", "0".repeat(64));
+
+  for attempt in 0.. {
+    if let Ok(response) = reqwest::blocking::get(format!("http://127.0.0.1:{port}/")) {
+      if response.status() == 200 {
+        assert_regex_match!(
+          response.text().unwrap(),
+          format!(
+            ".*<a href=/inscription/{}>.*<a href=/inscription/{}>.*",
+            id(0),
+            id(1)
+          )
+        );
+        break;
+      }
+    }
+
+    if attempt == 100 {
+      panic!("Server did not respond");
+    }
+
+    thread::sleep(Duration::from_millis(100));
+  }
+
+  assert_regex_match!(
+    reqwest::blocking::get(format!("http://127.0.0.1:{port}/preview/{}", id(0)))
+      .unwrap()
+      .text()
+      .unwrap(),
+    ".*<pre>FOO</pre>.*"
+  );
+
+  let response =
+    reqwest::blocking::get(format!("http://127.0.0.1:{port}/content/{}", id(1))).unwrap();
+
+  assert_eq!(
+    response.headers()["content-type"],
+    "text/html;charset=utf-8"
+  );
+  assert_eq!(response.text().unwrap(), "<p>BAR</p>");
+}
@@ -0,0 +1,445 @@
+use {
+  super::*,
+  crate::command_builder::ToArgs,
+  proto::{progress::Update, wallet_client::WalletClient},
+  std::net::TcpStream,
+  tokio::runtime::Runtime,
+  tonic::{transport::Channel, Code, Status},
+};
+
+mod proto {
+  tonic::include_proto!("ord");
+}
+
+struct Daemon {
+  child: Child,
+  grpc_port: u16,
+  runtime: Runtime,
+  #[allow(unused)]
+  tempdir: TempDir,
+}
+
+impl Daemon {
+  fn spawn(rpc_server: &test_bitcoincore_rpc::Handle) -> Self {
+    Self::spawn_with_api_keys(rpc_server, None)
+  }
+
+  fn spawn_with_api_keys(
+    rpc_server: &test_bitcoincore_rpc::Handle,
+    api_keys: Option<&str>,
+  ) -> Self {
+    let tempdir = TempDir::new().unwrap();
+    fs::write(tempdir.path().join(".cookie"), "foo:bar").unwrap();
+
+    let mut args = Vec::new();
+
+    if let Some(api_keys) = api_keys {
+      let path = tempdir.path().join("api-keys.yaml");
+      fs::write(&path, api_keys).unwrap();
+      args.push(format!("--api-keys {}", path.display()));
+    }
+
+    let [http_port, grpc_port] = [(); 2].map(|()| {
+      TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+    });
+
+    let child = Command::new(executable_path("ord"))
+      .args(
+        format!(
+          "--rpc-url {} --bitcoin-data-dir {} --data-dir {} daemon --address 127.0.0.1:{http_port} --grpc 127.0.0.1:{grpc_port} {}",
+          rpc_server.url(),
+          tempdir.path().display(),
+          tempdir.path().display(),
+          args.join(" "),
+        )
+        .to_args(),
+      )
+      .env("ORD_INTEGRATION_TEST", "1")
+      .current_dir(&tempdir)
+      .spawn()
+      .unwrap();
+
+    for i in 0.. {
+      match TcpStream::connect(("127.0.0.1", grpc_port)) {
+        Ok(_) => break,
+        Err(err) => {
+          if i == 400 {
+            panic!("Daemon failed to start: {err}");
+          }
+        }
+      }
+
+      thread::sleep(Duration::from_millis(25));
+    }
+
+    Self {
+      child,
+      grpc_port,
+      runtime: Runtime::new().unwrap(),
+      tempdir,
+    }
+  }
+
+  fn client(&self) -> WalletClient<Channel> {
+    self
+      .runtime
+      .block_on(WalletClient::connect(format!(
+        "http://127.0.0.1:{}",
+        self.grpc_port
+      )))
+      .unwrap()
+  }
+
+  /// Send `request`, and collect the status updates and outputs streamed
+  /// back, and the error status the stream ended with, if any.
+  fn inscribe(
+    &self,
+    request: proto::InscribeRequest,
+  ) -> (Vec<String>, Vec<String>, Option<Status>) {
+    let mut client = self.client();
+
+    self
+      .runtime
+      .block_on(async {
+        let mut stream = client.inscribe(request).await?.into_inner();
+        Self::collect(&mut stream).await
+      })
+      .unwrap_or_else(|status| (Vec::new(), Vec::new(), Some(status)))
+  }
+
+  fn send(&self, request: proto::SendRequest) -> (Vec<String>, Vec<String>, Option<Status>) {
+    let mut client = self.client();
+
+    self
+      .runtime
+      .block_on(async {
+        let mut stream = client.send(request).await?.into_inner();
+        Self::collect(&mut stream).await
+      })
+      .unwrap_or_else(|status| (Vec::new(), Vec::new(), Some(status)))
+  }
+
+  async fn collect(
+    stream: &mut tonic::Streaming<proto::Progress>,
+  ) -> Result<(Vec<String>, Vec<String>, Option<Status>), Status> {
+    let mut statuses = Vec::new();
+    let mut outputs = Vec::new();
+
+    loop {
+      match stream.message().await {
+        Ok(Some(progress)) => match progress.update.unwrap() {
+          Update::Status(status) => statuses.push(status),
+          Update::Output(output) => outputs.push(output),
+        },
+        Ok(None) => return Ok((statuses, outputs, None)),
+        Err(status) => return Ok((statuses, outputs, Some(status))),
+      }
+    }
+  }
+}
+
+impl Drop for Daemon {
+  fn drop(&mut self) {
+    self.child.kill().unwrap()
+  }
+}
+
+fn file(name: &str, content: &str) -> proto::File {
+  proto::File {
+    name: name.into(),
+    content: content.into(),
+  }
+}
+
+fn with_api_key<T>(message: T, key: &str) -> tonic::Request<T> {
+  let mut request = tonic::Request::new(message);
+  request
+    .metadata_mut()
+    .insert("authorization", format!("Bearer {key}").parse().unwrap());
+  request
+}
+
+#[test]
+fn estimate_fee() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let estimate = daemon
+    .runtime
+    .block_on(
+      daemon
+        .client()
+        .estimate_fee(proto::EstimateFeeRequest { blocks: 6 }),
+    )
+    .unwrap()
+    .into_inner();
+
+  assert_eq!(estimate.fee_rate, None);
+
+  rpc_server.set_fee_estimate(2.5);
+
+  let estimate = daemon
+    .runtime
+    .block_on(
+      daemon
+        .client()
+        .estimate_fee(proto::EstimateFeeRequest { blocks: 6 }),
+    )
+    .unwrap()
+    .into_inner();
+
+  assert_eq!(estimate.blocks, 1);
+  assert_eq!(estimate.fee_rate, Some(2.5));
+}
+
+#[test]
+fn estimate_fee_rejects_too_many_blocks() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let status = daemon
+    .runtime
+    .block_on(daemon.client().estimate_fee(proto::EstimateFeeRequest {
+      blocks: u32::from(u16::MAX) + 1,
+    }))
+    .unwrap_err();
+
+  assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[test]
+fn inscribe_streams_progress_and_output() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let (statuses, outputs, error) = daemon.inscribe(proto::InscribeRequest {
+    files: vec![file("foo.txt", "FOO")],
+    fee_rate: 1.0,
+    ..Default::default()
+  });
+
+  assert!(error.is_none(), "{error:?}");
+  assert_eq!(outputs.len(), 1);
+
+  let output = serde_json::from_str::<Inscribe>(&outputs[0]).unwrap();
+
+  assert_eq!(
+    statuses,
+    [
+      format!("broadcast commit transaction {}", output.commit),
+      format!("broadcast reveal transaction {}", output.reveals[0]),
+    ]
+  );
+
+  assert_eq!(output.inscriptions, [format!("{}i0", output.reveals[0])]);
+  assert_eq!(rpc_server.mempool().len(), 2);
+}
+
+#[test]
+fn inscribe_dry_run_streams_output_without_broadcasting() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let (statuses, outputs, error) = daemon.inscribe(proto::InscribeRequest {
+    files: vec![file("foo.txt", "FOO")],
+    fee_rate: 1.0,
+    dry_run: true,
+    ..Default::default()
+  });
+
+  assert!(error.is_none(), "{error:?}");
+  assert!(statuses.is_empty());
+  assert_eq!(outputs.len(), 1);
+  serde_json::from_str::<Inscribe>(&outputs[0]).unwrap();
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn invalid_inscribe_request_is_rejected() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let (_, _, error) = daemon.inscribe(proto::InscribeRequest {
+    fee_rate: 1.0,
+    ..Default::default()
+  });
+
+  let error = error.unwrap();
+  assert_eq!(error.code(), Code::InvalidArgument);
+  assert_eq!(error.message(), "no files to inscribe");
+
+  let (_, _, error) = daemon.inscribe(proto::InscribeRequest {
+    files: vec![file("foo.txt", "FOO")],
+    fee_rate: -1.0,
+    ..Default::default()
+  });
+
+  assert_eq!(error.unwrap().code(), Code::InvalidArgument);
+}
+
+#[test]
+fn failed_inscribe_ends_stream_with_error() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let (statuses, outputs, error) = daemon.inscribe(proto::InscribeRequest {
+    files: vec![file("foo.txt", "FOO")],
+    fee_rate: 1.0,
+    ..Default::default()
+  });
+
+  assert!(statuses.is_empty());
+  assert!(outputs.is_empty());
+
+  let error = error.unwrap();
+  assert_eq!(error.code(), Code::Internal);
+  assert_eq!(error.message(), "wallet contains no cardinal utxos");
+}
+
+#[test]
+fn list_inscriptions_and_send() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let daemon = Daemon::spawn(&rpc_server);
+
+  let (_, outputs, error) = daemon.inscribe(proto::InscribeRequest {
+    files: vec![file("foo.txt", "FOO")],
+    fee_rate: 1.0,
+    ..Default::default()
+  });
+
+  assert!(error.is_none(), "{error:?}");
+
+  let Inscribe {
+    inscriptions,
+    reveals,
+    ..
+  } = serde_json::from_str(&outputs[0]).unwrap();
+
+  rpc_server.mine_blocks(1);
+
+  let listed = daemon
+    .runtime
+    .block_on(
+      daemon
+        .client()
+        .list_inscriptions(proto::ListInscriptionsRequest { address: None }),
+    )
+    .unwrap()
+    .into_inner()
+    .inscriptions;
+
+  assert_eq!(
+    listed,
+    [proto::Inscription {
+      id: inscriptions[0].clone(),
+      number: 0,
+      location: format!("{}:0:0", reveals[0]),
+      postage: 10_000,
+      sat: None,
+      explorer: format!("https://ordinals.com/inscription/{}", inscriptions[0]),
+    }]
+  );
+
+  let (_, outputs, error) = daemon.send(proto::SendRequest {
+    address: "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".into(),
+    outgoing: inscriptions[0].clone(),
+    fee_rate: 1.0,
+    ..Default::default()
+  });
+
+  assert!(error.is_none(), "{error:?}");
+  assert_eq!(
+    serde_json::from_str::<Txid>(&outputs[0]).unwrap(),
+    rpc_server.mempool()[0].txid()
+  );
+
+  rpc_server.mine_blocks(1);
+
+  let listed = daemon
+    .runtime
+    .block_on(
+      daemon
+        .client()
+        .list_inscriptions(proto::ListInscriptionsRequest { address: None }),
+    )
+    .unwrap()
+    .into_inner()
+    .inscriptions;
+
+  assert!(listed.is_empty());
+}
+
+#[test]
+fn requests_require_api_key_with_scope() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let daemon = Daemon::spawn_with_api_keys(
+    &rpc_server,
+    Some("- {name: reader, key: secret, scope: read}"),
+  );
+
+  let status = daemon
+    .runtime
+    .block_on(
+      daemon
+        .client()
+        .estimate_fee(proto::EstimateFeeRequest { blocks: 6 }),
+    )
+    .unwrap_err();
+
+  assert_eq!(status.code(), Code::Unauthenticated);
+
+  let status = daemon
+    .runtime
+    .block_on(daemon.client().estimate_fee(with_api_key(
+      proto::EstimateFeeRequest { blocks: 6 },
+      "wrong",
+    )))
+    .unwrap_err();
+
+  assert_eq!(status.code(), Code::Unauthenticated);
+
+  daemon
+    .runtime
+    .block_on(daemon.client().estimate_fee(with_api_key(
+      proto::EstimateFeeRequest { blocks: 6 },
+      "secret",
+    )))
+    .unwrap();
+
+  let status = daemon
+    .runtime
+    .block_on(daemon.client().inscribe(with_api_key(
+      proto::InscribeRequest {
+        files: vec![file("foo.txt", "FOO")],
+        fee_rate: 1.0,
+        ..Default::default()
+      },
+      "secret",
+    )))
+    .unwrap_err();
+
+  assert_eq!(status.code(), Code::PermissionDenied);
+  assert!(rpc_server.mempool().is_empty());
+}
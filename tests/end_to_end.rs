@@ -0,0 +1,206 @@
+use {
+  super::{node::Node, snapshot::assert_snapshot, *},
+  ord::subcommand::wallet::cardinals::Cardinal,
+  serde_json::Value,
+};
+
+#[test]
+#[ignore]
+fn inscribe_send_and_reindex() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  let inscribe = node
+    .ord("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .run_and_check_output::<Inscribe>();
+
+  node.mine(1);
+
+  let inscription = &inscribe.inscriptions[0];
+
+  assert_snapshot(
+    "inscribe_send_and_reindex_inscriptions",
+    node
+      .ord("wallet inscriptions")
+      .run_and_check_output::<Value>(),
+  );
+
+  let address = node
+    .client()
+    .get_new_address(None, None)
+    .unwrap()
+    .assume_checked();
+
+  node
+    .ord(format!("wallet send --fee-rate 1 {address} {inscription}"))
+    .run_and_check_output::<Value>();
+
+  node.mine(1);
+
+  let balance = node.ord("wallet balance").run_and_check_output::<Value>();
+
+  assert_snapshot("inscribe_send_and_reindex_balance", balance.clone());
+
+  assert_eq!(
+    node
+      .ord("wallet inscriptions")
+      .run_and_check_output::<Value>(),
+    Value::Array(Vec::new())
+  );
+
+  let outputs = node.ord("wallet outputs").run_and_check_output::<Value>();
+
+  node.delete_index();
+
+  assert_eq!(
+    node.ord("wallet balance").run_and_check_output::<Value>(),
+    balance
+  );
+
+  assert_eq!(
+    node.ord("wallet outputs").run_and_check_output::<Value>(),
+    outputs
+  );
+
+  assert_snapshot("inscribe_send_and_reindex_outputs", outputs);
+}
+
+#[test]
+#[ignore]
+fn inscribe_cursed() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  assert_snapshot(
+    "inscribe_cursed",
+    node
+      .ord("wallet inscribe --cursed --fee-rate 1 foo.txt")
+      .write("foo.txt", "FOO")
+      .run_and_check_output::<Value>(),
+  );
+
+  node.mine(1);
+
+  assert_snapshot(
+    "inscribe_cursed_inscriptions",
+    node
+      .ord("wallet inscriptions")
+      .run_and_check_output::<Value>(),
+  );
+}
+
+#[test]
+#[ignore]
+fn inscribe_csv() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  let destination = node
+    .client()
+    .get_new_address(None, None)
+    .unwrap()
+    .assume_checked();
+
+  assert_snapshot(
+    "inscribe_csv",
+    node
+      .ord("wallet inscribe --fee-rate 1 --csv batch.csv --yes")
+      .write("foo.txt", "FOO")
+      .write("bar.html", "<p>BAR</p>")
+      .write(
+        "batch.csv",
+        format!("{destination},foo.txt,5000 sat\n{destination},bar.html,,text/html\n"),
+      )
+      .run_and_check_output::<Value>(),
+  );
+
+  node.mine(1);
+}
+
+#[test]
+#[ignore]
+fn inscribe_dump() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  assert_snapshot(
+    "inscribe_dump",
+    node
+      .ord("wallet inscribe --dump --fee-rate 1 foo.txt")
+      .write("foo.txt", "FOO")
+      .run_and_check_output::<Value>(),
+  );
+}
+
+#[test]
+#[ignore]
+fn inscribe_dump_and_broadcast_elsewhere() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  let dump = node
+    .ord("wallet inscribe --no-broadcast --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .run_and_extract_stdout();
+
+  let output = node
+    .ord("broadcast dump.json")
+    .write("dump.json", dump)
+    .run_and_check_output::<ord::subcommand::broadcast::Output>();
+
+  assert_eq!(output.commits.len(), 1);
+  assert_eq!(output.commits[0].reveals.len(), 1);
+  assert!(output.commits[0].failed.is_empty());
+
+  node.mine(1);
+
+  assert_eq!(
+    node
+      .ord("wallet inscriptions")
+      .run_and_check_output::<Vec<Value>>()
+      .len(),
+    1
+  );
+}
+
+#[test]
+#[ignore]
+fn send_satpoint_with_alignment() {
+  let node = Node::spawn();
+  node.create_wallet();
+  node.fund();
+
+  let cardinal = &node
+    .ord("wallet cardinals")
+    .run_and_check_output::<Vec<Cardinal>>()[0];
+
+  let destination = node
+    .client()
+    .get_new_address(None, None)
+    .unwrap()
+    .assume_checked();
+
+  let alignment = node
+    .client()
+    .get_new_address(None, None)
+    .unwrap()
+    .assume_checked();
+
+  assert_snapshot(
+    "send_satpoint_with_alignment",
+    node
+      .ord(format!(
+        "wallet send --fee-rate 1 --alignment {alignment} {destination} {}:5000",
+        cardinal.output
+      ))
+      .run_and_check_output::<Value>(),
+  );
+
+  node.mine(1);
+}
@@ -1,4 +1,7 @@
-use {super::*, ord::subcommand::find::Output};
+use {
+  super::*,
+  ord::subcommand::find::{HistoricalOutput, Output, OutputGroup, Range},
+};
 
 #[test]
 fn find_command_returns_satpoint_for_sat() {
@@ -43,3 +46,108 @@ fn no_satoshi_index() {
     .expected_exit_code(1)
     .run_and_extract_stdout();
 }
+
+#[test]
+fn no_sync_uses_index_without_bitcoin_core() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  let tempdir = TempDir::new().unwrap();
+  let index = tempdir.path().join("index.redb");
+
+  CommandBuilder::new(format!("--index-sats --index {} find 0", index.display()))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!(
+    "--no-sync --index-sats --index {} find 5000000000",
+    index.display()
+  ))
+  .expected_stderr("error: sat has not been mined as of index height\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn group_by_output_merges_contiguous_results() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  let groups = CommandBuilder::new("--index-sats find --file sats.txt --group-by output")
+    .write("sats.txt", "0-10\n30-40\n10-20\n")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<OutputGroup>>();
+
+  assert_eq!(
+    groups,
+    vec![OutputGroup {
+      outpoint: "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:0"
+        .parse()
+        .unwrap(),
+      size: 30,
+      ranges: vec![
+        Range {
+          start: 0,
+          size: 20,
+          offset: 0,
+          name: None,
+        },
+        Range {
+          start: 30,
+          size: 10,
+          offset: 30,
+          name: None,
+        },
+      ],
+      address: None,
+      blockhash: None,
+      date: None,
+      height: None,
+      timestamp: None,
+      value: None,
+    }]
+  );
+}
+
+#[test]
+fn summary_lists_outputs_containing_sats() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  let outpoints = CommandBuilder::new("--index-sats find --file sats.txt --summary")
+    .write("sats.txt", "0-10\n30-40\n")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<OutPoint>>();
+
+  assert_eq!(
+    outpoints,
+    vec![
+      "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:0"
+        .parse()
+        .unwrap()
+    ]
+  );
+}
+
+#[test]
+fn at_height_finds_where_sat_was() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("--index-sats find 0 --at-height 1")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<HistoricalOutput>();
+
+  assert_eq!(
+    output.satpoint,
+    Some(
+      "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b:0:0"
+        .parse()
+        .unwrap()
+    )
+  );
+
+  let output = CommandBuilder::new("--index-sats find 5000000000 --at-height 0")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<HistoricalOutput>();
+
+  assert_eq!(output.satpoint, None);
+}
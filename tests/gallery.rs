@@ -0,0 +1,59 @@
+use {super::*, ord::subcommand::gallery::Output};
+
+#[test]
+fn gallery_requires_source() {
+  CommandBuilder::new("gallery --output-dir gallery")
+    .stderr_regex(".*--address <ADDRESS>.*--wallet.*")
+    .expected_exit_code(2)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn wallet_gallery() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let output = CommandBuilder::new("gallery --output-dir gallery --wallet")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.inscriptions, 1);
+  assert!(output.index.ends_with("gallery/index.html"));
+
+  assert_regex_match!(
+    CommandBuilder::new("gallery --output-dir gallery --wallet")
+      .rpc_server(&rpc_server)
+      .stdout_regex(".*")
+      .run_and_extract_file("gallery/index.html"),
+    format!(
+      ".*<iframe sandbox=allow-scripts scrolling=no loading=lazy src=content/{inscription}.txt></iframe>.*<dd>{inscription}</dd>.*"
+    )
+  );
+
+  assert_eq!(
+    CommandBuilder::new("gallery --output-dir gallery --wallet")
+      .rpc_server(&rpc_server)
+      .stdout_regex(".*")
+      .run_and_extract_file(format!("gallery/content/{inscription}.txt")),
+    "FOO"
+  );
+}
+
+#[test]
+fn address_gallery_only_shows_inscriptions_owned_by_address() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  inscribe(&rpc_server);
+
+  let output = CommandBuilder::new(
+    "gallery --output-dir gallery --address bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+  )
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.inscriptions, 0);
+}
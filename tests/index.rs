@@ -1,4 +1,4 @@
-use {super::*, crate::command_builder::ToArgs};
+use {super::*, crate::command_builder::ToArgs, ord::subcommand::index::LoadUtxoSnapshotOutput};
 
 #[test]
 fn custom_index_path() {
@@ -79,7 +79,7 @@ fn export_inscription_number_to_id_tsv() {
 
   let tsv = CommandBuilder::new("index export --tsv foo.tsv")
     .rpc_server(&rpc_server)
-    .temp_dir(temp_dir)
+    .temp_dir(Arc::new(temp_dir))
     .run_and_extract_file("foo.tsv");
 
   let entries: std::collections::BTreeMap<i64, ord::Object> = tsv
@@ -99,3 +99,69 @@ fn export_inscription_number_to_id_tsv() {
     &ord::Object::from_str(&inscriptions[0]).unwrap()
   )
 }
+
+#[test]
+fn utxo_snapshot_can_be_loaded() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  let block = rpc_server.mine_blocks(1).remove(0);
+  let coinbase = &block.txdata[0];
+
+  let mut snapshot = bitcoin::consensus::serialize(&block.block_hash());
+  snapshot.extend(1u64.to_le_bytes());
+  snapshot.extend(bitcoin::consensus::serialize(&coinbase.txid()));
+  snapshot.extend(0u32.to_le_bytes());
+  // height 1 coinbase, 50 BTC, p2pkh
+  snapshot.extend([0x03, 0x32, 0x00]);
+  snapshot.extend([0; 20]);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let output = CommandBuilder::new("index load-utxo-snapshot snapshot.dat")
+    .temp_dir(tempdir.clone())
+    .write("snapshot.dat", snapshot)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<LoadUtxoSnapshotOutput>();
+
+  pretty_assert_eq!(
+    output,
+    LoadUtxoSnapshotOutput {
+      base_hash: block.block_hash(),
+      base_height: 1,
+      coins: 1,
+    }
+  );
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("index run")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn utxo_snapshot_older_than_index_is_rejected() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  let block = rpc_server.mine_blocks(1).remove(0);
+  rpc_server.mine_blocks(2);
+
+  let mut snapshot = bitcoin::consensus::serialize(&block.block_hash());
+  snapshot.extend(0u64.to_le_bytes());
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  CommandBuilder::new("index run")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_extract_stdout();
+
+  CommandBuilder::new("index load-utxo-snapshot snapshot.dat")
+    .temp_dir(tempdir)
+    .write("snapshot.dat", snapshot)
+    .rpc_server(&rpc_server)
+    .expected_stderr(
+      "error: UTXO snapshot is of height 1, but the index has already indexed blocks up to 3\n",
+    )
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
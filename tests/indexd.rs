@@ -0,0 +1,189 @@
+use {
+  super::*,
+  crate::command_builder::ToArgs,
+  ord::subcommand::wallet::balance::Output,
+  std::os::unix::net::{UnixListener, UnixStream},
+};
+
+struct Indexd {
+  child: Child,
+}
+
+impl Indexd {
+  fn spawn(rpc_server: &test_bitcoincore_rpc::Handle, tempdir: &TempDir) -> Self {
+    let cookiefile = tempdir.path().join("cookie");
+    fs::write(&cookiefile, "username:password").unwrap();
+
+    let child = Command::new(executable_path("ord"))
+      .args(
+        format!(
+          "--rpc-url {} --cookie-file {} --data-dir {} indexd",
+          rpc_server.url(),
+          cookiefile.display(),
+          tempdir.path().display(),
+        )
+        .to_args(),
+      )
+      .env("ORD_INTEGRATION_TEST", "1")
+      .current_dir(tempdir.path())
+      .spawn()
+      .unwrap();
+
+    for i in 0.. {
+      match UnixStream::connect(tempdir.path().join("indexd.sock")) {
+        Ok(_) => break,
+        Err(err) => {
+          if i == 400 {
+            panic!("indexd failed to start: {err}");
+          }
+        }
+      }
+
+      thread::sleep(Duration::from_millis(25));
+    }
+
+    Self { child }
+  }
+}
+
+impl Drop for Indexd {
+  fn drop(&mut self) {
+    self.child.kill().unwrap();
+    self.child.wait().unwrap();
+  }
+}
+
+/// Leave a socket file without a listener in `tempdir`, like an indexd that
+/// didn't shut down cleanly.
+fn stale_socket(tempdir: &TempDir) {
+  let path = tempdir.path().join("indexd.sock");
+  drop(UnixListener::bind(&path).unwrap());
+  assert!(path.exists());
+  assert!(UnixStream::connect(&path).is_err());
+}
+
+fn balance(rpc_server: &test_bitcoincore_rpc::Handle, tempdir: &Arc<TempDir>) -> u64 {
+  CommandBuilder::new("wallet balance")
+    .rpc_server(rpc_server)
+    .temp_dir(tempdir.clone())
+    .run_and_check_output::<Output>()
+    .cardinal
+}
+
+#[test]
+fn commands_are_proxied_to_running_indexd() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let _indexd = Indexd::spawn(&rpc_server, &tempdir);
+
+  // indexd holds the lock on the index, so opening it directly would fail
+  assert_eq!(balance(&rpc_server, &tempdir), 50 * COIN_VALUE);
+
+  rpc_server.mine_blocks(1);
+
+  assert_eq!(balance(&rpc_server, &tempdir), 100 * COIN_VALUE);
+}
+
+#[test]
+fn proxied_command_errors_are_returned() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let _indexd = Indexd::spawn(&rpc_server, &tempdir);
+
+  CommandBuilder::new("find 0")
+    .rpc_server(&rpc_server)
+    .temp_dir(tempdir.clone())
+    .expected_stderr("error: find requires index created with `--index-sats` flag\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn commands_that_use_files_are_not_proxied() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let _indexd = Indexd::spawn(&rpc_server, &tempdir);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .temp_dir(tempdir.clone())
+    .expected_stderr(
+      "error: indexd is running, and can't run commands that use files or ask for confirmation\n",
+    )
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+
+  assert_eq!(rpc_server.mempool().len(), 0);
+}
+
+#[test]
+fn commands_open_index_without_indexd() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  assert_eq!(balance(&rpc_server, &tempdir), 50 * COIN_VALUE);
+  assert!(tempdir.path().join("index.redb").exists());
+  assert!(!tempdir.path().join("indexd.sock").exists());
+}
+
+#[test]
+fn commands_open_index_if_indexd_socket_is_stale() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  stale_socket(&tempdir);
+
+  assert_eq!(balance(&rpc_server, &tempdir), 50 * COIN_VALUE);
+}
+
+#[test]
+fn indexd_replaces_stale_socket() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  stale_socket(&tempdir);
+
+  let _indexd = Indexd::spawn(&rpc_server, &tempdir);
+
+  assert_eq!(balance(&rpc_server, &tempdir), 50 * COIN_VALUE);
+}
+
+#[test]
+fn indexd_refuses_to_start_twice() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let _indexd = Indexd::spawn(&rpc_server, &tempdir);
+
+  CommandBuilder::new("indexd")
+    .rpc_server(&rpc_server)
+    .temp_dir(tempdir.clone())
+    .expected_stderr(format!(
+      "error: indexd is already listening on {}\n",
+      tempdir.path().join("indexd.sock").display()
+    ))
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
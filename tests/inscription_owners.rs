@@ -0,0 +1,68 @@
+use {super::*, ord::subcommand::snapshot::Output};
+
+#[test]
+fn snapshot_resolves_current_and_past_owners() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let before = CommandBuilder::new("snapshot --inscription-ids ids.txt --output before.csv")
+    .temp_dir(tempdir.clone())
+    .write("ids.txt", format!("{inscription}\n"))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(before.inscriptions, 1);
+  assert_eq!(before.owners, 1);
+
+  let owner = fs::read_to_string(tempdir.path().join("before.csv")).unwrap();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {inscription}"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(".*")
+  .run_and_extract_stdout();
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("snapshot --inscription-ids ids.txt --output after.csv")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(
+    fs::read_to_string(tempdir.path().join("after.csv")).unwrap(),
+    format!("inscription,address\n{inscription},bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4\n")
+  );
+
+  let output = CommandBuilder::new(format!(
+    "snapshot --inscription-ids ids.txt --at-height {} --output past.csv",
+    before.height
+  ))
+  .temp_dir(tempdir.clone())
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.height, before.height);
+  assert_eq!(
+    fs::read_to_string(tempdir.path().join("past.csv")).unwrap(),
+    owner
+  );
+}
+
+#[test]
+fn snapshot_at_height_above_tip_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  CommandBuilder::new("snapshot --inscription-ids ids.txt --at-height 100 --output owners.csv")
+    .write("ids.txt", "")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: --at-height 100 is above the index height 0\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
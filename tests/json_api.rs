@@ -152,6 +152,7 @@ fn get_inscription() {
       number: 0,
       genesis_height: 2,
       genesis_fee: 138,
+      genesis_vsize: Some(138),
       output_value: Some(10000),
       address: None,
       sat: Some(ord::Sat(50 * COIN_VALUE)),
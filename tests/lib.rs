@@ -19,6 +19,7 @@ use {
     path::Path,
     process::{Child, Command, Stdio},
     str::{self, FromStr},
+    sync::Arc,
     thread,
     time::Duration,
   },
@@ -93,19 +94,37 @@ fn create_wallet(rpc_server: &test_bitcoincore_rpc::Handle) {
 
 mod command_builder;
 mod expected;
+mod node;
+mod snapshot;
 mod test_server;
 
+mod address;
+mod audit;
+mod audit_content;
+mod block;
 mod core;
+mod daemon;
+mod end_to_end;
 mod epochs;
 mod find;
+mod gallery;
 mod index;
+#[cfg(unix)]
+mod indexd;
 mod info;
+mod inscription_owners;
 mod json_api;
 mod list;
+mod mempool;
+mod optimize;
 mod parse;
+mod scan_mempool;
 mod server;
+mod stats;
 mod subsidy;
 mod supply;
 mod traits;
+mod verify_numbers;
 mod version;
 mod wallet;
+mod watch;
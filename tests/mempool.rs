@@ -0,0 +1,54 @@
+use {
+  super::*,
+  ord::subcommand::mempool::{Bucket, Output},
+};
+
+#[test]
+fn mempool_summarizes_inscriptions_and_pending_reveals() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .temp_dir(tempdir.clone())
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let output = CommandBuilder::new("mempool")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.transactions, 2);
+  assert_eq!(output.inscriptions, 1);
+  assert_eq!(output.pending_reveals, 1);
+  assert_eq!(
+    output.histogram,
+    [Bucket {
+      min_fee_rate: 0.0,
+      transactions: 2,
+      vsize: output.vsize,
+    }]
+  );
+}
+
+#[test]
+fn empty_mempool() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  assert_eq!(
+    CommandBuilder::new("mempool")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Output>(),
+    Output {
+      transactions: 0,
+      vsize: 0,
+      inscriptions: 0,
+      pending_reveals: 0,
+      histogram: Vec::new(),
+    }
+  );
+}
@@ -0,0 +1,88 @@
+use {
+  super::{command_builder::ToArgs, *},
+  bitcoind::{
+    bitcoincore_rpc::{Client, RpcApi},
+    BitcoinD, Conf,
+  },
+  ord::subcommand::wallet::receive,
+};
+
+/// A regtest `bitcoind`, found at `BITCOIND_EXE` or on `PATH`, and an ord
+/// data dir shared by every command run against it. Tests that use it are
+/// ignored, run them with `cargo test end_to_end -- --ignored`.
+pub(crate) struct Node {
+  bitcoind: BitcoinD,
+  tempdir: Arc<TempDir>,
+}
+
+impl Node {
+  pub(crate) fn spawn() -> Self {
+    let mut conf = Conf::default();
+    conf.args.push("-txindex");
+
+    let bitcoind = BitcoinD::with_conf(
+      bitcoind::exe_path().expect("bitcoind not found, set BITCOIND_EXE or add it to PATH"),
+      &conf,
+    )
+    .unwrap();
+
+    Self {
+      bitcoind,
+      tempdir: Arc::new(TempDir::new().unwrap()),
+    }
+  }
+
+  /// An ord command against this node. Warnings are allowed on stderr,
+  /// since Bitcoin Core's output isn't under test.
+  pub(crate) fn ord(&self, args: impl ToArgs) -> CommandBuilder {
+    let mut full = vec![
+      "--regtest".to_string(),
+      "--rpc-url".to_string(),
+      self.bitcoind.rpc_url(),
+      "--cookie-file".to_string(),
+      self.bitcoind.params.cookie_file.display().to_string(),
+    ];
+
+    full.extend(args.to_args());
+
+    CommandBuilder::new(full)
+      .temp_dir(self.tempdir.clone())
+      .stderr_regex(".*")
+  }
+
+  /// Bitcoin Core's own wallet, which isn't ord's.
+  pub(crate) fn client(&self) -> &Client {
+    &self.bitcoind.client
+  }
+
+  pub(crate) fn create_wallet(&self) {
+    self.ord("wallet create").run_and_check_output::<Create>();
+  }
+
+  /// Mine 101 blocks to ord's wallet, so that it has a mature coinbase.
+  pub(crate) fn fund(&self) {
+    let address = self
+      .ord("wallet receive")
+      .run_and_check_output::<receive::Output>()
+      .address
+      .require_network(Network::Regtest)
+      .unwrap();
+
+    self.client().generate_to_address(101, &address).unwrap();
+  }
+
+  pub(crate) fn mine(&self, n: u64) {
+    let address = self
+      .client()
+      .get_new_address(None, None)
+      .unwrap()
+      .assume_checked();
+
+    self.client().generate_to_address(n, &address).unwrap();
+  }
+
+  /// Delete the index, so that the next command rebuilds it from scratch.
+  pub(crate) fn delete_index(&self) {
+    fs::remove_file(self.tempdir.path().join("regtest").join("index.redb")).unwrap();
+  }
+}
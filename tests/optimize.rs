@@ -0,0 +1,37 @@
+use {super::*, ord::subcommand::optimize::Output};
+
+#[test]
+fn optimize_strips_default_content_type() {
+  let output = CommandBuilder::new("optimize foo.bin --content-type application/octet-stream")
+    .write("foo.bin", "foo")
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.content_type, None);
+  assert_eq!(output.content_encoding, None);
+  assert_eq!(output.savings, 27);
+  assert_eq!(output.weight - output.optimized_weight, output.savings);
+}
+
+#[test]
+fn optimize_compresses_content() {
+  let output = CommandBuilder::new("optimize foo.txt --compress")
+    .write("foo.txt", "foo".repeat(1000))
+    .run_and_check_output::<Output>();
+
+  assert_eq!(
+    output.content_type,
+    Some("text/plain;charset=utf-8".to_string())
+  );
+  assert_eq!(output.content_encoding, Some("br".to_string()));
+  assert!(output.savings > 2000);
+}
+
+#[test]
+fn optimize_does_not_compress_when_larger() {
+  let output = CommandBuilder::new("optimize foo.txt --compress")
+    .write("foo.txt", "foo")
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.content_encoding, None);
+  assert_eq!(output.savings, 0);
+}
@@ -0,0 +1,33 @@
+use {super::*, ord::subcommand::scan_mempool::Envelope};
+
+#[test]
+fn scan_mempool_shows_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let envelope = serde_json::from_str::<Envelope>(
+    &CommandBuilder::new("scan-mempool --once --content-type text/*")
+      .rpc_server(&rpc_server)
+      .stdout_regex(".*\n")
+      .run_and_extract_stdout(),
+  )
+  .unwrap();
+
+  assert_eq!(envelope.inscription.to_string(), inscriptions[0]);
+  assert_eq!(
+    envelope.content_type.as_deref(),
+    Some("text/plain;charset=utf-8")
+  );
+  assert_eq!(envelope.content_length, Some(3));
+  assert_eq!(envelope.preview.as_deref(), Some("FOO"));
+
+  CommandBuilder::new("scan-mempool --once --min-size 4")
+    .rpc_server(&rpc_server)
+    .run_and_extract_stdout();
+}
@@ -0,0 +1,66 @@
+use {super::*, serde_json::Value};
+
+/// Compare `value` to the golden file `tests/snapshots/{name}.json`, after
+/// replacing txids, addresses, raw transactions, descriptors, and
+/// timestamps, which differ from run to run, with placeholders. Missing golden
+/// files are written, as are all of them when `UPDATE_SNAPSHOTS` is set, so
+/// that changes in output show up in review.
+pub(crate) fn assert_snapshot(name: &str, value: Value) {
+  let actual = serde_json::to_string_pretty(&redact(value)).unwrap() + "\n";
+
+  let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests/snapshots")
+    .join(format!("{name}.json"));
+
+  if std::env::var_os("UPDATE_SNAPSHOTS").is_some() || !path.exists() {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, &actual).unwrap();
+    return;
+  }
+
+  pretty_assert_eq!(actual, fs::read_to_string(&path).unwrap());
+}
+
+fn redact(value: Value) -> Value {
+  match value {
+    Value::Array(array) => Value::Array(array.into_iter().map(redact).collect()),
+    Value::Object(object) => Value::Object(
+      object
+        .into_iter()
+        .map(|(key, value)| {
+          let value = if key == "timestamp" {
+            Value::String("<timestamp>".into())
+          } else {
+            redact(value)
+          };
+
+          (key, value)
+        })
+        .collect(),
+    ),
+    Value::String(string) => Value::String(redact_string(&string)),
+    value => value,
+  }
+}
+
+fn redact_string(string: &str) -> String {
+  if Regex::new("^([0-9a-f]{2}){100,}$")
+    .unwrap()
+    .is_match(string)
+  {
+    return "<hex>".into();
+  }
+
+  let string = Regex::new(r"rawtr\([^)]*\)#[0-9a-z]{8}")
+    .unwrap()
+    .replace_all(string, "<descriptor>");
+
+  let string = Regex::new("bcrt1[02-9ac-hj-np-z]{8,87}")
+    .unwrap()
+    .replace_all(&string, "<address>");
+
+  Regex::new("[0-9a-f]{64}")
+    .unwrap()
+    .replace_all(&string, "<txid>")
+    .into()
+}
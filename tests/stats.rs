@@ -0,0 +1,59 @@
+use {
+  super::*,
+  ord::subcommand::stats::{Output, Row},
+};
+
+#[test]
+fn stats_by_block() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  inscribe(&rpc_server);
+  inscribe(&rpc_server);
+
+  let output = CommandBuilder::new("stats --by block")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.rows.len(), 2);
+  assert_eq!(output.rows[0].period, "2");
+  assert_eq!(output.rows[1].period, "4");
+
+  for row in &output.rows {
+    assert_eq!(row.inscriptions, 1);
+    assert_eq!(row.bytes, 3);
+    assert!(row.fees > 0);
+  }
+
+  CommandBuilder::new("stats --by block --csv")
+    .rpc_server(&rpc_server)
+    .stdout_regex(r"period,inscriptions,bytes,fees\n2,1,3,\d+\n4,1,3,\d+\n")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn stats_content_type_filter() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  inscribe(&rpc_server);
+
+  assert_eq!(
+    CommandBuilder::new("stats --content-type text/*")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Output>()
+      .rows
+      .iter()
+      .map(|row| row.inscriptions)
+      .sum::<u64>(),
+    1
+  );
+
+  assert_eq!(
+    CommandBuilder::new("stats --content-type image/*")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Output>()
+      .rows,
+    Vec::<Row>::new()
+  );
+}
@@ -0,0 +1,33 @@
+use {super::*, ord::subcommand::verify_numbers::Output};
+
+#[test]
+fn numbers_match_another_ord_server() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  inscribe(&rpc_server);
+  inscribe(&rpc_server);
+
+  let server = TestServer::spawn_with_args(&rpc_server, &["--enable-json-api"]);
+
+  let output = CommandBuilder::new(format!(
+    "verify-numbers --source {} --sample 10",
+    server.url()
+  ))
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.inscriptions, 2);
+  assert_eq!(output.first_divergence, None);
+}
+
+#[test]
+fn sample_must_not_be_zero() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+
+  CommandBuilder::new("verify-numbers --source http://127.0.0.1:1 --sample 0")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: --sample must be at least 1\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
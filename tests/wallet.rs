@@ -1,13 +1,26 @@
 use super::*;
 
+mod airdrop;
+mod await_inscription;
 mod balance;
+mod bump_fee;
 mod cardinals;
 mod create;
+mod export;
+mod failed_reveals;
+mod freeze;
 mod inscribe;
 mod inscriptions;
+mod label;
+mod migrate;
+mod mine;
 mod outputs;
+mod pending;
 mod receive;
 mod restore;
 mod sats;
+mod scheduled;
 mod send;
+mod shard;
 mod transactions;
+mod wrap;
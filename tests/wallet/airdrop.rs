@@ -0,0 +1,95 @@
+use {super::*, ord::subcommand::wallet::airdrop::Output};
+
+const RECIPIENT_A: &str = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4";
+const RECIPIENT_B: &str = "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297";
+
+#[test]
+fn airdrop_dry_run_batches_inscriptions_into_one_transaction() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let first = &inscriptions[0];
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let second = &inscriptions[0];
+
+  let output = CommandBuilder::new("wallet airdrop --fee-rate 1 --csv airdrop.csv --dry-run")
+    .write(
+      "airdrop.csv",
+      format!("inscription_id,address\n{first},{RECIPIENT_A}\n{second},{RECIPIENT_B}\n"),
+    )
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.transactions.len(), 1);
+
+  let transaction = &output.transactions[0];
+
+  assert_eq!(transaction.outputs.len(), 3);
+  assert_eq!(transaction.outputs[0].inscriptions.len(), 1);
+  assert_eq!(transaction.outputs[1].inscriptions.len(), 1);
+  assert!(transaction.outputs[2].inscriptions.is_empty());
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn airdrop_sends_and_skips_sent_inscriptions_when_run_again() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let first = &inscriptions[0];
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let second = &inscriptions[0];
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let output = CommandBuilder::new(
+    "wallet airdrop --fee-rate 1 --csv airdrop.csv --max-per-tx 1 --state airdrop.json",
+  )
+  .temp_dir(tempdir.clone())
+  .write(
+    "airdrop.csv",
+    format!("{first},{RECIPIENT_A}\n{second},{RECIPIENT_B}\n"),
+  )
+  .rpc_server(&rpc_server)
+  .stderr_regex(
+    r"\[sent airdrop transaction 1 of 2: .*\]\n\[sent airdrop transaction 2 of 2: .*\]\n",
+  )
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.transactions.len(), 2);
+  assert!(output.skipped.is_empty());
+  assert_eq!(rpc_server.mempool().len(), 2);
+
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new(
+    "wallet airdrop --fee-rate 1 --csv airdrop.csv --max-per-tx 1 --state airdrop.json",
+  )
+  .temp_dir(tempdir)
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert!(output.transactions.is_empty());
+  assert_eq!(output.skipped.len(), 2);
+}
+
+#[test]
+fn airdrop_of_inscription_not_in_wallet_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new("wallet airdrop --fee-rate 1 --csv airdrop.csv")
+    .write("airdrop.csv", format!("{txid}i0,{RECIPIENT_A}\n"))
+    .rpc_server(&rpc_server)
+    .expected_stderr(format!(
+      "error: inscription {txid}i0 is not in the wallet\n"
+    ))
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
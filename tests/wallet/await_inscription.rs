@@ -0,0 +1,81 @@
+use {super::*, ord::subcommand::wallet::await_inscription::Output};
+
+#[test]
+fn await_address_sees_inscription_in_mempool() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let destination = CommandBuilder::new("wallet receive")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<ord::subcommand::wallet::receive::Output>()
+    .address
+    .assume_checked();
+
+  let Inscribe {
+    inscriptions,
+    reveals,
+    ..
+  } = CommandBuilder::new(format!(
+    "wallet inscribe --destination {destination} --fee-rate 1 foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let output = CommandBuilder::new(format!("wallet await {destination} --timeout 10"))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.inscription.to_string(), inscriptions[0]);
+  assert_eq!(output.txid, reveals[0]);
+  assert_eq!(
+    output.satpoint.unwrap().outpoint,
+    OutPoint::new(reveals[0], 0)
+  );
+  assert!(!output.confirmed);
+}
+
+#[test]
+fn await_inscription_sees_it_move() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let send = CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {inscription}"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(".*")
+  .run_and_extract_stdout()
+  .trim()
+  .parse::<Txid>()
+  .unwrap();
+
+  let output = CommandBuilder::new(format!("wallet await {inscription} --timeout 10"))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.txid, send);
+  assert_eq!(output.satpoint.unwrap().outpoint, OutPoint::new(send, 0));
+  assert!(!output.confirmed);
+}
+
+#[test]
+fn await_times_out() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  CommandBuilder::new(format!("wallet await {inscription} --timeout 0"))
+    .rpc_server(&rpc_server)
+    .expected_stderr(format!(
+      "error: timed out after 0 seconds waiting for {inscription}\n"
+    ))
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
@@ -0,0 +1,163 @@
+use {super::*, ord::subcommand::wallet::bump_fee::Output};
+
+const P2A_SCRIPT_PUBKEY: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
+fn inscribe_with_anchor(rpc_server: &test_bitcoincore_rpc::Handle) -> Txid {
+  let Inscribe { reveals, .. } =
+    CommandBuilder::new("wallet inscribe --fee-rate 1 --anchor foo.txt")
+      .write("foo.txt", "FOO")
+      .rpc_server(rpc_server)
+      .run_and_check_output();
+
+  reveals[0]
+}
+
+#[test]
+fn inscribe_with_anchor_adds_anchor_output() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  inscribe_with_anchor(&rpc_server);
+
+  let anchor = rpc_server.mempool()[1].output.last().unwrap().clone();
+
+  assert_eq!(anchor.script_pubkey.as_bytes(), P2A_SCRIPT_PUBKEY);
+  assert_eq!(anchor.value, 240);
+}
+
+#[test]
+fn inscribe_with_anchor_value() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new([
+    "wallet",
+    "inscribe",
+    "--fee-rate",
+    "1",
+    "--anchor",
+    "--anchor-value",
+    "1000 sat",
+    "foo.txt",
+  ])
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let anchor = rpc_server.mempool()[1].output.last().unwrap().clone();
+
+  assert_eq!(anchor.script_pubkey.as_bytes(), P2A_SCRIPT_PUBKEY);
+  assert_eq!(anchor.value, 1000);
+}
+
+#[test]
+fn anchor_value_requires_anchor() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new([
+    "wallet",
+    "inscribe",
+    "--fee-rate",
+    "1",
+    "--anchor-value",
+    "1000 sat",
+    "foo.txt",
+  ])
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: --anchor-value requires --anchor\n")
+  .run_and_extract_stdout();
+}
+
+/// The fee paid by the child, and the fee rate of the parent and child
+/// combined.
+fn bump_fee(rpc_server: &test_bitcoincore_rpc::Handle, reveal: Txid) -> (Output, f64) {
+  let output = CommandBuilder::new(format!("wallet bump-fee --fee-rate 10 {reveal}"))
+    .rpc_server(rpc_server)
+    .run_and_check_output::<Output>();
+
+  let mempool = rpc_server.mempool();
+
+  let parent = mempool.iter().find(|tx| tx.txid() == reveal).unwrap();
+  let child = mempool.last().unwrap();
+
+  assert_eq!(output.parent, reveal);
+  assert_eq!(output.child, child.txid());
+
+  let anchor_vout = parent.output.len() - 1;
+
+  assert_eq!(child.input.len(), 2);
+  assert_eq!(
+    child.input[0].previous_output,
+    OutPoint::new(reveal, anchor_vout.try_into().unwrap())
+  );
+  assert!(child.input[0].witness.is_empty());
+
+  let cardinal = rpc_server
+    .get_utxo_amount(&child.input[1].previous_output)
+    .unwrap()
+    .to_sat();
+
+  assert_eq!(
+    child.output[0].value,
+    cardinal + parent.output[anchor_vout].value - output.fee
+  );
+
+  let fee_rate = output.fee as f64 / (parent.vsize() + child.vsize()) as f64;
+
+  (output, fee_rate)
+}
+
+#[test]
+fn bump_fee_spends_anchor() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(2);
+
+  let reveal = inscribe_with_anchor(&rpc_server);
+
+  let (_output, fee_rate) = bump_fee(&rpc_server, reveal);
+
+  assert!((10.0..10.1).contains(&fee_rate), "{fee_rate}");
+}
+
+#[test]
+fn bump_fee_accounts_for_signed_input_weight() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.use_p2wpkh_wallet();
+  rpc_server.mine_blocks(2);
+
+  let reveal = inscribe_with_anchor(&rpc_server);
+
+  // the signed p2wpkh input is heavier than a taproot key path spend
+  let (_output, fee_rate) = bump_fee(&rpc_server, reveal);
+
+  assert!((10.0..10.1).contains(&fee_rate), "{fee_rate}");
+}
+
+#[test]
+fn bump_fee_requires_anchor() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(2);
+
+  let Inscribe { reveals, .. } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output();
+
+  CommandBuilder::new(format!("wallet bump-fee --fee-rate 10 {}", reveals[0]))
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(format!(
+      "error: transaction {} has no anchor output\n",
+      reveals[0]
+    ))
+    .run_and_extract_stdout();
+}
@@ -20,3 +20,50 @@ fn cardinals() {
 
   assert_eq!(all_outputs.len() - cardinal_outputs.len(), 1);
 }
+
+#[test]
+fn cardinals_exclude_frozen_outputs() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let coinbase_tx = &rpc_server.mine_blocks(1)[0].txdata[0];
+  let outpoint = OutPoint::new(coinbase_tx.txid(), 0);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let cardinals = CommandBuilder::new("wallet cardinals")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<Cardinal>>();
+
+  assert_eq!(cardinals.len(), 1);
+  assert_eq!(cardinals[0].output, outpoint);
+  assert_eq!(cardinals[0].confirmations, 1);
+
+  CommandBuilder::new(format!("wallet freeze {outpoint}"))
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_extract_stdout();
+
+  assert!(CommandBuilder::new("wallet cardinals")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<Cardinal>>()
+    .is_empty());
+}
+
+#[test]
+fn cardinals_min_confirmations() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  rpc_server.mine_blocks(1);
+  rpc_server.mine_blocks(1);
+
+  let cardinals = CommandBuilder::new("wallet cardinals --min-confirmations 2")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<Cardinal>>();
+
+  assert_eq!(cardinals.len(), 1);
+  assert_eq!(cardinals[0].confirmations, 2);
+}
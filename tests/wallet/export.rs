@@ -0,0 +1,59 @@
+use {
+  super::*,
+  ord::subcommand::wallet::{export::Output, receive},
+};
+
+#[test]
+fn export_with_history() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let output = CommandBuilder::new("wallet export")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.inscriptions.len(), 1);
+  assert_eq!(output.inscriptions[0].transfers, None);
+
+  let address = CommandBuilder::new("wallet receive")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<receive::Output>()
+    .address;
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 {} {inscription}",
+    address.assume_checked()
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(".*")
+  .run_and_extract_stdout();
+
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet export --with-history")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.height, 3);
+  assert_eq!(output.transfer_log_start, Some(3));
+  assert_eq!(output.inscriptions.len(), 1);
+
+  let holding = &output.inscriptions[0];
+
+  assert_eq!(holding.inscription, inscription.parse().unwrap());
+  assert_eq!(holding.genesis.height, 2);
+  assert_eq!(holding.postage, 10_000);
+  assert_eq!(
+    holding
+      .transfers
+      .as_ref()
+      .unwrap()
+      .iter()
+      .map(|transfer| transfer.height)
+      .collect::<Vec<u64>>(),
+    [3]
+  );
+}
@@ -0,0 +1,127 @@
+use {
+  super::*,
+  ord::subcommand::wallet::{
+    failed_reveals::{ExportOutput, FailedCommit, ImportOutput, RetriedCommit},
+    pending,
+  },
+};
+
+#[test]
+fn failed_reveals_can_be_imported_retried_and_exported() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let Inscribe {
+    commit, reveals, ..
+  } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .temp_dir(tempdir.clone())
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  rpc_server.remove_from_mempool(reveals[0]);
+
+  let raw_reveal = CommandBuilder::new("wallet pending")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<pending::Output>()
+    .pending[0]
+    .raw_reveal
+    .clone()
+    .unwrap();
+
+  let filename = format!("failed-reveals-for-commit-{commit}.txt");
+
+  let output = CommandBuilder::new(format!("wallet failed-reveals import {filename}"))
+    .temp_dir(tempdir.clone())
+    .write(&filename, format!("# reveal failed\n{raw_reveal}\n"))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<ImportOutput>();
+
+  assert_eq!(output.commits, [commit]);
+
+  let output = CommandBuilder::new("wallet failed-reveals list")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<FailedCommit>>();
+
+  assert_eq!(
+    output,
+    [FailedCommit {
+      commit,
+      reveals: vec![reveals[0]],
+      notes: vec!["# reveal failed".into()],
+    }]
+  );
+
+  let output = CommandBuilder::new("wallet failed-reveals export --dir backup")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<ExportOutput>();
+
+  assert_eq!(output.files.len(), 1);
+  assert_eq!(
+    fs::read_to_string(tempdir.path().join("backup").join(&filename)).unwrap(),
+    format!("# reveal failed\n{raw_reveal}\n")
+  );
+
+  let output = CommandBuilder::new("wallet failed-reveals retry")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<RetriedCommit>>();
+
+  assert_eq!(
+    output,
+    [RetriedCommit {
+      commit,
+      rebroadcast: vec![reveals[0]],
+      failed: Vec::new(),
+    }]
+  );
+
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet failed-reveals list")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<FailedCommit>>();
+
+  assert_eq!(output, Vec::new());
+}
+
+#[test]
+fn retrying_unknown_commit_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let commit = "0000000000000000000000000000000000000000000000000000000000000000";
+
+  CommandBuilder::new(format!("wallet failed-reveals retry --commit {commit}"))
+    .rpc_server(&rpc_server)
+    .expected_stderr(format!(
+      "error: no failed reveals recorded for commit {commit}\n"
+    ))
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn importing_dump_with_newer_schema_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  CommandBuilder::new("wallet failed-reveals import dump.json")
+    .write(
+      "dump.json",
+      r#"{"schema": 99, "commit": {"hex": "00"}, "reveals": []}"#,
+    )
+    .rpc_server(&rpc_server)
+    .expected_stderr(
+      "error: dump has schema 99, but this version of ord only reads schemas up to 1\n",
+    )
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
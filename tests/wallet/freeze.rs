@@ -0,0 +1,33 @@
+use {super::*, ord::subcommand::wallet::freeze::Output};
+
+#[test]
+fn freeze_outpoint() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let coinbase_tx = &rpc_server.mine_blocks(1)[0].txdata[0];
+  let outpoint = OutPoint::new(coinbase_tx.txid(), 0);
+
+  let output = CommandBuilder::new(format!("wallet freeze {outpoint}"))
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.frozen, [outpoint]);
+}
+
+#[test]
+fn freeze_outpoint_not_in_wallet() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!("wallet freeze {}", OutPoint::null()))
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(format!(
+      "error: output {} is not in wallet\n",
+      OutPoint::null()
+    ))
+    .run_and_extract_stdout();
+}
@@ -81,6 +81,199 @@ fn inscribe_unknown_file_extension() {
     .run_and_extract_stdout();
 }
 
+#[test]
+fn inscribe_even_tag_requires_flag() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe hello.txt --fee-rate 1 --tag 8=00")
+    .write("hello.txt", "HELLOWORLD")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(
+      "error: tag 8 is even, so ord will not recognize the inscription, use --allow-even-tags to add it anyway\n",
+    )
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_watch_only_wallet_prints_psbt() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  rpc_server.disable_private_keys();
+
+  let output = CommandBuilder::new("wallet inscribe hello.txt --fee-rate 1")
+    .write("hello.txt", "HELLOWORLD")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<serde_json::Value>();
+
+  assert!(output["commit_psbt"]
+    .as_str()
+    .unwrap()
+    .starts_with("cHNidP8"));
+  assert_eq!(output["reveals"].as_array().unwrap().len(), 1);
+  assert_eq!(output["recovery_descriptors"].as_array().unwrap().len(), 1);
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn inscribe_with_funding_psbt_prints_combined_psbt() {
+  use {
+    base64::Engine,
+    bitcoin::{absolute::LockTime, psbt::Psbt, ScriptBuf, Transaction, TxIn, TxOut},
+  };
+
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let funding_outpoint = OutPoint {
+    txid: "0000000000000000000000000000000000000000000000000000000000000001"
+      .parse()
+      .unwrap(),
+    vout: 0,
+  };
+
+  let mut funding = Psbt::from_unsigned_tx(Transaction {
+    version: 2,
+    lock_time: LockTime::ZERO,
+    input: vec![TxIn {
+      previous_output: funding_outpoint,
+      ..Default::default()
+    }],
+    output: Vec::new(),
+  })
+  .unwrap();
+
+  funding.inputs[0].witness_utxo = Some(TxOut {
+    value: 100_000,
+    script_pubkey: ScriptBuf::from_bytes([[0x51, 0x20].as_slice(), &[1; 32]].concat()),
+  });
+
+  let funding = base64::engine::general_purpose::STANDARD.encode(funding.serialize());
+
+  let output = CommandBuilder::new(format!(
+    "wallet inscribe --funding-psbt {funding} --fee-rate 1 hello.txt"
+  ))
+  .write("hello.txt", "HELLOWORLD")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<serde_json::Value>();
+
+  let commit_psbt = Psbt::deserialize(
+    &base64::engine::general_purpose::STANDARD
+      .decode(output["commit_psbt"].as_str().unwrap())
+      .unwrap(),
+  )
+  .unwrap();
+
+  assert_eq!(commit_psbt.unsigned_tx.input.len(), 1);
+  assert_eq!(
+    commit_psbt.unsigned_tx.input[0].previous_output,
+    funding_outpoint
+  );
+  assert_eq!(
+    commit_psbt.inputs[0].witness_utxo.as_ref().unwrap().value,
+    100_000
+  );
+  assert_eq!(output["reveals"].as_array().unwrap().len(), 1);
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn inscribe_with_optimize_and_compress() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } =
+    CommandBuilder::new("wallet inscribe --fee-rate 1 --optimize --compress foo.txt")
+      .write("foo.txt", "foo".repeat(1000))
+      .rpc_server(&rpc_server)
+      .run_and_check_output();
+
+  assert!(rpc_server.mempool()[1].input[0].witness.serialized_len() < 1000);
+
+  rpc_server.mine_blocks(1);
+
+  let response =
+    TestServer::spawn_with_args(&rpc_server, &[]).request(format!("/content/{}", inscriptions[0]));
+
+  assert_eq!(response.status(), 200);
+  assert_eq!(response.text().unwrap(), "foo".repeat(1000));
+}
+
+#[test]
+fn inscribe_sat_by_number_or_rarity() {
+  for (sat, offset) in [("5000000100", 100), ("uncommon", 0)] {
+    let rpc_server = test_bitcoincore_rpc::spawn();
+    create_wallet(&rpc_server);
+    let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+    let output = CommandBuilder::new(format!(
+      "--index-sats wallet inscribe --fee-rate 1 --sat {sat} foo.txt"
+    ))
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<serde_json::Value>();
+
+    assert_eq!(output["satpoint"], format!("{txid}:0:{offset}"));
+    assert_eq!(
+      rpc_server.mempool()[0].input[0].previous_output,
+      OutPoint::new(txid, 0)
+    );
+  }
+}
+
+#[test]
+fn inscribe_sat_not_in_wallet() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("--index-sats wallet inscribe --fee-rate 1 --sat 0 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: sat 0 is not in wallet\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+
+  CommandBuilder::new("--index-sats wallet inscribe --fee-rate 1 --sat epic foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: no epic sat in wallet\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_sat_range_as_postage() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  let output = CommandBuilder::new(
+    "--index-sats wallet inscribe --fee-rate 1 --include-sat-range 5000000100-5000020100 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<serde_json::Value>();
+
+  assert_eq!(output["satpoint"], format!("{txid}:0:100"));
+  assert_eq!(rpc_server.mempool()[1].output[0].value, 20_000);
+
+  CommandBuilder::new(
+    "--index-sats wallet inscribe --fee-rate 1 --include-sat-range 0-20000 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: sats 0-20000 are not all in wallet\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
 #[test]
 fn inscribe_exceeds_chain_limit() {
   let rpc_server = test_bitcoincore_rpc::builder()
@@ -99,6 +292,54 @@ fn inscribe_exceeds_chain_limit() {
     .run_and_extract_stdout();
 }
 
+#[test]
+fn inscribe_fails_fast_on_file_too_heavy_to_reveal() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe small.txt large.txt --fee-rate 1")
+    .write("small.txt", "FOO")
+    .write("large.txt", [b'a'; 400_000])
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .stderr_regex(
+      "error: file 'large.txt' is too large for a standard reveal transaction, its envelope weighs [0-9]+ weight units, more than 400000 \\(MAX_STANDARD_TX_WEIGHT\\)\n",
+    )
+    .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn max_content_size_overrides_chain_limit() {
+  let rpc_server = test_bitcoincore_rpc::builder()
+    .network(Network::Signet)
+    .build();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "--chain signet --max-content-size 2048 wallet inscribe degenerate.png --fee-rate 1",
+  )
+  .write("degenerate.png", [1; 1025])
+  .rpc_server(&rpc_server)
+  .expected_stderr(
+    "warning: content size of 1025 bytes exceeds 1024 byte limit for signet inscriptions\n",
+  )
+  .stdout_regex(".*")
+  .run_and_extract_stdout();
+
+  CommandBuilder::new(
+    "--chain signet --max-content-size 1000 wallet inscribe degenerate.png --fee-rate 1",
+  )
+  .write("degenerate.png", [1; 1025])
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: content size of 1025 bytes exceeds maximum content size of 1000 bytes\n")
+  .run_and_extract_stdout();
+}
+
 #[test]
 fn regtest_has_no_content_size_limit() {
   let rpc_server = test_bitcoincore_rpc::builder()
@@ -346,6 +587,108 @@ fn inscribe_with_dry_run_flag() {
   assert_eq!(rpc_server.mempool().len(), 2);
 }
 
+#[test]
+fn inscribe_with_change_split() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --change-split 2 --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let commit_tx = &rpc_server.mempool()[0];
+
+  assert_eq!(commit_tx.output.len(), 3);
+  assert!(commit_tx.output[1].value - commit_tx.output[2].value < 2);
+
+  CommandBuilder::new("wallet inscribe --change-split 2 --change-position 0 --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: Cannot use both --change-position and --change-split\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_rng_seed() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --rng-seed 0123456789abcdef --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr(
+      "warning: --rng-seed makes reveal keys predictable, don't use it with real funds\n",
+    )
+    .run_and_check_output::<Inscribe>();
+
+  CommandBuilder::new("wallet inscribe --derive-keys --rng-seed 00 --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: Cannot use both --derive-keys and --rng-seed\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_derive_keys() {
+  use bitcoin::{
+    bip32::{DerivationPath, ExtendedPrivKey},
+    hashes::{sha256, Hash},
+    secp256k1::Secp256k1,
+  };
+
+  let rpc_server = test_bitcoincore_rpc::builder()
+    .network(Network::Regtest)
+    .build();
+
+  let Create { mnemonic } = CommandBuilder::new("--regtest wallet create")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Create>();
+
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("--regtest wallet inscribe --derive-keys --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  // the reveal key is derived from the account key with the inscribed sat's
+  // outpoint, here the only utxo, at m/86'/1'/0'/2'/<H>'/0'
+  let outpoint = OutPoint {
+    txid: rpc_server.tx(1, 0).txid(),
+    vout: 0,
+  };
+
+  let hash = sha256::Hash::hash(&bitcoin::consensus::serialize(&outpoint));
+  let outpoint_index =
+    u32::from_be_bytes(hash.to_byte_array()[..4].try_into().unwrap()) & 0x7fff_ffff;
+
+  let path = DerivationPath::from_str(&format!("m/86'/1'/0'/2'/{outpoint_index}'/0'")).unwrap();
+
+  let secp256k1 = Secp256k1::new();
+
+  let (expected, _parity) = ExtendedPrivKey::new_master(Network::Regtest, &mnemonic.to_seed(""))
+    .unwrap()
+    .derive_priv(&secp256k1, &path)
+    .unwrap()
+    .to_keypair(&secp256k1)
+    .x_only_public_key();
+
+  let reveal = rpc_server
+    .mempool()
+    .into_iter()
+    .find(|tx| tx.txid() == output.reveals[0])
+    .unwrap();
+
+  // the reveal key is the internal key in the control block
+  let control_block = reveal.input[0].witness.last().unwrap();
+  assert_eq!(control_block[1..33], expected.serialize());
+}
+
 #[test]
 fn inscribe_with_dry_run_flag_fees_inscrease() {
   let rpc_server = test_bitcoincore_rpc::spawn();
@@ -397,6 +740,60 @@ fn inscribe_to_specific_destination() {
   );
 }
 
+#[test]
+fn inscribe_to_destination_descriptor() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new([
+    "wallet",
+    "inscribe",
+    "--fee-rate",
+    "1",
+    "--destination-descriptor",
+    "wsh(and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),older(144)))",
+    "degenerate.png",
+  ])
+  .write("degenerate.png", [1; 520])
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<serde_json::Value>();
+
+  let descriptors = output["destination_descriptors"].as_array().unwrap();
+  assert_eq!(descriptors.len(), 1);
+  assert!(descriptors[0]
+    .as_str()
+    .unwrap()
+    .starts_with("wsh(and_v(v:pk(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798),older(144)))#"));
+
+  let reveal_tx = &rpc_server.mempool()[1];
+  assert!(reveal_tx.output[0].script_pubkey.is_v0_p2wsh());
+}
+
+#[test]
+fn inscribe_with_destination_descriptor_and_destination_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new([
+    "wallet",
+    "inscribe",
+    "--fee-rate",
+    "1",
+    "--destination-descriptor",
+    "wpkh(0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798)",
+    "--destination",
+    "bc1qcqgs2pps4u4yedfyl5pysdjjncs8et5utseepv",
+    "degenerate.png",
+  ])
+  .write("degenerate.png", [1; 520])
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: Cannot use both --destination and --destination-descriptor\n")
+  .run_and_extract_stdout();
+}
+
 #[test]
 fn inscribe_to_address_on_different_network() {
   let rpc_server = test_bitcoincore_rpc::spawn();
@@ -424,3 +821,1034 @@ fn inscribe_with_no_limit() {
     .write("degenerate.png", four_megger)
     .rpc_server(&rpc_server);
 }
+
+#[test]
+fn inscribe_with_csv_postage_and_content_type() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --csv batch.csv")
+    .write("foo.txt", "FOO")
+    .write(
+      "batch.csv",
+      "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,foo.txt,5000 sat,text/html",
+    )
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let reveal_tx = &rpc_server.mempool()[1];
+
+  assert_eq!(reveal_tx.output[0].value, 5000);
+  assert!(reveal_tx.input[0]
+    .witness
+    .iter()
+    .any(|element| element.windows(9).any(|window| window == b"text/html")));
+}
+
+#[test]
+fn inscribe_to_legacy_destination_requires_allow_legacy_destination() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr(
+    "error: destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 is a legacy address, pass --allow-legacy-destination to use it anyway\n",
+  )
+  .run_and_extract_stdout();
+
+  CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 --allow-legacy-destination foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+}
+
+#[test]
+fn inscribe_with_csv_repeated_destination_requires_yes() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let csv = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,foo.txt
+bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,bar.txt
+1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2,baz.txt";
+
+  let warnings =
+    "warning: destination bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 receives 2 inscriptions
+warning: destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 is a legacy address
+";
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --csv batch.csv")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .write("baz.txt", "BAZ")
+    .write("batch.csv", csv)
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(format!(
+      "{warnings}error: --csv destinations have warnings, pass --yes to inscribe anyway\n"
+    ))
+    .run_and_extract_stdout();
+
+  let output = CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --csv batch.csv --dry-run --allow-legacy-destination",
+  )
+  .write("foo.txt", "FOO")
+  .write("bar.txt", "BAR")
+  .write("baz.txt", "BAZ")
+  .write("batch.csv", csv)
+  .rpc_server(&rpc_server)
+  .expected_stderr(warnings)
+  .run_and_check_output::<serde_json::Value>();
+
+  assert_eq!(
+    output["destination_counts"],
+    serde_json::json!({
+      "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2": 1,
+      "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4": 2,
+    })
+  );
+
+  CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --csv batch.csv --yes --allow-legacy-destination",
+  )
+  .write("foo.txt", "FOO")
+  .write("bar.txt", "BAR")
+  .write("baz.txt", "BAZ")
+  .write("batch.csv", csv)
+  .rpc_server(&rpc_server)
+  .expected_stderr(warnings)
+  .run_and_check_output::<Inscribe>();
+}
+
+#[test]
+fn inscribe_with_csv_reports_all_errors() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --csv batch.csv")
+    .write("foo.txt", "FOO")
+    .write(
+      "batch.csv",
+      "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,foo.txt
+tb1qsgx55dp6gn53tsmyjjv4c2ye403hgxynxs0dnm,foo.txt
+bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,missing.txt
+bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4
+bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,foo.txt,1 sat
+bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4,foo.txt",
+    )
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .stderr_regex(
+      "error: Destination CSV file 'batch.csv' is not valid:
+line 2: address tb1qsgx55dp6gn53tsmyjjv4c2ye403hgxynxs0dnm belongs to network testnet which is different from required bitcoin
+line 3: error with file 'missing.txt': .*
+line 4: no comma, expected `destination,file\\[,postage\\[,content_type\\]\\]`
+line 5: postage 0.00000001 BTC is below the dust value of 0.00000294 BTC
+line 6: file 'foo.txt' is also on line 1
+",
+    )
+    .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn inscribe_dir_sorted_and_sliced() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --dir collection --sort size --start-index 1",
+  )
+  .write("collection/a.txt", "aaa")
+  .write("collection/b.txt", "b")
+  .write("collection/c.txt", "cc")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  assert_eq!(output.inscriptions.len(), 2);
+
+  let mempool = rpc_server.mempool();
+
+  for (reveal_tx, body) in mempool[1..].iter().zip([b"cc".as_slice(), b"aaa"]) {
+    assert!(reveal_tx.input[0]
+      .witness
+      .iter()
+      .any(|element| element.windows(body.len()).any(|window| window == body)));
+  }
+}
+
+#[test]
+fn inscribe_splits_batches_at_mempool_chain_limit() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(2);
+
+  let mut command = CommandBuilder::new("wallet inscribe --fee-rate 1 --dir collection");
+
+  for i in 0..30 {
+    command = command.write(format!("collection/{i:02}.txt"), format!("{i}"));
+  }
+
+  let output = command
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Vec<Inscribe>>();
+
+  assert_eq!(output.len(), 2);
+  assert_eq!(output[0].reveals.len(), 24);
+  assert_eq!(output[1].reveals.len(), 6);
+
+  let mempool = rpc_server.mempool();
+
+  assert_eq!(mempool.len(), 32);
+
+  for reveal_tx in &mempool[1..25] {
+    assert_eq!(reveal_tx.input[0].previous_output.txid, mempool[0].txid());
+  }
+
+  for reveal_tx in &mempool[26..] {
+    assert_eq!(reveal_tx.input[0].previous_output.txid, mempool[25].txid());
+  }
+}
+
+#[test]
+fn inscribe_does_not_split_batches_if_commit_confirms_first() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let mut command = CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --dir collection --wait-after-commit --dry-run",
+  );
+
+  for i in 0..30 {
+    command = command.write(format!("collection/{i:02}.txt"), format!("{i}"));
+  }
+
+  let output = command
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  assert_eq!(output.inscriptions.len(), 30);
+  assert_eq!(output.reveals.len(), 30);
+}
+
+#[test]
+fn inscribe_to_destination_script() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let script = "51210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179851ae";
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --fee-rate 1 --destination-script {script} foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let reveal_tx = &rpc_server.mempool()[1];
+
+  assert_eq!(reveal_tx.output[0].script_pubkey.to_hex_string(), script);
+  assert_eq!(reveal_tx.output[0].value, 10_000);
+}
+
+#[test]
+fn inscribe_destination_script_must_be_hex() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --destination-script xyz foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .stderr_regex("error: --destination-script is not a valid hex script\n.*")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_destination_script_conflicts_with_destination() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --destination-script 51 --destination bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: Cannot use both --destination and --destination-script\n")
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_preflight() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet inscribe --fee-rate 1 --preflight foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  assert_eq!(rpc_server.mempool().len(), 2);
+  assert_eq!(rpc_server.mempool()[1].txid(), output.reveals[0]);
+}
+
+#[test]
+fn preflight_package_error_prevents_broadcast() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+  rpc_server.reject_packages("package-too-large");
+
+  let stdout = CommandBuilder::new("wallet inscribe --fee-rate 1 --preflight foo.txt bar.txt")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: transactions would be rejected by the mempool, not broadcasting\n")
+    .run_and_extract_stdout();
+
+  let results = serde_json::from_str::<Vec<serde_json::Value>>(&stdout).unwrap();
+
+  assert_eq!(results.len(), 3);
+
+  for result in results {
+    assert_eq!(result["allowed"], false);
+    assert_eq!(result["package_error"], "package-too-large");
+  }
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn inscribe_does_not_preflight_by_default() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+  rpc_server.reject_packages("package-too-large");
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  assert_eq!(rpc_server.mempool().len(), 2);
+}
+
+#[test]
+fn preflight_conflicts_with_package_broadcast() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --preflight --package-broadcast foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: Cannot use both --preflight and --package-broadcast\n")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_expands_glob_patterns() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet inscribe --fee-rate 1 *.txt")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .write("baz.png", [1; 520])
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  assert_eq!(output.inscriptions.len(), 2);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 *.json")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: no files match *.json\n")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_fee_ladder() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --fee-ladder 2:3 foo.txt bar.txt")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+
+  let fee_rates = mempool[1..]
+    .iter()
+    .map(|reveal_tx| {
+      let input = reveal_tx.input[0].previous_output;
+      let fee = mempool[0].output[input.vout as usize].value
+        - reveal_tx
+          .output
+          .iter()
+          .map(|output| output.value)
+          .sum::<u64>();
+      fee as f64 / reveal_tx.vsize() as f64
+    })
+    .collect::<Vec<f64>>();
+
+  assert!((4.9..5.5).contains(&fee_rates[0]), "{fee_rates:?}");
+  assert!((1.9..2.5).contains(&fee_rates[1]), "{fee_rates:?}");
+}
+
+#[test]
+fn reveal_fee_rate_conflicts_with_fee_ladder() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --reveal-fee-rate 2 --fee-ladder 2:3 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: Cannot use both --reveal-fee-rate and --fee-ladder\n")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn commit_fee_rate_accounts_for_signed_input_weight() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+  rpc_server.use_p2wpkh_wallet();
+
+  CommandBuilder::new("wallet inscribe --fee-rate 10 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let commit_tx = &rpc_server.mempool()[0];
+  let mut fee = 0;
+  for input in &commit_tx.input {
+    fee += rpc_server
+      .get_utxo_amount(&input.previous_output)
+      .unwrap()
+      .to_sat();
+  }
+  for output in &commit_tx.output {
+    fee -= output.value;
+  }
+
+  // the signed p2wpkh input is heavier than the taproot key path spend that
+  // is assumed before signing
+  pretty_assert_eq!(
+    fee,
+    (commit_tx.weight().to_wu() as f64 * 10.0 / 4.0 - 1e-8).ceil() as u64
+  );
+}
+
+#[test]
+fn inscribe_reports_sat_flow() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("--index-sats wallet inscribe --sat-flow --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<serde_json::Value>();
+
+  let commit = output["commit"].as_str().unwrap();
+  let reveal = output["reveals"][0].as_str().unwrap();
+
+  let flow = output["sat_flow"].as_array().unwrap();
+
+  // the commit pays the reveal output, change, and fee, and the reveal pays
+  // the inscription output and fee
+  assert_eq!(flow.len(), 5);
+  assert_eq!(flow[0]["rarity"], "uncommon");
+  assert_eq!(flow[0]["output"], format!("{commit}:0:0"));
+  assert_eq!(flow[1]["change"], true);
+  assert_eq!(flow[2]["output"], serde_json::Value::Null);
+  assert_eq!(flow[3]["input"], format!("{commit}:0:0"));
+  assert_eq!(flow[3]["output"], format!("{reveal}:0:0"));
+  assert_eq!(flow[4]["output"], serde_json::Value::Null);
+}
+
+#[test]
+fn protect_rarity_keeps_rare_sats_out_of_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let coinbase = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  // the only utxo starts with an uncommon sat
+  CommandBuilder::new(
+    "--index-sats wallet inscribe --protect-rarity uncommon --fee-rate 1 foo.txt",
+  )
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: wallet contains no cardinal utxos\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  CommandBuilder::new(format!(
+    "--index-sats wallet inscribe --protect-rarity uncommon --allow-rare-utxo {coinbase}:0 --fee-rate 1 foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+}
+
+#[test]
+fn avoided_utxos_are_not_spent() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let coinbase = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --avoid-utxo {coinbase}:0 --fee-rate 1 foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: wallet contains no cardinal utxos\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --utxo {coinbase}:0 --avoid-utxo {coinbase}:0 --fee-rate 1 foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_stderr(format!("error: cannot both spend and avoid {coinbase}:0\n"))
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --avoid-utxo {coinbase}:0 --fee-rate 1 foo.txt"
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  assert!(rpc_server
+    .mempool()
+    .iter()
+    .flat_map(|tx| &tx.input)
+    .all(|input| input.previous_output != OutPoint::new(coinbase, 0)));
+}
+
+#[test]
+fn protect_rarity_requires_sat_index() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --protect-rarity uncommon --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: --protect-rarity requires index created with `--index-sats` flag\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_does_not_spend_outputs_of_pending_commits() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { reveals, .. } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let commit_output = rpc_server
+    .mempool()
+    .into_iter()
+    .find(|tx| tx.txid() == reveals[0])
+    .unwrap()
+    .input[0]
+    .previous_output;
+
+  // the reveal never makes it into a block
+  rpc_server.remove_from_mempool(reveals[0]);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { commit, .. } = CommandBuilder::new("wallet inscribe --fee-rate 1 bar.txt")
+    .write("bar.txt", "BAR")
+    .rpc_server(&rpc_server)
+    .expected_stderr(format!(
+      "warning: not spending {commit_output}, which commit {} in the mempool left for a reveal that hasn't been mined. Pass it with --utxo to spend it anyway.\n",
+      commit_output.txid,
+    ))
+    .run_and_check_output::<Inscribe>();
+
+  assert!(rpc_server
+    .mempool()
+    .into_iter()
+    .find(|tx| tx.txid() == commit)
+    .unwrap()
+    .input
+    .iter()
+    .all(|input| input.previous_output != commit_output));
+}
+
+#[test]
+fn inscribe_with_locktime_and_sequence() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --locktime 1 --sequence 4294967294 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+
+  let commit_tx = &mempool[0];
+  assert_eq!(commit_tx.lock_time.to_consensus_u32(), 0);
+  assert!(commit_tx
+    .input
+    .iter()
+    .all(|input| input.sequence.0 == 4294967294));
+
+  let reveal_tx = &mempool[1];
+  assert_eq!(reveal_tx.lock_time.to_consensus_u32(), 1);
+  assert!(reveal_tx
+    .input
+    .iter()
+    .all(|input| input.sequence.0 == 4294967294));
+}
+
+#[test]
+fn inscribe_with_final_sequence() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --sequence 4294967295 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  for tx in rpc_server.mempool() {
+    assert!(tx.input.iter().all(|input| input.sequence.0 == u32::MAX));
+  }
+}
+
+#[test]
+fn inscribe_locktime_requires_non_final_sequence() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --locktime 1 --sequence 4294967295 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: --locktime has no effect when --sequence is 4294967295\n")
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn inscribe_with_future_locktime_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --locktime 100 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(
+      "error: --locktime 100 is above the current block height 1, the transaction would not be accepted until then\n",
+    )
+    .run_and_extract_stdout();
+
+  // the median time past of the genesis block and block 1 is the genesis
+  // block's time
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --locktime 2000000000 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr(
+      "error: --locktime 2000000000 is not before the current median time past 1231006505, the transaction would not be accepted until then\n",
+    )
+    .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn inscribe_with_future_locktime_and_no_broadcast() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let stdout =
+    CommandBuilder::new("wallet inscribe --fee-rate 1 --locktime 100 --no-broadcast foo.txt")
+      .write("foo.txt", "FOO")
+      .rpc_server(&rpc_server)
+      .stdout_regex(".*")
+      .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+
+  let dump = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+
+  let reveal_tx = bitcoin::consensus::deserialize::<bitcoin::Transaction>(
+    &hex::decode(dump["reveals"][0]["hex"].as_str().unwrap()).unwrap(),
+  )
+  .unwrap();
+
+  assert_eq!(reveal_tx.lock_time.to_consensus_u32(), 100);
+}
+
+#[test]
+fn inscribe_dump_nests_commit_and_reveals() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let stdout = CommandBuilder::new("wallet inscribe --fee-rate 1 --no-broadcast foo.txt bar.txt")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .rpc_server(&rpc_server)
+    .stdout_regex(".*")
+    .run_and_extract_stdout();
+
+  let dump = serde_json::from_str::<serde_json::Value>(&stdout).unwrap();
+
+  let keys = |value: &serde_json::Value| {
+    value
+      .as_object()
+      .unwrap()
+      .keys()
+      .cloned()
+      .collect::<Vec<String>>()
+  };
+
+  assert_eq!(
+    keys(&dump),
+    [
+      "batch",
+      "commit",
+      "dust_to_fee",
+      "fees",
+      "recovery_descriptors",
+      "reveals",
+      "satpoint",
+      "schema",
+    ],
+  );
+
+  assert_eq!(keys(&dump["commit"]), ["hex", "txid", "weight"]);
+
+  let reveals = dump["reveals"].as_array().unwrap();
+
+  assert_eq!(reveals.len(), 2);
+
+  for (i, reveal) in reveals.iter().enumerate() {
+    assert_eq!(
+      keys(reveal),
+      [
+        "commit_address",
+        "filename",
+        "hex",
+        "inscription",
+        "recovery_descriptor",
+        "weight",
+      ],
+    );
+
+    assert_eq!(reveal["recovery_descriptor"], i);
+  }
+
+  assert_eq!(reveals[0]["filename"], "foo.txt");
+  assert_eq!(reveals[1]["filename"], "bar.txt");
+  assert_eq!(dump["recovery_descriptors"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn inscribe_with_package_broadcast() {
+  let rpc_server = test_bitcoincore_rpc::builder()
+    .network(Network::Regtest)
+    .version(250000)
+    .build();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new(
+    "--regtest wallet inscribe --fee-rate 1 --package-broadcast foo.txt bar.txt",
+  )
+  .write("foo.txt", "FOO")
+  .write("bar.txt", "BAR")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+
+  // the package holds the commit and the first reveal, and the second reveal
+  // is broadcast on its own
+  assert_eq!(mempool.len(), 3);
+  assert_eq!(mempool[0].txid(), output.commit);
+  assert_eq!(
+    mempool[1..]
+      .iter()
+      .map(|tx| tx.txid())
+      .collect::<Vec<Txid>>(),
+    output.reveals,
+  );
+}
+
+#[test]
+fn package_broadcast_falls_back_if_submitpackage_is_regtest_only() {
+  let rpc_server = test_bitcoincore_rpc::builder().version(260000).build();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --package-broadcast foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .stdout_regex(".*")
+    .expected_stderr(
+      "submitpackage unavailable (submitpackage is for regression testing (-regtest mode) only), broadcasting transactions separately\n",
+    )
+    .run_and_extract_stdout();
+
+  assert_eq!(rpc_server.mempool().len(), 2);
+}
+
+#[test]
+fn package_broadcast_falls_back_if_submitpackage_is_missing() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --package-broadcast foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .stdout_regex(".*")
+    .expected_stderr(
+      "submitpackage unavailable (Method not found), broadcasting transactions separately\n",
+    )
+    .run_and_extract_stdout();
+
+  assert_eq!(rpc_server.mempool().len(), 2);
+}
+
+/// The fee rate of a cursed reveal tx, whose first input is either a wallet
+/// utxo or an output split off by the commit tx.
+fn cursed_reveal_fee_rate(
+  rpc_server: &test_bitcoincore_rpc::Handle,
+  commit_tx: &bitcoin::Transaction,
+  reveal_tx: &bitcoin::Transaction,
+) -> f64 {
+  let input_value = reveal_tx
+    .input
+    .iter()
+    .map(|input| {
+      if input.previous_output.txid == commit_tx.txid() {
+        commit_tx.output[input.previous_output.vout as usize].value
+      } else {
+        rpc_server
+          .get_utxo_amount(&input.previous_output)
+          .unwrap()
+          .to_sat()
+      }
+    })
+    .sum::<u64>();
+
+  let output_value = reveal_tx
+    .output
+    .iter()
+    .map(|output| output.value)
+    .sum::<u64>();
+
+  (input_value - output_value) as f64 / reveal_tx.vsize() as f64
+}
+
+#[test]
+fn inscribe_cursed_batch_with_split_first_inputs() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  // the only cardinal funds the commit tx, which splits off a first input
+  // for each reveal tx
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --cursed foo.txt bar.txt")
+    .write("foo.txt", "FOO")
+    .write("bar.txt", "BAR")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+  assert_eq!(mempool.len(), 3);
+
+  let commit_tx = &mempool[0];
+
+  for (i, reveal_tx) in mempool[1..].iter().enumerate() {
+    let vout = u32::try_from(i).unwrap();
+
+    assert_eq!(
+      reveal_tx
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .collect::<Vec<OutPoint>>(),
+      [
+        OutPoint::new(commit_tx.txid(), 2 + vout),
+        OutPoint::new(commit_tx.txid(), vout),
+      ],
+    );
+
+    assert_eq!(commit_tx.output[2 + i].value, 330);
+    assert_eq!(reveal_tx.output[0].value, 330);
+    assert_eq!(reveal_tx.output[1].value, 10_000);
+
+    let fee_rate = cursed_reveal_fee_rate(&rpc_server, commit_tx, reveal_tx);
+    assert!((1.0..1.1).contains(&fee_rate), "{fee_rate}");
+  }
+}
+
+#[test]
+fn inscribe_cursed_batch_with_cursed_utxos() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let coinbases = rpc_server
+    .mine_blocks(3)
+    .iter()
+    .map(|block| block.txdata[0].txid())
+    .collect::<Vec<Txid>>();
+
+  // the first inputs are used in the order they're given
+  CommandBuilder::new(format!(
+    "wallet inscribe --fee-rate 1 --cursed --cursed-utxo {}:0 --cursed-utxo {}:0 foo.txt bar.txt",
+    coinbases[2], coinbases[1],
+  ))
+  .write("foo.txt", "FOO")
+  .write("bar.txt", "BAR")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+  assert_eq!(mempool.len(), 3);
+
+  let commit_tx = &mempool[0];
+
+  assert!(commit_tx
+    .input
+    .iter()
+    .all(|input| input.previous_output.txid == coinbases[0]));
+
+  for (i, (reveal_tx, coinbase)) in mempool[1..]
+    .iter()
+    .zip([coinbases[2], coinbases[1]])
+    .enumerate()
+  {
+    assert_eq!(
+      reveal_tx
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .collect::<Vec<OutPoint>>(),
+      [
+        OutPoint::new(coinbase, 0),
+        OutPoint::new(commit_tx.txid(), i.try_into().unwrap()),
+      ],
+    );
+
+    // the first input's value goes back to the first output
+    assert_eq!(reveal_tx.output[0].value, 50 * COIN_VALUE);
+    assert_eq!(reveal_tx.output[1].value, 10_000);
+
+    let fee_rate = cursed_reveal_fee_rate(&rpc_server, commit_tx, reveal_tx);
+    assert!((1.0..1.1).contains(&fee_rate), "{fee_rate}");
+  }
+}
+
+#[test]
+fn inscribe_cursed_batch_with_fewer_cursed_utxos_than_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let coinbases = rpc_server
+    .mine_blocks(2)
+    .iter()
+    .map(|block| block.txdata[0].txid())
+    .collect::<Vec<Txid>>();
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --fee-rate 1 --cursed --cursed-utxo {}:0 foo.txt bar.txt",
+    coinbases[1],
+  ))
+  .write("foo.txt", "FOO")
+  .write("bar.txt", "BAR")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  let mempool = rpc_server.mempool();
+  assert_eq!(mempool.len(), 3);
+
+  let commit_tx = &mempool[0];
+
+  // the second reveal's first input is the only split, after the commit
+  // outputs of both reveals
+  assert_eq!(
+    mempool[1].input[0].previous_output,
+    OutPoint::new(coinbases[1], 0)
+  );
+  assert_eq!(
+    mempool[2].input[0].previous_output,
+    OutPoint::new(commit_tx.txid(), 2)
+  );
+  assert_eq!(mempool[2].output[0].value, 330);
+
+  for reveal_tx in &mempool[1..] {
+    let fee_rate = cursed_reveal_fee_rate(&rpc_server, commit_tx, reveal_tx);
+    assert!((1.0..1.1).contains(&fee_rate), "{fee_rate}");
+  }
+}
+
+#[test]
+fn inscribe_cursed_batch_with_too_many_cursed_utxos() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let coinbases = rpc_server
+    .mine_blocks(3)
+    .iter()
+    .map(|block| block.txdata[0].txid())
+    .collect::<Vec<Txid>>();
+
+  CommandBuilder::new(format!(
+    "wallet inscribe --fee-rate 1 --cursed --cursed-utxo {}:0 --cursed-utxo {}:0 foo.txt",
+    coinbases[1], coinbases[2],
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: more --cursed-utxo than inscriptions\n")
+  .run_and_extract_stdout();
+}
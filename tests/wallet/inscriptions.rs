@@ -124,3 +124,23 @@ fn inscriptions_with_postage() {
 
   assert_eq!(output[0].postage, 9889);
 }
+
+#[test]
+fn inscriptions_link_to_explorer_url() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let output =
+    CommandBuilder::new("--explorer-url https://explorer.example.com/ wallet inscriptions")
+      .rpc_server(&rpc_server)
+      .run_and_check_output::<Vec<OutputWithoutSat>>();
+
+  assert_eq!(
+    output[0].explorer,
+    format!("https://explorer.example.com/inscription/{inscription}")
+  );
+}
@@ -0,0 +1,125 @@
+use {super::*, ord::subcommand::wallet::label::Output};
+
+#[test]
+fn send_to_labeled_address() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = TempDir::new().unwrap();
+  let index = tempdir.path().join("index.redb");
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+
+  let output = CommandBuilder::new(format!(
+    "--index {} wallet label bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 alice",
+    index.display()
+  ))
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.label, "alice");
+  assert_eq!(
+    output.destination,
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+  );
+  assert_eq!(
+    rpc_server.labels()["bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"],
+    "alice"
+  );
+
+  CommandBuilder::new(format!(
+    "--index {} wallet send --fee-rate 1 @alice {}",
+    index.display(),
+    inscriptions[0]
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r".*")
+  .run_and_extract_stdout();
+
+  assert_eq!(
+    rpc_server.mempool()[0].output[0].script_pubkey,
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+      .parse::<Address<NetworkUnchecked>>()
+      .unwrap()
+      .assume_checked()
+      .script_pubkey()
+  );
+}
+
+#[test]
+fn inscribe_to_labeled_address() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = TempDir::new().unwrap();
+  let index = tempdir.path().join("index.redb");
+
+  CommandBuilder::new(format!(
+    "--index {} wallet label bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 alice",
+    index.display()
+  ))
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  CommandBuilder::new(format!(
+    "--index {} wallet inscribe --destination @alice --fee-rate 1 foo.txt",
+    index.display()
+  ))
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Inscribe>();
+
+  assert_eq!(
+    rpc_server.mempool()[1].output[0].script_pubkey,
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"
+      .parse::<Address<NetworkUnchecked>>()
+      .unwrap()
+      .assume_checked()
+      .script_pubkey()
+  );
+}
+
+#[test]
+fn unknown_label() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new("wallet send --fee-rate 1 @alice 1btc")
+    .rpc_server(&rpc_server)
+    .expected_stderr("error: no destination is labeled @alice\n")
+    .expected_exit_code(1)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn outpoint_labels_are_not_addresses() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = TempDir::new().unwrap();
+  let index = tempdir.path().join("index.redb");
+
+  CommandBuilder::new(format!(
+    "--index {} wallet label {} bob",
+    index.display(),
+    OutPoint::null()
+  ))
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  CommandBuilder::new(format!(
+    "--index {} wallet send --fee-rate 1 @bob 1btc",
+    index.display()
+  ))
+  .rpc_server(&rpc_server)
+  .expected_stderr(format!(
+    "error: @bob labels {}, which is not an address\n",
+    OutPoint::null()
+  ))
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
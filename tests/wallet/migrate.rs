@@ -0,0 +1,66 @@
+use {super::*, ord::subcommand::wallet::migrate::Output};
+
+#[test]
+fn migrate_dry_run_plans_cardinal_sweep() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let coinbase_tx = &rpc_server.mine_blocks(1)[0].txdata[0];
+  let outpoint = OutPoint::new(coinbase_tx.txid(), 0);
+
+  let output = CommandBuilder::new(
+    "wallet migrate --fee-rate 1 --dry-run --to bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+  )
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.transactions.len(), 1);
+
+  let transaction = &output.transactions[0];
+
+  assert_eq!(transaction.inputs, [outpoint]);
+  assert_eq!(transaction.outputs.len(), 1);
+  assert_eq!(
+    transaction.outputs[0].value + transaction.fee,
+    coinbase_tx.output[0].value
+  );
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn migrate_moves_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let output = CommandBuilder::new(
+    "wallet migrate --fee-rate 1 --to bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+  )
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(output.transactions.len(), 1);
+
+  let transaction = &output.transactions[0];
+
+  assert_eq!(transaction.outputs[0].inscriptions.len(), 1);
+  assert_eq!(
+    transaction.outputs[0].inscriptions[0].to_string(),
+    *inscription
+  );
+  assert_eq!(rpc_server.mempool().len(), 1);
+  assert_eq!(rpc_server.mempool()[0].txid(), transaction.txid);
+
+  rpc_server.mine_blocks(1);
+
+  TestServer::spawn_with_args(&rpc_server, &[]).assert_response_regex(
+    format!("/inscription/{inscription}"),
+    format!(
+      r".*<dt>location</dt>\s*<dd class=monospace>{}:0:0</dd>.*",
+      transaction.txid
+    ),
+  );
+}
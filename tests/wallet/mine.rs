@@ -0,0 +1,16 @@
+use {super::*, ord::subcommand::wallet::mine::Output};
+
+#[test]
+fn mine_blocks_on_regtest() {
+  let rpc_server = test_bitcoincore_rpc::builder()
+    .network(Network::Regtest)
+    .build();
+  create_wallet(&rpc_server);
+
+  let output = CommandBuilder::new("--chain regtest wallet mine --blocks 3")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.blocks.len(), 3);
+  assert_eq!(output.height, 3);
+}
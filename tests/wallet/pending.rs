@@ -0,0 +1,77 @@
+use {
+  super::*,
+  ord::subcommand::wallet::pending::{Action, Output, Status},
+};
+
+#[test]
+fn pending_inscriptions_are_listed_until_they_confirm() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let Inscribe {
+    commit,
+    reveals,
+    inscriptions,
+    ..
+  } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .temp_dir(tempdir.clone())
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  let output = CommandBuilder::new("wallet pending")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.pending.len(), 1);
+  assert_eq!(output.pending[0].inscription.to_string(), inscriptions[0]);
+  assert_eq!(output.pending[0].commit, commit);
+  assert_eq!(output.pending[0].reveal, reveals[0]);
+  assert_eq!(output.pending[0].status, Status::Unconfirmed);
+  assert_eq!(output.pending[0].actions, Vec::new());
+  assert_eq!(output.pending[0].raw_reveal, None);
+
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet pending")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.pending, Vec::new());
+}
+
+#[test]
+fn failed_reveals_can_be_rebroadcast_or_recovered() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  let Inscribe { reveals, .. } = CommandBuilder::new("wallet inscribe --fee-rate 1 foo.txt")
+    .temp_dir(tempdir.clone())
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  rpc_server.remove_from_mempool(reveals[0]);
+  rpc_server.mine_blocks(1);
+
+  let output = CommandBuilder::new("wallet pending")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.pending.len(), 1);
+  assert_eq!(output.pending[0].status, Status::Failed);
+  assert_eq!(
+    output.pending[0].actions,
+    [Action::Rebroadcast, Action::Recover]
+  );
+  assert!(output.pending[0].raw_reveal.is_some());
+}
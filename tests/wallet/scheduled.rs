@@ -0,0 +1,70 @@
+use {super::*, ord::subcommand::wallet::scheduled::Output};
+
+#[test]
+fn inscribe_broadcasts_when_fee_rate_is_below_threshold() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  rpc_server.set_fee_estimate(2.0);
+
+  CommandBuilder::new("wallet inscribe --fee-rate 1 --broadcast-when-fee-below 5 foo.txt")
+    .write("foo.txt", "FOO")
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Inscribe>();
+
+  assert_eq!(rpc_server.mempool().len(), 2);
+}
+
+#[test]
+fn scheduled_inscription_can_be_resumed() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  rpc_server.set_fee_estimate(10.0);
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  CommandBuilder::new(
+    "wallet inscribe --fee-rate 1 --broadcast-when-fee-below 5 --timeout 0 foo.txt",
+  )
+  .temp_dir(tempdir.clone())
+  .write("foo.txt", "FOO")
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .stderr_regex(
+    "\\[fee rate is 10 sats/vB, waiting for it to drop below 5\\]
+error: the signed transactions were saved, broadcast them once the fee rate drops with `ord wallet scheduled resume`
+because: fee rate did not drop below 5 sats/vB within 0 seconds
+",
+  )
+  .run_and_extract_stdout();
+
+  assert_eq!(rpc_server.mempool().len(), 0);
+
+  let output = CommandBuilder::new("wallet scheduled list")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.scheduled.len(), 1);
+  assert_eq!(output.scheduled[0].fee_rate_below, 5.0);
+
+  rpc_server.set_fee_estimate(2.0);
+
+  let output = CommandBuilder::new("wallet scheduled resume")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.scheduled.len(), 1);
+  assert_eq!(rpc_server.mempool().len(), 2);
+
+  let output = CommandBuilder::new("wallet scheduled list")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert!(output.scheduled.is_empty());
+}
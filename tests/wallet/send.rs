@@ -1,4 +1,10 @@
-use {super::*, ord::subcommand::wallet::send::Output};
+use {
+  super::*,
+  ord::{
+    subcommand::wallet::send::{Output, SatFlowOutput},
+    Rarity,
+  },
+};
 
 #[test]
 fn inscriptions_can_be_sent() {
@@ -125,6 +131,35 @@ fn send_addresses_must_be_valid_for_network() {
   .run_and_extract_stdout();
 }
 
+#[test]
+fn inscriptions_cannot_be_sent_to_legacy_addresses() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 {inscription}"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_stderr(
+    "error: destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 is a legacy address, pass --allow-legacy-destination to use it anyway\n",
+  )
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --allow-legacy-destination 1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2 {inscription}"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r".*")
+  .run_and_extract_stdout();
+}
+
 #[test]
 fn send_on_mainnnet_works_with_wallet_named_ord() {
   let rpc_server = test_bitcoincore_rpc::builder().build();
@@ -238,6 +273,31 @@ fn inscriptions_cannot_be_sent_by_satpoint() {
   .run_and_extract_stdout();
 }
 
+#[test]
+fn send_fee_accounts_for_p2wpkh_inputs() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  rpc_server.use_p2wpkh_wallet();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 10 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex("[[:xdigit:]]{64}\n")
+  .run_and_extract_stdout();
+
+  let tx = &rpc_server.mempool()[0];
+
+  let fee = 50 * COIN_VALUE - tx.output.iter().map(|output| output.value).sum::<u64>();
+
+  // the signature is predicted to be 72 bytes, but the wallet signs with 71
+  pretty_assert_eq!(
+    fee,
+    ((tx.weight().to_wu() + 1) as f64 * 10.0 / 4.0 - 1e-8).ceil() as u64
+  );
+}
+
 #[test]
 fn send_btc() {
   let rpc_server = test_bitcoincore_rpc::spawn();
@@ -270,6 +330,60 @@ fn send_btc() {
   )
 }
 
+#[test]
+fn send_btc_does_not_support_avoid_utxo() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let coinbase = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --avoid-utxo {coinbase}:0 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 1btc"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: --avoid-utxo doesn't work when sending cardinals\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn reserve_only_works_when_sending_all_or_max() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new([
+    "wallet",
+    "send",
+    "--fee-rate",
+    "1",
+    "--reserve",
+    "1000 sat",
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+    "1btc",
+  ])
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: --reserve only works when sending all or max\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  CommandBuilder::new([
+    "wallet",
+    "send",
+    "--fee-rate",
+    "1",
+    "--reserve",
+    "1000 sat",
+    "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+    "all-1000sat",
+  ])
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: Cannot use both all-<RESERVE> and --reserve\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
 #[test]
 fn send_btc_locks_inscriptions() {
   let rpc_server = test_bitcoincore_rpc::spawn();
@@ -308,6 +422,30 @@ fn send_btc_locks_inscriptions() {
   )
 }
 
+#[test]
+fn send_btc_reports_locked_inscriptions() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { reveals, .. } = inscribe(&rpc_server);
+
+  let output = CommandBuilder::new(
+    "wallet send --fee-rate 1 --unlock-after bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 1btc",
+  )
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<Output>();
+
+  assert_eq!(
+    output.locked,
+    [OutPoint {
+      txid: reveals[0],
+      vout: 0,
+    }]
+  );
+}
+
 #[test]
 fn send_btc_fails_if_lock_unspent_fails() {
   let rpc_server = test_bitcoincore_rpc::builder()
@@ -377,3 +515,323 @@ fn user_must_provide_fee_rate_to_send() {
   )
   .run_and_extract_stdout();
 }
+
+#[test]
+fn watch_only_wallet_prints_psbt() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  rpc_server.mine_blocks(1);
+
+  rpc_server.disable_private_keys();
+
+  let stdout = CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {inscription}",
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r"cHNidP8.*\n")
+  .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+  assert!(!stdout.is_empty());
+}
+
+#[test]
+fn send_reports_sat_flow() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  let output = CommandBuilder::new(format!(
+    "--index-sats wallet send --fee-rate 1 --sat-flow bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .run_and_check_output::<SatFlowOutput>();
+
+  let tx = &rpc_server.mempool()[0];
+  assert_eq!(output.txid, tx.txid());
+
+  let flow = output.sat_flow;
+  assert_eq!(flow.len(), 3);
+
+  assert_eq!(flow[0].start, 50 * COIN_VALUE);
+  assert_eq!(flow[0].rarity, Rarity::Uncommon);
+  assert_eq!(flow[0].input, format!("{txid}:0:0").parse().unwrap());
+  assert_eq!(
+    flow[0].output,
+    Some(format!("{}:0:0", tx.txid()).parse().unwrap())
+  );
+  assert!(!flow[0].change);
+
+  assert_eq!(
+    flow[1].output,
+    Some(format!("{}:1:0", tx.txid()).parse().unwrap())
+  );
+  assert!(flow[1].change);
+
+  assert_eq!(flow[2].output, None);
+  assert_eq!(flow[2].end, 100 * COIN_VALUE);
+  assert_eq!(
+    flow[2].end - flow[2].start,
+    50 * COIN_VALUE - tx.output.iter().map(|output| output.value).sum::<u64>()
+  );
+}
+
+#[test]
+fn send_sat_by_name_or_degree() {
+  for sat in ["nvtcsezkbth", "0°1′1″0‴"] {
+    let rpc_server = test_bitcoincore_rpc::spawn();
+    create_wallet(&rpc_server);
+    let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+    let stdout = CommandBuilder::new(format!(
+      "--index-sats wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {sat}"
+    ))
+    .rpc_server(&rpc_server)
+    .stdout_regex(r"[[:xdigit:]]{64}\n")
+    .run_and_extract_stdout();
+
+    let tx = &rpc_server.mempool()[0];
+    assert_eq!(stdout.trim(), tx.txid().to_string());
+    assert_eq!(tx.input[0].previous_output, OutPoint::new(txid, 0));
+  }
+}
+
+#[test]
+fn send_sat_by_name_requires_sat_index() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 nvtcsezkbth",
+  )
+  .rpc_server(&rpc_server)
+  .expected_stderr(
+    "error: sending a sat by name or degree requires index created with `--index-sats` flag\n",
+  )
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn send_sat_not_in_wallet() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "--index-sats wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 nvtdijuwxlp",
+  )
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: sat 0 is not in wallet\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn send_with_change_split() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --change-split 3 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r"[[:xdigit:]]{64}\n")
+  .run_and_extract_stdout();
+
+  let tx = &rpc_server.mempool()[0];
+
+  assert_eq!(tx.output.len(), 4);
+  assert_eq!(tx.output[0].value, 10_000);
+  assert!(tx.output[2..]
+    .iter()
+    .all(|output| output.value == tx.output[2].value));
+  assert!(tx.output[1].value - tx.output[2].value < 3);
+}
+
+#[test]
+fn send_sat_flow_requires_sat_index() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --sat-flow bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_stderr("error: --sat-flow requires index created with `--index-sats` flag\n")
+  .expected_exit_code(1)
+  .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+/// A bare 1-of-1 multisig script, which is three bytes longer than the P2TR
+/// wallet address it replaces.
+const BARE_MULTISIG: &str =
+  "51210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179851ae";
+
+#[test]
+fn send_to_destination_script() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 2 --destination-script {BARE_MULTISIG} {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r"[[:xdigit:]]{64}\n")
+  .run_and_extract_stdout();
+
+  let tx = &rpc_server.mempool()[0];
+
+  assert_eq!(tx.output[0].script_pubkey.to_hex_string(), BARE_MULTISIG);
+  assert_eq!(tx.output[0].value, 10_000);
+
+  // the last output pays for the longer script, so the fee rate is unchanged
+  let fee = tx
+    .input
+    .iter()
+    .map(|input| {
+      rpc_server
+        .get_utxo_amount(&input.previous_output)
+        .unwrap()
+        .to_sat()
+    })
+    .sum::<u64>()
+    - tx.output.iter().map(|output| output.value).sum::<u64>();
+
+  pretty_assert_eq!(fee as f64 / (tx.weight().to_wu() as f64 / 4.0), 2.0);
+}
+
+#[test]
+fn send_to_destination_script_below_dust_value() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  // the dust value of a 3500 byte script is more than the 10,000 sat postage
+  let script = "51".repeat(3500);
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --destination-script {script} {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr(
+    "error: output value is below dust value for --destination-script: 0.00010000 BTC < 0.00010977 BTC\n",
+  )
+  .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn send_destination_script_conflicts_with_address() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --destination-script {BARE_MULTISIG} bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: Cannot use both an address and --destination-script\n")
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn send_destination_script_requires_inscription_or_satpoint() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --destination-script {BARE_MULTISIG} 1btc"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr(
+    "error: --destination-script only works when sending inscriptions or satpoints\n",
+  )
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn send_with_locktime_and_sequence() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --locktime 1 --sequence 4294967294 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(r"[[:xdigit:]]{64}\n")
+  .run_and_extract_stdout();
+
+  let tx = &rpc_server.mempool()[0];
+
+  assert_eq!(tx.lock_time.to_consensus_u32(), 1);
+  assert!(tx.input.iter().all(|input| input.sequence.0 == 4294967294));
+}
+
+#[test]
+fn send_with_future_locktime_fails() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --locktime 2 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr(
+    "error: --locktime 2 is above the current block height 1, the transaction would not be accepted until then\n",
+  )
+  .run_and_extract_stdout();
+
+  assert!(rpc_server.mempool().is_empty());
+}
+
+#[test]
+fn send_locktime_requires_non_final_sequence() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  let txid = rpc_server.mine_blocks(1)[0].txdata[0].txid();
+
+  CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 --locktime 1 --sequence 4294967295 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {txid}:0:0"
+  ))
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr("error: --locktime has no effect when --sequence is 4294967295\n")
+  .run_and_extract_stdout();
+}
+
+#[test]
+fn send_locktime_requires_inscription_or_satpoint() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(
+    "wallet send --fee-rate 1 --locktime 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 1btc",
+  )
+  .rpc_server(&rpc_server)
+  .expected_exit_code(1)
+  .expected_stderr(
+    "error: --locktime and --sequence only work when sending inscriptions or satpoints\n",
+  )
+  .run_and_extract_stdout();
+}
@@ -0,0 +1,38 @@
+use {super::*, ord::subcommand::wallet::shard::Output};
+
+#[test]
+fn shard_and_reassemble() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+  rpc_server.mine_blocks(1);
+
+  let content = "0123456789".repeat(250);
+
+  let output = CommandBuilder::new("wallet shard --chunk-size 1000 --fee-rate 1 big.txt")
+    .write("big.txt", &content)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.parts.len(), 3);
+
+  rpc_server.mine_blocks(1);
+
+  CommandBuilder::new(format!("reassemble {}", output.manifest))
+    .rpc_server(&rpc_server)
+    .stdout_regex(content)
+    .run_and_extract_stdout();
+}
+
+#[test]
+fn shard_requires_one_file() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  CommandBuilder::new("wallet shard --chunk-size 1000 --fee-rate 1 foo.txt bar.txt")
+    .write("foo.txt", "foo")
+    .write("bar.txt", "bar")
+    .rpc_server(&rpc_server)
+    .expected_exit_code(1)
+    .expected_stderr("error: provide exactly one file to shard\n")
+    .run_and_extract_stdout();
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn wrap_renders_template_for_each_item() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe { inscriptions, .. } = inscribe(&rpc_server);
+  let asset = &inscriptions[0];
+
+  let Inscribe { inscriptions, .. } =
+    CommandBuilder::new("wallet wrap --template template.html --data data.json --fee-rate 1")
+      .write(
+        "template.html",
+        "<script src=/content/{{asset}}></script><p>{{number}}</p>",
+      )
+      .write(
+        "data.json",
+        format!(r#"[{{"asset": "{asset}", "number": 1}}, {{"asset": "{asset}", "number": 2}}]"#),
+      )
+      .rpc_server(&rpc_server)
+      .run_and_check_output();
+
+  assert_eq!(inscriptions.len(), 2);
+
+  rpc_server.mine_blocks(1);
+
+  let server = TestServer::spawn_with_args(&rpc_server, &[]);
+
+  for (i, inscription) in inscriptions.iter().enumerate() {
+    let response = server.request(format!("/content/{inscription}"));
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+      response.text().unwrap(),
+      format!("<script src=/content/{asset}></script><p>{}</p>", i + 1)
+    );
+  }
+}
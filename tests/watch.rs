@@ -0,0 +1,93 @@
+use {
+  super::*,
+  ord::{
+    subcommand::watch::{Event, Item, Kind, Output},
+    SatPoint,
+  },
+};
+
+#[test]
+fn watched_inscriptions_and_satpoints_emit_events() {
+  let rpc_server = test_bitcoincore_rpc::spawn();
+  create_wallet(&rpc_server);
+
+  let Inscribe {
+    inscriptions,
+    reveals,
+    ..
+  } = inscribe(&rpc_server);
+  let inscription = &inscriptions[0];
+
+  let tempdir = Arc::new(TempDir::new().unwrap());
+
+  CommandBuilder::new(format!("watch add {inscription}"))
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  let output = CommandBuilder::new(format!("watch add {}:0:0", reveals[0]))
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(output.items.len(), 2);
+
+  let send = CommandBuilder::new(format!(
+    "wallet send --fee-rate 1 bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4 {inscription}"
+  ))
+  .rpc_server(&rpc_server)
+  .stdout_regex(".*")
+  .run_and_extract_stdout()
+  .trim()
+  .parse::<Txid>()
+  .unwrap();
+
+  rpc_server.mine_blocks(1);
+
+  let events = CommandBuilder::new("watch run --once")
+    .temp_dir(tempdir.clone())
+    .rpc_server(&rpc_server)
+    .stdout_regex(".*")
+    .run_and_extract_stdout()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect::<Vec<Event>>();
+
+  let satpoint = SatPoint {
+    outpoint: OutPoint::new(send, 0),
+    offset: 0,
+  };
+
+  assert_eq!(
+    events,
+    [
+      Event {
+        item: Item::Inscription(inscription.parse().unwrap()),
+        kind: Kind::Transferred,
+        height: 3,
+        txid: send,
+        satpoint: Some(satpoint),
+      },
+      Event {
+        item: Item::Satpoint(SatPoint {
+          outpoint: OutPoint::new(reveals[0], 0),
+          offset: 0,
+        }),
+        kind: Kind::Spent,
+        height: 3,
+        txid: send,
+        satpoint: None,
+      },
+    ]
+  );
+
+  let output = CommandBuilder::new("watch list")
+    .temp_dir(tempdir)
+    .rpc_server(&rpc_server)
+    .run_and_check_output::<Output>();
+
+  assert_eq!(
+    output.items,
+    [Item::Inscription(inscription.parse().unwrap())]
+  );
+}